@@ -0,0 +1,218 @@
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	env,
+	future::{ready, Ready},
+	path::Path,
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Arc,
+	},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use futures::StreamExt;
+use futures_channel::mpsc;
+use rspc::internal::jsonrpc::{
+	self, handle_json_rpc, OwnedMpscSender, Request, RequestId, Response, Sender,
+	SubscriptionUpgrade,
+};
+use sd_core::{api::Router, Node};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+// Same client id used by the server app
+const CLIENT_ID: &str = "04701823-a498-406e-aef9-22081c1dae34";
+
+type Subscriptions = Arc<futures_locks::Mutex<HashMap<RequestId, oneshot::Sender<()>>>>;
+
+/// Runs a Spacedrive node in this process and executes procedures of its router, exactly like a
+/// frontend would do over rspc
+pub struct Client {
+	node: Arc<Node>,
+	router: Arc<Router>,
+	subscriptions: Subscriptions,
+	next_id: AtomicU32,
+}
+
+struct CliSender<'a> {
+	resp: &'a mut Option<Response>,
+	events: Option<mpsc::Sender<Response>>,
+	subscriptions: Subscriptions,
+}
+
+impl<'a> Sender<'a> for CliSender<'a> {
+	type SendFut = Ready<()>;
+	type SubscriptionMap = Subscriptions;
+	type OwnedSender = OwnedMpscSender;
+
+	fn subscription(mut self) -> SubscriptionUpgrade<'a, Self> {
+		match self.events.take() {
+			Some(events) => SubscriptionUpgrade::Supported(
+				OwnedMpscSender::new(events),
+				self.subscriptions.clone(),
+			),
+			None => SubscriptionUpgrade::Unsupported(self),
+		}
+	}
+
+	fn send(self, resp: jsonrpc::Response) -> Self::SendFut {
+		*self.resp = Some(resp);
+		ready(())
+	}
+}
+
+impl Client {
+	pub async fn start(data_dir: &Path) -> Result<Self> {
+		let (node, router) = Node::new(
+			data_dir,
+			sd_core::Env {
+				api_url: env::var("SD_API_URL")
+					.unwrap_or_else(|_| "https://app.spacedrive.com".to_string()),
+				client_id: env::var("SD_CLIENT_ID").unwrap_or_else(|_| CLIENT_ID.to_string()),
+			},
+		)
+		.await
+		.with_context(|| format!("failed to start Spacedrive on '{}'", data_dir.display()))?;
+
+		Ok(Self {
+			node,
+			router,
+			subscriptions: Default::default(),
+			next_id: AtomicU32::new(0),
+		})
+	}
+
+	/// Pauses running jobs, so they are resumed the next time a node starts on the same data
+	/// directory
+	pub async fn shutdown(&self) {
+		self.node.shutdown().await;
+	}
+
+	pub async fn query<T: DeserializeOwned>(&self, path: &str, input: impl Serialize) -> Result<T> {
+		self.call("query", path, input).await
+	}
+
+	pub async fn mutation<T: DeserializeOwned>(
+		&self,
+		path: &str,
+		input: impl Serialize,
+	) -> Result<T> {
+		self.call("mutation", path, input).await
+	}
+
+	pub async fn library_query<T: DeserializeOwned>(
+		&self,
+		library_id: Uuid,
+		path: &str,
+		arg: impl Serialize,
+	) -> Result<T> {
+		self.query(path, json!({ "library_id": library_id, "arg": arg }))
+			.await
+	}
+
+	pub async fn library_mutation<T: DeserializeOwned>(
+		&self,
+		library_id: Uuid,
+		path: &str,
+		arg: impl Serialize,
+	) -> Result<T> {
+		self.mutation(path, json!({ "library_id": library_id, "arg": arg }))
+			.await
+	}
+
+	/// Runs a subscription until the core ends its stream, failing on the first error it sends
+	pub async fn library_subscription(
+		&self,
+		library_id: Uuid,
+		path: &str,
+		arg: impl Serialize,
+	) -> Result<()> {
+		let (tx, mut rx) = mpsc::channel(16);
+
+		if let Some(response) = self
+			.exec(
+				"subscription",
+				path,
+				json!({ "library_id": library_id, "arg": arg }),
+				Some(tx),
+			)
+			.await?
+		{
+			into_result(response)?;
+		}
+
+		// The channel is closed once the subscription's stream ends and its sender is dropped
+		while let Some(event) = rx.next().await {
+			into_result(event)?;
+		}
+
+		Ok(())
+	}
+
+	async fn call<T: DeserializeOwned>(
+		&self,
+		method: &str,
+		path: &str,
+		input: impl Serialize,
+	) -> Result<T> {
+		let response = self
+			.exec(method, path, serde_json::to_value(input)?, None)
+			.await?
+			.ok_or_else(|| anyhow!("the core didn't answer to '{path}'"))?;
+
+		serde_json::from_value(into_result(response)?)
+			.with_context(|| format!("unexpected result from '{path}'"))
+	}
+
+	async fn exec(
+		&self,
+		method: &str,
+		path: &str,
+		input: Value,
+		events: Option<mpsc::Sender<Response>>,
+	) -> Result<Option<Response>> {
+		let request = serde_json::from_value::<Request>(json!({
+			"jsonrpc": "2.0",
+			"id": self.next_id.fetch_add(1, Ordering::Relaxed),
+			"method": method,
+			"params": { "path": path, "input": input },
+		}))?;
+
+		let mut resp = None;
+
+		handle_json_rpc(
+			self.node.clone(),
+			request,
+			Cow::Borrowed(&self.router),
+			CliSender {
+				resp: &mut resp,
+				events,
+				subscriptions: self.subscriptions.clone(),
+			},
+		)
+		.await;
+
+		Ok(resp)
+	}
+}
+
+/// Responses can only be inspected through their JSON representation
+fn into_result(response: Response) -> Result<Value> {
+	let mut response = serde_json::to_value(response)?;
+	let mut result = response["result"].take();
+
+	match result["type"].as_str() {
+		Some("response" | "event") => Ok(result["data"].take()),
+		Some("error") => bail!(
+			"{} (code {})",
+			result["data"]["message"]
+				.as_str()
+				.unwrap_or("unknown error"),
+			result["data"]["code"]
+		),
+		_ => bail!("unexpected response from the core: {result}"),
+	}
+}
@@ -0,0 +1,61 @@
+use crate::{
+	client::Client,
+	output::{field, Output, Table},
+};
+
+use anyhow::Result;
+use clap::Subcommand;
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum JobCommand {
+	/// List the jobs of the library, grouped by the action that dispatched them
+	List,
+	/// Pause a running job
+	Pause { id: Uuid },
+	/// Resume a paused job
+	Resume { id: Uuid },
+	/// Cancel a job
+	Cancel { id: Uuid },
+}
+
+impl JobCommand {
+	pub async fn run(self, client: &Client, library_id: Uuid, output: Output) -> Result<()> {
+		let (procedure, id, done) = match self {
+			Self::List => {
+				let groups = client
+					.library_query::<Vec<Value>>(library_id, "jobs.reports", ())
+					.await?;
+
+				return output.print(&groups, |groups| {
+					let mut table = Table::new(["ID", "NAME", "STATUS", "PROGRESS", "CREATED"]);
+					for job in groups
+						.iter()
+						.filter_map(|group| group["jobs"].as_array())
+						.flatten()
+					{
+						table.row([
+							field(&job["id"]),
+							field(&job["name"]),
+							field(&job["status"]),
+							format!("{}/{}", job["completed_task_count"], job["task_count"]),
+							field(&job["created_at"]),
+						]);
+					}
+					table
+				});
+			}
+			Self::Pause { id } => ("jobs.pause", id, "Paused"),
+			Self::Resume { id } => ("jobs.resume", id, "Resumed"),
+			Self::Cancel { id } => ("jobs.cancel", id, "Canceled"),
+		};
+
+		client
+			.library_mutation::<()>(library_id, procedure, id)
+			.await?;
+		output.done(format!("{done} job {id}"));
+
+		Ok(())
+	}
+}
@@ -0,0 +1,63 @@
+use crate::{
+	client::Client,
+	output::{field, normalised_item, normalised_items, Output, Table},
+};
+
+use anyhow::Result;
+use clap::Subcommand;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum LibraryCommand {
+	/// List every library on this node
+	List,
+	/// Create a new library
+	Create { name: String },
+	/// Delete a library along with everything indexed on it
+	Delete { id: Uuid },
+}
+
+impl LibraryCommand {
+	pub async fn run(self, client: &Client, output: Output) -> Result<()> {
+		match self {
+			Self::List => {
+				let libraries =
+					normalised_items(&client.query::<Value>("library.list", ()).await?)?;
+
+				output.print(&libraries, |libraries| {
+					let mut table = Table::new(["ID", "NAME", "DESCRIPTION"]);
+					for library in libraries {
+						table.row([
+							field(&library["uuid"]),
+							field(&library["config"]["name"]),
+							field(&library["config"]["description"]),
+						]);
+					}
+					table
+				})
+			}
+			Self::Create { name } => {
+				let library = normalised_item(
+					&client
+						.mutation::<Value>(
+							"library.create",
+							json!({ "name": name, "default_locations": null }),
+						)
+						.await?,
+				)?;
+
+				output.print(&library, |library| {
+					let mut table = Table::new(["ID", "NAME"]);
+					table.row([field(&library["uuid"]), field(&library["config"]["name"])]);
+					table
+				})
+			}
+			Self::Delete { id } => {
+				client.mutation::<()>("library.delete", id).await?;
+				output.done(format!("Deleted library {id}"));
+				Ok(())
+			}
+		}
+	}
+}
@@ -0,0 +1,156 @@
+use crate::{
+	client::Client,
+	output::{field, human_size, normalised_items, size_from_bytes, Output, Table},
+};
+
+use anyhow::Result;
+use clap::Subcommand;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::wait_for_jobs;
+
+#[derive(Subcommand)]
+pub enum LocationCommand {
+	/// List the locations of the library
+	List,
+	/// Add a directory as a location and index it
+	Create {
+		path: PathBuf,
+		/// Indexer rules to apply, the default ones are used when none is given
+		#[arg(long = "rule")]
+		rules: Vec<i32>,
+		/// Return right after adding the location, the indexing is resumed on the next start
+		#[arg(long)]
+		no_wait: bool,
+	},
+	/// Remove a location along with everything indexed on it
+	Delete { id: i32 },
+	/// Index the whole location again
+	FullRescan {
+		id: i32,
+		/// Also regenerate the objects of every file, instead of only new ones
+		#[arg(long)]
+		reidentify_objects: bool,
+		/// Return right after dispatching the rescan, it is resumed on the next start
+		#[arg(long)]
+		no_wait: bool,
+	},
+	/// Quickly rescan a single directory of the location, without its subdirectories
+	QuickRescan {
+		id: i32,
+		/// Directory to rescan, relative to the location root
+		#[arg(long, default_value = "")]
+		sub_path: String,
+	},
+}
+
+impl LocationCommand {
+	pub async fn run(self, client: &Client, library_id: Uuid, output: Output) -> Result<()> {
+		match self {
+			Self::List => {
+				let locations = normalised_items(
+					&client
+						.library_query::<Value>(library_id, "locations.list", ())
+						.await?,
+				)?;
+
+				output.print(&locations, |locations| {
+					let mut table = Table::new(["ID", "NAME", "PATH", "SIZE"]);
+					for location in locations {
+						table.row([
+							field(&location["id"]),
+							field(&location["name"]),
+							field(&location["path"]),
+							size_from_bytes(&location["size_in_bytes"])
+								.map_or_else(|| "-".to_string(), human_size),
+						]);
+					}
+					table
+				})
+			}
+			Self::Create {
+				path,
+				mut rules,
+				no_wait,
+			} => {
+				if rules.is_empty() {
+					rules = default_indexer_rules(client, library_id).await?;
+				}
+
+				let id = client
+					.library_mutation::<Option<i32>>(
+						library_id,
+						"locations.create",
+						json!({ "path": path, "dry_run": false, "indexer_rules_ids": rules }),
+					)
+					.await?;
+
+				if !no_wait {
+					wait_for_jobs(client, library_id).await?;
+				}
+
+				output.print(&id, |id| {
+					let mut table = Table::new(["ID", "PATH"]);
+					table.row([
+						id.map_or_else(|| "-".to_string(), |id| id.to_string()),
+						path.display().to_string(),
+					]);
+					table
+				})
+			}
+			Self::Delete { id } => {
+				client
+					.library_mutation::<()>(library_id, "locations.delete", id)
+					.await?;
+				output.done(format!("Deleted location {id}"));
+				Ok(())
+			}
+			Self::FullRescan {
+				id,
+				reidentify_objects,
+				no_wait,
+			} => {
+				client
+					.library_mutation::<()>(
+						library_id,
+						"locations.fullRescan",
+						json!({ "location_id": id, "reidentify_objects": reidentify_objects }),
+					)
+					.await?;
+
+				if !no_wait {
+					wait_for_jobs(client, library_id).await?;
+				}
+
+				output.done(format!("Rescanned location {id}"));
+				Ok(())
+			}
+			Self::QuickRescan { id, sub_path } => {
+				client
+					.library_subscription(
+						library_id,
+						"locations.quickRescan",
+						json!({ "location_id": id, "sub_path": sub_path }),
+					)
+					.await?;
+				output.done(format!("Rescanned location {id}"));
+				Ok(())
+			}
+		}
+	}
+}
+
+async fn default_indexer_rules(client: &Client, library_id: Uuid) -> Result<Vec<i32>> {
+	Ok(normalised_items(
+		&client
+			.library_query::<Value>(library_id, "locations.indexer_rules.list", ())
+			.await?,
+	)?
+	.into_iter()
+	.filter(|rule| rule["default"].as_bool().unwrap_or(false))
+	.filter_map(|rule| rule["id"].as_i64())
+	.filter_map(|id| i32::try_from(id).ok())
+	.collect())
+}
@@ -0,0 +1,57 @@
+use crate::{client::Client, output::normalised_items};
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::sleep;
+use uuid::Uuid;
+
+pub mod job;
+pub mod library;
+pub mod location;
+pub mod search;
+pub mod tag;
+
+const JOBS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Finds the library to run a command on, by id or name, falling back to the only library when
+/// there is just one
+pub async fn resolve_library(client: &Client, library: Option<&str>) -> Result<Uuid> {
+	let libraries = normalised_items(&client.query::<Value>("library.list", ()).await?)?;
+
+	let mut matching = libraries.iter().filter(|wrapped| match library {
+		Some(library) => {
+			wrapped["uuid"].as_str() == Some(library)
+				|| wrapped["config"]["name"].as_str() == Some(library)
+		}
+		None => true,
+	});
+
+	match (matching.next(), matching.next()) {
+		(Some(wrapped), None) => Ok(serde_json::from_value(wrapped["uuid"].clone())?),
+		(None, _) => match library {
+			Some(library) => bail!("library '{library}' not found"),
+			None => bail!("there are no libraries yet, create one with `library create`"),
+		},
+		(Some(_), Some(_)) => match library {
+			Some(library) => bail!("more than one library is named '{library}', use its id"),
+			None => bail!("there is more than one library, choose one with --library"),
+		},
+	}
+}
+
+/// Jobs only run while the node is up, so commands that dispatch them can wait until the library
+/// has no more jobs running
+pub async fn wait_for_jobs(client: &Client, library_id: Uuid) -> Result<()> {
+	// Jobs are dispatched in the background, give them a moment to be registered
+	sleep(JOBS_POLL_INTERVAL).await;
+
+	while client
+		.library_query::<bool>(library_id, "jobs.isActive", ())
+		.await?
+	{
+		sleep(JOBS_POLL_INTERVAL).await;
+	}
+
+	Ok(())
+}
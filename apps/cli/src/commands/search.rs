@@ -0,0 +1,134 @@
+use crate::{
+	client::Client,
+	output::{field, human_size, normalised_items, size_from_bytes, Output, Table},
+};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[derive(Args)]
+pub struct SearchArgs {
+	/// Only files whose name contains this text
+	query: Option<String>,
+	/// Only files on these locations
+	#[arg(long = "location")]
+	locations: Vec<i32>,
+	/// Only files below this directory, relative to the root of the single given location
+	#[arg(long, requires = "locations")]
+	path: Option<String>,
+	/// Only files with these extensions
+	#[arg(long = "extension")]
+	extensions: Vec<String>,
+	/// Only hidden or only visible files
+	#[arg(long)]
+	hidden: Option<bool>,
+	/// Only files created after this RFC 3339 date
+	#[arg(long)]
+	created_after: Option<String>,
+	/// Only files created before this RFC 3339 date
+	#[arg(long)]
+	created_before: Option<String>,
+	/// Only files modified after this RFC 3339 date
+	#[arg(long)]
+	modified_after: Option<String>,
+	/// Only files modified before this RFC 3339 date
+	#[arg(long)]
+	modified_before: Option<String>,
+	/// Maximum number of results
+	#[arg(long, default_value_t = 100)]
+	take: u8,
+}
+
+impl SearchArgs {
+	pub async fn run(self, client: &Client, library_id: Uuid, output: Output) -> Result<()> {
+		let filters = self.filters()?;
+
+		let paths = normalised_items(
+			&client
+				.library_query::<Value>(
+					library_id,
+					"search.paths",
+					json!({ "take": self.take, "filters": filters }),
+				)
+				.await?,
+		)?
+		.into_iter()
+		.map(|mut explorer_item| explorer_item["item"].take())
+		.collect::<Vec<_>>();
+
+		output.print(&paths, |paths| {
+			let mut table = Table::new(["ID", "LOCATION", "PATH", "SIZE", "MODIFIED"]);
+			for path in paths {
+				table.row([
+					field(&path["id"]),
+					field(&path["location_id"]),
+					full_path(path),
+					size_from_bytes(&path["size_in_bytes_bytes"])
+						.map_or_else(|| "-".to_string(), human_size),
+					field(&path["date_modified"]),
+				]);
+			}
+			table
+		})
+	}
+
+	/// Builds the `FilePathFilterArgs` of the search, which must all match
+	fn filters(&self) -> Result<Vec<Value>> {
+		let mut filters = vec![];
+
+		if let Some(query) = &self.query {
+			filters.push(json!({ "name": { "contains": query } }));
+		}
+
+		match (&self.path, self.locations.as_slice()) {
+			(Some(path), [location_id]) => filters.push(json!({
+				"path": { "location_id": location_id, "path": path, "include_descendants": true }
+			})),
+			(Some(_), _) => bail!("--path must be used along with a single --location"),
+			(None, []) => {}
+			(None, locations) => filters.push(json!({ "locations": { "in": locations } })),
+		}
+
+		if !self.extensions.is_empty() {
+			filters.push(json!({ "extension": { "in": self.extensions } }));
+		}
+
+		if let Some(hidden) = self.hidden {
+			filters.push(json!({ "hidden": hidden }));
+		}
+
+		for (field, from, to) in [
+			("createdAt", &self.created_after, &self.created_before),
+			("modifiedAt", &self.modified_after, &self.modified_before),
+		] {
+			if let Some(from) = from {
+				filters.push(json!({ field: { "from": from } }));
+			}
+			if let Some(to) = to {
+				filters.push(json!({ field: { "to": to } }));
+			}
+		}
+
+		Ok(filters
+			.into_iter()
+			.map(|filter| json!({ "filePath": filter }))
+			.collect())
+	}
+}
+
+fn full_path(path: &Value) -> String {
+	let mut full_path = format!(
+		"{}{}",
+		path["materialized_path"].as_str().unwrap_or("/"),
+		path["name"].as_str().unwrap_or_default()
+	);
+
+	if let Some(extension) = path["extension"].as_str().filter(|ext| !ext.is_empty()) {
+		full_path.push('.');
+		full_path.push_str(extension);
+	}
+
+	full_path
+}
@@ -0,0 +1,134 @@
+use crate::{
+	client::Client,
+	output::{field, normalised_items, Output, Table},
+};
+
+use anyhow::{bail, Result};
+use clap::Subcommand;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+#[derive(Subcommand)]
+pub enum TagCommand {
+	/// List the tags of the library
+	List,
+	/// Create a new tag
+	Create {
+		name: String,
+		/// Color of the tag, as a hex code
+		#[arg(long, default_value = "#A717D9")]
+		color: String,
+	},
+	/// Delete a tag
+	Delete { id: i32 },
+	/// Assign a tag to objects or file paths
+	Assign {
+		id: i32,
+		#[command(flatten)]
+		targets: Targets,
+	},
+	/// Remove a tag from objects or file paths
+	Unassign {
+		id: i32,
+		#[command(flatten)]
+		targets: Targets,
+	},
+}
+
+#[derive(clap::Args)]
+pub struct Targets {
+	#[arg(long = "object")]
+	objects: Vec<i32>,
+	#[arg(long = "file-path")]
+	file_paths: Vec<i32>,
+}
+
+impl Targets {
+	/// Builds the `Target`s expected by `tags.assign`
+	fn into_json(self) -> Result<Vec<Value>> {
+		if self.objects.is_empty() && self.file_paths.is_empty() {
+			bail!("at least one --object or --file-path is required");
+		}
+
+		Ok(self
+			.objects
+			.into_iter()
+			.map(|id| json!({ "Object": id }))
+			.chain(
+				self.file_paths
+					.into_iter()
+					.map(|id| json!({ "FilePath": id })),
+			)
+			.collect())
+	}
+}
+
+impl TagCommand {
+	pub async fn run(self, client: &Client, library_id: Uuid, output: Output) -> Result<()> {
+		match self {
+			Self::List => {
+				let tags = normalised_items(
+					&client
+						.library_query::<Value>(library_id, "tags.list", ())
+						.await?,
+				)?;
+
+				output.print(&tags, |tags| {
+					let mut table = Table::new(["ID", "NAME", "COLOR"]);
+					for tag in tags {
+						table.row([field(&tag["id"]), field(&tag["name"]), field(&tag["color"])]);
+					}
+					table
+				})
+			}
+			Self::Create { name, color } => {
+				let tag = client
+					.library_mutation::<Value>(
+						library_id,
+						"tags.create",
+						json!({ "name": name, "color": color }),
+					)
+					.await?;
+
+				output.print(&tag, |tag| {
+					let mut table = Table::new(["ID", "NAME", "COLOR"]);
+					table.row([field(&tag["id"]), field(&tag["name"]), field(&tag["color"])]);
+					table
+				})
+			}
+			Self::Delete { id } => {
+				client
+					.library_mutation::<()>(library_id, "tags.delete", id)
+					.await?;
+				output.done(format!("Deleted tag {id}"));
+				Ok(())
+			}
+			Self::Assign { id, targets } => {
+				assign(client, library_id, id, targets, false).await?;
+				output.done(format!("Assigned tag {id}"));
+				Ok(())
+			}
+			Self::Unassign { id, targets } => {
+				assign(client, library_id, id, targets, true).await?;
+				output.done(format!("Unassigned tag {id}"));
+				Ok(())
+			}
+		}
+	}
+}
+
+async fn assign(
+	client: &Client,
+	library_id: Uuid,
+	tag_id: i32,
+	targets: Targets,
+	unassign: bool,
+) -> Result<()> {
+	client
+		.library_mutation(
+			library_id,
+			"tags.assign",
+			json!({ "tag_id": tag_id, "targets": targets.into_json()?, "unassign": unassign }),
+		)
+		.await
+}
@@ -1,85 +1,121 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use indoc::printdoc;
-use sd_crypto::header::file::FileHeader;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
-use tokio::fs::File;
+use tokio::signal;
+use tracing_subscriber::EnvFilter;
 
+mod client;
+mod commands;
+mod crypto;
+mod output;
+
+use client::Client;
+use commands::{
+	job::JobCommand, library::LibraryCommand, location::LocationCommand, resolve_library,
+	search::SearchArgs, tag::TagCommand,
+};
+use output::Output;
+
+/// Headless Spacedrive client, running a node on the given data directory
 #[derive(Parser)]
-struct Args {
-	#[arg(help = "the file path to get details for")]
-	path: PathBuf,
+#[command(version)]
+struct Cli {
+	/// Data directory of the node, shared with the Spacedrive app or server that uses it
+	#[arg(long, env = "DATA_DIR", global = true)]
+	data_dir: Option<PathBuf>,
+	/// Library to run the command on, by id or name. Defaults to the only library of the node
+	#[arg(long, short, env = "SD_LIBRARY", global = true)]
+	library: Option<String>,
+	/// Print results as JSON instead of tables
+	#[arg(long, global = true)]
+	json: bool,
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Manage the libraries of the node
+	#[command(subcommand)]
+	Library(LibraryCommand),
+	/// Manage the locations of a library
+	#[command(subcommand)]
+	Location(LocationCommand),
+	/// Search the indexed files of a library
+	Search(SearchArgs),
+	/// Manage the jobs of a library
+	#[command(subcommand)]
+	Job(JobCommand),
+	/// Manage the tags of a library
+	#[command(subcommand)]
+	Tag(TagCommand),
+	/// Print the header of a file encrypted by Spacedrive
+	Header {
+		#[arg(help = "the file path to get details for")]
+		path: PathBuf,
+	},
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-	let args = Args::parse();
+	let cli = Cli::parse();
+	let output = Output { json: cli.json };
 
-	let mut reader = File::open(args.path).await.context("unable to open file")?;
-	let (header, aad) = FileHeader::from_reader(&mut reader).await?;
-	print_crypto_details(&header, &aad);
+	// Logs go to stderr, so they never get mixed with the results
+	tracing_subscriber::fmt()
+		.with_env_filter(
+			EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+		)
+		.with_writer(std::io::stderr)
+		.init();
 
-	Ok(())
-}
+	if let Command::Header { path } = &cli.command {
+		return crypto::print_header(path).await;
+	}
+
+	let data_dir = cli
+		.data_dir
+		.context("the data directory must be set with --data-dir or $DATA_DIR")?;
+
+	let client = Client::start(&data_dir).await?;
 
-fn print_crypto_details(header: &FileHeader, aad: &[u8]) {
-	printdoc! {"
-        Header version: {version}
-        Encryption algorithm: {algorithm}
-        AAD (hex): {hex}
-    ",
-		version = header.version,
-		algorithm = header.algorithm,
-		hex = hex::encode(aad)
+	let result = tokio::select! {
+		result = run(&client, cli.command, cli.library.as_deref(), output) => result,
+		_ = signal::ctrl_c() => Ok(()),
 	};
 
-	header.keyslots.iter().enumerate().for_each(|(i, k)| {
-		printdoc! {"
-            Keyslot {index}:
-              Version: {version}
-              Algorithm: {algorithm}
-              Hashing algorithm: {hashing_algorithm}
-              Salt (hex): {salt}
-              Master Key (hex, encrypted): {master}
-              Master key nonce (hex): {nonce}
-        ",
-			index = i + i,
-			version = k.version,
-			algorithm = k.algorithm,
-			hashing_algorithm = k.hashing_algorithm,
-			salt = hex::encode(&*k.salt),
-			master = hex::encode(&*k.master_key),
-			nonce = hex::encode(k.nonce)
-		};
-	});
+	client.shutdown().await;
 
-	header.metadata.iter().for_each(|m| {
-		printdoc! {"
-            Metadata:
-              Version: {version}
-              Algorithm: {algorithm}
-              Encrypted size: {size}
-              Nonce (hex): {nonce}
-        ",
-			version = m.version,
-			algorithm = m.algorithm,
-			size = m.metadata.len(),
-			nonce = hex::encode(m.metadata_nonce)
-		}
-	});
+	result
+}
 
-	header.preview_media.iter().for_each(|p| {
-		printdoc! {"
-            Preview Media:
-              Version: {version}
-              Algorithm: {algorithm}
-              Encrypted size: {size}
-              Nonce (hex): {nonce}
-        ",
-			version = p.version,
-			algorithm = p.algorithm,
-			size = p.media.len(),
-			nonce = hex::encode(p.media_nonce)
-		};
-	});
+async fn run(
+	client: &Client,
+	command: Command,
+	library: Option<&str>,
+	output: Output,
+) -> Result<()> {
+	match command {
+		Command::Library(command) => command.run(client, output).await,
+		Command::Location(command) => {
+			command
+				.run(client, resolve_library(client, library).await?, output)
+				.await
+		}
+		Command::Search(args) => {
+			args.run(client, resolve_library(client, library).await?, output)
+				.await
+		}
+		Command::Job(command) => {
+			command
+				.run(client, resolve_library(client, library).await?, output)
+				.await
+		}
+		Command::Tag(command) => {
+			command
+				.run(client, resolve_library(client, library).await?, output)
+				.await
+		}
+		Command::Header { path } => crypto::print_header(&path).await,
+	}
 }
@@ -0,0 +1,160 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Prints results as human readable tables, or as JSON for scripting
+#[derive(Clone, Copy)]
+pub struct Output {
+	pub json: bool,
+}
+
+impl Output {
+	pub fn print<T: Serialize>(&self, value: &T, table: impl FnOnce(&T) -> Table) -> Result<()> {
+		if self.json {
+			println!("{}", serde_json::to_string_pretty(value)?);
+		} else {
+			table(value).print();
+		}
+
+		Ok(())
+	}
+
+	/// Confirms mutations without a result worth showing
+	pub fn done(&self, message: impl AsRef<str>) {
+		if self.json {
+			println!("null");
+		} else {
+			println!("{}", message.as_ref());
+		}
+	}
+}
+
+pub struct Table {
+	headers: Vec<&'static str>,
+	rows: Vec<Vec<String>>,
+}
+
+impl Table {
+	pub fn new(headers: impl IntoIterator<Item = &'static str>) -> Self {
+		Self {
+			headers: headers.into_iter().collect(),
+			rows: vec![],
+		}
+	}
+
+	pub fn row<I>(&mut self, cells: I)
+	where
+		I: IntoIterator,
+		I::Item: ToString,
+	{
+		self.rows
+			.push(cells.into_iter().map(|cell| cell.to_string()).collect());
+	}
+
+	pub fn print(&self) {
+		if self.rows.is_empty() {
+			println!("Nothing to show");
+			return;
+		}
+
+		let mut widths = self
+			.headers
+			.iter()
+			.map(|header| header.chars().count())
+			.collect::<Vec<_>>();
+
+		for row in &self.rows {
+			for (width, cell) in widths.iter_mut().zip(row) {
+				*width = (*width).max(cell.chars().count());
+			}
+		}
+
+		let headers = self
+			.headers
+			.iter()
+			.map(ToString::to_string)
+			.collect::<Vec<_>>();
+		let separators = widths
+			.iter()
+			.map(|width| "-".repeat(*width))
+			.collect::<Vec<_>>();
+
+		for row in [&headers, &separators].into_iter().chain(&self.rows) {
+			let line = row
+				.iter()
+				.zip(&widths)
+				.map(|(cell, width)| format!("{cell:<width$}"))
+				.collect::<Vec<_>>()
+				.join("  ");
+
+			println!("{}", line.trim_end());
+		}
+	}
+}
+
+/// Resolves the references of normalised results (like `NormalisedResults` or `SearchData`) into
+/// the items they point to
+pub fn normalised_items(value: &Value) -> Result<Vec<Value>> {
+	value["items"]
+		.as_array()
+		.context("missing items on normalised result")?
+		.iter()
+		.map(|item| resolve_reference(value, item))
+		.collect()
+}
+
+/// Same as [`normalised_items`] but for a single `NormalisedResult`
+pub fn normalised_item(value: &Value) -> Result<Value> {
+	resolve_reference(value, &value["item"])
+}
+
+fn resolve_reference(value: &Value, reference: &Value) -> Result<Value> {
+	value["nodes"]
+		.as_array()
+		.context("missing nodes on normalised result")?
+		.iter()
+		.find(|node| reference_key(node) == reference_key(reference))
+		.map(|node| node["#node"].clone())
+		.with_context(|| format!("missing node for reference {reference}"))
+}
+
+fn reference_key(value: &Value) -> (String, String) {
+	(value["__type"].to_string(), value["__id"].to_string())
+}
+
+/// Formats optional JSON fields, showing a dash for missing ones
+pub fn field(value: &Value) -> String {
+	match value {
+		Value::Null => "-".to_string(),
+		Value::String(s) => s.clone(),
+		value => value.to_string(),
+	}
+}
+
+/// Sizes are sent as big endian bytes, as they don't fit in JavaScript numbers
+pub fn size_from_bytes(value: &Value) -> Option<u64> {
+	let bytes = value
+		.as_array()?
+		.iter()
+		.map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+		.collect::<Option<Vec<_>>>()?;
+
+	Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+pub fn human_size(bytes: u64) -> String {
+	const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	if unit == 0 {
+		format!("{bytes} B")
+	} else {
+		format!("{size:.1} {}", UNITS[unit])
+	}
+}
@@ -57,6 +57,7 @@ const EditLocationSettingsScreen = ({
 			sync_preview_media: data.syncPreviewMedia,
 			generate_preview_media: data.generatePreviewMedia,
 			hidden: data.hidden,
+			strict_identity: null,
 			indexer_rules_ids: []
 		})
 	);
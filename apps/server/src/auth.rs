@@ -0,0 +1,370 @@
+use std::{
+	collections::HashMap,
+	net::{IpAddr, SocketAddr},
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use argon2::{
+	password_hash::{
+		rand_core::{OsRng, RngCore},
+		SaltString,
+	},
+	Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use axum::{
+	extract::{ConnectInfo, State},
+	http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
+	middleware::Next,
+	response::{Html, IntoResponse, Redirect, Response},
+	Form,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use constant_time_eq::constant_time_eq;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::config::AuthConfig;
+
+const SESSION_COOKIE: &str = "sd_session";
+const SESSION_DURATION: Duration = Duration::from_secs(30 * 24 * 60 * 60); // 30 days
+/// WebDAV clients can't log in on the web page, they send the credentials on every request instead
+const WEBDAV_PATH: &str = "/dav";
+/// Failed logins allowed from a single address before it is locked out for the rest of the window
+const MAX_FAILED_LOGINS: u32 = 10;
+const FAILED_LOGINS_WINDOW: Duration = Duration::from_secs(15 * 60); // 15 minutes
+/// How long checked `Authorization: Basic` headers are trusted, so a changed password takes effect
+const BASIC_CREDENTIALS_DURATION: Duration = Duration::from_secs(10 * 60); // 10 minutes
+const MAX_BASIC_CREDENTIALS: usize = 64;
+
+/// Checks the credentials of every request, except for the health check and the login page.
+///
+/// Sessions only live in memory, so users must log in again after the server restarts.
+pub struct Auth {
+	token: Option<String>,
+	credentials: Option<(String, String)>,
+	secure_cookie: bool,
+	sessions: Mutex<HashMap<String, Instant>>,
+	/// `Authorization: Basic` headers that were already checked, as verifying the password hash on
+	/// every request made by a WebDAV client would be way too slow
+	basic_credentials: Mutex<HashMap<String, Instant>>,
+	/// Failed logins by address, with the time of the first failure of the current window
+	failed_logins: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid password hash, it must be an argon2 hash in the PHC string format: {0}")]
+pub struct InvalidPasswordHash(argon2::password_hash::Error);
+
+impl Auth {
+	/// Returns `None` when no credentials are configured, leaving the server open
+	pub fn new(
+		config: AuthConfig,
+		secure_cookie: bool,
+	) -> Result<Option<Self>, InvalidPasswordHash> {
+		if !config.is_enabled() {
+			return Ok(None);
+		}
+
+		let credentials = match (config.username, config.password_hash) {
+			(Some(username), Some(password_hash)) => {
+				PasswordHash::new(&password_hash).map_err(InvalidPasswordHash)?;
+				Some((username, password_hash))
+			}
+			_ => None,
+		};
+
+		Ok(Some(Self {
+			token: config.token,
+			credentials,
+			secure_cookie,
+			sessions: Mutex::new(HashMap::new()),
+			basic_credentials: Mutex::new(HashMap::new()),
+			failed_logins: Mutex::new(HashMap::new()),
+		}))
+	}
+
+	fn check_token(&self, token: &str) -> bool {
+		self.token.as_ref().map_or(false, |expected| {
+			constant_time_eq(expected.as_bytes(), token.as_bytes())
+		})
+	}
+
+	async fn check_credentials(&self, username: &str, password: &str) -> bool {
+		let Some((expected_username, password_hash)) = self.credentials.clone() else {
+			return false;
+		};
+
+		if !constant_time_eq(expected_username.as_bytes(), username.as_bytes()) {
+			return false;
+		}
+
+		// Argon2 is slow on purpose, so it can't run on the async runtime
+		let password = password.to_string();
+		tokio::task::spawn_blocking(move || {
+			// Already validated when the server started
+			PasswordHash::new(&password_hash).map_or(false, |password_hash| {
+				Argon2::default()
+					.verify_password(password.as_bytes(), &password_hash)
+					.is_ok()
+			})
+		})
+		.await
+		.unwrap_or(false)
+	}
+
+	async fn is_locked_out(&self, ip: IpAddr) -> bool {
+		let mut failed_logins = self.failed_logins.lock().await;
+		let now = Instant::now();
+		failed_logins.retain(|_, (_, since)| now.duration_since(*since) < FAILED_LOGINS_WINDOW);
+
+		failed_logins
+			.get(&ip)
+			.map_or(false, |(count, _)| *count >= MAX_FAILED_LOGINS)
+	}
+
+	async fn record_login(&self, ip: IpAddr, authorized: bool) {
+		let mut failed_logins = self.failed_logins.lock().await;
+		if authorized {
+			failed_logins.remove(&ip);
+		} else {
+			failed_logins.entry(ip).or_insert((0, Instant::now())).0 += 1;
+		}
+	}
+
+	async fn create_session(&self) -> String {
+		let mut bytes = [0u8; 32];
+		OsRng.fill_bytes(&mut bytes);
+		let session = hex::encode(bytes);
+
+		let mut sessions = self.sessions.lock().await;
+		let now = Instant::now();
+		sessions.retain(|_, expires_at| *expires_at > now);
+		sessions.insert(session.clone(), now + SESSION_DURATION);
+
+		session
+	}
+
+	async fn check_session(&self, session: &str) -> bool {
+		self.sessions
+			.lock()
+			.await
+			.get(session)
+			.map_or(false, |expires_at| *expires_at > Instant::now())
+	}
+
+	/// Accepts the username and password, or any username with the token as the password
+	async fn check_basic(&self, encoded: &str, ip: IpAddr) -> bool {
+		if self
+			.basic_credentials
+			.lock()
+			.await
+			.get(encoded)
+			.map_or(false, |expires_at| *expires_at > Instant::now())
+		{
+			return true;
+		}
+
+		if self.is_locked_out(ip).await {
+			return false;
+		}
+
+		let Some(decoded) = STANDARD
+			.decode(encoded)
+			.ok()
+			.and_then(|decoded| String::from_utf8(decoded).ok())
+		else {
+			return false;
+		};
+		let Some((username, password)) = decoded.split_once(':') else {
+			return false;
+		};
+
+		let authorized =
+			self.check_token(password) || self.check_credentials(username, password).await;
+		self.record_login(ip, authorized).await;
+		if authorized {
+			let mut basic_credentials = self.basic_credentials.lock().await;
+			let now = Instant::now();
+			basic_credentials.retain(|_, expires_at| *expires_at > now);
+
+			// Anyone with valid credentials could fill the cache by varying the username
+			if basic_credentials.len() >= MAX_BASIC_CREDENTIALS {
+				if let Some(oldest) = basic_credentials
+					.iter()
+					.min_by_key(|(_, expires_at)| **expires_at)
+					.map(|(encoded, _)| encoded.clone())
+				{
+					basic_credentials.remove(&oldest);
+				}
+			}
+
+			basic_credentials.insert(encoded.to_string(), now + BASIC_CREDENTIALS_DURATION);
+		} else {
+			warn!("Failed basic authentication attempt <username='{username}'>");
+		}
+
+		authorized
+	}
+
+	async fn is_authorized(&self, headers: &HeaderMap, ip: IpAddr) -> bool {
+		let authorization = headers
+			.get(header::AUTHORIZATION)
+			.and_then(|value| value.to_str().ok());
+
+		if let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+			return self.check_token(token.trim());
+		}
+
+		if let Some(encoded) = authorization.and_then(|value| value.strip_prefix("Basic ")) {
+			return self.check_basic(encoded.trim(), ip).await;
+		}
+
+		match session_cookie(headers) {
+			Some(session) => self.check_session(session).await,
+			None => false,
+		}
+	}
+
+	fn session_cookie_header(&self, session: &str, max_age: Duration) -> HeaderValue {
+		let mut cookie = format!(
+			"{SESSION_COOKIE}={session}; Path=/; Max-Age={}; HttpOnly; SameSite=Strict",
+			max_age.as_secs()
+		);
+		if self.secure_cookie {
+			cookie.push_str("; Secure");
+		}
+
+		HeaderValue::from_str(&cookie).expect("session cookies only have ascii characters")
+	}
+}
+
+fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+	headers
+		.get_all(header::COOKIE)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(';'))
+		.filter_map(|cookie| cookie.trim().split_once('='))
+		.find(|(name, _)| *name == SESSION_COOKIE)
+		.map(|(_, session)| session)
+}
+
+/// Middleware rejecting unauthenticated requests, which also covers the rspc websocket upgrade as
+/// it is a regular `GET` request until it is accepted
+pub async fn require_auth<B>(
+	State(auth): State<Arc<Auth>>,
+	ConnectInfo(addr): ConnectInfo<SocketAddr>,
+	req: Request<B>,
+	next: Next<B>,
+) -> Response {
+	if auth.is_authorized(req.headers(), addr.ip()).await {
+		return next.run(req).await;
+	}
+
+	if req.uri().path().starts_with(WEBDAV_PATH) {
+		return (
+			StatusCode::UNAUTHORIZED,
+			[(
+				header::WWW_AUTHENTICATE,
+				r#"Basic realm="Spacedrive", charset="UTF-8""#,
+			)],
+			"Unauthorized",
+		)
+			.into_response();
+	}
+
+	// Browsers navigating to the web UI are sent to the login page, everything else gets a 401
+	let wants_html = req
+		.headers()
+		.get(header::ACCEPT)
+		.and_then(|value| value.to_str().ok())
+		.map_or(false, |accept| accept.contains("text/html"));
+
+	if req.method() == Method::GET && wants_html {
+		Redirect::to("/login").into_response()
+	} else {
+		(
+			StatusCode::UNAUTHORIZED,
+			[(header::WWW_AUTHENTICATE, "Bearer")],
+			"Unauthorized",
+		)
+			.into_response()
+	}
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+	#[serde(default)]
+	username: String,
+	password: String,
+}
+
+pub async fn login_page() -> Html<&'static str> {
+	Html(include_str!("login.html"))
+}
+
+/// Logs in with the username and password, or with the token as the password
+pub async fn login(
+	State(auth): State<Arc<Auth>>,
+	ConnectInfo(addr): ConnectInfo<SocketAddr>,
+	Form(form): Form<LoginForm>,
+) -> Response {
+	if auth.is_locked_out(addr.ip()).await {
+		warn!(
+			"Rejected login attempt from locked out address <ip='{}'>",
+			addr.ip()
+		);
+		return (
+			StatusCode::TOO_MANY_REQUESTS,
+			"Too many failed login attempts, try again later",
+		)
+			.into_response();
+	}
+
+	let authorized = if form.username.is_empty() {
+		auth.check_token(&form.password)
+	} else {
+		auth.check_credentials(&form.username, &form.password).await
+	};
+	auth.record_login(addr.ip(), authorized).await;
+
+	if !authorized {
+		warn!("Failed login attempt <username='{}'>", form.username);
+		return Redirect::to("/login?failed").into_response();
+	}
+
+	let session = auth.create_session().await;
+
+	(
+		[(
+			header::SET_COOKIE,
+			auth.session_cookie_header(&session, SESSION_DURATION),
+		)],
+		Redirect::to("/"),
+	)
+		.into_response()
+}
+
+pub async fn logout(State(auth): State<Arc<Auth>>, headers: HeaderMap) -> Response {
+	if let Some(session) = session_cookie(&headers) {
+		auth.sessions.lock().await.remove(session);
+	}
+
+	(
+		[(
+			header::SET_COOKIE,
+			auth.session_cookie_header("", Duration::ZERO),
+		)],
+		Redirect::to("/login"),
+	)
+		.into_response()
+}
+
+/// Hashes a password to be used as `password_hash` on the config
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+	Ok(Argon2::default()
+		.hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))?
+		.to_string())
+}
@@ -0,0 +1,96 @@
+use std::{
+	env, fs, io,
+	path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// Name of the optional config file, looked up on the data directory unless `$SD_SERVER_CONFIG`
+/// points somewhere else
+const CONFIG_FILE_NAME: &str = "server.json";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+	pub auth: AuthConfig,
+	pub tls: Option<TlsConfig>,
+}
+
+/// Requests are accepted with the bearer token, or with a session created by logging in with the
+/// username and password. Both can be enabled at the same time.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+	pub token: Option<String>,
+	pub username: Option<String>,
+	/// Argon2 hash of the password in the PHC string format, as printed by `sd-server hash-password`
+	pub password_hash: Option<String>,
+}
+
+impl AuthConfig {
+	pub fn is_enabled(&self) -> bool {
+		self.token.is_some() || self.password_hash.is_some()
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TlsConfig {
+	pub cert: PathBuf,
+	pub key: PathBuf,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+	#[error("failed to read the server config file at '{}': {1}", .0.display())]
+	Read(PathBuf, io::Error),
+	#[error("invalid server config file at '{}': {1}", .0.display())]
+	Parse(PathBuf, serde_json::Error),
+	#[error("both $SD_TLS_CERT and $SD_TLS_KEY must be set to enable TLS")]
+	IncompleteTls,
+	#[error("the username and password hash must be set together")]
+	IncompleteCredentials,
+}
+
+impl ServerConfig {
+	/// Loads the config file, if there is one, and applies the overrides from environment
+	/// variables on top of it
+	pub fn load(data_dir: &Path) -> Result<Self, ConfigError> {
+		let (path, required) = match env::var_os("SD_SERVER_CONFIG") {
+			Some(path) => (PathBuf::from(path), true),
+			None => (data_dir.join(CONFIG_FILE_NAME), false),
+		};
+
+		let mut config = match fs::read(&path) {
+			Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| ConfigError::Parse(path, e))?,
+			Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Self::default(),
+			Err(e) => return Err(ConfigError::Read(path, e)),
+		};
+
+		if let Ok(token) = env::var("SD_AUTH_TOKEN") {
+			config.auth.token = Some(token);
+		}
+		if let Ok(username) = env::var("SD_AUTH_USERNAME") {
+			config.auth.username = Some(username);
+		}
+		if let Ok(password_hash) = env::var("SD_AUTH_PASSWORD_HASH") {
+			config.auth.password_hash = Some(password_hash);
+		}
+
+		match (env::var_os("SD_TLS_CERT"), env::var_os("SD_TLS_KEY")) {
+			(Some(cert), Some(key)) => {
+				config.tls = Some(TlsConfig {
+					cert: cert.into(),
+					key: key.into(),
+				})
+			}
+			(None, None) => {}
+			_ => return Err(ConfigError::IncompleteTls),
+		}
+
+		if config.auth.username.is_some() != config.auth.password_hash.is_some() {
+			return Err(ConfigError::IncompleteCredentials);
+		}
+
+		Ok(config)
+	}
+}
@@ -1,17 +1,41 @@
-use std::{env, net::SocketAddr, path::Path};
+use std::{env, io, net::SocketAddr, path::Path, sync::Arc, time::Duration};
 
-use axum::routing::get;
+use axum::{
+	middleware,
+	routing::{get, post},
+};
+use axum_server::{tls_rustls::RustlsConfig, Handle};
 use sd_core::{custom_uri, Node};
-use tracing::info;
+use tracing::{info, warn};
 
+mod auth;
+mod config;
 mod utils;
 
+use auth::Auth;
+use config::ServerConfig;
+
 #[cfg(feature = "assets")]
 static ASSETS_DIR: include_dir::Dir<'static> =
 	include_dir::include_dir!("$CARGO_MANIFEST_DIR/../web/dist");
 
 #[tokio::main]
 async fn main() {
+	// `sd-server hash-password` reads a password from stdin and prints the hash to use on the config
+	if env::args().nth(1).as_deref() == Some("hash-password") {
+		let mut password = String::new();
+		io::stdin()
+			.read_line(&mut password)
+			.expect("failed to read the password from stdin");
+
+		match auth::hash_password(password.trim_end_matches(['\r', '\n'])) {
+			Ok(hash) => println!("{hash}"),
+			Err(e) => panic!("failed to hash the password: {e}"),
+		}
+
+		return;
+	}
+
 	let data_dir = match env::var("DATA_DIR") {
 		Ok(path) => Path::new(&path).to_path_buf(),
 		Err(_e) => {
@@ -39,6 +63,20 @@ async fn main() {
 		}
 	};
 
+	let config = match ServerConfig::load(&data_dir) {
+		Ok(config) => config,
+		Err(e) => {
+			panic!("{}", e.to_string())
+		}
+	};
+
+	let auth = match Auth::new(config.auth, config.tls.is_some()) {
+		Ok(auth) => auth.map(Arc::new),
+		Err(e) => {
+			panic!("{}", e.to_string())
+		}
+	};
+
 	let (node, router) = match Node::new(
 		data_dir,
 		sd_core::Env {
@@ -58,8 +96,12 @@ async fn main() {
 	let signal = utils::axum_shutdown_signal(node.clone());
 
 	let app = axum::Router::new()
-		.route("/health", get(|| async { "OK" }))
 		.nest("/spacedrive", custom_uri::router(node.clone()))
+		// Without authentication anyone could change the files, so WebDAV is only mounted as read-only
+		.nest(
+			"/dav",
+			custom_uri::webdav::router(node.clone(), auth.is_none()),
+		)
 		.nest("/rspc", router.endpoint(move || node.clone()).axum());
 
 	#[cfg(feature = "assets")]
@@ -136,12 +178,67 @@ async fn main() {
 		.route("/", get(|| async { "Spacedrive Server!" }))
 		.fallback(|| async { "404 Not Found: We're past the event horizon..." });
 
+	// Everything registered above requires authentication, including the file serving routes, WebDAV
+	// and the rspc websocket
+	let app = match auth {
+		Some(auth) => app
+			.layer(middleware::from_fn_with_state(
+				auth.clone(),
+				auth::require_auth,
+			))
+			.merge(
+				axum::Router::new()
+					.route("/login", get(auth::login_page).post(auth::login))
+					.route("/logout", post(auth::logout))
+					.with_state(auth),
+			),
+		None => {
+			warn!(
+				"Authentication is disabled, anyone who can reach this server has access to every \
+				file of every location! WebDAV is read-only until it's enabled. Set $SD_AUTH_TOKEN or $SD_AUTH_USERNAME and \
+				$SD_AUTH_PASSWORD_HASH to enable it."
+			);
+			app
+		}
+	};
+
+	let app = app.route("/health", get(|| async { "OK" }));
+
+	let handle = Handle::new();
+	tokio::spawn({
+		let handle = handle.clone();
+		async move {
+			signal.await;
+			handle.graceful_shutdown(Some(Duration::from_secs(10)));
+		}
+	});
+
 	let mut addr = "[::]:8080".parse::<SocketAddr>().unwrap(); // This listens on IPv6 and IPv4
 	addr.set_port(port);
-	info!("Listening on http://localhost:{}", port);
-	axum::Server::bind(&addr)
-		.serve(app.into_make_service())
-		.with_graceful_shutdown(signal)
-		.await
-		.expect("Error with HTTP server!");
+
+	match config.tls {
+		Some(tls) => {
+			let tls_config = match RustlsConfig::from_pem_file(&tls.cert, &tls.key).await {
+				Ok(tls_config) => tls_config,
+				Err(e) => {
+					panic!("Failed to load the TLS certificate and key: {e}")
+				}
+			};
+
+			info!("Listening on https://localhost:{}", port);
+			axum_server::bind_rustls(addr, tls_config)
+				.handle(handle)
+				.serve(app.into_make_service_with_connect_info::<SocketAddr>())
+				.await
+				.expect("Error with HTTPS server!");
+		}
+		None => {
+			info!("Listening on http://localhost:{}", port);
+			axum_server::bind(addr)
+				.handle(handle)
+				.serve(app.into_make_service_with_connect_info::<SocketAddr>())
+				.await
+				.expect("Error with HTTP server!");
+		}
+	}
 }
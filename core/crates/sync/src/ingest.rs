@@ -20,7 +20,7 @@ use crate::{
 /// Stuff that can be handled outside the actor
 pub enum Request {
 	Messages { timestamps: Vec<(Uuid, NTP64)> },
-	Ingested,
+	Ingested(CRDTOperation),
 	FinishedIngesting,
 }
 
@@ -172,7 +172,7 @@ impl Actor {
 
 		write_crdt_op_to_db(&op, &self.db).await?;
 
-		self.io.req_tx.send(Request::Ingested).await.ok();
+		self.io.req_tx.send(Request::Ingested(op)).await.ok();
 
 		Ok(())
 	}
@@ -153,7 +153,7 @@ async fn bruh() -> Result<(), Box<dyn std::error::Error>> {
 							.await
 							.unwrap();
 					}
-					ingest::Request::Ingested => {
+					ingest::Request::Ingested(_) => {
 						instance2.sync.tx.send(SyncMessage::Ingested).ok();
 					}
 					_ => todo!(),
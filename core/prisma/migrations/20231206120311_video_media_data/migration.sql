@@ -0,0 +1,6 @@
+-- AlterTable
+ALTER TABLE "media_data" ADD COLUMN "container" TEXT;
+ALTER TABLE "media_data" ADD COLUMN "video_codec" TEXT;
+ALTER TABLE "media_data" ADD COLUMN "frame_rate" REAL;
+ALTER TABLE "media_data" ADD COLUMN "rotation" INTEGER;
+ALTER TABLE "media_data" ADD COLUMN "streams" BLOB;
@@ -0,0 +1,19 @@
+-- CreateTable
+CREATE TABLE "statistics_per_location" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "location_id" INTEGER NOT NULL,
+    "file_count" INTEGER NOT NULL DEFAULT 0,
+    "total_bytes" TEXT NOT NULL DEFAULT '0',
+    "statistics_id" INTEGER NOT NULL,
+    CONSTRAINT "statistics_per_location_statistics_id_fkey" FOREIGN KEY ("statistics_id") REFERENCES "statistics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "statistics_per_kind" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "kind" INTEGER NOT NULL,
+    "file_count" INTEGER NOT NULL DEFAULT 0,
+    "total_bytes" TEXT NOT NULL DEFAULT '0',
+    "statistics_id" INTEGER NOT NULL,
+    CONSTRAINT "statistics_per_kind_statistics_id_fkey" FOREIGN KEY ("statistics_id") REFERENCES "statistics" ("id") ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "album" ADD COLUMN "smart_filter" TEXT;
@@ -0,0 +1,3 @@
+-- AlterTable
+ALTER TABLE "object_in_space" ADD COLUMN "date_created" DATETIME;
+ALTER TABLE "object_in_space" ADD COLUMN "position" INTEGER NOT NULL DEFAULT 0;
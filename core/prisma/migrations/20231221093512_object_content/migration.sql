@@ -0,0 +1,2 @@
+-- CreateTable
+CREATE VIRTUAL TABLE IF NOT EXISTS "object_content" USING fts5("content", tokenize = 'unicode61 remove_diacritics 2');
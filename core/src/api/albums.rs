@@ -0,0 +1,364 @@
+use chrono::Utc;
+use rspc::{alpha::AlphaRouter, ErrorCode};
+use sd_cache::{Normalise, NormalisedResult, NormalisedResults};
+use sd_prisma::{prisma, prisma_sync};
+use sd_sync::OperationFactory;
+use serde::Deserialize;
+use serde_json::json;
+use specta::Type;
+
+use crate::{
+	invalidate_query,
+	library::Library,
+	object::album::{AlbumCreateArgs, SmartAlbumFilter},
+	prisma::{album, object, object_in_album},
+};
+
+use super::{utils::library, Ctx, R};
+
+#[derive(Debug, Type, Deserialize)]
+#[specta(inline)]
+struct AlbumAssignArgs {
+	album_id: album::id::Type,
+	object_ids: Vec<object::id::Type>,
+}
+
+pub(crate) fn mount() -> AlphaRouter<Ctx> {
+	R.router()
+		.procedure("list", {
+			R.with2(library()).query(|(_, library), _: ()| async move {
+				let albums = library.db.album().find_many(vec![]).exec().await?;
+
+				let (nodes, items) = albums.normalise(|i| i.id.to_string());
+
+				Ok(NormalisedResults { nodes, items })
+			})
+		})
+		.procedure("get", {
+			R.with2(library())
+				.query(|(_, library), album_id: album::id::Type| async move {
+					Ok(library
+						.db
+						.album()
+						.find_unique(album::id::equals(album_id))
+						.exec()
+						.await?
+						.map(|album| NormalisedResult::from(album, |i| i.id.to_string())))
+				})
+		})
+		.procedure("create", {
+			R.with2(library())
+				.mutation(|(_, library), args: AlbumCreateArgs| async move {
+					if args
+						.smart_filter
+						.as_ref()
+						.map_or(false, SmartAlbumFilter::is_empty)
+					{
+						return Err(rspc::Error::new(
+							ErrorCode::BadRequest,
+							"A smart album needs at least one criteria".to_string(),
+						));
+					}
+
+					let created_album = args.exec(&library).await?;
+
+					invalidate_query!(library, "albums.list");
+					invalidate_query!(library, "search.objects");
+
+					Ok(created_album)
+				})
+		})
+		.procedure("update", {
+			#[derive(Type, Deserialize)]
+			pub struct AlbumUpdateArgs {
+				pub id: album::id::Type,
+				pub name: Option<String>,
+				pub is_hidden: Option<bool>,
+				/// Only smart albums can have their filter replaced
+				pub smart_filter: Option<SmartAlbumFilter>,
+			}
+
+			R.with2(library())
+				.mutation(|(_, library), args: AlbumUpdateArgs| async move {
+					let Library { sync, db, .. } = library.as_ref();
+
+					let album = find_album(db, args.id).await?;
+
+					if let Some(filter) = &args.smart_filter {
+						if album.smart_filter.is_none() || filter.is_empty() {
+							return Err(rspc::Error::new(
+								ErrorCode::BadRequest,
+								"Only smart albums can have a filter, with at least one criteria"
+									.to_string(),
+							));
+						}
+					}
+
+					let smart_filter = args.smart_filter.as_ref().map(|filter| {
+						serde_json::to_string(filter)
+							.expect("smart album filters are always serializable")
+					});
+
+					let (sync_params, db_params): (Vec<_>, Vec<_>) = [
+						args.name.map(|name| {
+							(
+								(album::name::NAME, json!(&name)),
+								album::name::set(Some(name)),
+							)
+						}),
+						args.is_hidden.map(|is_hidden| {
+							(
+								(album::is_hidden::NAME, json!(is_hidden)),
+								album::is_hidden::set(Some(is_hidden)),
+							)
+						}),
+						smart_filter.map(|filter| {
+							(
+								(album::smart_filter::NAME, json!(&filter)),
+								album::smart_filter::set(Some(filter)),
+							)
+						}),
+					]
+					.into_iter()
+					.flatten()
+					.unzip();
+
+					sync.write_ops(
+						db,
+						(
+							sync_params
+								.into_iter()
+								.map(|(k, v)| {
+									sync.shared_update(
+										prisma_sync::album::SyncId {
+											pub_id: album.pub_id.clone(),
+										},
+										k,
+										v,
+									)
+								})
+								.collect(),
+							db.album().update(
+								album::id::equals(args.id),
+								db_params
+									.into_iter()
+									.chain([album::date_modified::set(Some(Utc::now().into()))])
+									.collect(),
+							),
+						),
+					)
+					.await?;
+
+					if let Some(filter) = &args.smart_filter {
+						filter.refresh(db, args.id, None).await?;
+
+						invalidate_query!(library, "search.objects");
+					}
+
+					invalidate_query!(library, "albums.list");
+					invalidate_query!(library, "albums.get");
+
+					Ok(())
+				})
+		})
+		.procedure("assign", {
+			R.with2(library())
+				.mutation(|(_, library), args: AlbumAssignArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let album = find_manual_album(db, args.album_id).await?;
+
+					// Adding an object twice would break the primary key of the relation
+					let objects = db
+						.object()
+						.find_many(vec![
+							object::id::in_vec(args.object_ids),
+							object::albums::none(vec![object_in_album::album_id::equals(
+								args.album_id,
+							)]),
+						])
+						.select(object::select!({ id pub_id }))
+						.exec()
+						.await?;
+
+					let date_created = Utc::now();
+
+					let (sync_ops, db_creates) = objects.into_iter().fold(
+						(vec![], vec![]),
+						|(mut sync_ops, mut db_creates), object| {
+							db_creates.push(object_in_album::CreateUnchecked {
+								album_id: args.album_id,
+								object_id: object.id,
+								_params: vec![object_in_album::date_created::set(Some(
+									date_created.into(),
+								))],
+							});
+
+							sync_ops.extend(sync.relation_create(
+								sync_id(album.pub_id.clone(), object.pub_id),
+								[(
+									object_in_album::date_created::NAME,
+									json!(date_created.to_rfc3339()),
+								)],
+							));
+
+							(sync_ops, db_creates)
+						},
+					);
+
+					sync.write_ops(db, (sync_ops, db.object_in_album().create_many(db_creates)))
+						.await?;
+
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				})
+		})
+		.procedure("unassign", {
+			R.with2(library())
+				.mutation(|(_, library), args: AlbumAssignArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let album = find_manual_album(db, args.album_id).await?;
+
+					let objects = db
+						.object()
+						.find_many(vec![object::id::in_vec(args.object_ids)])
+						.select(object::select!({ id pub_id }))
+						.exec()
+						.await?;
+
+					sync.write_ops(
+						db,
+						(
+							objects
+								.iter()
+								.map(|object| {
+									sync.relation_delete(sync_id(
+										album.pub_id.clone(),
+										object.pub_id.clone(),
+									))
+								})
+								.collect(),
+							db.object_in_album().delete_many(vec![
+								object_in_album::album_id::equals(args.album_id),
+								object_in_album::object_id::in_vec(
+									objects.into_iter().map(|object| object.id).collect(),
+								),
+							]),
+						),
+					)
+					.await?;
+
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				})
+		})
+		.procedure(
+			"delete",
+			R.with2(library())
+				.mutation(|(_, library), album_id: album::id::Type| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let album = db
+						.album()
+						.find_unique(album::id::equals(album_id))
+						.select(album::select!({
+							pub_id
+							smart_filter
+							objects: select {
+								object: select { pub_id }
+							}
+						}))
+						.exec()
+						.await?
+						.ok_or_else(album_not_found)?;
+
+					// Objects have to be removed first, as the relation doesn't cascade deletes.
+					// The objects of smart albums were never synced, so neither is their removal.
+					sync.write_ops(
+						db,
+						(
+							if album.smart_filter.is_none() {
+								album
+									.objects
+									.into_iter()
+									.map(|album_object| {
+										sync.relation_delete(sync_id(
+											album.pub_id.clone(),
+											album_object.object.pub_id,
+										))
+									})
+									.collect()
+							} else {
+								vec![]
+							},
+							db.object_in_album()
+								.delete_many(vec![object_in_album::album_id::equals(album_id)]),
+						),
+					)
+					.await?;
+
+					sync.write_op(
+						db,
+						sync.shared_delete(prisma_sync::album::SyncId {
+							pub_id: album.pub_id,
+						}),
+						db.album().delete(album::id::equals(album_id)),
+					)
+					.await?;
+
+					invalidate_query!(library, "albums.list");
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				}),
+		)
+}
+
+fn sync_id(album_pub_id: Vec<u8>, object_pub_id: Vec<u8>) -> prisma_sync::object_in_album::SyncId {
+	prisma_sync::object_in_album::SyncId {
+		album: prisma_sync::album::SyncId {
+			pub_id: album_pub_id,
+		},
+		object: prisma_sync::object::SyncId {
+			pub_id: object_pub_id,
+		},
+	}
+}
+
+fn album_not_found() -> rspc::Error {
+	rspc::Error::new(ErrorCode::NotFound, "Album not found".to_string())
+}
+
+album::select!(album_to_update { pub_id smart_filter });
+
+async fn find_album(
+	db: &prisma::PrismaClient,
+	album_id: album::id::Type,
+) -> Result<album_to_update::Data, rspc::Error> {
+	db.album()
+		.find_unique(album::id::equals(album_id))
+		.select(album_to_update::select())
+		.exec()
+		.await?
+		.ok_or_else(album_not_found)
+}
+
+/// The objects of smart albums are picked by their filter, so they can't be changed by hand
+async fn find_manual_album(
+	db: &prisma::PrismaClient,
+	album_id: album::id::Type,
+) -> Result<album_to_update::Data, rspc::Error> {
+	let album = find_album(db, album_id).await?;
+
+	if album.smart_filter.is_some() {
+		return Err(rspc::Error::new(
+			ErrorCode::BadRequest,
+			"Objects can't be added to or removed from smart albums".to_string(),
+		));
+	}
+
+	Ok(album)
+}
@@ -10,12 +10,17 @@ use crate::{
 		get_location_path_from_location_id, LocationError,
 	},
 	object::{
+		deduplicator::deduplicator_job::DeduplicatorJobInit,
 		fs::{
-			copy::FileCopierJobInit, cut::FileCutterJobInit, delete::FileDeleterJobInit,
-			erase::FileEraserJobInit, error::FileSystemJobsError,
-			find_available_filename_for_duplicate,
+			archiver::FileArchiverJobInit, copy::FileCopierJobInit, cut::FileCutterJobInit,
+			decrypt::FileDecryptorJobInit, delete::FileDeleterJobInit,
+			encrypt::FileEncryptorJobInit, erase::FileEraserJobInit, error::FileSystemJobsError,
+			extractor::FileExtractorJobInit, find_available_filename_for_duplicate,
+		},
+		media::{
+			media_data_audio_from_prisma_data, media_data_image_from_prisma_data,
+			media_data_video_from_prisma_data,
 		},
-		media::media_data_image_from_prisma_data,
 	},
 	prisma::{file_path, location, object},
 	util::{db::maybe_missing, error::FileIOError},
@@ -137,7 +142,17 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 										media_data_image_from_prisma_data(obj.media_data?).ok()?,
 									))
 								}
-								_ => return None, // TODO(brxken128): audio and video
+								Some(v) if v == ObjectKind::Audio as i32 => {
+									MediaMetadata::Audio(Box::new(
+										media_data_audio_from_prisma_data(obj.media_data?).ok()?,
+									))
+								}
+								Some(v) if v == ObjectKind::Video as i32 => {
+									MediaMetadata::Video(Box::new(
+										media_data_video_from_prisma_data(obj.media_data?).ok()?,
+									))
+								}
+								_ => return None,
 							})
 						})
 						.ok_or_else(|| {
@@ -286,18 +301,24 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 					Ok(())
 				})
 		})
-		// .procedure("encryptFiles", {
-		// 	R.with2(library())
-		// 		.mutation(|(node, library), args: FileEncryptorJobInit| async move {
-		// 			Job::new(args).spawn(&node, &library).await.map_err(Into::into)
-		// 		})
-		// })
-		// .procedure("decryptFiles", {
-		// 	R.with2(library())
-		// 		.mutation(|(node, library), args: FileDecryptorJobInit| async move {
-		// 			Job::new(args).spawn(&node, &library).await.map_err(Into::into)
-		// 		})
-		// })
+		.procedure("encryptFiles", {
+			R.with2(library())
+				.mutation(|(node, library), args: FileEncryptorJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
+		.procedure("decryptFiles", {
+			R.with2(library())
+				.mutation(|(node, library), args: FileDecryptorJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
 		.procedure("deleteFiles", {
 			R.with2(library())
 				.mutation(|(node, library), args: FileDeleterJobInit| async move {
@@ -533,6 +554,33 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 						.map_err(Into::into)
 				})
 		})
+		.procedure("archiveFiles", {
+			R.with2(library())
+				.mutation(|(node, library), args: FileArchiverJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
+		.procedure("extractFiles", {
+			R.with2(library())
+				.mutation(|(node, library), args: FileExtractorJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
+		.procedure("deduplicate", {
+			R.with2(library())
+				.mutation(|(node, library), args: DeduplicatorJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
 		.procedure("renameFile", {
 			#[derive(Type, Deserialize)]
 			pub struct RenameOne {
@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, HashSet};
+
+use itertools::{Either, Itertools};
+use rspc::{alpha::AlphaRouter, ErrorCode};
+use sd_cache::{Normalise, NormalisedResults};
+use sd_prisma::{prisma, prisma_sync};
+use sd_sync::OperationFactory;
+use serde::Deserialize;
+use specta::Type;
+
+use crate::{
+	invalidate_query,
+	library::Library,
+	object::label::LabelCreateArgs,
+	prisma::{file_path, label, label_on_object, object},
+};
+
+use super::{utils::library, Ctx, R};
+
+#[derive(Debug, Type, Deserialize)]
+#[specta(inline)]
+/// Same as the target of `tags.assign`, but a type can't share its name with another
+enum LabelTarget {
+	Object(prisma::object::id::Type),
+	FilePath(prisma::file_path::id::Type),
+}
+
+#[derive(Debug, Type, Deserialize)]
+#[specta(inline)]
+struct LabelAssignArgs {
+	targets: Vec<LabelTarget>,
+	label_id: label::id::Type,
+}
+
+pub(crate) fn mount() -> AlphaRouter<Ctx> {
+	R.router()
+		.procedure("list", {
+			R.with2(library()).query(|(_, library), _: ()| async move {
+				let labels = library.db.label().find_many(vec![]).exec().await?;
+
+				let (nodes, items) = labels.normalise(|i| i.id.to_string());
+
+				Ok(NormalisedResults { nodes, items })
+			})
+		})
+		.procedure("getForObject", {
+			R.with2(library())
+				.query(|(_, library), object_id: object::id::Type| async move {
+					let labels = library
+						.db
+						.label()
+						.find_many(vec![label::label_objects::some(vec![
+							label_on_object::object_id::equals(object_id),
+						])])
+						.exec()
+						.await?;
+
+					let (nodes, items) = labels.normalise(|i| i.id.to_string());
+
+					Ok(NormalisedResults { nodes, items })
+				})
+		})
+		.procedure("getWithObjects", {
+			R.with2(library()).query(
+				|(_, library), object_ids: Vec<object::id::Type>| async move {
+					let Library { db, .. } = library.as_ref();
+
+					let labels_with_objects = db
+						.label()
+						.find_many(vec![label::label_objects::some(vec![
+							label_on_object::object_id::in_vec(object_ids.clone()),
+						])])
+						.select(label::select!({
+							id
+							label_objects(vec![label_on_object::object_id::in_vec(object_ids.clone())]): select {
+								date_created
+								object: select {
+									id
+								}
+							}
+						}))
+						.exec()
+						.await?;
+
+					// This doesn't need normalised caching because it doesn't return whole models.
+					Ok(labels_with_objects
+						.into_iter()
+						.map(|label| (label.id, label.label_objects))
+						.collect::<BTreeMap<_, _>>())
+				},
+			)
+		})
+		.procedure("create", {
+			R.with2(library())
+				.mutation(|(_, library), args: LabelCreateArgs| async move {
+					let created_label = args.exec(&library).await?;
+
+					invalidate_query!(library, "labels.list");
+
+					Ok(created_label)
+				})
+		})
+		.procedure("assign", {
+			R.with2(library())
+				.mutation(|(_, library), args: LabelAssignArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let label_pub_id = find_label_pub_id(db, args.label_id).await?;
+
+					// Unlike tags, labels describe the contents of a file, so they're only assigned
+					// to file paths that were already identified
+					let objects = find_target_objects(db, args.targets).await?;
+
+					// Assigning a label twice would break the primary key of the relation
+					let assigned = db
+						.label_on_object()
+						.find_many(vec![
+							label_on_object::label_id::equals(args.label_id),
+							label_on_object::object_id::in_vec(
+								objects.iter().map(|(id, _)| *id).collect(),
+							),
+						])
+						.select(label_on_object::select!({ object_id }))
+						.exec()
+						.await?
+						.into_iter()
+						.map(|label_object| label_object.object_id)
+						.collect::<HashSet<_>>();
+
+					let (sync_ops, db_creates) = objects
+						.into_iter()
+						.filter(|(id, _)| !assigned.contains(id))
+						.fold(
+							(vec![], vec![]),
+							|(mut sync_ops, mut db_creates), (id, pub_id)| {
+								db_creates.push(label_on_object::CreateUnchecked {
+									label_id: args.label_id,
+									object_id: id,
+									_params: vec![],
+								});
+
+								sync_ops.extend(
+									sync.relation_create(sync_id(label_pub_id.clone(), pub_id), []),
+								);
+
+								(sync_ops, db_creates)
+							},
+						);
+
+					sync.write_ops(db, (sync_ops, db.label_on_object().create_many(db_creates)))
+						.await?;
+
+					invalidate_query!(library, "labels.getForObject");
+					invalidate_query!(library, "labels.getWithObjects");
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				})
+		})
+		.procedure("unassign", {
+			R.with2(library())
+				.mutation(|(_, library), args: LabelAssignArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let label_pub_id = find_label_pub_id(db, args.label_id).await?;
+
+					let objects = find_target_objects(db, args.targets).await?;
+
+					sync.write_ops(
+						db,
+						(
+							objects
+								.iter()
+								.map(|(_, pub_id)| {
+									sync.relation_delete(sync_id(
+										label_pub_id.clone(),
+										pub_id.clone(),
+									))
+								})
+								.collect(),
+							db.label_on_object().delete_many(vec![
+								label_on_object::label_id::equals(args.label_id),
+								label_on_object::object_id::in_vec(
+									objects.into_iter().map(|(id, _)| id).collect(),
+								),
+							]),
+						),
+					)
+					.await?;
+
+					invalidate_query!(library, "labels.getForObject");
+					invalidate_query!(library, "labels.getWithObjects");
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				})
+		})
+		.procedure(
+			"delete",
+			R.with2(library())
+				.mutation(|(_, library), label_id: label::id::Type| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let label = db
+						.label()
+						.find_unique(label::id::equals(label_id))
+						.select(label::select!({
+							pub_id
+							label_objects: select {
+								object: select { pub_id }
+							}
+						}))
+						.exec()
+						.await?
+						.ok_or_else(|| {
+							rspc::Error::new(ErrorCode::NotFound, "Label not found".to_string())
+						})?;
+
+					// Objects have to be unlinked first, as the relation doesn't cascade deletes
+					sync.write_ops(
+						db,
+						(
+							label
+								.label_objects
+								.into_iter()
+								.map(|label_object| {
+									sync.relation_delete(sync_id(
+										label.pub_id.clone(),
+										label_object.object.pub_id,
+									))
+								})
+								.collect(),
+							db.label_on_object()
+								.delete_many(vec![label_on_object::label_id::equals(label_id)]),
+						),
+					)
+					.await?;
+
+					sync.write_op(
+						db,
+						sync.shared_delete(prisma_sync::label::SyncId {
+							pub_id: label.pub_id,
+						}),
+						db.label().delete(label::id::equals(label_id)),
+					)
+					.await?;
+
+					invalidate_query!(library, "labels.list");
+					invalidate_query!(library, "search.objects");
+
+					Ok(())
+				}),
+		)
+}
+
+fn sync_id(label_pub_id: Vec<u8>, object_pub_id: Vec<u8>) -> prisma_sync::label_on_object::SyncId {
+	prisma_sync::label_on_object::SyncId {
+		label: prisma_sync::label::SyncId {
+			pub_id: label_pub_id,
+		},
+		object: prisma_sync::object::SyncId {
+			pub_id: object_pub_id,
+		},
+	}
+}
+
+async fn find_label_pub_id(
+	db: &prisma::PrismaClient,
+	label_id: label::id::Type,
+) -> Result<Vec<u8>, rspc::Error> {
+	Ok(db
+		.label()
+		.find_unique(label::id::equals(label_id))
+		.select(label::select!({ pub_id }))
+		.exec()
+		.await?
+		.ok_or_else(|| rspc::Error::new(ErrorCode::NotFound, "Label not found".to_string()))?
+		.pub_id)
+}
+
+/// Ids and pub ids of the targeted objects, including the objects of the targeted file paths
+async fn find_target_objects(
+	db: &prisma::PrismaClient,
+	targets: Vec<LabelTarget>,
+) -> Result<Vec<(object::id::Type, Vec<u8>)>, rspc::Error> {
+	let (object_ids, file_path_ids): (Vec<_>, Vec<_>) =
+		targets.into_iter().partition_map(|target| match target {
+			LabelTarget::Object(id) => Either::Left(id),
+			LabelTarget::FilePath(id) => Either::Right(id),
+		});
+
+	let (objects, file_paths) = db
+		._batch((
+			db.object()
+				.find_many(vec![object::id::in_vec(object_ids)])
+				.select(object::select!({ id pub_id })),
+			db.file_path()
+				.find_many(vec![file_path::id::in_vec(file_path_ids)])
+				.select(file_path::select!({ object: select { id pub_id } })),
+		))
+		.await?;
+
+	Ok(objects
+		.into_iter()
+		.map(|o| (o.id, o.pub_id))
+		.chain(
+			file_paths
+				.into_iter()
+				.filter_map(|fp| fp.object.map(|o| (o.id, o.pub_id))),
+		)
+		.unique_by(|(id, _)| *id)
+		.collect())
+}
@@ -1,18 +1,17 @@
 use crate::{
-	library::{Library, LibraryConfig, LibraryName},
+	library::{get_library_statistics, Library, LibraryConfig, LibraryName},
 	location::{scan_location, LocationCreateArgs},
 	util::MaybeUndefined,
-	volume::get_volumes,
 	Node,
 };
 
 use sd_cache::{Model, Normalise, NormalisedResult, NormalisedResults};
 use sd_p2p::spacetunnel::RemoteIdentity;
-use sd_prisma::prisma::{indexer_rule, statistics};
+use sd_prisma::prisma::{indexer_rule, statistics, SortOrder};
 
 use std::{convert::identity, sync::Arc};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use directories::UserDirs;
 use futures_concurrency::future::Join;
 use rspc::{alpha::AlphaRouter, ErrorCode};
@@ -22,10 +21,7 @@ use tokio::spawn;
 use tracing::{debug, error};
 use uuid::Uuid;
 
-use super::{
-	utils::{get_size, library},
-	Ctx, R,
-};
+use super::{utils::library, Ctx, R};
 
 // TODO(@Oscar): Replace with `specta::json`
 #[derive(Serialize, Type)]
@@ -82,62 +78,32 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 		.procedure("statistics", {
 			R.with2(library())
 				.query(|(node, library), _: ()| async move {
-					// TODO: get from database if library is offline
-					// let _statistics = library
-					// 	.db
-					// 	.statistics()
-					// 	.find_unique(statistics::id::equals(library.node_local_id))
-					// 	.exec()
-					// 	.await?;
-
-					let volumes = get_volumes().await;
-					// save_volume(&library).await?;
-
-					let mut total_capacity: u64 = 0;
-					let mut available_capacity: u64 = 0;
-					for volume in volumes {
-						total_capacity += volume.total_capacity;
-						available_capacity += volume.available_capacity;
-					}
-
-					let library_db_size = get_size(
-						node.config
-							.data_directory()
-							.join("libraries")
-							.join(&format!("{}.db", library.id)),
-					)
-					.await
-					.unwrap_or(0);
-
-					let thumbnail_folder_size =
-						get_size(node.config.data_directory().join("thumbnails"))
-							.await
-							.unwrap_or(0);
-
-					use statistics::*;
-					let params = vec![
-						id::set(1), // Each library is a database so only one of these ever exists
-						date_captured::set(Utc::now().into()),
-						total_object_count::set(0),
-						library_db_size::set(library_db_size.to_string()),
-						total_bytes_used::set(0.to_string()),
-						total_bytes_capacity::set(total_capacity.to_string()),
-						total_unique_bytes::set(0.to_string()),
-						total_bytes_free::set(available_capacity.to_string()),
-						preview_media_bytes::set(thumbnail_folder_size.to_string()),
-					];
+					// Read from the database, so it also works for offline libraries
+					Ok(get_library_statistics(&node, &library).await?)
+				})
+		})
+		.procedure("statisticsHistory", {
+			#[derive(Deserialize, Type)]
+			pub struct StatisticsHistoryArgs {
+				#[specta(optional)]
+				since: Option<DateTime<Utc>>,
+			}
 
+			R.with2(library()).query(
+				|(_, library), StatisticsHistoryArgs { since }: StatisticsHistoryArgs| async move {
 					Ok(library
 						.db
 						.statistics()
-						.upsert(
-							statistics::id::equals(1), // Each library is a database so only one of these ever exists
-							statistics::create(params.clone()),
-							params,
+						.find_many(
+							since
+								.map(|since| vec![statistics::date_captured::gte(since.into())])
+								.unwrap_or_default(),
 						)
+						.order_by(statistics::date_captured::order(SortOrder::Asc))
 						.exec()
 						.await?)
-				})
+				},
+			)
 		})
 		.procedure("create", {
 			#[derive(Deserialize, Type, Default)]
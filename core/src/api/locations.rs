@@ -240,6 +240,7 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 				pub generate_preview_media: Option<bool>,
 				pub sync_preview_media: Option<bool>,
 				pub hidden: Option<bool>,
+				pub strict_identity: Option<bool>,
 				pub date_created: Option<DateTime<FixedOffset>>,
 				pub instance_id: Option<i32>,
 				pub indexer_rules: Vec<Reference<indexer_rule::Data>>,
@@ -268,6 +269,7 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 						generate_preview_media: value.generate_preview_media,
 						sync_preview_media: value.sync_preview_media,
 						hidden: value.hidden,
+						strict_identity: value.strict_identity,
 						date_created: value.date_created,
 						instance_id: value.instance_id,
 						indexer_rules: value
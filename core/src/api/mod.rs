@@ -15,6 +15,7 @@ use serde::{Deserialize, Serialize};
 use specta::Type;
 use uuid::Uuid;
 
+mod albums;
 mod auth;
 mod backups;
 mod cloud;
@@ -23,6 +24,7 @@ mod ephemeral_files;
 mod files;
 mod jobs;
 mod keys;
+mod labels;
 mod libraries;
 pub mod locations;
 mod nodes;
@@ -30,6 +32,7 @@ pub mod notifications;
 mod p2p;
 mod preferences;
 pub(crate) mod search;
+mod spaces;
 mod sync;
 mod tags;
 pub mod utils;
@@ -194,8 +197,11 @@ pub(crate) fn mount() -> Arc<Router> {
 		.merge("library.", libraries::mount())
 		.merge("volumes.", volumes::mount())
 		.merge("tags.", tags::mount())
+		.merge("albums.", albums::mount())
+		.merge("labels.", labels::mount())
+		.merge("spaces.", spaces::mount())
 		// .merge("categories.", categories::mount())
-		// .merge("keys.", keys::mount())
+		.merge("keys.", keys::mount())
 		.merge("locations.", locations::mount())
 		.merge("ephemeralFiles.", ephemeral_files::mount())
 		.merge("files.", files::mount())
@@ -1,4 +1,7 @@
-use crate::{invalidate_query, prisma::location, util::MaybeUndefined};
+use crate::{
+	invalidate_query, object::media::thumbnail::ThumbnailSize, prisma::location,
+	util::MaybeUndefined,
+};
 
 use sd_prisma::prisma::instance;
 
@@ -107,11 +110,17 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 			#[derive(Deserialize, Type)]
 			pub struct UpdateThumbnailerPreferences {
 				pub background_processing_percentage: u8, // 0-100
+				#[specta(optional)]
+				pub sizes: Option<Vec<ThumbnailSize>>,
+				#[specta(optional)]
+				pub quality: Option<u8>, // 0-100
 			}
 			R.mutation(
 				|node,
 				 UpdateThumbnailerPreferences {
 				     background_processing_percentage,
+				     sizes,
+				     quality,
 				 }: UpdateThumbnailerPreferences| async move {
 					node.config
 						.update_preferences(|preferences| {
@@ -120,6 +129,14 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 								.set_background_processing_percentage(
 									background_processing_percentage,
 								);
+
+							if let Some(sizes) = sizes {
+								preferences.thumbnailer.set_sizes(sizes);
+							}
+
+							if let Some(quality) = quality {
+								preferences.thumbnailer.set_quality(quality);
+							}
 						})
 						.await
 						.map_err(|e| {
@@ -5,9 +5,12 @@ use specta::Type;
 use std::path::PathBuf;
 use uuid::Uuid;
 
-use crate::p2p::{operations, P2PEvent, PairingDecision};
+use crate::{
+	p2p::{operations, P2PEvent, PairingDecision},
+	prisma::file_path,
+};
 
-use super::{Ctx, R};
+use super::{utils::library, Ctx, R};
 
 pub(crate) fn mount() -> AlphaRouter<Ctx> {
 	R.router()
@@ -77,15 +80,51 @@ pub(crate) fn mount() -> AlphaRouter<Ctx> {
 				})
 			})
 		})
+		.procedure("spacedropFilePaths", {
+			#[derive(Type, Deserialize)]
+			pub struct SpacedropFilePathsArgs {
+				identity: RemoteIdentity,
+				file_path_ids: Vec<file_path::id::Type>,
+			}
+
+			R.with2(library()).mutation(
+				|(node, library), args: SpacedropFilePathsArgs| async move {
+					let mut paths = library.get_file_paths(args.file_path_ids.clone()).await?;
+
+					// Keeping the order the files were picked in
+					let paths = args
+						.file_path_ids
+						.into_iter()
+						.map(|id| {
+							paths.remove(&id).flatten().ok_or_else(|| {
+								rspc::Error::new(
+									ErrorCode::NotFound,
+									format!("File path '{id}' not found"),
+								)
+							})
+						})
+						.collect::<Result<Vec<_>, _>>()?;
+
+					operations::spacedrop(node.p2p.clone(), args.identity, paths)
+						.await
+						.map_err(|_err| {
+							rspc::Error::new(ErrorCode::InternalServerError, "todo: error".into())
+						})
+				},
+			)
+		})
+		// `files` are the indexes of the files to accept, or all of them if `null`
 		.procedure("acceptSpacedrop", {
-			R.mutation(|node, (id, path): (Uuid, Option<String>)| async move {
-				match path {
-					Some(path) => node.p2p.accept_spacedrop(id, path).await,
-					None => node.p2p.reject_spacedrop(id).await,
-				};
+			R.mutation(
+				|node, (id, path, files): (Uuid, Option<String>, Option<Vec<u32>>)| async move {
+					match path {
+						Some(path) => node.p2p.accept_spacedrop(id, path, files).await,
+						None => node.p2p.reject_spacedrop(id).await,
+					};
 
-				Ok(())
-			})
+					Ok(())
+				},
+			)
 		})
 		.procedure("cancelSpacedrop", {
 			R.mutation(|node, id: Uuid| async move { Ok(node.p2p.cancel_spacedrop(id).await) })
@@ -8,12 +8,26 @@ use super::utils::*;
 #[serde(rename_all = "camelCase", tag = "field", content = "value")]
 pub enum MediaDataOrder {
 	EpochTime(SortOrder),
+	Duration(SortOrder),
+	Title(SortOrder),
+	Artist(SortOrder),
+	Album(SortOrder),
+	Genre(SortOrder),
+	TrackNumber(SortOrder),
+	Year(SortOrder),
 }
 
 impl MediaDataOrder {
 	pub fn get_sort_order(&self) -> prisma::SortOrder {
 		(*match self {
-			Self::EpochTime(v) => v,
+			Self::EpochTime(v)
+			| Self::Duration(v)
+			| Self::Title(v)
+			| Self::Artist(v)
+			| Self::Album(v)
+			| Self::Genre(v)
+			| Self::TrackNumber(v)
+			| Self::Year(v) => v,
 		})
 		.into()
 	}
@@ -23,6 +37,54 @@ impl MediaDataOrder {
 		use media_data::*;
 		match self {
 			Self::EpochTime(_) => epoch_time::order(dir),
+			Self::Duration(_) => duration::order(dir),
+			Self::Title(_) => title::order(dir),
+			Self::Artist(_) => artist::order(dir),
+			Self::Album(_) => album::order(dir),
+			Self::Genre(_) => genre::order(dir),
+			Self::TrackNumber(_) => track_number::order(dir),
+			Self::Year(_) => year::order(dir),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Type, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaDataFilterArgs {
+	Title(TextMatch),
+	Artist(TextMatch),
+	Album(TextMatch),
+	Genre(TextMatch),
+	Year(Range<i32>),
+}
+
+impl MediaDataFilterArgs {
+	pub fn into_params(self) -> Vec<media_data::WhereParam> {
+		use media_data::*;
+
+		macro_rules! text_match {
+			($field:ident, $value:ident) => {
+				$value
+					.into_param(
+						$field::contains,
+						$field::starts_with,
+						$field::ends_with,
+						|v| $field::equals(Some(v)),
+					)
+					.map(|v| vec![v])
+					.unwrap_or_default()
+			};
+		}
+
+		match self {
+			Self::Title(v) => text_match!(title, v),
+			Self::Artist(v) => text_match!(artist, v),
+			Self::Album(v) => text_match!(album, v),
+			Self::Genre(v) => text_match!(genre, v),
+			Self::Year(v) => vec![match v {
+				Range::From(v) => year::gte(v),
+				Range::To(v) => year::lte(v),
+			}],
 		}
 	}
 }
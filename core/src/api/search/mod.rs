@@ -12,11 +12,21 @@ use crate::{
 		utils::library,
 	},
 	library::Library,
-	location::{non_indexed, LocationError},
-	object::media::thumbnail::get_indexed_thumb_key,
+	location::{
+		archive::{self, ArchivePath},
+		non_indexed, LocationError,
+	},
+	object::{
+		content::{search_content, ContentMatch, MAX_CONTENT_MATCHES},
+		deduplicator::{self, find_duplicates},
+		media::{similar_images::find_similar_images, thumbnail::get_indexed_thumb_key},
+	},
 };
 
-use std::path::PathBuf;
+use std::{
+	collections::{HashMap, HashSet},
+	path::PathBuf,
+};
 
 use rspc::{alpha::AlphaRouter, ErrorCode};
 use sd_cache::{CacheNode, Model, Normalise, Reference};
@@ -27,12 +37,16 @@ use specta::Type;
 use super::{Ctx, R};
 
 const MAX_TAKE: u8 = 100;
+/// Images whose perceptual hashes differ in more bits than this are usually different shots
+const DEFAULT_MAX_IMAGE_DISTANCE: u32 = 10;
 
 #[derive(Serialize, Type, Debug)]
 struct SearchData<T: Model> {
 	cursor: Option<Vec<u8>>,
 	items: Vec<Reference<T>>,
 	nodes: Vec<CacheNode>,
+	/// The parts of the content of the returned objects that matched the content filters
+	content_matches: Vec<ContentMatch>,
 }
 
 impl<T: Model> Model for SearchData<T> {
@@ -57,7 +71,7 @@ impl SearchFilterArgs {
 	) -> Result<Vec<T>, rspc::Error> {
 		Ok(match self {
 			Self::FilePath(v) => file_path(v.into_params(db).await?),
-			Self::Object(v) => object(v.into_params()),
+			Self::Object(v) => object(v.into_params(db).await?),
 		})
 	}
 
@@ -78,6 +92,77 @@ impl SearchFilterArgs {
 	}
 }
 
+/// Takes the content filters out of `filters`, matching all of them at once, so the results can be
+/// sorted by relevance and show the parts of their content that matched
+async fn take_content_matches(
+	filters: &mut Vec<SearchFilterArgs>,
+	db: &PrismaClient,
+) -> Result<Option<Vec<ContentMatch>>, rspc::Error> {
+	let mut queries = vec![];
+
+	filters.retain(|filter| {
+		if let SearchFilterArgs::Object(ObjectFilterArgs::Content(query)) = filter {
+			queries.push(query.clone());
+			false
+		} else {
+			true
+		}
+	});
+
+	if queries.is_empty() {
+		return Ok(None);
+	}
+
+	Ok(Some(
+		search_content(db, &queries.join(" "), MAX_CONTENT_MATCHES).await?,
+	))
+}
+
+/// Sorts the items from the most to the least relevant content, keeping at most `take` of the ones
+/// ranked after the cursor item, if any. Returns whether there are more items left.
+fn rank_by_content<T>(
+	items: &mut Vec<T>,
+	content_matches: &[ContentMatch],
+	object_id: impl Fn(&T) -> Option<prisma::object::id::Type>,
+	is_cursor: impl Fn(&T) -> bool,
+	take: Option<usize>,
+) -> bool {
+	let ranks = content_matches
+		.iter()
+		.enumerate()
+		.map(|(rank, content_match)| (content_match.object_id, rank))
+		.collect::<HashMap<_, _>>();
+
+	items.sort_by_key(|item| {
+		object_id(item)
+			.and_then(|id| ranks.get(&id).copied())
+			.unwrap_or(usize::MAX)
+	});
+
+	if let Some(cursor_position) = items.iter().position(is_cursor) {
+		items.drain(..=cursor_position);
+	}
+
+	match take {
+		Some(take) if items.len() > take => {
+			items.truncate(take);
+			true
+		}
+		_ => false,
+	}
+}
+
+fn returned_content_matches(
+	content_matches: Option<Vec<ContentMatch>>,
+	object_ids: HashSet<prisma::object::id::Type>,
+) -> Vec<ContentMatch> {
+	content_matches
+		.unwrap_or_default()
+		.into_iter()
+		.filter(|content_match| object_ids.contains(&content_match.object_id))
+		.collect()
+}
+
 pub fn mount() -> AlphaRouter<Ctx> {
 	R.router()
 		.procedure("ephemeralPaths", {
@@ -113,8 +198,11 @@ pub fn mount() -> AlphaRouter<Ctx> {
 				     with_hidden_files,
 				     order,
 				 }| async move {
-					let mut paths =
-						non_indexed::walk(path, with_hidden_files, node, library).await?;
+					let mut paths = if let Some(archive_path) = ArchivePath::find(&path).await {
+						archive::walk(archive_path, with_hidden_files).await?
+					} else {
+						non_indexed::walk(path, with_hidden_files, node, library).await?
+					};
 
 					macro_rules! order_match {
 						($order:ident, [$(($variant:ident, |$i:ident| $func:expr)),+]) => {{
@@ -181,11 +269,31 @@ pub fn mount() -> AlphaRouter<Ctx> {
 				 FilePathSearchArgs {
 				     take,
 				     order_and_pagination,
-				     filters,
+				     mut filters,
 				     group_directories,
 				 }| async move {
 					let Library { db, .. } = library.as_ref();
 
+					let content_matches = take_content_matches(&mut filters, db).await?;
+					// Without an explicit order, the most relevant content comes first and the
+					// following pages continue after the last item of the previous one
+					let ranked_cursor = match (&content_matches, &order_and_pagination) {
+						(Some(_), None) => Some(None),
+						(
+							Some(_),
+							Some(file_path::OrderAndPagination::Cursor {
+								id,
+								cursor:
+									FilePathCursor {
+										variant: FilePathCursorVariant::None,
+										..
+									},
+							}),
+						) => Some(Some(*id)),
+						_ => None,
+					};
+					let ranked = ranked_cursor.is_some();
+
 					let mut query = db.file_path().find_many({
 						let mut params = Vec::new();
 
@@ -193,10 +301,21 @@ pub fn mount() -> AlphaRouter<Ctx> {
 							params.extend(filter.into_file_path_params(db).await?);
 						}
 
+						if let Some(content_matches) = &content_matches {
+							params.push(prisma::file_path::object::is(vec![
+								prisma::object::id::in_vec(
+									content_matches
+										.iter()
+										.map(|content_match| content_match.object_id)
+										.collect(),
+								),
+							]));
+						}
+
 						params
 					});
 
-					if let Some(take) = take {
+					if let Some(take) = take.filter(|_| !ranked) {
 						query = query.take(take as i64);
 					}
 
@@ -207,15 +326,34 @@ pub fn mount() -> AlphaRouter<Ctx> {
 					}
 
 					// WARN: this order_by for sorting data MUST always come after the other order_by
-					if let Some(order_and_pagination) = order_and_pagination {
+					if let Some(order_and_pagination) = order_and_pagination.filter(|_| !ranked) {
 						order_and_pagination.apply(&mut query, group_directories)
 					}
 
-					let file_paths = query
+					let mut file_paths = query
 						.include(file_path_with_object::include())
 						.exec()
 						.await?;
 
+					if let (Some(cursor), Some(content_matches)) = (ranked_cursor, &content_matches)
+					{
+						rank_by_content(
+							&mut file_paths,
+							content_matches,
+							|file_path| file_path.object_id,
+							|file_path| Some(file_path.id) == cursor,
+							take.map(usize::from),
+						);
+					}
+
+					let content_matches = returned_content_matches(
+						content_matches,
+						file_paths
+							.iter()
+							.filter_map(|file_path| file_path.object_id)
+							.collect(),
+					);
+
 					let mut items = Vec::with_capacity(file_paths.len());
 
 					for file_path in file_paths {
@@ -244,6 +382,7 @@ pub fn mount() -> AlphaRouter<Ctx> {
 						items,
 						cursor: None,
 						nodes,
+						content_matches,
 					})
 				},
 			)
@@ -292,26 +431,52 @@ pub fn mount() -> AlphaRouter<Ctx> {
 				 ObjectSearchArgs {
 				     take,
 				     order_and_pagination,
-				     filters,
+				     mut filters,
 				 }| async move {
 					let Library { db, .. } = library.as_ref();
 
 					let take = take.max(MAX_TAKE);
 
-					let mut query = db
-						.object()
-						.find_many({
-							let mut params = Vec::new();
+					let content_matches = take_content_matches(&mut filters, db).await?;
+					// Without an explicit order, the most relevant content comes first and the
+					// following pages continue after the last item of the previous one
+					let ranked_cursor = match (&content_matches, &order_and_pagination) {
+						(Some(_), None) => Some(None),
+						(
+							Some(_),
+							Some(object::OrderAndPagination::Cursor {
+								id,
+								cursor: ObjectCursor::None,
+							}),
+						) => Some(Some(*id)),
+						_ => None,
+					};
+					let ranked = ranked_cursor.is_some();
 
-							for filter in filters {
-								params.extend(filter.into_object_params(db).await?);
-							}
+					let mut query = db.object().find_many({
+						let mut params = Vec::new();
 
-							params
-						})
-						.take(take as i64);
+						for filter in filters {
+							params.extend(filter.into_object_params(db).await?);
+						}
+
+						if let Some(content_matches) = &content_matches {
+							params.push(prisma::object::id::in_vec(
+								content_matches
+									.iter()
+									.map(|content_match| content_match.object_id)
+									.collect(),
+							));
+						}
+
+						params
+					});
+
+					if !ranked {
+						query = query.take(take as i64);
+					}
 
-					if let Some(order_and_pagination) = order_and_pagination {
+					if let Some(order_and_pagination) = order_and_pagination.filter(|_| !ranked) {
 						order_and_pagination.apply(&mut query);
 					}
 
@@ -321,14 +486,37 @@ pub fn mount() -> AlphaRouter<Ctx> {
 							.exec()
 							.await?;
 
-						let cursor = (objects.len() as u8 > take)
-							.then(|| objects.pop())
-							.flatten()
-							.map(|r| r.pub_id);
+						if let (Some(cursor), Some(content_matches)) =
+							(ranked_cursor, &content_matches)
+						{
+							let has_more = rank_by_content(
+								&mut objects,
+								content_matches,
+								|object| Some(object.id),
+								|object| Some(object.id) == cursor,
+								Some(take as usize),
+							);
+
+							let cursor = has_more
+								.then(|| objects.last().map(|object| object.pub_id.clone()))
+								.flatten();
+
+							(objects, cursor)
+						} else {
+							let cursor = (objects.len() as u8 > take)
+								.then(|| objects.pop())
+								.flatten()
+								.map(|r| r.pub_id);
 
-						(objects, cursor)
+							(objects, cursor)
+						}
 					};
 
+					let content_matches = returned_content_matches(
+						content_matches,
+						objects.iter().map(|object| object.id).collect(),
+					);
+
 					let mut items = Vec::with_capacity(objects.len());
 
 					for object in objects {
@@ -363,6 +551,7 @@ pub fn mount() -> AlphaRouter<Ctx> {
 						nodes,
 						items,
 						cursor,
+						content_matches,
 					})
 				},
 			)
@@ -395,5 +584,189 @@ pub fn mount() -> AlphaRouter<Ctx> {
 						.await? as u32)
 				})
 		})
+		.procedure("duplicates", {
+			#[derive(Deserialize, Type, Debug)]
+			#[serde(rename_all = "camelCase")]
+			#[specta(inline)]
+			struct Args {
+				/// Also splits the duplicates by their full checksum, leaving out the files that
+				/// weren't validated yet
+				#[serde(default)]
+				by_integrity_checksum: bool,
+				#[specta(optional)]
+				take: Option<u32>,
+			}
+
+			#[derive(Serialize, Type, Debug)]
+			struct DuplicateGroup {
+				object_id: prisma::object::id::Type,
+				integrity_checksum: Option<String>,
+				size_in_bytes: String,
+				wasted_bytes: String,
+				file_paths: Vec<Reference<prisma::file_path::Data>>,
+			}
+
+			#[derive(Serialize, Type, Debug)]
+			struct DuplicatesResult {
+				groups: Vec<DuplicateGroup>,
+				nodes: Vec<CacheNode>,
+				/// Bytes freed by keeping a single file of every group, not only the returned ones
+				reclaimable_bytes: String,
+			}
+
+			R.with2(library()).query(
+				|(_, library),
+				 Args {
+				     by_integrity_checksum,
+				     take,
+				 }| async move {
+					let groups = find_duplicates(
+						&library.db,
+						library.config().await.instance_id,
+						by_integrity_checksum,
+					)
+					.await?;
+
+					let reclaimable_bytes = groups
+						.iter()
+						.map(deduplicator::DuplicateGroup::wasted_bytes)
+						.sum::<u64>();
+
+					let mut nodes = Vec::new();
+					let groups = groups
+						.into_iter()
+						.take(take.unwrap_or(MAX_TAKE as u32) as usize)
+						.map(|group| DuplicateGroup {
+							object_id: group.object_id,
+							integrity_checksum: group.integrity_checksum.clone(),
+							size_in_bytes: group.size_in_bytes.to_string(),
+							wasted_bytes: group.wasted_bytes().to_string(),
+							file_paths: group
+								.file_paths
+								.into_iter()
+								.map(|file_path| {
+									let id = file_path.id.to_string();
+									nodes.push(CacheNode::new(id.clone(), file_path));
+									Reference::new(id)
+								})
+								.collect(),
+						})
+						.collect();
+
+					Ok(DuplicatesResult {
+						groups,
+						nodes,
+						reclaimable_bytes: reclaimable_bytes.to_string(),
+					})
+				},
+			)
+		})
+		.procedure("similarImages", {
+			#[derive(Deserialize, Type, Debug)]
+			#[serde(rename_all = "camelCase")]
+			#[specta(inline)]
+			struct Args {
+				object_id: prisma::object::id::Type,
+				/// How many of the 64 bits of the perceptual hashes can differ, `0` only finds
+				/// resized or re-encoded copies while higher values also find edited ones
+				#[specta(optional)]
+				max_distance: Option<u32>,
+				#[specta(optional)]
+				take: Option<u32>,
+			}
+
+			#[derive(Serialize, Type, Debug)]
+			struct SimilarImage {
+				distance: u32,
+				item: Reference<ExplorerItem>,
+			}
+
+			#[derive(Serialize, Type, Debug)]
+			struct SimilarImagesResult {
+				items: Vec<SimilarImage>,
+				nodes: Vec<CacheNode>,
+			}
+
+			R.with2(library()).query(
+				|(node, library),
+				 Args {
+				     object_id,
+				     max_distance,
+				     take,
+				 }| async move {
+					let Library { db, .. } = library.as_ref();
+
+					let mut similar = find_similar_images(
+						db,
+						object_id,
+						max_distance.unwrap_or(DEFAULT_MAX_IMAGE_DISTANCE),
+					)
+					.await?
+					.ok_or_else(|| {
+						rspc::Error::new(
+							ErrorCode::NotFound,
+							"Image not found or without a perceptual hash yet".to_string(),
+						)
+					})?;
+
+					similar.truncate(take.unwrap_or(MAX_TAKE as u32) as usize);
+
+					let mut objects = db
+						.object()
+						.find_many(vec![prisma::object::id::in_vec(
+							similar.iter().map(|(id, _)| *id).collect(),
+						)])
+						.include(object_with_file_paths::include())
+						.exec()
+						.await?;
+
+					// Keeping the order of the most similar images first
+					let mut distances = Vec::with_capacity(similar.len());
+					let mut items = Vec::with_capacity(similar.len());
+
+					for (id, distance) in similar {
+						let Some(idx) = objects.iter().position(|object| object.id == id) else {
+							continue;
+						};
+						let object = objects.swap_remove(idx);
+
+						let cas_id = object
+							.file_paths
+							.iter()
+							.find_map(|file_path| file_path.cas_id.as_ref());
+
+						let thumbnail_exists_locally = if let Some(cas_id) = cas_id {
+							library.thumbnail_exists(&node, cas_id).await.map_err(|e| {
+								rspc::Error::with_cause(
+									ErrorCode::InternalServerError,
+									"Failed to check that thumbnail exists".to_string(),
+									e,
+								)
+							})?
+						} else {
+							false
+						};
+
+						distances.push(distance);
+						items.push(ExplorerItem::Object {
+							has_local_thumbnail: thumbnail_exists_locally,
+							thumbnail_key: cas_id.map(|i| get_indexed_thumb_key(i, library.id)),
+							item: object,
+						});
+					}
+
+					let (nodes, items) = items.normalise(|item| item.id());
+
+					Ok(SimilarImagesResult {
+						items: distances
+							.into_iter()
+							.zip(items)
+							.map(|(distance, item)| SimilarImage { distance, item })
+							.collect(),
+						nodes,
+					})
+				},
+			)
+		})
 		.merge("saved.", saved::mount())
 }
@@ -1,10 +1,12 @@
 use chrono::{DateTime, FixedOffset};
 use prisma_client_rust::not;
 use prisma_client_rust::{or, OrderByQuery, PaginatedQuery, WhereQuery};
-use sd_prisma::prisma::{self, object, tag_on_object};
+use sd_prisma::prisma::{self, label_on_object, object, object_in_album, tag_on_object};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
+use crate::object::content::{search_content, MAX_CONTENT_MATCHES};
+
 // use crate::library::Category;
 
 use super::media_data::*;
@@ -111,14 +113,22 @@ pub enum ObjectFilterArgs {
 	Hidden(ObjectHiddenFilter),
 	Kind(InOrNotIn<i32>),
 	Tags(InOrNotIn<i32>),
+	Labels(InOrNotIn<i32>),
+	Albums(InOrNotIn<i32>),
 	DateAccessed(Range<chrono::DateTime<FixedOffset>>),
+	MediaData(MediaDataFilterArgs),
+	/// Objects whose indexed text has every word of the query
+	Content(String),
 }
 
 impl ObjectFilterArgs {
-	pub fn into_params(self) -> Vec<object::WhereParam> {
+	pub async fn into_params(
+		self,
+		db: &prisma::PrismaClient,
+	) -> Result<Vec<object::WhereParam>, rspc::Error> {
 		use object::*;
 
-		match self {
+		Ok(match self {
 			Self::Favorite(v) => vec![favorite::equals(Some(v))],
 			Self::Hidden(v) => v.to_param().map(|v| vec![v]).unwrap_or_default(),
 			Self::Tags(v) => v
@@ -128,6 +138,20 @@ impl ObjectFilterArgs {
 				)
 				.map(|v| vec![v])
 				.unwrap_or_default(),
+			Self::Labels(v) => v
+				.into_param(
+					|v| labels::some(vec![label_on_object::label_id::in_vec(v)]),
+					|v| labels::none(vec![label_on_object::label_id::in_vec(v)]),
+				)
+				.map(|v| vec![v])
+				.unwrap_or_default(),
+			Self::Albums(v) => v
+				.into_param(
+					|v| albums::some(vec![object_in_album::album_id::in_vec(v)]),
+					|v| albums::none(vec![object_in_album::album_id::in_vec(v)]),
+				)
+				.map(|v| vec![v])
+				.unwrap_or_default(),
 			Self::Kind(v) => v
 				.into_param(kind::in_vec, kind::not_in_vec)
 				.map(|v| vec![v])
@@ -141,7 +165,15 @@ impl ObjectFilterArgs {
 					},
 				]
 			}
-		}
+			Self::MediaData(v) => vec![media_data::is(v.into_params())],
+			Self::Content(query) => vec![id::in_vec(
+				search_content(db, &query, MAX_CONTENT_MATCHES)
+					.await?
+					.into_iter()
+					.map(|content_match| content_match.object_id)
+					.collect(),
+			)],
+		})
 	}
 }
 
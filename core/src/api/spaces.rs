@@ -0,0 +1,450 @@
+use chrono::Utc;
+use itertools::Itertools;
+use rspc::{alpha::AlphaRouter, ErrorCode};
+use sd_cache::{Normalise, NormalisedResult, NormalisedResults};
+use sd_prisma::{prisma, prisma_sync};
+use sd_sync::OperationFactory;
+use serde::Deserialize;
+use serde_json::json;
+use specta::Type;
+
+use crate::{
+	invalidate_query,
+	job::Job,
+	library::Library,
+	object::space::{exporter::SpaceExporterJobInit, SpaceCreateArgs},
+	prisma::{object, object_in_space, space, SortOrder},
+};
+
+use super::{utils::library, Ctx, R};
+
+#[derive(Debug, Type, Deserialize)]
+#[specta(inline)]
+struct SpaceObjectsArgs {
+	space_id: space::id::Type,
+	object_ids: Vec<object::id::Type>,
+}
+
+pub(crate) fn mount() -> AlphaRouter<Ctx> {
+	R.router()
+		.procedure("list", {
+			R.with2(library()).query(|(_, library), _: ()| async move {
+				let spaces = library.db.space().find_many(vec![]).exec().await?;
+
+				let (nodes, items) = spaces.normalise(|i| i.id.to_string());
+
+				Ok(NormalisedResults { nodes, items })
+			})
+		})
+		.procedure("get", {
+			R.with2(library())
+				.query(|(_, library), space_id: space::id::Type| async move {
+					Ok(library
+						.db
+						.space()
+						.find_unique(space::id::equals(space_id))
+						.exec()
+						.await?
+						.map(|space| NormalisedResult::from(space, |i| i.id.to_string())))
+				})
+		})
+		.procedure("getObjects", {
+			R.with2(library())
+				.query(|(_, library), space_id: space::id::Type| async move {
+					// Sorted by their position in the space
+					let objects = library
+						.db
+						.object_in_space()
+						.find_many(vec![object_in_space::space_id::equals(space_id)])
+						.order_by(object_in_space::position::order(SortOrder::Asc))
+						.select(object_in_space::select!({ object }))
+						.exec()
+						.await?
+						.into_iter()
+						.map(|space_object| space_object.object)
+						.collect::<Vec<_>>();
+
+					let (nodes, items) = objects.normalise(|i| i.id.to_string());
+
+					Ok(NormalisedResults { nodes, items })
+				})
+		})
+		.procedure("create", {
+			R.with2(library())
+				.mutation(|(_, library), args: SpaceCreateArgs| async move {
+					let created_space = args.exec(&library).await?;
+
+					invalidate_query!(library, "spaces.list");
+
+					Ok(created_space)
+				})
+		})
+		.procedure("update", {
+			#[derive(Type, Deserialize)]
+			pub struct SpaceUpdateArgs {
+				pub id: space::id::Type,
+				pub name: Option<String>,
+				pub description: Option<String>,
+			}
+
+			R.with2(library())
+				.mutation(|(_, library), args: SpaceUpdateArgs| async move {
+					let Library { sync, db, .. } = library.as_ref();
+
+					let space_pub_id = find_space_pub_id(db, args.id).await?;
+
+					let (sync_params, db_params): (Vec<_>, Vec<_>) = [
+						args.name.map(|name| {
+							(
+								(space::name::NAME, json!(&name)),
+								space::name::set(Some(name)),
+							)
+						}),
+						args.description.map(|description| {
+							(
+								(space::description::NAME, json!(&description)),
+								space::description::set(Some(description)),
+							)
+						}),
+					]
+					.into_iter()
+					.flatten()
+					.unzip();
+
+					sync.write_ops(
+						db,
+						(
+							sync_params
+								.into_iter()
+								.map(|(k, v)| {
+									sync.shared_update(
+										prisma_sync::space::SyncId {
+											pub_id: space_pub_id.clone(),
+										},
+										k,
+										v,
+									)
+								})
+								.collect(),
+							db.space().update(
+								space::id::equals(args.id),
+								db_params
+									.into_iter()
+									.chain([space::date_modified::set(Some(Utc::now().into()))])
+									.collect(),
+							),
+						),
+					)
+					.await?;
+
+					invalidate_query!(library, "spaces.list");
+					invalidate_query!(library, "spaces.get");
+
+					Ok(())
+				})
+		})
+		.procedure("addObjects", {
+			R.with2(library())
+				.mutation(|(_, library), args: SpaceObjectsArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let space_pub_id = find_space_pub_id(db, args.space_id).await?;
+
+					// Adding an object twice would break the primary key of the relation
+					let objects = db
+						.object()
+						.find_many(vec![
+							object::id::in_vec(args.object_ids.clone()),
+							object::spaces::none(vec![object_in_space::space_id::equals(
+								args.space_id,
+							)]),
+						])
+						.select(object::select!({ id pub_id }))
+						.exec()
+						.await?;
+
+					// New objects go to the end of the space, in the order they were given
+					let mut next_position = db
+						.object_in_space()
+						.find_first(vec![object_in_space::space_id::equals(args.space_id)])
+						.order_by(object_in_space::position::order(SortOrder::Desc))
+						.select(object_in_space::select!({ position }))
+						.exec()
+						.await?
+						.map_or(0, |last| last.position + 1);
+
+					let date_created = Utc::now();
+
+					let (sync_ops, db_creates) = args
+						.object_ids
+						.iter()
+						.unique()
+						.filter_map(|id| objects.iter().find(|object| object.id == *id))
+						.fold(
+							(vec![], vec![]),
+							|(mut sync_ops, mut db_creates), object| {
+								db_creates.push(object_in_space::CreateUnchecked {
+									space_id: args.space_id,
+									object_id: object.id,
+									_params: vec![
+										object_in_space::position::set(next_position),
+										object_in_space::date_created::set(Some(
+											date_created.into(),
+										)),
+									],
+								});
+
+								sync_ops.extend(sync.relation_create(
+									sync_id(space_pub_id.clone(), object.pub_id.clone()),
+									[
+										(object_in_space::position::NAME, json!(next_position)),
+										(
+											object_in_space::date_created::NAME,
+											json!(date_created.to_rfc3339()),
+										),
+									],
+								));
+
+								next_position += 1;
+
+								(sync_ops, db_creates)
+							},
+						);
+
+					sync.write_ops(db, (sync_ops, db.object_in_space().create_many(db_creates)))
+						.await?;
+
+					invalidate_query!(library, "spaces.getObjects");
+
+					Ok(())
+				})
+		})
+		.procedure("removeObjects", {
+			R.with2(library())
+				.mutation(|(_, library), args: SpaceObjectsArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let space_pub_id = find_space_pub_id(db, args.space_id).await?;
+
+					let objects = db
+						.object()
+						.find_many(vec![
+							object::id::in_vec(args.object_ids),
+							object::spaces::some(vec![object_in_space::space_id::equals(
+								args.space_id,
+							)]),
+						])
+						.select(object::select!({ id pub_id }))
+						.exec()
+						.await?;
+
+					sync.write_ops(
+						db,
+						(
+							objects
+								.iter()
+								.map(|object| {
+									sync.relation_delete(sync_id(
+										space_pub_id.clone(),
+										object.pub_id.clone(),
+									))
+								})
+								.collect(),
+							db.object_in_space().delete_many(vec![
+								object_in_space::space_id::equals(args.space_id),
+								object_in_space::object_id::in_vec(
+									objects.into_iter().map(|object| object.id).collect(),
+								),
+							]),
+						),
+					)
+					.await?;
+
+					invalidate_query!(library, "spaces.getObjects");
+
+					Ok(())
+				})
+		})
+		.procedure("reorder", {
+			R.with2(library())
+				.mutation(|(_, library), args: SpaceObjectsArgs| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let space_pub_id = find_space_pub_id(db, args.space_id).await?;
+
+					let space_objects = db
+						.object_in_space()
+						.find_many(vec![object_in_space::space_id::equals(args.space_id)])
+						.order_by(object_in_space::position::order(SortOrder::Asc))
+						.select(object_in_space::select!({
+							object_id
+							position
+							object: select { pub_id }
+						}))
+						.exec()
+						.await?;
+
+					let new_order = new_order(
+						&args.object_ids,
+						&space_objects
+							.iter()
+							.map(|space_object| space_object.object_id)
+							.collect::<Vec<_>>(),
+					);
+
+					let (sync_ops, db_updates): (Vec<_>, Vec<_>) = new_order
+						.into_iter()
+						.zip(0..)
+						.filter_map(|(object_id, position)| {
+							space_objects
+								.iter()
+								.find(|space_object| space_object.object_id == object_id)
+								.filter(|space_object| space_object.position != position)
+								.map(|space_object| {
+									(
+										sync.relation_update(
+											sync_id(
+												space_pub_id.clone(),
+												space_object.object.pub_id.clone(),
+											),
+											object_in_space::position::NAME,
+											json!(position),
+										),
+										db.object_in_space().update(
+											object_in_space::space_id_object_id(
+												args.space_id,
+												object_id,
+											),
+											vec![object_in_space::position::set(position)],
+										),
+									)
+								})
+						})
+						.unzip();
+
+					sync.write_ops(db, (sync_ops, db_updates)).await?;
+
+					invalidate_query!(library, "spaces.getObjects");
+
+					Ok(())
+				})
+		})
+		.procedure(
+			"delete",
+			R.with2(library())
+				.mutation(|(_, library), space_id: space::id::Type| async move {
+					let Library { db, sync, .. } = library.as_ref();
+
+					let space = db
+						.space()
+						.find_unique(space::id::equals(space_id))
+						.select(space::select!({
+							pub_id
+							objects: select {
+								object: select { pub_id }
+							}
+						}))
+						.exec()
+						.await?
+						.ok_or_else(space_not_found)?;
+
+					// Objects have to be removed first, as the relation doesn't cascade deletes
+					sync.write_ops(
+						db,
+						(
+							space
+								.objects
+								.into_iter()
+								.map(|space_object| {
+									sync.relation_delete(sync_id(
+										space.pub_id.clone(),
+										space_object.object.pub_id,
+									))
+								})
+								.collect(),
+							db.object_in_space()
+								.delete_many(vec![object_in_space::space_id::equals(space_id)]),
+						),
+					)
+					.await?;
+
+					sync.write_op(
+						db,
+						sync.shared_delete(prisma_sync::space::SyncId {
+							pub_id: space.pub_id,
+						}),
+						db.space().delete(space::id::equals(space_id)),
+					)
+					.await?;
+
+					invalidate_query!(library, "spaces.list");
+
+					Ok(())
+				}),
+		)
+		.procedure("export", {
+			R.with2(library())
+				.mutation(|(node, library), args: SpaceExporterJobInit| async move {
+					Job::new(args)
+						.spawn(&node, &library)
+						.await
+						.map_err(Into::into)
+				})
+		})
+}
+
+/// Moves the given objects to the start of the space, in the given order, followed by the
+/// remaining ones in their current order
+fn new_order(
+	object_ids: &[object::id::Type],
+	current_order: &[object::id::Type],
+) -> Vec<object::id::Type> {
+	object_ids
+		.iter()
+		.filter(|id| current_order.contains(id))
+		.chain(current_order.iter().filter(|id| !object_ids.contains(id)))
+		.unique()
+		.copied()
+		.collect()
+}
+
+fn sync_id(space_pub_id: Vec<u8>, object_pub_id: Vec<u8>) -> prisma_sync::object_in_space::SyncId {
+	prisma_sync::object_in_space::SyncId {
+		space: prisma_sync::space::SyncId {
+			pub_id: space_pub_id,
+		},
+		object: prisma_sync::object::SyncId {
+			pub_id: object_pub_id,
+		},
+	}
+}
+
+fn space_not_found() -> rspc::Error {
+	rspc::Error::new(ErrorCode::NotFound, "Space not found".to_string())
+}
+
+async fn find_space_pub_id(
+	db: &prisma::PrismaClient,
+	space_id: space::id::Type,
+) -> Result<Vec<u8>, rspc::Error> {
+	Ok(db
+		.space()
+		.find_unique(space::id::equals(space_id))
+		.select(space::select!({ pub_id }))
+		.exec()
+		.await?
+		.ok_or_else(space_not_found)?
+		.pub_id)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reordering_moves_given_objects_first() {
+		assert_eq!(new_order(&[3, 1], &[1, 2, 3, 4]), vec![3, 1, 2, 4]);
+		// Objects that aren't in the space are ignored
+		assert_eq!(new_order(&[5, 2, 2], &[1, 2, 3]), vec![2, 1, 3]);
+	}
+}
@@ -1,7 +1,9 @@
 use super::Library;
+use crate::object::album::refresh_ingested_smart_album;
 use sd_core_sync::GetOpsArgs;
 use std::sync::Arc;
 use tokio::sync::Notify;
+use tracing::error;
 use uuid::Uuid;
 
 pub async fn run_actor(library: Arc<Library>, notify: Arc<Notify>) {
@@ -24,7 +26,12 @@ pub async fn run_actor(library: Arc<Library>, notify: Arc<Notify>) {
 			let timestamps = match req {
 				Request::FinishedIngesting => break,
 				Request::Messages { timestamps } => timestamps,
-				_ => continue,
+				Request::Ingested(op) => {
+					if let Err(e) = refresh_ingested_smart_album(&library, op).await {
+						error!("Failed to refresh an ingested smart album: {e:#?}");
+					}
+					continue;
+				}
 			};
 
 			let ops = sync
@@ -1,8 +1,14 @@
 use crate::{
 	api::{utils::InvalidateOperationEvent, CoreEvent},
 	library::Library,
-	location::file_path_helper::{file_path_to_handle_custom_uri, IsolatedFilePathData},
-	object::media::thumbnail::WEBP_EXTENSION,
+	location::{
+		archive::ArchivePath,
+		file_path_helper::{file_path_to_handle_custom_uri, IsolatedFilePathData},
+	},
+	object::{
+		fs::archive::ArchiveError,
+		media::thumbnail::{find_nearest_thumbnail, ThumbnailSize, WEBP_EXTENSION},
+	},
 	p2p::{operations, IdentityOrRemoteIdentity},
 	prisma::{file_path, location},
 	util::{db::*, InfallibleResponse},
@@ -18,6 +24,7 @@ use std::{
 	path::{Path, PathBuf},
 	str::FromStr,
 	sync::{atomic::Ordering, Arc},
+	time::{Duration, SystemTime},
 };
 
 use async_stream::stream;
@@ -34,6 +41,8 @@ use bytes::Bytes;
 use mini_moka::sync::Cache;
 use sd_file_ext::text::is_text;
 use sd_p2p::{spaceblock::Range, spacetunnel::RemoteIdentity};
+use serde::Deserialize;
+use tempfile::TempPath;
 use tokio::{
 	fs::{self, File},
 	io::{AsyncReadExt, AsyncSeekExt},
@@ -49,8 +58,13 @@ mod mpsc_to_async_write;
 mod serve_file;
 mod utils;
 
+pub mod webdav;
+
 type CacheKey = (Uuid, file_path::id::Type);
 
+/// The archive, its last modification time and the entry inside of it
+type ArchiveEntryCacheKey = (PathBuf, SystemTime, String);
+
 #[derive(Debug, Clone)]
 struct CacheValue {
 	name: PathBuf,
@@ -60,6 +74,8 @@ struct CacheValue {
 }
 
 const MAX_TEXT_READ_LENGTH: usize = 10 * 1024; // 10KB
+const ARCHIVE_ENTRIES_CACHE_CAPACITY: u64 = 16;
+const ARCHIVE_ENTRIES_CACHE_TTI: Duration = Duration::from_secs(5 * 60);
 
 #[derive(Debug, Clone)]
 pub enum ServeFrom {
@@ -77,10 +93,20 @@ struct LocalState {
 	// The main advantage of this LRU Cache is for video files. Video files are fetch in multiple chunks and the cache prevents a DB lookup on every chunk reducing the request time from 15-25ms to 1-10ms.
 	// TODO: We should listen to events when deleting or moving a location and evict the cache accordingly.
 	file_metadata_cache: Arc<Cache<CacheKey, CacheValue>>,
+
+	// Media players fetch files in many ranges, so we keep entries extracted from archives around
+	// instead of decompressing them again on every request. Evicted entries are removed from disk.
+	archive_entries_cache: Arc<Cache<ArchiveEntryCacheKey, Arc<TempPath>>>,
 }
 
 type ExtractedPath = extract::Path<(String, String, String)>;
 
+#[derive(Deserialize)]
+struct ThumbnailQuery {
+	/// Defaults to the medium size, and falls back to the nearest generated size
+	size: Option<ThumbnailSize>,
+}
+
 async fn get_or_init_lru_entry(
 	state: &LocalState,
 	extract::Path((lib_id, loc_id, path_id)): ExtractedPath,
@@ -154,6 +180,7 @@ pub fn router(node: Arc<Node>) -> Router<()> {
 			get(
 				|State(state): State<LocalState>,
 				 extract::Path(path): extract::Path<String>,
+				 extract::Query(ThumbnailQuery { size }): extract::Query<ThumbnailQuery>,
 				 request: Request<Body>| async move {
 					let thumbnail_path = state.node.config.data_directory().join("thumbnails");
 					let path = thumbnail_path.join(path);
@@ -165,6 +192,22 @@ pub fn router(node: Arc<Node>) -> Router<()> {
 					.then_some(())
 					.ok_or_else(|| not_found(()))?;
 
+					// The requested path is `<shard>/<cas_id>.webp`, but each size has its own file
+					let (Some(shard_dir), Some(cas_id)) =
+						(path.parent(), path.file_stem().and_then(OsStr::to_str))
+					else {
+						return Err(not_found(()));
+					};
+
+					let path = find_nearest_thumbnail(
+						shard_dir,
+						cas_id,
+						size.unwrap_or(ThumbnailSize::Medium),
+					)
+					.await
+					.map_err(internal_server_error)?
+					.ok_or_else(|| not_found(()))?;
+
 					let file = File::open(&path).await.map_err(|err| {
 						InfallibleResponse::builder()
 							.status(if err.kind() == io::ErrorKind::NotFound {
@@ -257,6 +300,7 @@ pub fn router(node: Arc<Node>) -> Router<()> {
 									tokio::spawn(async move {
 										let Ok(()) = operations::request_file(
 											stream,
+											&identity,
 											&library,
 											file_path_pub_id,
 											Range::Full,
@@ -287,23 +331,76 @@ pub fn router(node: Arc<Node>) -> Router<()> {
 		.route(
 			"/local-file-by-path/:path",
 			get(
-				|extract::Path(path): extract::Path<String>, request: Request<Body>| async move {
+				|State(state): State<LocalState>,
+				 extract::Path(path): extract::Path<String>,
+				 request: Request<Body>| async move {
 					let path = PathBuf::from(path);
 
-					let metadata = fs::metadata(&path).await.map_err(internal_server_error)?;
-					(!metadata.is_dir())
-						.then_some(())
-						.ok_or_else(|| not_found(()))?;
+					let (mut file, metadata) = match fs::metadata(&path).await {
+						Ok(metadata) => {
+							(!metadata.is_dir())
+								.then_some(())
+								.ok_or_else(|| not_found(()))?;
 
-					let mut file = File::open(&path).await.map_err(|err| {
-						InfallibleResponse::builder()
-							.status(if err.kind() == io::ErrorKind::NotFound {
-								StatusCode::NOT_FOUND
-							} else {
-								StatusCode::INTERNAL_SERVER_ERROR
-							})
-							.body(body::boxed(Full::from("")))
-					})?;
+							let file = File::open(&path).await.map_err(|err| {
+								InfallibleResponse::builder()
+									.status(if err.kind() == io::ErrorKind::NotFound {
+										StatusCode::NOT_FOUND
+									} else {
+										StatusCode::INTERNAL_SERVER_ERROR
+									})
+									.body(body::boxed(Full::from("")))
+							})?;
+
+							(file, metadata)
+						}
+						// Paths that don't exist on disk may point to an entry inside of an archive
+						Err(err) if err.kind() == io::ErrorKind::NotFound => {
+							let archive_path = ArchivePath::find(&path)
+								.await
+								.ok_or_else(|| not_found(()))?;
+
+							let archive_modified = fs::metadata(&archive_path.archive_path)
+								.await
+								.and_then(|metadata| metadata.modified())
+								.map_err(internal_server_error)?;
+
+							let cache_key = (
+								archive_path.archive_path.clone(),
+								archive_modified,
+								archive_path.inner_path.clone(),
+							);
+
+							let temp_path = match state.archive_entries_cache.get(&cache_key) {
+								Some(temp_path) => temp_path,
+								None => {
+									let temp_path = Arc::new(
+										archive_path.extract_to_temp_path().await.map_err(
+											|err| match err {
+												ArchiveError::EntryNotFound(_, _) => not_found(err),
+												err => internal_server_error(err),
+											},
+										)?,
+									);
+
+									state
+										.archive_entries_cache
+										.insert(cache_key, temp_path.clone());
+
+									temp_path
+								}
+							};
+
+							let file = File::open(&*temp_path)
+								.await
+								.map_err(internal_server_error)?;
+
+							let metadata = file.metadata().await.map_err(internal_server_error)?;
+
+							(file, metadata)
+						}
+						Err(err) => return Err(internal_server_error(err)),
+					};
 
 					let resp = InfallibleResponse::builder().header(
 						"Content-Type",
@@ -352,6 +449,12 @@ pub fn router(node: Arc<Node>) -> Router<()> {
 			LocalState {
 				node,
 				file_metadata_cache,
+				archive_entries_cache: Arc::new(
+					Cache::builder()
+						.max_capacity(ARCHIVE_ENTRIES_CACHE_CAPACITY)
+						.time_to_idle(ARCHIVE_ENTRIES_CACHE_TTI)
+						.build(),
+				),
 			}
 		})
 }
@@ -0,0 +1,849 @@
+//! WebDAV access to the locations of every library, so they can be mounted by file managers,
+//! rclone or office suites.
+//!
+//! Resources are addressed as `/<library_id>/<location_id>/<path inside the location>`, where the
+//! root lists the libraries and each library lists its locations. Only compliance class 1 is
+//! implemented, so there is no locking.
+
+use crate::{
+	job::{Job, JobManagerError, StatefulJob},
+	library::Library,
+	location::{
+		file_path_helper::loose_find_existing_file_path_params, find_location, light_scan_location,
+		location_with_indexer_rules,
+	},
+	object::fs::{copy::FileCopierJobInit, cut::FileCutterJobInit, delete::FileDeleterJobInit},
+	prisma::{file_path, location},
+	util::InfallibleResponse,
+	Node,
+};
+
+use std::{
+	ffi::OsStr,
+	fs::Metadata,
+	io,
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::Arc,
+	time::Duration,
+};
+
+use async_recursion::async_recursion;
+use axum::{
+	body::{self, Body, BoxBody, Full},
+	extract::{OriginalUri, State},
+	http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode},
+	routing::any,
+	Router,
+};
+use http_body::Body as _;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use tokio::{
+	fs::{self, File},
+	io::AsyncWriteExt,
+	time::sleep,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use self::propfind::{multistatus, Entry};
+use super::{infer_the_mime_type, serve_file::serve_file, utils::*};
+
+mod propfind;
+
+const ALLOWED_METHODS: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, DELETE, COPY, MOVE";
+const READ_ONLY_METHODS: &str = "OPTIONS, PROPFIND, GET, HEAD";
+
+/// Characters left as is on the hrefs we send, everything else is percent-encoded
+const HREF_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+	.remove(b'-')
+	.remove(b'_')
+	.remove(b'.')
+	.remove(b'~');
+
+/// How often we check if a job dispatched by a request is done
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+struct WebDavState {
+	node: Arc<Node>,
+	read_only: bool,
+}
+
+/// The router can be nested anywhere, hrefs are built from the path it was mounted on.
+///
+/// When `read_only` is set, every method that changes files is rejected.
+pub fn router(node: Arc<Node>, read_only: bool) -> Router<()> {
+	Router::new()
+		.route("/", any(handle))
+		.route("/*path", any(handle))
+		.with_state(WebDavState { node, read_only })
+}
+
+async fn handle(
+	State(WebDavState { node, read_only }): State<WebDavState>,
+	OriginalUri(original_uri): OriginalUri,
+	request: Request<Body>,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let base = base_path(original_uri.path(), request.uri().path());
+	let segments = decode_segments(request.uri().path()).ok_or_else(|| bad_request(()))?;
+
+	let allowed_methods = if read_only {
+		READ_ONLY_METHODS
+	} else {
+		ALLOWED_METHODS
+	};
+
+	if request.method() == Method::OPTIONS {
+		return Ok(InfallibleResponse::builder()
+			.header("DAV", HeaderValue::from_static("1"))
+			.header("Allow", HeaderValue::from_static(allowed_methods))
+			// Makes Microsoft clients use WebDAV instead of FrontPage extensions
+			.header("MS-Author-Via", HeaderValue::from_static("DAV"))
+			.body(body::boxed(Full::from(""))));
+	}
+
+	if read_only && !matches!(request.method().as_str(), "PROPFIND" | "GET" | "HEAD") {
+		return Err(method_not_allowed(allowed_methods));
+	}
+
+	let resource = Resource::resolve(&node, &segments).await?;
+
+	match request.method().as_str() {
+		"PROPFIND" => propfind(&node, resource, &base, &segments, request.headers()).await,
+		"GET" | "HEAD" => get(resource, request).await,
+		"PUT" => put(&node, resource, request.into_body()).await,
+		"MKCOL" => mkcol(&node, resource).await,
+		"DELETE" => delete(&node, resource).await,
+		"COPY" => transfer(&node, resource, &base, request.headers(), Transfer::Copy).await,
+		"MOVE" => transfer(&node, resource, &base, request.headers(), Transfer::Move).await,
+		_ => Err(method_not_allowed(allowed_methods)),
+	}
+}
+
+enum Resource {
+	Root,
+	Library(Arc<Library>),
+	Location(LocationPath),
+}
+
+struct LocationPath {
+	library: Arc<Library>,
+	location: location_with_indexer_rules::Data,
+	location_path: PathBuf,
+	/// Full path on disk, equal to `location_path` at the root of the location
+	path: PathBuf,
+}
+
+impl Resource {
+	async fn resolve(node: &Node, segments: &[String]) -> Result<Self, Response<BoxBody>> {
+		let Some((library_id, segments)) = segments.split_first() else {
+			return Ok(Self::Root);
+		};
+
+		let library = match Uuid::from_str(library_id) {
+			Ok(library_id) => node.libraries.get_library(&library_id).await,
+			Err(_) => None,
+		}
+		.ok_or_else(|| not_found(()))?;
+
+		let Some((location_id, segments)) = segments.split_first() else {
+			return Ok(Self::Library(library));
+		};
+
+		let instance_id = library.config().await.instance_id;
+
+		// Only the locations of this node can be served, the others are on another device
+		let location = find_location(
+			&library,
+			location_id
+				.parse::<location::id::Type>()
+				.map_err(not_found)?,
+		)
+		.include(location_with_indexer_rules::include())
+		.exec()
+		.await
+		.map_err(internal_server_error)?
+		.filter(|location| location.instance_id == Some(instance_id))
+		.ok_or_else(|| not_found(()))?;
+
+		let location_path = PathBuf::from(
+			location
+				.path
+				.as_ref()
+				.ok_or_else(|| internal_server_error("location without a path"))?,
+		);
+		let path = segments
+			.iter()
+			.fold(location_path.clone(), |path, segment| path.join(segment));
+
+		Ok(Self::Location(LocationPath {
+			library,
+			location,
+			location_path,
+			path,
+		}))
+	}
+
+	/// Resources that can be created, modified or removed: anything inside of a location, but
+	/// not the libraries, the locations themselves nor the root
+	fn into_entry(self) -> Result<LocationPath, Response<BoxBody>> {
+		match self {
+			Self::Location(location_path) if !location_path.is_root() => Ok(location_path),
+			_ => Err(status(StatusCode::FORBIDDEN)),
+		}
+	}
+}
+
+impl LocationPath {
+	fn is_root(&self) -> bool {
+		self.path == self.location_path
+	}
+
+	fn parent(&self) -> &Path {
+		self.path.parent().unwrap_or(&self.location_path)
+	}
+
+	async fn metadata(&self) -> Result<Option<Metadata>, Response<BoxBody>> {
+		match fs::metadata(&self.path).await {
+			Ok(metadata) => Ok(Some(metadata)),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(io_error(e)),
+		}
+	}
+
+	/// RFC 4918 wants a 409 when creating a resource whose parent collection doesn't exist
+	async fn ensure_parent_exists(&self) -> Result<(), Response<BoxBody>> {
+		match fs::metadata(self.parent()).await {
+			Ok(metadata) if metadata.is_dir() => Ok(()),
+			Ok(_) => Err(status(StatusCode::CONFLICT)),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Err(status(StatusCode::CONFLICT)),
+			Err(e) => Err(io_error(e)),
+		}
+	}
+
+	async fn file_path_id(&self) -> Result<Option<file_path::id::Type>, Response<BoxBody>> {
+		Ok(self
+			.library
+			.db
+			.file_path()
+			.find_first(
+				loose_find_existing_file_path_params(
+					self.location.id,
+					&self.location_path,
+					&self.path,
+				)
+				.map_err(internal_server_error)?,
+			)
+			.select(file_path::select!({ id }))
+			.exec()
+			.await
+			.map_err(internal_server_error)?
+			.map(|file_path| file_path.id))
+	}
+
+	/// Updates the index right away, so the changes show up as soon as the request is answered
+	async fn rescan_parent(&self, node: &Arc<Node>) {
+		if let Err(e) = light_scan_location(
+			node.clone(),
+			self.library.clone(),
+			self.location.clone(),
+			self.parent(),
+		)
+		.await
+		{
+			error!("light scan error after a WebDAV request: {e:#?}");
+		}
+	}
+}
+
+async fn propfind(
+	node: &Node,
+	resource: Resource,
+	base: &str,
+	segments: &[String],
+	headers: &HeaderMap,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	// An infinite depth is answered as a depth of 1, as walking a whole location would be way too slow
+	let list_children = headers
+		.get("Depth")
+		.map_or(true, |depth| depth.as_bytes() != b"0");
+
+	let mut entries = vec![];
+
+	match resource {
+		Resource::Root => {
+			entries.push(Entry {
+				href: href(base, segments, true),
+				display_name: "Spacedrive".to_string(),
+				metadata: None,
+			});
+
+			if list_children {
+				for library in node.libraries.get_all().await {
+					entries.push(Entry {
+						href: href(base, &[library.id.to_string()], true),
+						display_name: library.config().await.name.to_string(),
+						metadata: None,
+					});
+				}
+			}
+		}
+		Resource::Library(library) => {
+			let config = library.config().await;
+
+			entries.push(Entry {
+				href: href(base, segments, true),
+				display_name: config.name.to_string(),
+				metadata: None,
+			});
+
+			if list_children {
+				for location in library
+					.db
+					.location()
+					.find_many(vec![location::instance_id::equals(Some(
+						config.instance_id,
+					))])
+					.select(location::select!({ id name }))
+					.exec()
+					.await
+					.map_err(internal_server_error)?
+				{
+					entries.push(Entry {
+						href: href(base, &child_segments(segments, location.id), true),
+						display_name: location.name.unwrap_or_else(|| location.id.to_string()),
+						metadata: None,
+					});
+				}
+			}
+		}
+		Resource::Location(location_path) => {
+			let metadata = location_path
+				.metadata()
+				.await?
+				.ok_or_else(|| not_found(()))?;
+			let is_dir = metadata.is_dir();
+
+			entries.push(Entry {
+				href: href(base, segments, is_dir),
+				display_name: if location_path.is_root() {
+					location_path.location.name.clone()
+				} else {
+					segments.last().cloned()
+				}
+				.unwrap_or_default(),
+				metadata: Some(metadata),
+			});
+
+			if is_dir && list_children {
+				let mut read_dir = fs::read_dir(&location_path.path).await.map_err(io_error)?;
+
+				while let Some(entry) = read_dir.next_entry().await.map_err(io_error)? {
+					// Names that aren't valid UTF-8 can't be addressed on a URL
+					let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+						continue;
+					};
+
+					// Following symlinks, as we do when serving files
+					let Ok(metadata) = fs::metadata(entry.path()).await else {
+						continue;
+					};
+
+					entries.push(Entry {
+						href: href(base, &child_segments(segments, &name), metadata.is_dir()),
+						display_name: name,
+						metadata: Some(metadata),
+					});
+				}
+			}
+		}
+	}
+
+	Ok(InfallibleResponse::builder()
+		.status(StatusCode::MULTI_STATUS)
+		.header(
+			"Content-Type",
+			HeaderValue::from_static("application/xml; charset=utf-8"),
+		)
+		.body(body::boxed(Full::from(multistatus(&entries)))))
+}
+
+async fn get(
+	resource: Resource,
+	request: Request<Body>,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let Resource::Location(LocationPath { path, .. }) = resource else {
+		return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+	};
+
+	let mut file = File::open(&path).await.map_err(io_error)?;
+	let metadata = file.metadata().await.map_err(io_error)?;
+	if metadata.is_dir() {
+		return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+	}
+
+	let mime_type = match path.extension().and_then(OsStr::to_str) {
+		Some(extension) => infer_the_mime_type(extension, &mut file, &metadata)
+			.await
+			.ok(),
+		None => None,
+	}
+	.unwrap_or_else(|| "application/octet-stream".to_string());
+
+	serve_file(
+		file,
+		Ok(metadata),
+		request.into_parts().0,
+		InfallibleResponse::builder().header(
+			"Content-Type",
+			HeaderValue::from_str(&mime_type).map_err(internal_server_error)?,
+		),
+	)
+	.await
+}
+
+async fn put(
+	node: &Arc<Node>,
+	resource: Resource,
+	mut body: Body,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let target = resource.into_entry()?;
+
+	let existed = match target.metadata().await? {
+		Some(metadata) if metadata.is_dir() => {
+			return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+		}
+		Some(_) => true,
+		None => false,
+	};
+	target.ensure_parent_exists().await?;
+
+	let mut file = File::create(&target.path).await.map_err(io_error)?;
+	while let Some(chunk) = body.data().await {
+		file.write_all(&chunk.map_err(bad_request)?)
+			.await
+			.map_err(internal_server_error)?;
+	}
+	file.flush().await.map_err(internal_server_error)?;
+
+	target.rescan_parent(node).await;
+
+	Ok(status(if existed {
+		StatusCode::NO_CONTENT
+	} else {
+		StatusCode::CREATED
+	}))
+}
+
+async fn mkcol(
+	node: &Arc<Node>,
+	resource: Resource,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let target = resource.into_entry()?;
+
+	if target.metadata().await?.is_some() {
+		return Err(status(StatusCode::METHOD_NOT_ALLOWED));
+	}
+	target.ensure_parent_exists().await?;
+
+	fs::create_dir(&target.path).await.map_err(io_error)?;
+
+	target.rescan_parent(node).await;
+
+	Ok(status(StatusCode::CREATED))
+}
+
+async fn delete(
+	node: &Arc<Node>,
+	resource: Resource,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let target = resource.into_entry()?;
+	let metadata = target.metadata().await?.ok_or_else(|| not_found(()))?;
+
+	match target.file_path_id().await? {
+		Some(file_path_id) => {
+			run_job(
+				node,
+				&target.library,
+				FileDeleterJobInit {
+					location_id: target.location.id,
+					file_path_ids: vec![file_path_id],
+				},
+			)
+			.await?;
+
+			if target.metadata().await?.is_some() {
+				return Err(internal_server_error(
+					"the deleter job didn't remove the file",
+				));
+			}
+		}
+		None => remove(&target.path, &metadata).await.map_err(io_error)?,
+	}
+
+	target.rescan_parent(node).await;
+
+	Ok(status(StatusCode::NO_CONTENT))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+	Copy,
+	Move,
+}
+
+async fn transfer(
+	node: &Arc<Node>,
+	resource: Resource,
+	base: &str,
+	headers: &HeaderMap,
+	transfer: Transfer,
+) -> Result<Response<BoxBody>, Response<BoxBody>> {
+	let source = resource.into_entry()?;
+	let destination = resolve_destination(node, base, headers).await?;
+
+	// Files can be copied or moved between the locations of a library, but not between libraries.
+	// The source and destination also can't be inside of each other.
+	if destination.library.id != source.library.id
+		|| destination.path.starts_with(&source.path)
+		|| source.path.starts_with(&destination.path)
+	{
+		return Err(status(StatusCode::FORBIDDEN));
+	}
+
+	let source_metadata = source.metadata().await?.ok_or_else(|| not_found(()))?;
+
+	let overwrite = headers
+		.get("Overwrite")
+		.map_or(true, |overwrite| overwrite.as_bytes() != b"F");
+
+	// An overwritten destination is only moved aside, so it can be restored if the transfer fails
+	let overwritten = match destination.metadata().await? {
+		Some(_) if !overwrite => return Err(status(StatusCode::PRECONDITION_FAILED)),
+		Some(metadata) => {
+			let aside = destination.parent().join(format!(
+				".{}.{}.sd-overwritten",
+				destination
+					.path
+					.file_name()
+					.unwrap_or_default()
+					.to_string_lossy(),
+				Uuid::new_v4()
+			));
+			fs::rename(&destination.path, &aside)
+				.await
+				.map_err(io_error)?;
+			Some((aside, metadata))
+		}
+		None => None,
+	};
+
+	let result = async {
+		destination.ensure_parent_exists().await?;
+		transfer_entry(node, &source, &destination, transfer).await
+	}
+	.await;
+
+	if let Some((aside, metadata)) = &overwritten {
+		let cleanup = match &result {
+			Ok(_) => remove(aside, metadata).await,
+			Err(_) => restore(&destination.path, aside).await,
+		};
+		if let Err(e) = cleanup {
+			error!(
+				"Failed to clean up the overwritten destination <path='{}'>: {e:#?}",
+				aside.display()
+			);
+		}
+	}
+
+	// Only removed once the destination is in place, so the files are never lost
+	if result? {
+		remove(&source.path, &source_metadata)
+			.await
+			.map_err(io_error)?;
+	}
+
+	if transfer == Transfer::Move {
+		source.rescan_parent(node).await;
+	}
+	destination.rescan_parent(node).await;
+
+	Ok(status(if overwritten.is_some() {
+		StatusCode::NO_CONTENT
+	} else {
+		StatusCode::CREATED
+	}))
+}
+
+/// Creates the destination, returning if the source still has to be removed because a move had
+/// to be done as a copy
+async fn transfer_entry(
+	node: &Arc<Node>,
+	source: &LocationPath,
+	destination: &LocationPath,
+	transfer: Transfer,
+) -> Result<bool, Response<BoxBody>> {
+	// Our jobs keep the name of the files, so they can only be used when it doesn't change
+	match source.file_path_id().await? {
+		Some(file_path_id) if source.path.file_name() == destination.path.file_name() => {
+			let source_location_id = source.location.id;
+			let target_location_id = destination.location.id;
+			let sources_file_path_ids = vec![file_path_id];
+			let target_location_relative_directory_path = destination
+				.parent()
+				.strip_prefix(&destination.location_path)
+				.map_err(internal_server_error)?
+				.to_path_buf();
+
+			match transfer {
+				Transfer::Copy => {
+					run_job(
+						node,
+						&source.library,
+						FileCopierJobInit {
+							source_location_id,
+							target_location_id,
+							sources_file_path_ids,
+							target_location_relative_directory_path,
+						},
+					)
+					.await?
+				}
+				Transfer::Move => {
+					run_job(
+						node,
+						&source.library,
+						FileCutterJobInit {
+							source_location_id,
+							target_location_id,
+							sources_file_path_ids,
+							target_location_relative_directory_path,
+						},
+					)
+					.await?
+				}
+			}
+
+			if destination.metadata().await?.is_none() {
+				return Err(internal_server_error(
+					"the job didn't create the destination",
+				));
+			}
+		}
+		_ => match transfer {
+			Transfer::Copy => copy_recursively(&source.path, &destination.path)
+				.await
+				.map_err(io_error)?,
+			Transfer::Move => {
+				if let Err(e) = fs::rename(&source.path, &destination.path).await {
+					// Renaming doesn't work across file systems, which locations can be on
+					debug!("Failed to rename, copying instead: {e:#?}");
+					copy_recursively(&source.path, &destination.path)
+						.await
+						.map_err(io_error)?;
+					return Ok(true);
+				}
+			}
+		},
+	}
+
+	Ok(false)
+}
+
+/// The `Destination` header holds an absolute URL, which must point to this same server
+async fn resolve_destination(
+	node: &Node,
+	base: &str,
+	headers: &HeaderMap,
+) -> Result<LocationPath, Response<BoxBody>> {
+	let destination = headers
+		.get("Destination")
+		.and_then(|destination| destination.to_str().ok())
+		.ok_or_else(|| bad_request("missing Destination header"))?;
+
+	let path = match destination.split_once("://") {
+		Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+		None => destination,
+	};
+
+	let segments = path
+		.strip_prefix(base)
+		.filter(|path| path.is_empty() || path.starts_with('/'))
+		.and_then(decode_segments)
+		.ok_or_else(|| status(StatusCode::BAD_GATEWAY))?;
+
+	Resource::resolve(node, &segments).await?.into_entry()
+}
+
+/// Dispatches a job and waits until it's done, as clients expect the changes to be made once
+/// they get a response
+async fn run_job<SJob: StatefulJob>(
+	node: &Arc<Node>,
+	library: &Arc<Library>,
+	init: SJob,
+) -> Result<(), Response<BoxBody>> {
+	let job_hash = StatefulJob::hash(&init);
+
+	Job::new(init)
+		.spawn(node, library)
+		.await
+		.map_err(|e| match e {
+			JobManagerError::AlreadyRunningJob { .. } => status(StatusCode::CONFLICT),
+			e => internal_server_error(e),
+		})?;
+
+	while node.jobs.has_pending_job(job_hash).await {
+		sleep(JOB_POLL_INTERVAL).await;
+	}
+
+	Ok(())
+}
+
+async fn remove(path: &Path, metadata: &Metadata) -> io::Result<()> {
+	if metadata.is_dir() {
+		fs::remove_dir_all(path).await
+	} else {
+		fs::remove_file(path).await
+	}
+}
+
+/// Puts back a destination that was moved aside, removing whatever a failed transfer left there
+async fn restore(path: &Path, aside: &Path) -> io::Result<()> {
+	match fs::symlink_metadata(path).await {
+		Ok(metadata) => remove(path, &metadata).await?,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+		Err(e) => return Err(e),
+	}
+
+	fs::rename(aside, path).await
+}
+
+#[async_recursion]
+async fn copy_recursively(source: &Path, target: &Path) -> io::Result<()> {
+	if !fs::metadata(source).await?.is_dir() {
+		return fs::copy(source, target).await.map(|_| ());
+	}
+
+	fs::create_dir(target).await?;
+
+	let mut read_dir = fs::read_dir(source).await?;
+	while let Some(entry) = read_dir.next_entry().await? {
+		copy_recursively(&entry.path(), &target.join(entry.file_name())).await?;
+	}
+
+	Ok(())
+}
+
+fn method_not_allowed(allowed_methods: &'static str) -> Response<BoxBody> {
+	InfallibleResponse::builder()
+		.status(StatusCode::METHOD_NOT_ALLOWED)
+		.header("Allow", HeaderValue::from_static(allowed_methods))
+		.body(body::boxed(Full::from("")))
+}
+
+fn status(status: StatusCode) -> Response<BoxBody> {
+	InfallibleResponse::builder()
+		.status(status)
+		.body(body::boxed(Full::from("")))
+}
+
+#[track_caller]
+fn io_error(err: io::Error) -> Response<BoxBody> {
+	match err.kind() {
+		io::ErrorKind::NotFound => not_found(err),
+		io::ErrorKind::PermissionDenied => status(StatusCode::FORBIDDEN),
+		_ => internal_server_error(err),
+	}
+}
+
+/// The path the router was mounted on, without the trailing slash
+fn base_path(original_path: &str, path: &str) -> String {
+	original_path
+		.strip_suffix(path)
+		.unwrap_or(original_path)
+		.trim_end_matches('/')
+		.to_string()
+}
+
+/// Percent-decodes every segment of the path, rejecting the ones that could escape the location
+fn decode_segments(path: &str) -> Option<Vec<String>> {
+	path.split('/')
+		.filter(|segment| !segment.is_empty())
+		.map(|segment| {
+			let segment = percent_decode_str(segment).decode_utf8().ok()?;
+
+			(segment != "." && segment != ".." && !segment.contains(['/', '\\']))
+				.then(|| segment.into_owned())
+		})
+		.collect()
+}
+
+fn child_segments(segments: &[String], child: impl ToString) -> Vec<String> {
+	let mut child_segments = segments.to_vec();
+	child_segments.push(child.to_string());
+	child_segments
+}
+
+/// Collections end with a slash, as some clients rely on it to tell them apart from files
+fn href(base: &str, segments: &[String], is_collection: bool) -> String {
+	let mut href = base.to_string();
+	for segment in segments {
+		href.push('/');
+		href.extend(utf8_percent_encode(segment, HREF_SEGMENT));
+	}
+
+	if is_collection || segments.is_empty() {
+		href.push('/');
+	}
+
+	href
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn base_path_of_nested_router() {
+		assert_eq!(base_path("/dav", "/"), "/dav");
+		assert_eq!(base_path("/dav/", "/"), "/dav");
+		assert_eq!(
+			base_path("/dav/library/1/a%20b", "/library/1/a%20b"),
+			"/dav"
+		);
+	}
+
+	#[test]
+	fn rejects_traversal() {
+		assert_eq!(
+			decode_segments("/library/1/a%20b/"),
+			Some(vec![
+				"library".to_string(),
+				"1".to_string(),
+				"a b".to_string()
+			])
+		);
+		assert_eq!(decode_segments("/library/1/../../etc"), None);
+		assert_eq!(decode_segments("/library/1/%2E%2E/etc"), None);
+		assert_eq!(decode_segments("/library/1/a%2F..%2Fb"), None);
+	}
+
+	#[test]
+	fn hrefs_round_trip() {
+		let segments = vec![
+			"library".to_string(),
+			"1".to_string(),
+			"Tom & Jerry #1.mp4".to_string(),
+		];
+		let href = href("/dav", &segments, false);
+
+		assert_eq!(href, "/dav/library/1/Tom%20%26%20Jerry%20%231.mp4");
+		assert_eq!(
+			decode_segments(href.strip_prefix("/dav").expect("has the base")),
+			Some(segments)
+		);
+		assert_eq!(super::href("/dav", &[], true), "/dav/");
+	}
+}
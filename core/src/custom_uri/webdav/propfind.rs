@@ -0,0 +1,118 @@
+use std::{fmt::Write, fs::Metadata, time::UNIX_EPOCH};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// A resource listed in a `PROPFIND` response
+pub(super) struct Entry {
+	pub href: String,
+	pub display_name: String,
+	/// Libraries and locations are virtual collections, without any metadata of their own
+	pub metadata: Option<Metadata>,
+}
+
+impl Entry {
+	fn is_collection(&self) -> bool {
+		self.metadata.as_ref().map_or(true, Metadata::is_dir)
+	}
+}
+
+/// Builds the `207 Multi-Status` body for the given entries, always answering with all the
+/// properties we know about (`allprop`), regardless of the ones that were asked for
+pub(super) fn multistatus(entries: &[Entry]) -> String {
+	let mut xml =
+		String::from(r#"<?xml version="1.0" encoding="utf-8"?><D:multistatus xmlns:D="DAV:">"#);
+
+	for entry in entries {
+		xml.push_str("<D:response>");
+		write_element(&mut xml, "href", &entry.href);
+		xml.push_str("<D:propstat><D:prop>");
+		write_element(&mut xml, "displayname", &entry.display_name);
+
+		if entry.is_collection() {
+			xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+		} else {
+			xml.push_str("<D:resourcetype/>");
+		}
+
+		if let Some(metadata) = &entry.metadata {
+			if !metadata.is_dir() {
+				write_element(&mut xml, "getcontentlength", &metadata.len().to_string());
+			}
+
+			if let Ok(modified) = metadata.modified() {
+				write_element(
+					&mut xml,
+					"getlastmodified",
+					&DateTime::<Utc>::from(modified)
+						.format("%a, %d %b %Y %H:%M:%S GMT")
+						.to_string(),
+				);
+
+				// Same ETag as the one sent by `serve_file`
+				if let Ok(since_epoch) = modified.duration_since(UNIX_EPOCH) {
+					write_element(
+						&mut xml,
+						"getetag",
+						&format!(r#""{}""#, since_epoch.as_millis()),
+					);
+				}
+			}
+
+			if let Ok(created) = metadata.created() {
+				write_element(
+					&mut xml,
+					"creationdate",
+					&DateTime::<Utc>::from(created).to_rfc3339_opts(SecondsFormat::Secs, true),
+				);
+			}
+		}
+
+		xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>");
+	}
+
+	xml.push_str("</D:multistatus>");
+
+	xml
+}
+
+fn write_element(xml: &mut String, name: &str, value: &str) {
+	write!(xml, "<D:{name}>{}</D:{name}>", escape(value)).expect("writing to a string can't fail");
+}
+
+fn escape(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escapes_names() {
+		assert_eq!(escape("<Tom & Jerry's>"), "&lt;Tom &amp; Jerry&apos;s&gt;");
+	}
+
+	#[test]
+	fn virtual_collections() {
+		let xml = multistatus(&[Entry {
+			href: "/dav/".to_string(),
+			display_name: "Spacedrive".to_string(),
+			metadata: None,
+		}]);
+
+		assert!(xml.contains("<D:href>/dav/</D:href>"));
+		assert!(xml.contains("<D:resourcetype><D:collection/></D:resourcetype>"));
+		assert!(!xml.contains("getcontentlength"));
+	}
+}
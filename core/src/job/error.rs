@@ -1,8 +1,9 @@
 use crate::{
 	location::{indexer::IndexerError, LocationError},
 	object::{
-		file_identifier::FileIdentifierJobError, fs::error::FileSystemJobsError,
-		media::media_processor::MediaProcessorError, validation::ValidatorError,
+		content::ContentExtractorError, file_identifier::FileIdentifierJobError,
+		fs::error::FileSystemJobsError, media::media_processor::MediaProcessorError,
+		validation::ValidatorError,
 	},
 	util::{db::MissingFieldError, error::FileIOError},
 };
@@ -32,6 +33,11 @@ pub enum JobError {
 	MetadataSerialization(#[from] serde_json::Error),
 	#[error("tried to resume a job with unknown name: job <name='{1}', uuid='{0}'>")]
 	UnknownJobName(Uuid, String),
+	#[error(
+		"job can't be resumed as it needs a password, it has to be started again: \
+		job <name='{1}', uuid='{0}'>"
+	)]
+	NotResumable(Uuid, String),
 	#[error(
 		"Tried to resume a job that doesn't have saved state data: job <name='{1}', uuid='{0}'>"
 	)]
@@ -65,6 +71,8 @@ pub enum JobError {
 	#[error(transparent)]
 	Validator(#[from] ValidatorError),
 	#[error(transparent)]
+	ContentExtractor(#[from] ContentExtractorError),
+	#[error(transparent)]
 	FileSystemJobsError(#[from] FileSystemJobsError),
 	#[error(transparent)]
 	CryptoError(#[from] CryptoError),
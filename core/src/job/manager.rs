@@ -3,12 +3,17 @@ use crate::{
 	library::Library,
 	location::indexer::indexer_job::IndexerJobInit,
 	object::{
+		content::content_extractor_job::ContentExtractorJobInit,
+		deduplicator::deduplicator_job::DeduplicatorJobInit,
 		file_identifier::file_identifier_job::FileIdentifierJobInit,
 		fs::{
-			copy::FileCopierJobInit, cut::FileCutterJobInit, delete::FileDeleterJobInit,
-			erase::FileEraserJobInit,
+			archiver::FileArchiverJobInit, copy::FileCopierJobInit, cut::FileCutterJobInit,
+			decrypt::FileDecryptorJobInit, delete::FileDeleterJobInit,
+			encrypt::FileEncryptorJobInit, erase::FileEraserJobInit,
+			extractor::FileExtractorJobInit,
 		},
 		media::media_processor::MediaProcessorJobInit,
+		space::exporter::SpaceExporterJobInit,
 		validation::validator_job::ObjectValidatorJobInit,
 	},
 	prisma::job,
@@ -355,6 +360,11 @@ impl Jobs {
 		false
 	}
 
+	/// Check if a job with the given hash is still queued or running.
+	pub async fn has_pending_job(&self, job_hash: u64) -> bool {
+		self.current_jobs_hashes.read().await.contains(&job_hash)
+	}
+
 	pub async fn has_job_running(&self, predicate: impl Fn(JobIdentity) -> bool) -> bool {
 		for worker in self.running_workers.read().await.values() {
 			if worker.who_am_i().await.map(&predicate).unwrap_or(false) {
@@ -379,11 +389,19 @@ mod macros {
         }};
     }
 }
+
+/// Jobs whose init has secrets that are never persisted, like passwords, so they can't be resumed
+const NON_RESUMABLE_JOBS: [&str; 2] = [FileEncryptorJobInit::NAME, FileDecryptorJobInit::NAME];
+
 /// This function is used to initialize a  DynJob from a job report.
 fn initialize_resumable_job(
 	job_report: JobReport,
 	next_jobs: Option<VecDeque<Box<dyn DynJob>>>,
 ) -> Result<Box<dyn DynJob>, JobError> {
+	if NON_RESUMABLE_JOBS.contains(&job_report.name.as_str()) {
+		return Err(JobError::NotResumable(job_report.id, job_report.name));
+	}
+
 	dispatch_call_to_job_by_name!(
 		job_report.name.as_str(),
 		T -> Job::<T>::new_from_report(job_report, next_jobs),
@@ -403,6 +421,11 @@ fn initialize_resumable_job(
 			FileCopierJobInit,
 			FileDeleterJobInit,
 			FileEraserJobInit,
+			FileArchiverJobInit,
+			FileExtractorJobInit,
+			SpaceExporterJobInit,
+			DeduplicatorJobInit,
+			ContentExtractorJobInit,
 		]
 	)
 }
@@ -121,6 +121,10 @@ pub trait StatefulJob:
 		run_metadata: &Self::RunMetadata,
 	) -> JobResult;
 
+	/// is called when the job is canceled or fails while running its steps, to remove what the steps
+	/// left behind. It isn't called on shutdown, as the job will be resumed later
+	async fn cleanup(&self, _ctx: &WorkerContext, _data: &Self::Data) {}
+
 	fn hash(&self) -> u64 {
 		let mut s = DefaultHasher::new();
 		Self::NAME.hash(&mut s);
@@ -666,7 +670,10 @@ impl<SJob: StatefulJob> DynJob for Job<SJob> {
 						info!("{e}");
 						break;
 					}
-					Err(e) => return Err(e),
+					Err(e) => {
+						stateful_job.cleanup(&ctx, &working_data_arc).await;
+						return Err(e);
+					}
 				}
 				// remove the step from the queue
 				step_number += 1;
@@ -1054,6 +1061,7 @@ async fn handle_single_step<SJob: StatefulJob>(
 					"Job <id='{id}', name='{name}'> \
 					failed to run step #{step_number} due to an internal error: {join_error:#?}",
 				);
+				stateful_job.cleanup(&worker_ctx, &working_data).await;
 				return Err(join_error.into());
 			}
 			StreamMessage::StepResult(Ok(output)) => {
@@ -1171,6 +1179,7 @@ async fn handle_single_step<SJob: StatefulJob>(
 								"Total paused time {:?} Job <id='{id}', name='{name}'>",
 								paused_time.elapsed(),
 							);
+							stateful_job.cleanup(&worker_ctx, &working_data).await;
 							return Err(JobError::Canceled(signal_tx));
 						}
 						WorkerCommand::Pause(_) => {
@@ -1183,6 +1192,7 @@ async fn handle_single_step<SJob: StatefulJob>(
 								timed out at step #{step_number} after {elapsed:?} without updates"
 							);
 							tx.send(()).ok();
+							stateful_job.cleanup(&worker_ctx, &working_data).await;
 							return Err(JobError::Timeout(elapsed));
 						}
 					}
@@ -1242,6 +1252,7 @@ async fn handle_single_step<SJob: StatefulJob>(
 					when.elapsed(),
 					job_init_time.elapsed(),
 				);
+				stateful_job.cleanup(&worker_ctx, &working_data).await;
 				return Err(JobError::Canceled(signal_tx));
 			}
 			StreamMessage::NewCommand(WorkerCommand::Timeout(elapsed, tx)) => {
@@ -1250,6 +1261,7 @@ async fn handle_single_step<SJob: StatefulJob>(
 					timed out at step #{step_number} after {elapsed:?} without updates"
 				);
 				tx.send(()).ok();
+				stateful_job.cleanup(&worker_ctx, &working_data).await;
 				return Err(JobError::Timeout(elapsed));
 			}
 		}
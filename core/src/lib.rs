@@ -104,7 +104,12 @@ impl Node {
 		let (locations, locations_actor) = location::Locations::new();
 		let (jobs, jobs_actor) = job::Jobs::new();
 		let libraries = library::Libraries::new(data_dir.join("libraries")).await?;
-		let (p2p, p2p_actor) = p2p::P2PManager::new(config.clone(), libraries.clone()).await?;
+		let (p2p, p2p_actor) = p2p::P2PManager::new(
+			config.clone(),
+			libraries.clone(),
+			data_dir.join("spacedrop"),
+		)
+		.await?;
 		let node = Arc::new(Node {
 			data_dir: data_dir.to_path_buf(),
 			jobs,
@@ -65,6 +65,7 @@ impl Category {
 		match self {
 			Category::Recents => not![object::date_accessed::equals(None)],
 			Category::Favorites => object::favorite::equals(Some(true)),
+			Category::Albums => object::albums::some(vec![]),
 			Category::Photos
 			| Category::Videos
 			| Category::Music
@@ -6,13 +6,17 @@ use crate::{
 	env,
 	location::file_path_helper::{file_path_to_full_path, IsolatedFilePathData},
 	notifications,
-	object::{media::thumbnail::get_indexed_thumbnail_path, orphan_remover::OrphanRemoverActor},
+	object::{
+		media::thumbnail::{find_indexed_thumbnail, ThumbnailSize},
+		orphan_remover::OrphanRemoverActor,
+	},
 	prisma::{file_path, location, PrismaClient},
 	sync,
 	util::{db::maybe_missing, error::FileIOError},
 	Node,
 };
 
+use sd_crypto::keys::keymanager::KeyManager;
 use sd_p2p::spacetunnel::Identity;
 use sd_prisma::prisma::notification;
 
@@ -24,7 +28,7 @@ use std::{
 };
 
 use chrono::{DateTime, Utc};
-use tokio::{fs, io, sync::broadcast, sync::RwLock};
+use tokio::{sync::broadcast, sync::RwLock};
 use tracing::warn;
 use uuid::Uuid;
 
@@ -48,7 +52,7 @@ pub struct Library {
 	pub db: Arc<PrismaClient>,
 	pub sync: Arc<sync::Manager>,
 	/// key manager that provides encryption keys to functions that require them
-	// pub key_manager: Arc<KeyManager>,
+	pub key_manager: Arc<KeyManager>,
 	/// p2p identity
 	pub identity: Arc<Identity>,
 	pub orphan_remover: OrphanRemoverActor,
@@ -82,6 +86,7 @@ impl Library {
 		config: LibraryConfig,
 		instance_uuid: Uuid,
 		identity: Arc<Identity>,
+		key_manager: Arc<KeyManager>,
 		db: Arc<PrismaClient>,
 		node: &Arc<Node>,
 		sync: Arc<sync::Manager>,
@@ -91,7 +96,7 @@ impl Library {
 			config: RwLock::new(config),
 			sync,
 			db: db.clone(),
-			// key_manager,
+			key_manager,
 			identity,
 			orphan_remover: OrphanRemoverActor::spawn(db),
 			notifications: node.notifications.clone(),
@@ -125,13 +130,9 @@ impl Library {
 	}
 
 	pub async fn thumbnail_exists(&self, node: &Node, cas_id: &str) -> Result<bool, FileIOError> {
-		let thumb_path = get_indexed_thumbnail_path(node, cas_id, self.id);
-
-		match fs::metadata(&thumb_path).await {
-			Ok(_) => Ok(true),
-			Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
-			Err(e) => Err(FileIOError::from((thumb_path, e))),
-		}
+		find_indexed_thumbnail(node, cas_id, self.id, ThumbnailSize::Medium)
+			.await
+			.map(|thumb_path| thumb_path.is_some())
 	}
 
 	/// Returns the full path of a file
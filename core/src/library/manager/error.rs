@@ -23,8 +23,10 @@ pub enum LibraryManagerError {
 	Uuid(#[from] uuid::Error),
 	#[error("failed to run indexer rules seeder: {0}")]
 	IndexerRulesSeeder(#[from] indexer::rules::seed::SeederError),
-	// #[error("failed to initialise the key manager: {0}")]
-	// KeyManager(#[from] sd_crypto::Error),
+	#[error("failed to initialise the key manager: {0}")]
+	KeyManager(#[from] sd_crypto::Error),
+	#[error("failed to load the library's keys: {0}")]
+	StoredKey(#[from] db::StoredKeyError),
 	#[error("error migrating the library: {0}")]
 	MigrationError(#[from] db::MigrationError),
 	#[error("invalid library configuration: {0}")]
@@ -39,6 +41,8 @@ pub enum LibraryManagerError {
 	InvalidIdentity,
 	#[error("current instance with id '{0}' was not found in the database")]
 	CurrentInstanceNotFound(String),
+	#[error("statistics snapshot with id '{0}' was not found in the database")]
+	StatisticsNotFound(i32),
 	#[error("missing-field: {0}")]
 	MissingField(#[from] MissingFieldError),
 
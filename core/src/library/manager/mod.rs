@@ -20,6 +20,7 @@ use crate::{
 };
 
 use sd_core_sync::SyncMessage;
+use sd_crypto::keys::keymanager::KeyManager;
 use sd_p2p::spacetunnel::Identity;
 use sd_prisma::prisma::{instance, shared_operation};
 use sd_utils::from_bytes_to_uuid;
@@ -438,8 +439,10 @@ impl Libraries {
 
 		// TODO: Move this reconciliation into P2P and do reconciliation of both local and remote nodes.
 
-		// let key_manager = Arc::new(KeyManager::new(vec![]).await?);
-		// seed_keymanager(&db, &key_manager).await?;
+		// Headless nodes usually don't have an OS keyring, so secrets are kept in the data directory
+		let key_manager =
+			Arc::new(KeyManager::new(vec![], Some(node.data_dir.join("keyring"))).await?);
+		db::seed_keymanager(&db, &key_manager).await?;
 
 		let timestamps = db
 			._batch(
@@ -471,7 +474,7 @@ impl Libraries {
 			config,
 			instance_id,
 			identity,
-			// key_manager,
+			key_manager,
 			db,
 			node,
 			Arc::new(sync.manager),
@@ -543,9 +546,7 @@ async fn sync_rx_actor(
 			SyncMessage::Ingested => node.emit(CoreEvent::InvalidateOperation(
 				InvalidateOperationEvent::all(),
 			)),
-			SyncMessage::Created => {
-				p2p::sync::originator(library.id, &library.sync, &node.p2p).await
-			}
+			SyncMessage::Created => p2p::sync::originator(&library, &node.p2p).await,
 		}
 	}
 }
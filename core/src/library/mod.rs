@@ -4,11 +4,13 @@ mod config;
 mod library;
 mod manager;
 mod name;
+mod statistics;
 
 // pub use cat::*;
 pub use config::*;
 pub use library::*;
 pub use manager::*;
 pub use name::*;
+pub use statistics::*;
 
 pub type LibraryId = uuid::Uuid;
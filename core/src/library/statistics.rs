@@ -0,0 +1,306 @@
+use crate::{
+	api::utils::get_size,
+	invalidate_query,
+	prisma::{file_path, statistics, statistics_per_kind, statistics_per_location, SortOrder},
+	volume::get_volumes,
+	Node,
+};
+
+use sd_file_ext::kind::ObjectKind;
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::{Arc, Mutex, PoisonError},
+	time::Duration,
+};
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use tokio::{spawn, time::sleep};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use super::{Library, LibraryManagerError};
+
+// Reading all file paths at once could take too much memory on big libraries
+const CHUNK_SIZE: i64 = 10_000;
+/// Older snapshots are removed, so the history of big libraries with many scans stays small
+const MAX_STATISTICS_SNAPSHOTS: i64 = 500;
+const REFRESH_DEBOUNCE: Duration = Duration::from_secs(30);
+
+/// Libraries with a statistics refresh waiting to run
+static PENDING_REFRESHES: Lazy<Mutex<HashSet<Uuid>>> = Lazy::new(Mutex::default);
+
+statistics::include!(statistics_with_breakdowns { locations kinds });
+
+file_path::select!(file_path_for_statistics {
+	id
+	location_id
+	size_in_bytes_bytes
+	object_id
+	object: select { kind }
+});
+
+#[derive(Default)]
+struct Usage {
+	file_count: i32,
+	total_bytes: u64,
+}
+
+impl Usage {
+	fn add(&mut self, size: u64) {
+		self.file_count += 1;
+		self.total_bytes += size;
+	}
+}
+
+/// Returns the most recent statistics snapshot of the library, capturing the first one if the
+/// library doesn't have any yet
+pub async fn get_library_statistics(
+	node: &Node,
+	library: &Library,
+) -> Result<statistics_with_breakdowns::Data, LibraryManagerError> {
+	match library
+		.db
+		.statistics()
+		.find_first(vec![])
+		.order_by(statistics::date_captured::order(SortOrder::Desc))
+		.include(statistics_with_breakdowns::include())
+		.exec()
+		.await?
+	{
+		Some(statistics) => Ok(statistics),
+		None => update_library_statistics(node, library).await,
+	}
+}
+
+/// Captures a new statistics snapshot, kept along with the previous ones so the library's growth
+/// can be charted over time.
+///
+/// Sizes come from the indexed file paths, where `total_unique_bytes` only counts each object once
+/// as file paths sharing an object have the same content.
+pub async fn update_library_statistics(
+	node: &Node,
+	library: &Library,
+) -> Result<statistics_with_breakdowns::Data, LibraryManagerError> {
+	let db = &library.db;
+
+	let mut total_bytes_used = 0u64;
+	let mut total_unique_bytes = 0u64;
+	let mut seen_objects = HashSet::new();
+	let mut per_location = HashMap::<_, Usage>::new();
+	let mut per_kind = HashMap::<_, Usage>::new();
+
+	let mut cursor = 0;
+	loop {
+		let file_paths = db
+			.file_path()
+			.find_many(vec![
+				file_path::id::gt(cursor),
+				file_path::is_dir::equals(Some(false)),
+			])
+			.order_by(file_path::id::order(SortOrder::Asc))
+			.take(CHUNK_SIZE)
+			.select(file_path_for_statistics::select())
+			.exec()
+			.await?;
+
+		let Some(last) = file_paths.last() else {
+			break;
+		};
+		cursor = last.id;
+
+		for file_path in file_paths {
+			let size = file_path
+				.size_in_bytes_bytes
+				.as_deref()
+				.and_then(|bytes| bytes.try_into().ok())
+				.map(u64::from_be_bytes)
+				.unwrap_or_default();
+
+			total_bytes_used += size;
+
+			// File paths which weren't identified yet can't be deduplicated
+			if file_path
+				.object_id
+				.map_or(true, |object_id| seen_objects.insert(object_id))
+			{
+				total_unique_bytes += size;
+			}
+
+			if let Some(location_id) = file_path.location_id {
+				per_location.entry(location_id).or_default().add(size);
+			}
+
+			per_kind
+				.entry(
+					file_path
+						.object
+						.and_then(|object| object.kind)
+						.unwrap_or(ObjectKind::Unknown as i32),
+				)
+				.or_default()
+				.add(size);
+		}
+	}
+
+	let total_object_count = db.object().count(vec![]).exec().await?;
+
+	let (total_bytes_capacity, total_bytes_free) = get_volumes().await.into_iter().fold(
+		(0u64, 0u64),
+		|(total_capacity, available_capacity), volume| {
+			(
+				total_capacity + volume.total_capacity,
+				available_capacity + volume.available_capacity,
+			)
+		},
+	);
+
+	let library_db_size = get_size(
+		node.config
+			.data_directory()
+			.join("libraries")
+			.join(&format!("{}.db", library.id)),
+	)
+	.await
+	.unwrap_or(0);
+
+	let thumbnail_folder_size = get_size(node.config.data_directory().join("thumbnails"))
+		.await
+		.unwrap_or(0);
+
+	let statistics = {
+		use statistics::*;
+
+		db.statistics()
+			.create(vec![
+				date_captured::set(Utc::now().into()),
+				total_object_count::set(total_object_count as i32),
+				library_db_size::set(library_db_size.to_string()),
+				total_bytes_used::set(total_bytes_used.to_string()),
+				total_bytes_capacity::set(total_bytes_capacity.to_string()),
+				total_unique_bytes::set(total_unique_bytes.to_string()),
+				total_bytes_free::set(total_bytes_free.to_string()),
+				preview_media_bytes::set(thumbnail_folder_size.to_string()),
+			])
+			.exec()
+			.await?
+	};
+
+	db._batch((
+		db.statistics_per_location().create_many(
+			per_location
+				.into_iter()
+				.map(|(location_id, usage)| {
+					statistics_per_location::create_unchecked(
+						location_id,
+						statistics.id,
+						vec![
+							statistics_per_location::file_count::set(usage.file_count),
+							statistics_per_location::total_bytes::set(
+								usage.total_bytes.to_string(),
+							),
+						],
+					)
+				})
+				.collect(),
+		),
+		db.statistics_per_kind().create_many(
+			per_kind
+				.into_iter()
+				.map(|(kind, usage)| {
+					statistics_per_kind::create_unchecked(
+						kind,
+						statistics.id,
+						vec![
+							statistics_per_kind::file_count::set(usage.file_count),
+							statistics_per_kind::total_bytes::set(usage.total_bytes.to_string()),
+						],
+					)
+				})
+				.collect(),
+		),
+	))
+	.await?;
+
+	remove_old_statistics(library).await?;
+
+	debug!(
+		"Captured statistics for library <id='{}'>: {total_bytes_used} bytes used, \
+		{total_unique_bytes} unique bytes",
+		library.id
+	);
+
+	invalidate_query!(library, "library.statistics");
+	invalidate_query!(library, "library.statisticsHistory");
+
+	db.statistics()
+		.find_unique(statistics::id::equals(statistics.id))
+		.include(statistics_with_breakdowns::include())
+		.exec()
+		.await?
+		.ok_or(LibraryManagerError::StatisticsNotFound(statistics.id))
+}
+
+/// Keeps only the most recent snapshots, their breakdowns are removed along with them
+async fn remove_old_statistics(library: &Library) -> Result<(), LibraryManagerError> {
+	let db = &library.db;
+
+	let old_statistics_ids = db
+		.statistics()
+		.find_many(vec![])
+		.order_by(statistics::date_captured::order(SortOrder::Desc))
+		.skip(MAX_STATISTICS_SNAPSHOTS)
+		.select(statistics::select!({ id }))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|statistics| statistics.id)
+		.collect::<Vec<_>>();
+
+	if !old_statistics_ids.is_empty() {
+		db.statistics()
+			.delete_many(vec![statistics::id::in_vec(old_statistics_ids)])
+			.exec()
+			.await?;
+	}
+
+	Ok(())
+}
+
+/// Refreshes the statistics in the background, after jobs that change the indexed files.
+///
+/// Scanning a location runs a few of these jobs in a row, so the refresh waits until the library
+/// has no running jobs and every request made meanwhile shares it.
+pub fn spawn_update_library_statistics(node: Arc<Node>, library: Arc<Library>) {
+	if !PENDING_REFRESHES
+		.lock()
+		.unwrap_or_else(PoisonError::into_inner)
+		.insert(library.id)
+	{
+		return;
+	}
+
+	spawn(async move {
+		loop {
+			sleep(REFRESH_DEBOUNCE).await;
+
+			if !node.jobs.has_active_workers(library.id).await {
+				break;
+			}
+		}
+
+		// Jobs finishing from now on need a new refresh, as this one may not see their changes
+		PENDING_REFRESHES
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.remove(&library.id);
+
+		if let Err(e) = update_library_statistics(&node, &library).await {
+			error!(
+				"Failed to update statistics of library <id='{}'>: {e:#?}",
+				library.id
+			);
+		}
+	});
+}
@@ -0,0 +1,161 @@
+use crate::{
+	api::locations::ExplorerItem,
+	object::fs::archive::{self, normalize_entry_path, ArchiveError, ArchiveFormat},
+	util::error::FileIOError,
+};
+
+use std::path::{Path, PathBuf};
+
+use sd_file_ext::{extensions::Extension, kind::ObjectKind, magic::ExtensionPossibility};
+
+use chrono::{DateTime, Utc};
+use tempfile::{NamedTempFile, TempPath};
+use tokio::{fs, task::spawn_blocking};
+
+use super::non_indexed::{
+	NonIndexedFileSystemEntries, NonIndexedLocationError, NonIndexedPathItem,
+};
+
+/// A path pointing inside of an archive file, like `/data/backup.zip/photos/beach.jpg`
+#[derive(Debug, Clone)]
+pub struct ArchivePath {
+	pub archive_path: PathBuf,
+	pub format: ArchiveFormat,
+	/// Path of the entry relative to the archive root, empty for the root itself
+	pub inner_path: String,
+}
+
+impl ArchivePath {
+	/// Walks up the ancestors of `path` looking for a supported archive file,
+	/// returns `None` if the path isn't inside of an archive
+	pub async fn find(path: impl AsRef<Path>) -> Option<Self> {
+		let path = path.as_ref();
+
+		for ancestor in path.ancestors() {
+			let Some(format) = ArchiveFormat::from_path(ancestor) else {
+				continue;
+			};
+
+			if fs::metadata(ancestor)
+				.await
+				.map(|metadata| metadata.is_file())
+				.unwrap_or(false)
+			{
+				return Some(Self {
+					archive_path: ancestor.to_path_buf(),
+					format,
+					inner_path: path
+						.strip_prefix(ancestor)
+						.map(|inner| normalize_entry_path(&inner.to_string_lossy()))
+						.unwrap_or_default(),
+				});
+			}
+		}
+
+		None
+	}
+
+	/// Decompresses the entry pointed by this path into a temporary file, so it can be served
+	/// like any other file. The file is removed once the returned path is dropped.
+	pub async fn extract_to_temp_path(&self) -> Result<TempPath, ArchiveError> {
+		let Self {
+			archive_path,
+			format,
+			inner_path,
+		} = self.clone();
+
+		spawn_blocking(move || {
+			let mut file =
+				NamedTempFile::new().map_err(|e| FileIOError::from((&archive_path, e)))?;
+
+			archive::extract_entry(&archive_path, format, &inner_path, file.as_file_mut())?;
+
+			Ok::<_, ArchiveError>(file.into_temp_path())
+		})
+		.await?
+	}
+}
+
+/// Lists the entries directly inside `archive_path` as non indexed paths, so the explorer
+/// can browse archives the same way it browses ephemeral locations. As we would have to
+/// decompress every entry, no thumbnails are generated for them.
+pub async fn walk(
+	ArchivePath {
+		archive_path,
+		format,
+		inner_path,
+	}: ArchivePath,
+	with_hidden_files: bool,
+) -> Result<NonIndexedFileSystemEntries, NonIndexedLocationError> {
+	// Entries without a modification date inherit it from the archive file itself
+	let archive_date: DateTime<Utc> = fs::metadata(&archive_path)
+		.await
+		.and_then(|metadata| metadata.modified())
+		.map_err(|e| (&archive_path, e))?
+		.into();
+
+	let archive_entries = spawn_blocking({
+		let archive_path = archive_path.clone();
+		move || archive::read_dir(archive_path, format, &inner_path)
+	})
+	.await
+	.map_err(ArchiveError::from)??;
+
+	let entries = archive_entries
+		.into_iter()
+		.filter(|entry| with_hidden_files || !entry.name().starts_with('.'))
+		.map(|entry| {
+			let virtual_path = archive_path.join(&entry.path);
+			let date = entry.date_modified.unwrap_or(archive_date);
+
+			let (name, extension, kind) = if entry.is_dir {
+				(entry.name().to_string(), String::new(), ObjectKind::Folder)
+			} else {
+				let name = virtual_path
+					.file_stem()
+					.map(|stem| stem.to_string_lossy().to_string())
+					.unwrap_or_default();
+
+				let extension = virtual_path
+					.extension()
+					.map(|ext| ext.to_string_lossy().to_string())
+					.unwrap_or_default();
+
+				// We can't peek at the magic bytes without decompressing the entry,
+				// so conflicting extensions are resolved to their first possibility
+				let kind = match Extension::from_str(&extension) {
+					Some(ExtensionPossibility::Known(ext)) => ext.into(),
+					Some(ExtensionPossibility::Conflicts(exts)) => exts
+						.into_iter()
+						.next()
+						.map(Into::into)
+						.unwrap_or(ObjectKind::Unknown),
+					None => ObjectKind::Unknown,
+				};
+
+				(name, extension, kind)
+			};
+
+			ExplorerItem::NonIndexedPath {
+				has_local_thumbnail: false,
+				thumbnail_key: None,
+				item: NonIndexedPathItem {
+					hidden: entry.name().starts_with('.'),
+					path: virtual_path.to_string_lossy().to_string(),
+					name,
+					extension,
+					kind: kind as i32,
+					is_dir: entry.is_dir,
+					date_created: date,
+					date_modified: date,
+					size_in_bytes_bytes: entry.size.to_be_bytes().to_vec(),
+				},
+			}
+		})
+		.collect();
+
+	Ok(NonIndexedFileSystemEntries {
+		entries,
+		errors: vec![],
+	})
+}
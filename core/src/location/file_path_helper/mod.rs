@@ -46,6 +46,21 @@ file_path::select!(file_path_for_object_validator {
 	extension
 	integrity_checksum
 });
+file_path::select!(file_path_for_strict_identity {
+	pub_id
+	location_id
+	materialized_path
+	is_dir
+	name
+	extension
+	cas_id
+	integrity_checksum
+	object: select { pub_id }
+	location: select {
+		path
+		instance_id
+	}
+});
 file_path::select!(file_path_for_media_processor {
 	id
 	materialized_path
@@ -118,6 +133,21 @@ file_path::select!(file_path_to_full_path {
 		path
 	}
 });
+file_path::select!(file_path_for_deduplicator {
+	id
+	pub_id
+	object_id
+	integrity_checksum
+	materialized_path
+	is_dir
+	name
+	extension
+	size_in_bytes_bytes
+	location: select {
+		id
+		path
+	}
+});
 
 // File Path includes!
 file_path::include!(file_path_with_object { object });
@@ -4,7 +4,7 @@ use crate::{
 		CurrentStep, JobError, JobInitOutput, JobReportUpdate, JobResult, JobRunMetadata,
 		JobStepOutput, StatefulJob, WorkerContext,
 	},
-	library::Library,
+	library::{spawn_update_library_statistics, Library},
 	location::{
 		file_path_helper::{
 			ensure_file_path_exists, ensure_sub_path_is_directory, ensure_sub_path_is_in_location,
@@ -206,6 +206,7 @@ impl StatefulJob for IndexerJobInit {
 			errors,
 			paths_and_sizes,
 		} = walk(
+			location_path,
 			&to_walk_path,
 			&indexer_rules,
 			update_notifier_fn(ctx),
@@ -393,6 +394,7 @@ impl StatefulJob for IndexerJobInit {
 					errors,
 					paths_and_sizes,
 				} = keep_walking(
+					location_path,
 					to_walk_entry,
 					&data.indexer_rules,
 					update_notifier_fn(ctx),
@@ -529,6 +531,8 @@ impl StatefulJob for IndexerJobInit {
 					.await
 					.map_err(IndexerError::from)?;
 			}
+
+			spawn_update_library_statistics(Arc::clone(&ctx.node), Arc::clone(&ctx.library));
 		}
 
 		Ok(Some(json!({"init: ": init, "run_metadata": run_metadata})))
@@ -1,5 +1,6 @@
 use crate::{
 	library::Library,
+	object::content::remove_content,
 	util::{db::inode_to_db, error::FileIOError},
 };
 
@@ -292,6 +293,17 @@ async fn execute_indexer_update_step(
 
 	trace!("Updated {updated:?} records");
 
+	// The files changed while Spacedrive was offline, so the content of their objects is outdated
+	remove_content(
+		db,
+		&update_step
+			.to_update
+			.iter()
+			.filter_map(|entry| entry.maybe_object_id)
+			.collect::<Vec<_>>(),
+	)
+	.await?;
+
 	Ok(updated.len() as i64)
 }
 
@@ -0,0 +1,218 @@
+use crate::util::error::FileIOError;
+
+use std::{
+	io,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use ignore::{
+	gitignore::{Gitignore, GitignoreBuilder},
+	Match,
+};
+use tokio::fs;
+use tracing::warn;
+
+use super::{IndexerRule, RulePerKind};
+
+/// Stack of ignore files (like `.gitignore` or `.sdignore`) that apply to a directory being walked.
+///
+/// Each directory can have its own ignore files, which apply to everything below it and take
+/// precedence over the ones from its ancestors, so walkers must carry the stack from the location
+/// root down to each directory they read.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFiles {
+	file_names: Arc<[String]>,
+	matchers: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreFiles {
+	/// Collects the ignore file names from every `RejectFilesByIgnoreFiles` rule
+	pub fn new(rules: &[IndexerRule]) -> Self {
+		let mut file_names = Vec::<String>::new();
+
+		for rule in rules {
+			for rule_per_kind in &rule.rules {
+				if let RulePerKind::RejectFilesByIgnoreFiles(names) = rule_per_kind {
+					for name in names {
+						if !file_names.contains(name) {
+							file_names.push(name.clone());
+						}
+					}
+				}
+			}
+		}
+
+		Self {
+			file_names: file_names.into(),
+			matchers: vec![],
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.file_names.is_empty()
+	}
+
+	/// Checks if `path` is one of the ignore files, in which case changing it can change what
+	/// should be indexed below its directory
+	pub fn is_ignore_file(&self, path: impl AsRef<Path>) -> bool {
+		path.as_ref()
+			.file_name()
+			.and_then(|name| name.to_str())
+			.map_or(false, |name| {
+				self.file_names.iter().any(|file_name| file_name == name)
+			})
+	}
+
+	/// Returns a new stack with the ignore files found in `dir` on top of this one
+	pub async fn with_dir(&self, dir: impl AsRef<Path>) -> Self {
+		let dir = dir.as_ref();
+
+		if self.is_empty() {
+			return self.clone();
+		}
+
+		let mut builder = GitignoreBuilder::new(dir);
+		let mut found_any = false;
+
+		for file_name in self.file_names.iter() {
+			let path = dir.join(file_name);
+
+			match fs::read_to_string(&path).await {
+				Ok(contents) => {
+					found_any = true;
+					add_lines(&mut builder, path, &contents);
+				}
+				Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+				Err(e) => warn!(
+					"Failed to read ignore file: {:#?}",
+					FileIOError::from((&path, e))
+				),
+			}
+		}
+
+		let mut ignore_files = self.clone();
+
+		if found_any {
+			match builder.build() {
+				Ok(matcher) => ignore_files.matchers.push(Arc::new(matcher)),
+				Err(e) => warn!(
+					"Failed to build ignore files matcher for <dir='{}'>: {e:#?}",
+					dir.display()
+				),
+			}
+		}
+
+		ignore_files
+	}
+
+	/// Returns a new stack with the ignore files of every directory from `root` down to the parent
+	/// of `path`, which are the ones that apply to `path` itself
+	pub async fn with_ancestors(&self, root: impl AsRef<Path>, path: impl AsRef<Path>) -> Self {
+		let root = root.as_ref();
+
+		let mut ancestors = path
+			.as_ref()
+			.ancestors()
+			.skip(1)
+			.take_while(|ancestor| ancestor.starts_with(root))
+			.collect::<Vec<_>>();
+		ancestors.reverse();
+
+		let mut ignore_files = self.clone();
+		for ancestor in ancestors {
+			ignore_files = ignore_files.with_dir(ancestor).await;
+		}
+
+		ignore_files
+	}
+
+	/// Checks `path` against the stack, where the deepest ignore file that has something to say
+	/// about it wins, just like git does
+	pub fn is_ignored(&self, path: impl AsRef<Path>, is_dir: bool) -> bool {
+		let path = path.as_ref();
+
+		for matcher in self.matchers.iter().rev() {
+			// The matcher panics for paths outside of its directory
+			if path == matcher.path() || !path.starts_with(matcher.path()) {
+				continue;
+			}
+
+			match matcher.matched_path_or_any_parents(path, is_dir) {
+				Match::None => continue,
+				Match::Ignore(_) => return true,
+				Match::Whitelist(_) => return false,
+			}
+		}
+
+		false
+	}
+}
+
+fn add_lines(builder: &mut GitignoreBuilder, path: PathBuf, contents: &str) {
+	for line in contents.lines() {
+		if let Err(e) = builder.add_line(Some(path.clone()), line) {
+			warn!(
+				"Invalid line on ignore file <path='{}'>: {e:#?}",
+				path.display()
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+	use super::*;
+
+	use chrono::Utc;
+	use tempfile::tempdir;
+
+	#[tokio::test]
+	async fn test_nested_ignore_files() {
+		let root = tempdir().unwrap();
+		let root_path = root.path();
+
+		fs::write(root_path.join(".gitignore"), "*.log\n!keep.log\nbuild/\n")
+			.await
+			.unwrap();
+		fs::create_dir_all(root_path.join("project/build"))
+			.await
+			.unwrap();
+		fs::write(root_path.join("project/.sdignore"), "secret.txt\n!*.log\n")
+			.await
+			.unwrap();
+
+		let ignore_files = IgnoreFiles::new(&[IndexerRule {
+			id: None,
+			name: "Respect Ignore Files".to_string(),
+			default: false,
+			rules: vec![RulePerKind::RejectFilesByIgnoreFiles(vec![
+				".gitignore".to_string(),
+				".sdignore".to_string(),
+			])],
+			date_created: Utc::now(),
+			date_modified: Utc::now(),
+		}]);
+
+		assert!(ignore_files.is_ignore_file(root_path.join("project/.sdignore")));
+		assert!(!ignore_files.is_ignore_file(root_path.join("project/notes.txt")));
+
+		let root_ignore_files = ignore_files.with_dir(root_path).await;
+
+		assert!(root_ignore_files.is_ignored(root_path.join("debug.log"), false));
+		assert!(!root_ignore_files.is_ignored(root_path.join("keep.log"), false));
+		assert!(root_ignore_files.is_ignored(root_path.join("build"), true));
+		assert!(!root_ignore_files.is_ignored(root_path.join("build"), false));
+		assert!(!root_ignore_files.is_ignored(root_path.join("secret.txt"), false));
+
+		let project_ignore_files = ignore_files
+			.with_ancestors(root_path, root_path.join("project/secret.txt"))
+			.await;
+
+		assert!(project_ignore_files.is_ignored(root_path.join("project/secret.txt"), false));
+		assert!(!project_ignore_files.is_ignored(root_path.join("project/debug.log"), false));
+		assert!(project_ignore_files.is_ignored(root_path.join("project/build"), true));
+		assert!(project_ignore_files.is_ignored(root_path.join("project/build/out.bin"), false));
+	}
+}
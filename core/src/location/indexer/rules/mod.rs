@@ -1,5 +1,8 @@
+mod ignore_files;
 pub mod seed;
 
+pub use ignore_files::IgnoreFiles;
+
 use crate::{
 	library::Library,
 	prisma::indexer_rule,
@@ -9,10 +12,14 @@ use crate::{
 	},
 };
 
+use sd_file_ext::{extensions::Extension, kind::ObjectKind};
+
 use std::{
 	collections::{HashMap, HashSet},
 	marker::PhantomData,
 	path::Path,
+	str::FromStr,
+	time::{Duration, SystemTime},
 };
 
 use chrono::{DateTime, Utc};
@@ -32,6 +39,8 @@ pub enum IndexerRuleError {
 	// User errors
 	#[error("invalid indexer rule kind integer: {0}")]
 	InvalidRuleKindInt(i32),
+	#[error("invalid parameters for {0:?} rule: {1}")]
+	InvalidRuleParameters(RuleKind, String),
 	#[error("glob builder error: {0}")]
 	Glob(#[from] globset::Error),
 	#[error(transparent)]
@@ -46,6 +55,10 @@ pub enum IndexerRuleError {
 	AcceptByItsChildrenFileIO(FileIOError),
 	#[error("reject by its children file I/O error: {0}")]
 	RejectByItsChildrenFileIO(FileIOError),
+	#[error("file metadata I/O error: {0}")]
+	FileMetadataFileIO(FileIOError),
+	#[error("extended attributes file I/O error: {0}")]
+	ExtendedAttributesFileIO(FileIOError),
 	#[error("database error: {0}")]
 	Database(#[from] prisma_client_rust::QueryError),
 	#[error("missing-field: {0}")]
@@ -56,6 +69,7 @@ impl From<IndexerRuleError> for rspc::Error {
 	fn from(err: IndexerRuleError) -> Self {
 		match err {
 			IndexerRuleError::InvalidRuleKindInt(_)
+			| IndexerRuleError::InvalidRuleParameters(..)
 			| IndexerRuleError::Glob(_)
 			| IndexerRuleError::NonUtf8Path(_) => {
 				rspc::Error::with_cause(ErrorCode::BadRequest, err.to_string(), err)
@@ -74,6 +88,18 @@ impl From<IndexerRuleError> for rspc::Error {
 ///
 /// In case of `RuleKind::AcceptIfChildrenDirectoriesArePresent` or `RuleKind::RejectIfChildrenDirectoriesArePresent` the
 /// `parameters` field must be a vector of strings containing the names of the directories.
+///
+/// In case of `RuleKind::RejectFilesLargerThan` or `RuleKind::AcceptFilesModifiedWithinDays` the `parameters` field
+/// must be a vector with a single string containing the maximum size in bytes or the number of days.
+///
+/// In case of `RuleKind::AcceptFilesByKind` the `parameters` field must be a vector of strings containing
+/// `ObjectKind` names, like `Image` or `Video`.
+///
+/// In case of `RuleKind::RejectFilesByExtendedAttribute` the `parameters` field must be a vector of strings
+/// containing the names of the extended attributes, like `user.sd.ignore`.
+///
+/// In case of `RuleKind::RejectFilesByIgnoreFiles` the `parameters` field must be a vector of strings
+/// containing the names of the ignore files, like `.gitignore`, where the later ones take precedence.
 #[derive(Type, Deserialize)]
 pub struct IndexerRuleCreateArgs {
 	pub name: String,
@@ -118,6 +144,31 @@ impl IndexerRuleCreateArgs {
 							parameters.into_iter().collect(),
 						))
 					}
+					RuleKind::RejectFilesLargerThan => {
+						single_parameter(kind, parameters).map(RulePerKind::RejectFilesLargerThan)
+					}
+					RuleKind::AcceptFilesModifiedWithinDays => single_parameter(kind, parameters)
+						.map(RulePerKind::AcceptFilesModifiedWithinDays),
+					RuleKind::AcceptFilesByKind => parameters
+						.into_iter()
+						.map(|parameter| {
+							ObjectKind::from_str(parameter.trim()).map_err(|_| {
+								IndexerRuleError::InvalidRuleParameters(
+									kind,
+									format!("unknown object kind \"{parameter}\""),
+								)
+							})
+						})
+						.collect::<Result<_, _>>()
+						.map(RulePerKind::AcceptFilesByKind),
+					RuleKind::RejectFilesByExtendedAttribute => {
+						Ok(RulePerKind::RejectFilesByExtendedAttribute(
+							parameters.into_iter().collect(),
+						))
+					}
+					RuleKind::RejectFilesByIgnoreFiles => {
+						Ok(RulePerKind::RejectFilesByIgnoreFiles(parameters))
+					}
 				})
 				.collect::<Result<Vec<_>, _>>()?,
 		)?;
@@ -157,12 +208,31 @@ pub enum RuleKind {
 	RejectFilesByGlob = 1,
 	AcceptIfChildrenDirectoriesArePresent = 2,
 	RejectIfChildrenDirectoriesArePresent = 3,
+	RejectFilesLargerThan = 4,
+	AcceptFilesModifiedWithinDays = 5,
+	AcceptFilesByKind = 6,
+	RejectFilesByExtendedAttribute = 7,
+	RejectFilesByIgnoreFiles = 8,
 }
 
 impl RuleKind {
+	/// Kinds whose rules reject a path as soon as any of them doesn't pass
+	pub const REJECT_KINDS: [Self; 3] = [
+		Self::RejectFilesByGlob,
+		Self::RejectFilesLargerThan,
+		Self::RejectFilesByExtendedAttribute,
+	];
+
+	/// Kinds whose rules reject a path only if none of them passes
+	pub const ACCEPT_KINDS: [Self; 3] = [
+		Self::AcceptFilesByGlob,
+		Self::AcceptFilesModifiedWithinDays,
+		Self::AcceptFilesByKind,
+	];
+
 	pub const fn variant_count() -> usize {
 		// TODO: Use https://doc.rust-lang.org/std/mem/fn.variant_count.html if it ever gets stabilized
-		4
+		9
 	}
 }
 
@@ -173,15 +243,25 @@ impl RuleKind {
 ///
 /// In case of `ParametersPerKind::AcceptIfChildrenDirectoriesArePresent` or `ParametersPerKind::RejectIfChildrenDirectoriesArePresent`
 /// first we change the data structure to a vector, then we serialize it.
+///
+/// Rules about size, modification date and kind only apply to files, directories always pass them
+/// as their children are checked on their own.
 #[derive(Debug)]
 pub enum RulePerKind {
-	// TODO: Add an indexer rule that filter files based on their extended attributes
-	// https://learn.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants
-	// https://en.wikipedia.org/wiki/Extended_file_attributes
 	AcceptFilesByGlob(Vec<Glob>, GlobSet),
 	RejectFilesByGlob(Vec<Glob>, GlobSet),
 	AcceptIfChildrenDirectoriesArePresent(HashSet<String>),
 	RejectIfChildrenDirectoriesArePresent(HashSet<String>),
+	RejectFilesLargerThan(u64),
+	AcceptFilesModifiedWithinDays(u32),
+	AcceptFilesByKind(HashSet<ObjectKind>),
+	// TODO: Windows doesn't have extended attributes like unix does, it could use alternate data streams
+	// https://learn.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants
+	// https://en.wikipedia.org/wiki/Extended_file_attributes
+	RejectFilesByExtendedAttribute(HashSet<String>),
+	/// Names of the ignore files, from the lowest to the highest precedence. As they depend on
+	/// where a path is in the location, they're applied by the walkers through [`IgnoreFiles`]
+	RejectFilesByIgnoreFiles(Vec<String>),
 }
 
 impl RulePerKind {
@@ -246,6 +326,40 @@ impl Serialize for RulePerKind {
 					"RejectIfChildrenDirectoriesArePresent",
 					children,
 				),
+			RulePerKind::RejectFilesLargerThan(ref max_size) => serializer
+				.serialize_newtype_variant(
+					"ParametersPerKind",
+					4,
+					"RejectFilesLargerThan",
+					max_size,
+				),
+			RulePerKind::AcceptFilesModifiedWithinDays(ref days) => serializer
+				.serialize_newtype_variant(
+					"ParametersPerKind",
+					5,
+					"AcceptFilesModifiedWithinDays",
+					days,
+				),
+			RulePerKind::AcceptFilesByKind(ref kinds) => serializer.serialize_newtype_variant(
+				"ParametersPerKind",
+				6,
+				"AcceptFilesByKind",
+				kinds,
+			),
+			RulePerKind::RejectFilesByExtendedAttribute(ref attributes) => serializer
+				.serialize_newtype_variant(
+					"ParametersPerKind",
+					7,
+					"RejectFilesByExtendedAttribute",
+					attributes,
+				),
+			RulePerKind::RejectFilesByIgnoreFiles(ref file_names) => serializer
+				.serialize_newtype_variant(
+					"ParametersPerKind",
+					8,
+					"RejectFilesByIgnoreFiles",
+					file_names,
+				),
 		}
 	}
 }
@@ -260,6 +374,11 @@ impl<'de> Deserialize<'de> for RulePerKind {
 			"RejectFilesByGlob",
 			"AcceptIfChildrenDirectoriesArePresent",
 			"RejectIfChildrenDirectoriesArePresent",
+			"RejectFilesLargerThan",
+			"AcceptFilesModifiedWithinDays",
+			"AcceptFilesByKind",
+			"RejectFilesByExtendedAttribute",
+			"RejectFilesByIgnoreFiles",
 		];
 
 		enum Fields {
@@ -267,6 +386,11 @@ impl<'de> Deserialize<'de> for RulePerKind {
 			RejectFilesByGlob,
 			AcceptIfChildrenDirectoriesArePresent,
 			RejectIfChildrenDirectoriesArePresent,
+			RejectFilesLargerThan,
+			AcceptFilesModifiedWithinDays,
+			AcceptFilesByKind,
+			RejectFilesByExtendedAttribute,
+			RejectFilesByIgnoreFiles,
 		}
 
 		struct FieldsVisitor;
@@ -279,7 +403,12 @@ impl<'de> Deserialize<'de> for RulePerKind {
 					"`AcceptFilesByGlob` \
 				or `RejectFilesByGlob` \
 				or `AcceptIfChildrenDirectoriesArePresent` \
-				or `RejectIfChildrenDirectoriesArePresent`",
+				or `RejectIfChildrenDirectoriesArePresent` \
+				or `RejectFilesLargerThan` \
+				or `AcceptFilesModifiedWithinDays` \
+				or `AcceptFilesByKind` \
+				or `RejectFilesByExtendedAttribute` \
+				or `RejectFilesByIgnoreFiles`",
 				)
 			}
 
@@ -292,9 +421,14 @@ impl<'de> Deserialize<'de> for RulePerKind {
 					1 => Ok(Fields::RejectFilesByGlob),
 					2 => Ok(Fields::AcceptIfChildrenDirectoriesArePresent),
 					3 => Ok(Fields::RejectIfChildrenDirectoriesArePresent),
+					4 => Ok(Fields::RejectFilesLargerThan),
+					5 => Ok(Fields::AcceptFilesModifiedWithinDays),
+					6 => Ok(Fields::AcceptFilesByKind),
+					7 => Ok(Fields::RejectFilesByExtendedAttribute),
+					8 => Ok(Fields::RejectFilesByIgnoreFiles),
 					_ => Err(de::Error::invalid_value(
 						de::Unexpected::Unsigned(value),
-						&"variant index 0 <= i < 3",
+						&"variant index 0 <= i < 9",
 					)),
 				}
 			}
@@ -311,6 +445,11 @@ impl<'de> Deserialize<'de> for RulePerKind {
 					"RejectIfChildrenDirectoriesArePresent" => {
 						Ok(Fields::RejectIfChildrenDirectoriesArePresent)
 					}
+					"RejectFilesLargerThan" => Ok(Fields::RejectFilesLargerThan),
+					"AcceptFilesModifiedWithinDays" => Ok(Fields::AcceptFilesModifiedWithinDays),
+					"AcceptFilesByKind" => Ok(Fields::AcceptFilesByKind),
+					"RejectFilesByExtendedAttribute" => Ok(Fields::RejectFilesByExtendedAttribute),
+					"RejectFilesByIgnoreFiles" => Ok(Fields::RejectFilesByIgnoreFiles),
 					_ => Err(de::Error::unknown_variant(value, VARIANTS)),
 				}
 			}
@@ -327,6 +466,11 @@ impl<'de> Deserialize<'de> for RulePerKind {
 					b"RejectIfChildrenDirectoriesArePresent" => {
 						Ok(Fields::RejectIfChildrenDirectoriesArePresent)
 					}
+					b"RejectFilesLargerThan" => Ok(Fields::RejectFilesLargerThan),
+					b"AcceptFilesModifiedWithinDays" => Ok(Fields::AcceptFilesModifiedWithinDays),
+					b"AcceptFilesByKind" => Ok(Fields::AcceptFilesByKind),
+					b"RejectFilesByExtendedAttribute" => Ok(Fields::RejectFilesByExtendedAttribute),
+					b"RejectFilesByIgnoreFiles" => Ok(Fields::RejectFilesByIgnoreFiles),
 					_ => Err(de::Error::unknown_variant(
 						&String::from_utf8_lossy(bytes),
 						VARIANTS,
@@ -412,6 +556,33 @@ impl<'de> Deserialize<'de> for RulePerKind {
 						reject_if_children_directories_are_present,
 					)
 					.map(Self::Value::RejectIfChildrenDirectoriesArePresent),
+					(Fields::RejectFilesLargerThan, reject_files_larger_than) => {
+						de::VariantAccess::newtype_variant::<u64>(reject_files_larger_than)
+							.map(Self::Value::RejectFilesLargerThan)
+					}
+					(Fields::AcceptFilesModifiedWithinDays, accept_files_modified_within_days) => {
+						de::VariantAccess::newtype_variant::<u32>(accept_files_modified_within_days)
+							.map(Self::Value::AcceptFilesModifiedWithinDays)
+					}
+					(Fields::AcceptFilesByKind, accept_files_by_kind) => {
+						de::VariantAccess::newtype_variant::<HashSet<ObjectKind>>(
+							accept_files_by_kind,
+						)
+						.map(Self::Value::AcceptFilesByKind)
+					}
+					(
+						Fields::RejectFilesByExtendedAttribute,
+						reject_files_by_extended_attribute,
+					) => de::VariantAccess::newtype_variant::<HashSet<String>>(
+						reject_files_by_extended_attribute,
+					)
+					.map(Self::Value::RejectFilesByExtendedAttribute),
+					(Fields::RejectFilesByIgnoreFiles, reject_files_by_ignore_files) => {
+						de::VariantAccess::newtype_variant::<Vec<String>>(
+							reject_files_by_ignore_files,
+						)
+						.map(Self::Value::RejectFilesByIgnoreFiles)
+					}
 				})
 			}
 		}
@@ -449,6 +620,27 @@ impl RulePerKind {
 				RuleKind::RejectFilesByGlob,
 				reject_by_glob(source, reject_glob_set),
 			)),
+
+			RulePerKind::RejectFilesLargerThan(max_size) => reject_by_size(source, *max_size)
+				.await
+				.map(|rejected| (RuleKind::RejectFilesLargerThan, rejected)),
+			RulePerKind::AcceptFilesModifiedWithinDays(days) => {
+				accept_by_modified_date(source, *days)
+					.await
+					.map(|accepted| (RuleKind::AcceptFilesModifiedWithinDays, accepted))
+			}
+			RulePerKind::AcceptFilesByKind(kinds) => accept_by_kind(source, kinds)
+				.await
+				.map(|accepted| (RuleKind::AcceptFilesByKind, accepted)),
+			RulePerKind::RejectFilesByExtendedAttribute(attributes) => {
+				reject_by_extended_attribute(source, attributes)
+					.map(|rejected| (RuleKind::RejectFilesByExtendedAttribute, rejected))
+			}
+			// Ignore files depend on the path's ancestors, so the walkers handle them with
+			// `IgnoreFiles`, here we just let the path pass
+			RulePerKind::RejectFilesByIgnoreFiles(_) => {
+				Ok((RuleKind::RejectFilesByIgnoreFiles, true))
+			}
 		}
 	}
 }
@@ -487,6 +679,24 @@ impl IndexerRule {
 				)
 			})
 	}
+
+	/// Returns the first of [`RuleKind::REJECT_KINDS`] that rejected the path, if any
+	pub fn rejected_by(rules_per_kind: &HashMap<RuleKind, Vec<bool>>) -> Option<RuleKind> {
+		RuleKind::REJECT_KINDS.into_iter().find(|kind| {
+			rules_per_kind.get(kind).map_or(false, |reject_results| {
+				reject_results.iter().any(|reject| !reject)
+			})
+		})
+	}
+
+	/// Returns the first of [`RuleKind::ACCEPT_KINDS`] that didn't accept the path, if any
+	pub fn not_accepted_by(rules_per_kind: &HashMap<RuleKind, Vec<bool>>) -> Option<RuleKind> {
+		RuleKind::ACCEPT_KINDS.into_iter().find(|kind| {
+			rules_per_kind.get(kind).map_or(false, |accept_results| {
+				accept_results.iter().all(|accept| !accept)
+			})
+		})
+	}
 }
 
 impl TryFrom<&indexer_rule::Data> for IndexerRule {
@@ -609,6 +819,103 @@ async fn reject_dir_for_its_children(
 	Ok(true)
 }
 
+fn single_parameter<T: FromStr>(
+	kind: RuleKind,
+	parameters: Vec<String>,
+) -> Result<T, IndexerRuleError> {
+	match parameters.as_slice() {
+		[parameter] => parameter.trim().parse().map_err(|_| {
+			IndexerRuleError::InvalidRuleParameters(kind, format!("invalid number \"{parameter}\""))
+		}),
+		_ => Err(IndexerRuleError::InvalidRuleParameters(
+			kind,
+			format!("expected a single parameter, got {}", parameters.len()),
+		)),
+	}
+}
+
+async fn file_metadata(source: &Path) -> Result<std::fs::Metadata, IndexerRuleError> {
+	fs::metadata(source)
+		.await
+		.map_err(|e| IndexerRuleError::FileMetadataFileIO(FileIOError::from((source, e))))
+}
+
+async fn reject_by_size(source: impl AsRef<Path>, max_size: u64) -> Result<bool, IndexerRuleError> {
+	let metadata = file_metadata(source.as_ref()).await?;
+
+	Ok(metadata.is_dir() || metadata.len() <= max_size)
+}
+
+async fn accept_by_modified_date(
+	source: impl AsRef<Path>,
+	days: u32,
+) -> Result<bool, IndexerRuleError> {
+	let source = source.as_ref();
+	let metadata = file_metadata(source).await?;
+
+	// Old directories can still have recently modified files in them
+	if metadata.is_dir() {
+		return Ok(true);
+	}
+
+	let modified = metadata
+		.modified()
+		.map_err(|e| IndexerRuleError::FileMetadataFileIO(FileIOError::from((source, e))))?;
+
+	// Files modified in the future are accepted, as we can't tell how old they really are
+	Ok(SystemTime::now()
+		.duration_since(modified)
+		.map_or(true, |age| {
+			age <= Duration::from_secs(u64::from(days) * 24 * 60 * 60)
+		}))
+}
+
+async fn accept_by_kind(
+	source: impl AsRef<Path>,
+	kinds: &HashSet<ObjectKind>,
+) -> Result<bool, IndexerRuleError> {
+	let source = source.as_ref();
+
+	if file_metadata(source).await?.is_dir() {
+		return Ok(true);
+	}
+
+	let kind = Extension::resolve_conflicting(source, false)
+		.await
+		.map(Into::into)
+		.unwrap_or(ObjectKind::Unknown);
+
+	Ok(kinds.contains(&kind))
+}
+
+#[cfg(unix)]
+fn reject_by_extended_attribute(
+	source: impl AsRef<Path>,
+	attributes: &HashSet<String>,
+) -> Result<bool, IndexerRuleError> {
+	let source = source.as_ref();
+
+	match xattr::list(source) {
+		Ok(mut names) => Ok(!names.any(|name| {
+			name.to_str()
+				.map_or(false, |name| attributes.contains(name))
+		})),
+		// Filesystems without extended attributes can't have the ones we're looking for
+		Err(e) if e.kind() == std::io::ErrorKind::Unsupported => Ok(true),
+		Err(e) => Err(IndexerRuleError::ExtendedAttributesFileIO(
+			FileIOError::from((source, e)),
+		)),
+	}
+}
+
+#[cfg(not(unix))]
+fn reject_by_extended_attribute(
+	_source: impl AsRef<Path>,
+	_attributes: &HashSet<String>,
+) -> Result<bool, IndexerRuleError> {
+	Ok(true)
+}
+
 pub fn generate_pub_id() -> Uuid {
 	loop {
 		let pub_id = Uuid::new_v4();
@@ -796,6 +1103,58 @@ mod tests {
 		assert!(check_rule(&rule, not_project).await);
 	}
 
+	#[tokio::test]
+	async fn test_file_metadata_rules() {
+		let root = tempdir().unwrap();
+
+		let dir = root.path().join("dir");
+		let small = root.path().join("small.txt");
+		let big = root.path().join("big.png");
+
+		fs::create_dir(&dir).await.unwrap();
+		fs::write(&small, b"small").await.unwrap();
+		fs::write(&big, vec![0; 1024]).await.unwrap();
+
+		let rule = IndexerRule::new(
+			"no big files".to_string(),
+			false,
+			vec![RulePerKind::RejectFilesLargerThan(512)],
+		);
+
+		assert!(check_rule(&rule, &dir).await);
+		assert!(check_rule(&rule, &small).await);
+		assert!(!check_rule(&rule, &big).await);
+
+		let rule = IndexerRule::new(
+			"only images".to_string(),
+			false,
+			vec![RulePerKind::AcceptFilesByKind(
+				[ObjectKind::Image].into_iter().collect(),
+			)],
+		);
+
+		assert!(check_rule(&rule, &dir).await);
+		assert!(!check_rule(&rule, &small).await);
+		assert!(check_rule(&rule, &big).await);
+
+		let rule = IndexerRule::new(
+			"modified today".to_string(),
+			false,
+			vec![RulePerKind::AcceptFilesModifiedWithinDays(1)],
+		);
+
+		assert!(check_rule(&rule, &small).await);
+
+		let rule = IndexerRule::new(
+			"modified right now".to_string(),
+			false,
+			vec![RulePerKind::AcceptFilesModifiedWithinDays(0)],
+		);
+
+		assert!(check_rule(&rule, &dir).await);
+		assert!(!check_rule(&rule, &small).await);
+	}
+
 	impl PartialEq for RulePerKind {
 		fn eq(&self, other: &Self) -> bool {
 			match (self, other) {
@@ -815,6 +1174,26 @@ mod tests {
 					RulePerKind::RejectIfChildrenDirectoriesArePresent(self_childrens),
 					RulePerKind::RejectIfChildrenDirectoriesArePresent(other_childrens),
 				) => self_childrens == other_childrens,
+				(
+					RulePerKind::RejectFilesLargerThan(self_max_size),
+					RulePerKind::RejectFilesLargerThan(other_max_size),
+				) => self_max_size == other_max_size,
+				(
+					RulePerKind::AcceptFilesModifiedWithinDays(self_days),
+					RulePerKind::AcceptFilesModifiedWithinDays(other_days),
+				) => self_days == other_days,
+				(
+					RulePerKind::AcceptFilesByKind(self_kinds),
+					RulePerKind::AcceptFilesByKind(other_kinds),
+				) => self_kinds == other_kinds,
+				(
+					RulePerKind::RejectFilesByExtendedAttribute(self_attributes),
+					RulePerKind::RejectFilesByExtendedAttribute(other_attributes),
+				) => self_attributes == other_attributes,
+				(
+					RulePerKind::RejectFilesByIgnoreFiles(self_file_names),
+					RulePerKind::RejectFilesByIgnoreFiles(other_file_names),
+				) => self_file_names == other_file_names,
 				_ => false,
 			}
 		}
@@ -848,6 +1227,31 @@ mod tests {
 			)],
 		);
 
+		let expected =
+			rmp_serde::from_slice::<IndexerRule>(&rmp_serde::to_vec_named(&actual).unwrap())
+				.unwrap();
+
+		assert_eq!(actual, expected);
+	}
+	#[test]
+	fn serde_file_metadata_rules() {
+		let actual = IndexerRule::new(
+			"Recent small videos".to_string(),
+			false,
+			vec![
+				RulePerKind::RejectFilesLargerThan(1024 * 1024),
+				RulePerKind::AcceptFilesModifiedWithinDays(30),
+				RulePerKind::AcceptFilesByKind([ObjectKind::Video].into_iter().collect()),
+				RulePerKind::RejectFilesByExtendedAttribute(
+					["user.sd.ignore".to_string()].into_iter().collect(),
+				),
+				RulePerKind::RejectFilesByIgnoreFiles(vec![
+					".gitignore".to_string(),
+					".sdignore".to_string(),
+				]),
+			],
+		);
+
 		let expected =
 			rmp_serde::from_slice::<IndexerRule>(&rmp_serde::to_vec_named(&actual).unwrap())
 				.unwrap();
@@ -37,9 +37,16 @@ impl From<SystemIndexerRule> for IndexerRule {
 /// Seeds system indexer rules into a new or existing library,
 pub async fn new_or_existing_library(library: &Library) -> Result<(), SeederError> {
 	// DO NOT REORDER THIS ARRAY!
-	for (i, rule) in [no_os_protected(), no_hidden(), no_git(), only_images()]
-		.into_iter()
-		.enumerate()
+	for (i, rule) in [
+		no_os_protected(),
+		no_hidden(),
+		no_git(),
+		only_images(),
+		no_ignored(),
+		respect_ignore_files(),
+	]
+	.into_iter()
+	.enumerate()
 	{
 		let pub_id = sd_utils::uuid_to_bytes(Uuid::from_u128(i as u128));
 		let rules = rmp_serde::to_vec_named(&rule.rules).map_err(IndexerRuleError::from)?;
@@ -205,3 +212,27 @@ fn only_images() -> SystemIndexerRule {
 		.expect("this is hardcoded and should always work")],
 	}
 }
+
+/// Files can be marked to be ignored by Spacedrive with `xattr -w user.sd.ignore 1 <path>`
+fn no_ignored() -> SystemIndexerRule {
+	SystemIndexerRule {
+		name: "No Ignored",
+		default: false,
+		rules: vec![RulePerKind::RejectFilesByExtendedAttribute(
+			["user.sd.ignore".to_string()].into_iter().collect(),
+		)],
+	}
+}
+
+/// Rejects whatever is listed on `.gitignore` and `.sdignore` files, from the location root down to
+/// each directory
+pub fn respect_ignore_files() -> SystemIndexerRule {
+	SystemIndexerRule {
+		name: "Respect Ignore Files",
+		default: false,
+		rules: vec![RulePerKind::RejectFilesByIgnoreFiles(vec![
+			".gitignore".to_string(),
+			".sdignore".to_string(),
+		])],
+	}
+}
@@ -77,6 +77,7 @@ pub async fn shallow(
 
 	let (walked, to_update, to_remove, errors, _s) = {
 		walk_single_dir(
+			location_path,
 			&to_walk_path,
 			&indexer_rules,
 			|_, _| {},
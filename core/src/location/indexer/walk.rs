@@ -20,7 +20,7 @@ use tracing::trace;
 use uuid::Uuid;
 
 use super::{
-	rules::{IndexerRule, RuleKind},
+	rules::{IgnoreFiles, IndexerRule, RuleKind},
 	IndexerError,
 };
 
@@ -43,6 +43,10 @@ pub struct ToWalkEntry {
 	path: PathBuf,
 	parent_dir_accepted_by_its_children: Option<bool>,
 	maybe_parent: Option<PathBuf>,
+	/// Ignore files from the ancestors of `path`, they aren't kept on the job's state as we can
+	/// just read them again when resuming
+	#[serde(skip)]
+	ignore_files: Option<IgnoreFiles>,
 }
 
 #[derive(Debug)]
@@ -119,6 +123,7 @@ where
 /// a list of accepted entries. There are some useful comments in the implementation of this function
 /// in case of doubts.
 pub(super) async fn walk<FilePathDBFetcherFut, ToRemoveDbFetcherFut>(
+	location_root: impl AsRef<Path>,
 	root: impl AsRef<Path>,
 	indexer_rules: &[IndexerRule],
 	mut update_notifier: impl FnMut(&Path, usize),
@@ -149,6 +154,11 @@ where
 		path: root.to_path_buf(),
 		parent_dir_accepted_by_its_children: None,
 		maybe_parent: None,
+		ignore_files: Some(
+			IgnoreFiles::new(indexer_rules)
+				.with_ancestors(location_root, root)
+				.await,
+		),
 	});
 	let mut indexed_paths = HashSet::with_capacity(WALKER_PATHS_BUFFER_INITIAL_CAPACITY);
 	let mut errors = vec![];
@@ -160,6 +170,10 @@ where
 		let (entry_size, current_to_remove) = inner_walk_single_dir(
 			root,
 			&entry,
+			entry
+				.ignore_files
+				.as_ref()
+				.unwrap_or(&IgnoreFiles::default()),
 			indexer_rules,
 			&mut update_notifier,
 			&to_remove_db_fetcher,
@@ -200,6 +214,7 @@ where
 }
 
 pub(super) async fn keep_walking<FilePathDBFetcherFut, ToRemoveDbFetcherFut>(
+	location_root: impl AsRef<Path>,
 	to_walk_entry: &ToWalkEntry,
 	indexer_rules: &[IndexerRule],
 	mut update_notifier: impl FnMut(&Path, usize),
@@ -227,9 +242,20 @@ where
 	let mut paths_buffer = HashSet::with_capacity(WALK_SINGLE_DIR_PATHS_BUFFER_INITIAL_CAPACITY);
 	let mut errors = vec![];
 
+	// Entries restored from a paused job lose their ignore files, so we read them again
+	let ignore_files = match &to_walk_entry.ignore_files {
+		Some(ignore_files) => ignore_files.clone(),
+		None => {
+			IgnoreFiles::new(indexer_rules)
+				.with_ancestors(location_root, &to_walk_entry.path)
+				.await
+		}
+	};
+
 	let (to_walk_entry_size, to_remove) = inner_walk_single_dir(
 		to_walk_entry.path.clone(),
 		to_walk_entry,
+		&ignore_files,
 		indexer_rules,
 		&mut update_notifier,
 		&to_remove_db_fetcher,
@@ -265,6 +291,7 @@ where
 }
 
 pub(super) async fn walk_single_dir<FilePathDBFetcherFut, ToRemoveDbFetcherFut>(
+	location_root: impl AsRef<Path>,
 	root: impl AsRef<Path>,
 	indexer_rules: &[IndexerRule],
 	mut update_notifier: impl FnMut(&Path, usize) + '_,
@@ -308,13 +335,19 @@ where
 	let mut paths_buffer = HashSet::with_capacity(WALK_SINGLE_DIR_PATHS_BUFFER_INITIAL_CAPACITY);
 	let mut errors = vec![];
 
+	let ignore_files = IgnoreFiles::new(indexer_rules)
+		.with_ancestors(location_root, root)
+		.await;
+
 	let (root_size, to_remove) = inner_walk_single_dir(
 		root,
 		&ToWalkEntry {
 			path: root.to_path_buf(),
 			parent_dir_accepted_by_its_children: None,
 			maybe_parent: None,
+			ignore_files: None,
 		},
+		&ignore_files,
 		indexer_rules,
 		&mut update_notifier,
 		&to_remove_db_fetcher,
@@ -438,6 +471,7 @@ async fn inner_walk_single_dir<ToRemoveDbFetcherFut>(
 		parent_dir_accepted_by_its_children,
 		..
 	}: &ToWalkEntry,
+	ignore_files: &IgnoreFiles,
 	indexer_rules: &[IndexerRule],
 	update_notifier: &mut impl FnMut(&Path, usize),
 	to_remove_db_fetcher: impl Fn(
@@ -470,6 +504,9 @@ where
 
 	let root = root.as_ref();
 
+	// The ignore files of this directory apply to all of its entries and their children
+	let ignore_files = ignore_files.with_dir(path).await;
+
 	// Just to make sure...
 	paths_buffer.clear();
 
@@ -518,13 +555,9 @@ where
 			continue 'entries;
 		};
 
-		if rules_per_kind
-			.get(&RuleKind::RejectFilesByGlob)
-			.map_or(false, |reject_results| {
-				reject_results.iter().any(|reject| !reject)
-			}) {
+		if let Some(kind) = IndexerRule::rejected_by(&rules_per_kind) {
 			trace!(
-				"Path {} rejected by `RuleKind::RejectFilesByGlob`",
+				"Path {} rejected by `RuleKind::{kind:?}`",
 				current_path.display()
 			);
 			continue 'entries;
@@ -545,6 +578,14 @@ where
 
 		let is_dir = metadata.is_dir();
 
+		if ignore_files.is_ignored(&current_path, is_dir) {
+			trace!(
+				"Path {} rejected by `RuleKind::RejectFilesByIgnoreFiles`",
+				current_path.display()
+			);
+			continue 'entries;
+		}
+
 		if is_dir {
 			// If it is a directory, first we check if we must reject it and its children entirely
 			if rules_per_kind
@@ -583,17 +624,14 @@ where
 					path: current_path.clone(),
 					parent_dir_accepted_by_its_children: accept_by_children_dir,
 					maybe_parent: Some(path.clone()),
+					ignore_files: Some(ignore_files.clone()),
 				});
 			}
 		}
 
-		if rules_per_kind
-			.get(&RuleKind::AcceptFilesByGlob)
-			.map_or(false, |accept_rules| {
-				accept_rules.iter().all(|accept| !accept)
-			}) {
+		if let Some(kind) = IndexerRule::not_accepted_by(&rules_per_kind) {
 			trace!(
-				"Path {} reject because it didn't passed in any AcceptFilesByGlob rules",
+				"Path {} reject because it didn't passed in any {kind:?} rules",
 				current_path.display()
 			);
 			continue 'entries;
@@ -823,6 +861,7 @@ mod tests {
 		.collect::<HashSet<_>>();
 
 		let walk_result = walk(
+			root_path,
 			root_path.to_path_buf(),
 			&[],
 			|_, _| {},
@@ -888,6 +927,7 @@ mod tests {
 		)];
 
 		let walk_result = walk(
+			root_path,
 			root_path.to_path_buf(),
 			only_photos_rule,
 			|_, _| {},
@@ -962,6 +1002,7 @@ mod tests {
 		)];
 
 		let walk_result = walk(
+			root_path,
 			root_path.to_path_buf(),
 			git_repos,
 			|_, _| {},
@@ -1054,6 +1095,7 @@ mod tests {
 		];
 
 		let walk_result = walk(
+			root_path,
 			root_path.to_path_buf(),
 			git_repos_no_deps_no_build_dirs,
 			|_, _| {},
@@ -24,7 +24,7 @@ use tracing::error;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
-use super::file_path_helper::FilePathError;
+use super::{file_path_helper::FilePathError, indexer::rules::IndexerRuleError};
 
 #[cfg(feature = "location-watcher")]
 mod watcher;
@@ -110,6 +110,8 @@ pub enum LocationManagerError {
 	JobManager(#[from] JobManagerError),
 	#[error("missing-field")]
 	MissingField(#[from] MissingFieldError),
+	#[error("Indexer rule error: (error: {0})")]
+	IndexerRule(#[from] IndexerRuleError),
 
 	#[error(transparent)]
 	FileIO(#[from] FileIOError),
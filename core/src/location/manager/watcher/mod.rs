@@ -27,7 +27,7 @@ mod windows;
 
 mod utils;
 
-use utils::check_event;
+use utils::{check_event, rescan_on_ignore_file_change};
 
 #[cfg(target_os = "linux")]
 type Handler<'lib> = linux::LinuxEventHandler<'lib>;
@@ -190,8 +190,8 @@ impl LocationWatcher {
 		location_pub_id: Uuid,
 		event: Event,
 		event_handler: &mut impl EventHandler<'lib>,
-		node: &'lib Node,
-		_library: &'lib Library,
+		node: &'lib Arc<Node>,
+		library: &'lib Arc<Library>,
 		ignore_paths: &HashSet<PathBuf>,
 	) -> Result<(), LocationManagerError> {
 		if !check_event(&event, ignore_paths) {
@@ -212,6 +212,10 @@ impl LocationWatcher {
 			return Ok(());
 		}
 
+		if let Err(e) = rescan_on_ignore_file_change(location_id, &event, node, library).await {
+			error!("Failed to rescan location after ignore files changed: {e:#?}");
+		}
+
 		event_handler.handle_event(event).await
 	}
 
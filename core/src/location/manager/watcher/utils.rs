@@ -11,18 +11,19 @@ use crate::{
 			IsolatedFilePathData, MetadataExt,
 		},
 		find_location,
-		indexer::reverse_update_directories_sizes,
+		indexer::{
+			reverse_update_directories_sizes,
+			rules::{IgnoreFiles, IndexerRule},
+		},
 		location_with_indexer_rules,
 		manager::LocationManagerError,
 		scan_location_sub_path, update_location_size,
 	},
 	object::{
-		file_identifier::FileMetadata,
-		media::{
-			media_data_extractor::{can_extract_media_data_for_image, extract_media_data},
-			media_data_image_to_query_params,
-			thumbnail::get_indexed_thumbnail_path,
-		},
+		album::refresh_smart_albums,
+		content::remove_content,
+		file_identifier::{FileMetadata, IntegrityChecker},
+		media::{media_data_extractor::extract_any_media_data, media_data_to_query_params},
 		validation::hash::file_checksum,
 	},
 	prisma::{file_path, location, object},
@@ -44,14 +45,13 @@ use std::{
 	ffi::OsStr,
 	fs::Metadata,
 	path::{Path, PathBuf},
-	str::FromStr,
 	sync::Arc,
 };
 
-use sd_file_ext::{extensions::ImageExtension, kind::ObjectKind};
+use sd_file_ext::kind::ObjectKind;
 
 use chrono::{DateTime, FixedOffset, Local, Utc};
-use notify::Event;
+use notify::{Event, EventKind};
 use prisma_client_rust::{raw, PrismaValue};
 use sd_prisma::{prisma::media_data, prisma_sync};
 use sd_sync::OperationFactory;
@@ -101,6 +101,10 @@ pub(super) async fn create_dir(
 		path.display()
 	);
 
+	if !accepted_by_indexer_rules(&location, path, true).await? {
+		return Ok(());
+	}
+
 	let iso_file_path = IsolatedFilePathData::new(location.id, location_path, path, true)?;
 
 	let parent_iso_file_path = iso_file_path.parent();
@@ -137,6 +141,113 @@ pub(super) async fn create_dir(
 	Ok(())
 }
 
+/// New paths found by the watcher don't go through the walker, so we check the location's indexer
+/// rules here. Like the walker, directories are only checked against reject rules, as their
+/// children can still be accepted. Rules about children directories depend on the walked ancestors,
+/// so they're left to the indexer job dispatched for new directories.
+///
+/// Ignore files are read from every directory between the location root and the path.
+async fn accepted_by_indexer_rules(
+	location: &location_with_indexer_rules::Data,
+	path: &Path,
+	is_dir: bool,
+) -> Result<bool, LocationManagerError> {
+	let indexer_rules = location
+		.indexer_rules
+		.iter()
+		.map(|rule| IndexerRule::try_from(&rule.indexer_rule))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let rules_per_kind = IndexerRule::apply_all(&indexer_rules, path).await?;
+
+	if let Some(kind) = IndexerRule::rejected_by(&rules_per_kind).or_else(|| {
+		(!is_dir)
+			.then(|| IndexerRule::not_accepted_by(&rules_per_kind))
+			.flatten()
+	}) {
+		trace!(
+			"Watcher ignoring path {} due to `RuleKind::{kind:?}`",
+			path.display()
+		);
+		return Ok(false);
+	}
+
+	let ignore_files = IgnoreFiles::new(&indexer_rules);
+	if !ignore_files.is_empty()
+		&& ignore_files
+			.with_ancestors(maybe_missing(&location.path, "location.path")?, path)
+			.await
+			.is_ignored(path, is_dir)
+	{
+		trace!(
+			"Watcher ignoring path {} due to `RuleKind::RejectFilesByIgnoreFiles`",
+			path.display()
+		);
+		return Ok(false);
+	}
+
+	Ok(true)
+}
+
+/// Changing an ignore file (like `.gitignore`) changes what should be indexed below its directory,
+/// so we scan that directory again, which also removes the paths that are ignored now
+pub(super) async fn rescan_on_ignore_file_change(
+	location_id: location::id::Type,
+	event: &Event,
+	node: &Arc<Node>,
+	library: &Arc<Library>,
+) -> Result<(), LocationManagerError> {
+	if matches!(event.kind, EventKind::Access(_)) {
+		return Ok(());
+	}
+
+	let location = find_location(library, location_id)
+		.include(location_with_indexer_rules::include())
+		.exec()
+		.await?
+		.ok_or(LocationManagerError::MissingLocation(location_id))?;
+
+	let ignore_files = IgnoreFiles::new(
+		&location
+			.indexer_rules
+			.iter()
+			.map(|rule| IndexerRule::try_from(&rule.indexer_rule))
+			.collect::<Result<Vec<_>, _>>()?,
+	);
+
+	let dirs_to_rescan = event
+		.paths
+		.iter()
+		.filter(|path| ignore_files.is_ignore_file(path))
+		.filter_map(|path| path.parent())
+		.collect::<HashSet<_>>();
+
+	if dirs_to_rescan.is_empty() {
+		return Ok(());
+	}
+
+	let location_path = maybe_missing(&location.path, "location.path")?;
+
+	for dir in dirs_to_rescan {
+		let sub_path = if dir == Path::new(location_path) {
+			"/".to_string()
+		} else {
+			IsolatedFilePathData::new(location.id, location_path, dir, true)?
+				.materialized_path_for_children()
+				.expect("we're building it as a directory")
+		};
+
+		debug!(
+			"Ignore files changed at {}, rescanning <location_id={location_id}, sub_path={sub_path}>",
+			dir.display()
+		);
+
+		scan_location_sub_path(node, library, location.clone(), sub_path).await?;
+	}
+
+	Ok(())
+}
+
 pub(super) async fn create_file(
 	location_id: location::id::Type,
 	path: impl AsRef<Path>,
@@ -177,6 +288,16 @@ async fn inner_create_file(
 		path.display()
 	);
 
+	let location = find_location(library, location_id)
+		.include(location_with_indexer_rules::include())
+		.exec()
+		.await?
+		.ok_or(LocationManagerError::MissingLocation(location_id))?;
+
+	if !accepted_by_indexer_rules(&location, path, false).await? {
+		return Ok(());
+	}
+
 	let iso_file_path = IsolatedFilePathData::new(location_id, location_path, path, false)?;
 	let extension = iso_file_path.extension.to_string();
 
@@ -246,15 +367,44 @@ async fn inner_create_file(
 
 	object::select!(object_ids { id pub_id });
 
-	let existing_object = db
-		.object()
-		.find_first(vec![object::file_paths::some(vec![
-			file_path::cas_id::equals(cas_id.clone()),
-			file_path::pub_id::not(created_file.pub_id.clone()),
-		])])
-		.select(object_ids::select())
+	let strict_identity = db
+		.location()
+		.find_unique(location::id::equals(location_id))
+		.select(location::select!({ strict_identity }))
 		.exec()
-		.await?;
+		.await?
+		.and_then(|location| location.strict_identity)
+		.unwrap_or(false);
+
+	let existing_object = match &cas_id {
+		Some(cas_id) if strict_identity => {
+			let mut checker = IntegrityChecker::new(library, vec![cas_id.clone()]).await?;
+			let object_pub_id = checker
+				.find_object(&created_file.pub_id, path, cas_id)
+				.await?;
+			checker.save().await?;
+
+			if let Some(object_pub_id) = object_pub_id {
+				db.object()
+					.find_unique(object::pub_id::equals(object_pub_id))
+					.select(object_ids::select())
+					.exec()
+					.await?
+			} else {
+				None
+			}
+		}
+		_ => {
+			db.object()
+				.find_first(vec![object::file_paths::some(vec![
+					file_path::cas_id::equals(cas_id.clone()),
+					file_path::pub_id::not(created_file.pub_id.clone()),
+				])])
+				.select(object_ids::select())
+				.exec()
+				.await?
+		}
+	};
 
 	let object_ids::Data {
 		id: object_id,
@@ -312,7 +462,16 @@ async fn inner_create_file(
 	)
 	.await?;
 
-	if !extension.is_empty() && matches!(kind, ObjectKind::Image | ObjectKind::Video) {
+	if !extension.is_empty()
+		&& matches!(
+			kind,
+			ObjectKind::Image
+				| ObjectKind::Video
+				| ObjectKind::Audio
+				| ObjectKind::Text
+				| ObjectKind::Code
+				| ObjectKind::Config
+		) {
 		// Running in a detached task as thumbnail generation can take a while and we don't want to block the watcher
 
 		if let Some(cas_id) = cas_id {
@@ -321,11 +480,14 @@ async fn inner_create_file(
 				let path = path.to_path_buf();
 				let node = node.clone();
 				let library_id = *library_id;
+				let db = Arc::clone(db);
 
 				async move {
 					if let Err(e) = node
 						.thumbnailer
-						.generate_single_indexed_thumbnail(&extension, cas_id, path, library_id)
+						.generate_single_indexed_thumbnail(
+							&extension, cas_id, path, library_id, &db,
+						)
 						.await
 					{
 						error!("Failed to generate thumbnail in the watcher: {e:#?}");
@@ -334,31 +496,30 @@ async fn inner_create_file(
 			});
 		}
 
-		// TODO: Currently we only extract media data for images, remove this if later
-		if matches!(kind, ObjectKind::Image) {
-			if let Ok(image_extension) = ImageExtension::from_str(&extension) {
-				if can_extract_media_data_for_image(&image_extension) {
-					if let Ok(media_data) = extract_media_data(path)
-						.await
-						.map_err(|e| error!("Failed to extract media data: {e:#?}"))
-					{
-						if let Ok(media_data_params) = media_data_image_to_query_params(media_data)
-							.map_err(|e| {
-								error!("Failed to prepare media data create params: {e:#?}")
-							}) {
-							db.media_data()
-								.upsert(
-									media_data::object_id::equals(object_id),
-									media_data::create(
-										object::id::equals(object_id),
-										media_data_params.clone(),
-									),
-									media_data_params,
-								)
-								.exec()
-								.await?;
-						}
-					}
+		if matches!(
+			kind,
+			ObjectKind::Image | ObjectKind::Audio | ObjectKind::Video
+		) {
+			if let Ok(Some(media_data)) = extract_any_media_data(&extension, path)
+				.await
+				.map_err(|e| error!("Failed to extract media data: {e:#?}"))
+			{
+				if let Ok(media_data_params) = media_data_to_query_params(media_data)
+					.map_err(|e| error!("Failed to prepare media data create params: {e:#?}"))
+				{
+					db.media_data()
+						.upsert(
+							media_data::object_id::equals(object_id),
+							media_data::create(
+								object::id::equals(object_id),
+								media_data_params.clone(),
+							),
+							media_data_params,
+						)
+						.exec()
+						.await?;
+
+					refresh_smart_albums(db, &[object_id]).await?;
 				}
 			}
 		}
@@ -567,6 +728,10 @@ async fn inner_update_file(
 				.exec()
 				.await? == 1
 			{
+				// The object is kept for the new content of its only file, so the extracted
+				// content is outdated
+				remove_content(db, &[object.id]).await?;
+
 				if object.kind.map(|k| k != int_kind).unwrap_or_default() {
 					sync.write_op(
 						db,
@@ -640,13 +805,14 @@ async fn inner_update_file(
 							let node = Arc::clone(node);
 							let path = full_path.to_path_buf();
 							let library_id = library.id;
+							let db = Arc::clone(db);
 							let old_cas_id = old_cas_id.clone();
 							spawn(async move {
 								let was_overwritten = old_cas_id == cas_id;
 								if let Err(e) = node
 									.thumbnailer
 									.generate_single_indexed_thumbnail(
-										&ext, cas_id, path, library_id,
+										&ext, cas_id, path, library_id, &db,
 									)
 									.await
 								{
@@ -656,15 +822,10 @@ async fn inner_update_file(
 								// If only a few bytes changed, cas_id will probably remains intact
 								// so we overwrote our previous thumbnail, so we can't remove it
 								if !was_overwritten {
-									// remove the old thumbnail as we're generating a new one
-									let thumb_path =
-										get_indexed_thumbnail_path(&node, &old_cas_id, library_id);
-									if let Err(e) = fs::remove_file(&thumb_path).await {
-										error!(
-											"Failed to remove old thumbnail: {:#?}",
-											FileIOError::from((thumb_path, e))
-										);
-									}
+									// remove the old thumbnails as we're generating new ones
+									node.thumbnailer
+										.remove_indexed_cas_ids(vec![old_cas_id], library_id)
+										.await;
 								}
 							});
 						}
@@ -672,32 +833,32 @@ async fn inner_update_file(
 				}
 			}
 
-			// TODO: Change this if to include ObjectKind::Video in the future
 			if let Some(ext) = &file_path.extension {
-				if let Ok(image_extension) = ImageExtension::from_str(ext) {
-					if can_extract_media_data_for_image(&image_extension)
-						&& matches!(kind, ObjectKind::Image)
+				if matches!(
+					kind,
+					ObjectKind::Image | ObjectKind::Audio | ObjectKind::Video
+				) {
+					if let Ok(Some(media_data)) = extract_any_media_data(ext, full_path)
+						.await
+						.map_err(|e| error!("Failed to extract media data: {e:#?}"))
 					{
-						if let Ok(media_data) = extract_media_data(full_path)
-							.await
-							.map_err(|e| error!("Failed to extract media data: {e:#?}"))
-						{
-							if let Ok(media_data_params) =
-								media_data_image_to_query_params(media_data).map_err(|e| {
-									error!("Failed to prepare media data create params: {e:#?}")
-								}) {
-								db.media_data()
-									.upsert(
-										media_data::object_id::equals(object.id),
-										media_data::create(
-											object::id::equals(object.id),
-											media_data_params.clone(),
-										),
-										media_data_params,
-									)
-									.exec()
-									.await?;
-							}
+						if let Ok(media_data_params) = media_data_to_query_params(media_data)
+							.map_err(|e| {
+								error!("Failed to prepare media data create params: {e:#?}")
+							}) {
+							db.media_data()
+								.upsert(
+									media_data::object_id::equals(object.id),
+									media_data::create(
+										object::id::equals(object.id),
+										media_data_params.clone(),
+									),
+									media_data_params,
+								)
+								.exec()
+								.await?;
+
+							refresh_smart_albums(db, &[object.id]).await?;
 						}
 					}
 				}
@@ -4,6 +4,7 @@ use crate::{
 	library::Library,
 	location::file_path_helper::filter_existing_file_path_params,
 	object::{
+		content::content_extractor_job::ContentExtractorJobInit,
 		file_identifier::{self, file_identifier_job::FileIdentifierJobInit},
 		media::{media_processor, MediaProcessorJobInit},
 	},
@@ -35,6 +36,7 @@ use tokio::{fs, io, time::Instant};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+pub mod archive;
 mod error;
 pub mod file_path_helper;
 pub mod indexer;
@@ -273,6 +275,7 @@ pub struct LocationUpdateArgs {
 	generate_preview_media: Option<bool>,
 	sync_preview_media: Option<bool>,
 	hidden: Option<bool>,
+	strict_identity: Option<bool>,
 	indexer_rules_ids: Vec<i32>,
 	path: Option<String>,
 }
@@ -316,6 +319,12 @@ impl LocationUpdateArgs {
 					location::hidden::set(Some(v)),
 				)
 			}),
+			self.strict_identity.map(|v| {
+				(
+					(location::strict_identity::NAME, json!(v)),
+					location::strict_identity::set(Some(v)),
+				)
+			}),
 			self.path.clone().map(|v| {
 				(
 					(location::path::NAME, json!(v)),
@@ -461,10 +470,14 @@ pub async fn scan_location(
 		sub_path: None,
 	})
 	.queue_next(MediaProcessorJobInit {
-		location: location_base_data,
+		location: location_base_data.clone(),
 		sub_path: None,
 		regenerate_thumbnails: false,
 	})
+	.queue_next(ContentExtractorJobInit {
+		location: location_base_data,
+		sub_path: None,
+	})
 	.spawn(node, library)
 	.await
 	.map_err(Into::into)
@@ -500,9 +513,13 @@ pub async fn scan_location_sub_path(
 		sub_path: Some(sub_path.clone()),
 	})
 	.queue_next(MediaProcessorJobInit {
+		location: location_base_data.clone(),
+		sub_path: Some(sub_path.clone()),
+		regenerate_thumbnails: false,
+	})
+	.queue_next(ContentExtractorJobInit {
 		location: location_base_data,
 		sub_path: Some(sub_path),
-		regenerate_thumbnails: false,
 	})
 	.spawn(node, library)
 	.await
@@ -862,6 +879,7 @@ impl From<location_with_indexer_rules::Data> for location::Data {
 			generate_preview_media: data.generate_preview_media,
 			sync_preview_media: data.sync_preview_media,
 			hidden: data.hidden,
+			strict_identity: data.strict_identity,
 			date_created: data.date_created,
 			file_paths: None,
 			indexer_rules: None,
@@ -885,6 +903,7 @@ impl From<&location_with_indexer_rules::Data> for location::Data {
 			generate_preview_media: data.generate_preview_media,
 			sync_preview_media: data.sync_preview_media,
 			hidden: data.hidden,
+			strict_identity: data.strict_identity,
 			date_created: data.date_created,
 			file_paths: None,
 			indexer_rules: None,
@@ -3,6 +3,7 @@ use crate::{
 	library::Library,
 	object::{
 		cas::generate_cas_id,
+		fs::archive::ArchiveError,
 		media::thumbnail::{get_ephemeral_thumb_key, BatchToProcess, GenerateThumbnailArgs},
 	},
 	prisma::location,
@@ -30,8 +31,8 @@ use tracing::{error, warn};
 use super::{
 	file_path_helper::{path_is_hidden, MetadataExt},
 	indexer::rules::{
-		seed::{no_hidden, no_os_protected},
-		IndexerRule, RuleKind,
+		seed::{no_hidden, no_os_protected, respect_ignore_files},
+		IgnoreFiles, IndexerRule, RuleKind,
 	},
 	normalize_path,
 };
@@ -46,12 +47,16 @@ pub enum NonIndexedLocationError {
 
 	#[error("database error: {0}")]
 	Database(#[from] prisma_client_rust::QueryError),
+
+	#[error(transparent)]
+	Archive(#[from] ArchiveError),
 }
 
 impl From<NonIndexedLocationError> for rspc::Error {
 	fn from(err: NonIndexedLocationError) -> Self {
 		match err {
-			NonIndexedLocationError::NotFound(_) => {
+			NonIndexedLocationError::NotFound(_)
+			| NonIndexedLocationError::Archive(ArchiveError::EntryNotFound(_, _)) => {
 				rspc::Error::with_cause(ErrorCode::NotFound, err.to_string(), err)
 			}
 			_ => rspc::Error::with_cause(ErrorCode::InternalServerError, err.to_string(), err),
@@ -103,12 +108,23 @@ pub async fn walk(
 
 	let rules = chain_optional_iter(
 		[IndexerRule::from(no_os_protected())],
-		[(!with_hidden_files).then(|| IndexerRule::from(no_hidden()))],
+		[
+			(!with_hidden_files).then(|| IndexerRule::from(no_hidden())),
+			(!with_hidden_files).then(|| IndexerRule::from(respect_ignore_files())),
+		],
 	);
 
+	// There is no location root here, so every ancestor can have ignore files for this directory
+	let ignore_files = IgnoreFiles::new(&rules)
+		.with_ancestors(path.ancestors().last().unwrap_or(path), path)
+		.await
+		.with_dir(path)
+		.await;
+
 	let mut thumbnails_to_generate = vec![];
-	// Generating thumbnails for PDFs is kinda slow, so we're leaving them for last in the batch
-	let mut document_thumbnails_to_generate = vec![];
+	// Generating thumbnails for PDFs and audio waveforms is kinda slow, so we're leaving them for
+	// last in the batch
+	let mut slow_thumbnails_to_generate = vec![];
 
 	while let Some(entry) = read_dir.next_entry().await.map_err(|e| (path, e))? {
 		let Ok((entry_path, name)) = normalize_path(entry.path())
@@ -122,6 +138,7 @@ pub async fn walk(
 			.map_err(|e| errors.push(e.into()))
 		{
 			// No OS Protected and No Hidden rules, must always be from this kind, should panic otherwise
+			// (ignore files are checked below, as they always pass here)
 			if rule_results[&RuleKind::RejectFilesByGlob]
 				.iter()
 				.any(|reject| !reject)
@@ -140,6 +157,10 @@ pub async fn walk(
 			continue;
 		};
 
+		if ignore_files.is_ignored(&entry_path, metadata.is_dir()) {
+			continue;
+		}
+
 		if metadata.is_dir() {
 			directories.push((entry_path, name, metadata));
 		} else {
@@ -168,13 +189,22 @@ pub async fn walk(
 				{
 					matches!(
 						kind,
-						ObjectKind::Image | ObjectKind::Video | ObjectKind::Document
+						ObjectKind::Image
+							| ObjectKind::Video | ObjectKind::Document
+							| ObjectKind::Audio | ObjectKind::Text
+							| ObjectKind::Code | ObjectKind::Config
 					)
 				}
 
 				#[cfg(not(feature = "ffmpeg"))]
 				{
-					matches!(kind, ObjectKind::Image | ObjectKind::Document)
+					matches!(
+						kind,
+						ObjectKind::Image
+							| ObjectKind::Document | ObjectKind::Audio
+							| ObjectKind::Text | ObjectKind::Code
+							| ObjectKind::Config
+					)
 				}
 			};
 
@@ -183,8 +213,13 @@ pub async fn walk(
 					.await
 					.map_err(|e| errors.push(NonIndexedLocationError::from((path, e)).into()))
 				{
-					if kind == ObjectKind::Document {
-						document_thumbnails_to_generate.push(GenerateThumbnailArgs::new(
+					if matches!(
+						kind,
+						ObjectKind::Document
+							| ObjectKind::Audio | ObjectKind::Text
+							| ObjectKind::Code | ObjectKind::Config
+					) {
+						slow_thumbnails_to_generate.push(GenerateThumbnailArgs::new(
 							extension.clone(),
 							cas_id.clone(),
 							path.to_path_buf(),
@@ -223,7 +258,7 @@ pub async fn walk(
 		}
 	}
 
-	thumbnails_to_generate.extend(document_thumbnails_to_generate);
+	thumbnails_to_generate.extend(slow_thumbnails_to_generate);
 
 	node.thumbnailer
 		.new_ephemeral_thumbnails_batch(BatchToProcess::new(thumbnails_to_generate, false, false))
@@ -0,0 +1,393 @@
+use crate::{
+	invalidate_query,
+	library::Library,
+	prisma::{album, media_data, object, object_in_album, PrismaClient, SortOrder},
+};
+
+use sd_media_metadata::image::{CameraData, MediaLocation};
+use sd_prisma::prisma_sync::{self, ModelSyncData};
+use sd_sync::*;
+use sd_utils::chain_optional_iter;
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use prisma_client_rust::{not, QueryError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tracing::error;
+use uuid::Uuid;
+
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Type, Deserialize, Clone)]
+pub struct AlbumCreateArgs {
+	pub name: String,
+	#[specta(optional)]
+	pub smart_filter: Option<SmartAlbumFilter>,
+}
+
+impl AlbumCreateArgs {
+	/// Smart albums are filled right away, from the media data extracted so far
+	pub async fn exec(
+		self,
+		Library { db, sync, .. }: &Library,
+	) -> prisma_client_rust::Result<album::Data> {
+		let pub_id = Uuid::new_v4().as_bytes().to_vec();
+		let date_created: DateTime<FixedOffset> = Utc::now().into();
+		let smart_filter = self.smart_filter.as_ref().map(|filter| {
+			serde_json::to_string(filter).expect("smart album filters are always serializable")
+		});
+
+		let album = sync
+			.write_ops(
+				db,
+				(
+					sync.shared_create(
+						prisma_sync::album::SyncId {
+							pub_id: pub_id.clone(),
+						},
+						chain_optional_iter(
+							[
+								(album::name::NAME, json!(&self.name)),
+								(album::date_created::NAME, json!(&date_created.to_rfc3339())),
+							],
+							[smart_filter
+								.as_ref()
+								.map(|filter| (album::smart_filter::NAME, json!(filter)))],
+						),
+					),
+					db.album().create(
+						pub_id,
+						vec![
+							album::name::set(Some(self.name)),
+							album::date_created::set(Some(date_created)),
+							album::smart_filter::set(smart_filter),
+						],
+					),
+				),
+			)
+			.await?;
+
+		if let Some(filter) = &self.smart_filter {
+			filter.refresh(db, album.id, None).await?;
+		}
+
+		Ok(album)
+	}
+}
+
+/// Smart albums hold every object whose media data matches all of the given criteria, instead of
+/// objects picked by hand.
+///
+/// Their objects are computed by each node from its own media data, so they aren't synced, only
+/// the filter is.
+#[derive(Serialize, Deserialize, Type, Debug, Clone, Default, PartialEq)]
+pub struct SmartAlbumFilter {
+	#[specta(optional)]
+	pub near: Option<SmartAlbumLocation>,
+	#[specta(optional)]
+	pub taken_after: Option<DateTime<Utc>>,
+	#[specta(optional)]
+	pub taken_before: Option<DateTime<Utc>>,
+	/// Matched against `CameraData::device_model`, ignoring the case
+	#[specta(optional)]
+	pub device_model: Option<String>,
+}
+
+/// Media taken within `radius` metres of the given coordinates
+#[derive(Serialize, Deserialize, Type, Debug, Clone, PartialEq)]
+pub struct SmartAlbumLocation {
+	pub latitude: f64,
+	pub longitude: f64,
+	pub radius: f64,
+}
+
+media_data::select!(media_data_for_smart_albums {
+	id
+	object_id
+	media_location
+	camera_data
+});
+
+impl SmartAlbumFilter {
+	/// A filter without any criteria would match every media file of the library
+	pub fn is_empty(&self) -> bool {
+		self == &Self::default()
+	}
+
+	/// Narrows the media data down to the ones that can match, as locations and camera data
+	/// are stored serialized and can only be checked by [`Self::matches`]
+	fn params(&self) -> Vec<media_data::WhereParam> {
+		chain_optional_iter(
+			[],
+			[
+				self.taken_after
+					.map(|date| media_data::epoch_time::gte(date.timestamp())),
+				self.taken_before
+					.map(|date| media_data::epoch_time::lte(date.timestamp())),
+				self.near
+					.as_ref()
+					.map(|_| not![media_data::media_location::equals(None)]),
+				self.device_model
+					.as_ref()
+					.map(|_| not![media_data::camera_data::equals(None)]),
+			],
+		)
+	}
+
+	fn matches(&self, media_data: &media_data_for_smart_albums::Data) -> bool {
+		let near = self.near.as_ref().map_or(true, |near| {
+			media_data
+				.media_location
+				.as_deref()
+				.and_then(|location| serde_json::from_slice::<MediaLocation>(location).ok())
+				.map_or(false, |location| {
+					location.distance_to(&MediaLocation::new(
+						near.latitude,
+						near.longitude,
+						None,
+						None,
+					)) <= near.radius
+				})
+		});
+
+		let device_model = self.device_model.as_ref().map_or(true, |device_model| {
+			media_data
+				.camera_data
+				.as_deref()
+				.and_then(|camera_data| serde_json::from_slice::<CameraData>(camera_data).ok())
+				.and_then(|camera_data| camera_data.device_model)
+				.map_or(false, |model| {
+					model.trim().eq_ignore_ascii_case(device_model.trim())
+				})
+		});
+
+		near && device_model
+	}
+
+	fn memberships(
+		&self,
+		album_id: album::id::Type,
+		media_datas: &[media_data_for_smart_albums::Data],
+	) -> Vec<object_in_album::CreateUnchecked> {
+		let date_created = Utc::now().into();
+
+		media_datas
+			.iter()
+			.filter(|media_data| self.matches(media_data))
+			.map(|media_data| media_data.object_id)
+			.collect::<HashSet<_>>()
+			.into_iter()
+			.map(|object_id| object_in_album::CreateUnchecked {
+				album_id,
+				object_id,
+				_params: vec![object_in_album::date_created::set(Some(date_created))],
+			})
+			.collect()
+	}
+
+	/// Replaces the objects of the album with the ones matching the filter, among the given
+	/// objects or the whole library when there are none
+	pub async fn refresh(
+		&self,
+		db: &PrismaClient,
+		album_id: album::id::Type,
+		object_ids: Option<&[object::id::Type]>,
+	) -> Result<(), QueryError> {
+		if let Some(object_ids) = object_ids {
+			for object_ids in object_ids.chunks(BATCH_SIZE) {
+				let media_datas = db
+					.media_data()
+					.find_many(
+						self.params()
+							.into_iter()
+							.chain([media_data::object_id::in_vec(object_ids.to_vec())])
+							.collect(),
+					)
+					.select(media_data_for_smart_albums::select())
+					.exec()
+					.await?;
+
+				db._batch((
+					db.object_in_album().delete_many(vec![
+						object_in_album::album_id::equals(album_id),
+						object_in_album::object_id::in_vec(object_ids.to_vec()),
+					]),
+					db.object_in_album()
+						.create_many(self.memberships(album_id, &media_datas)),
+				))
+				.await?;
+			}
+
+			return Ok(());
+		}
+
+		db.object_in_album()
+			.delete_many(vec![object_in_album::album_id::equals(album_id)])
+			.exec()
+			.await?;
+
+		let mut cursor = None;
+		loop {
+			let media_datas = db
+				.media_data()
+				.find_many(
+					self.params()
+						.into_iter()
+						.chain(cursor.map(media_data::id::gt))
+						.collect(),
+				)
+				.order_by(media_data::id::order(SortOrder::Asc))
+				.take(BATCH_SIZE as i64)
+				.select(media_data_for_smart_albums::select())
+				.exec()
+				.await?;
+
+			let Some(last) = media_datas.last() else {
+				break;
+			};
+			cursor = Some(last.id);
+
+			db.object_in_album()
+				.create_many(self.memberships(album_id, &media_datas))
+				.exec()
+				.await?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Re-evaluates every smart album of the library for the given objects, so they show up as soon
+/// as their media data is extracted
+pub async fn refresh_smart_albums(
+	db: &PrismaClient,
+	object_ids: &[object::id::Type],
+) -> Result<(), QueryError> {
+	if object_ids.is_empty() {
+		return Ok(());
+	}
+
+	let albums = db
+		.album()
+		.find_many(vec![not![album::smart_filter::equals(None)]])
+		.select(album::select!({ id smart_filter }))
+		.exec()
+		.await?;
+
+	for album in albums {
+		let Some(filter) = album
+			.smart_filter
+			.and_then(|filter| parse_smart_filter(album.id, &filter))
+		else {
+			continue;
+		};
+
+		filter.refresh(db, album.id, Some(object_ids)).await?;
+	}
+
+	Ok(())
+}
+
+/// Smart albums are filled by each instance from its own media data, so the ones created or
+/// changed on other instances are filled once their filter is ingested through sync
+pub async fn refresh_ingested_smart_album(
+	library: &Library,
+	op: CRDTOperation,
+) -> Result<(), QueryError> {
+	let Some(ModelSyncData::Album(id, SharedOperationData::Update { field, .. })) =
+		ModelSyncData::from_op(op.typ)
+	else {
+		return Ok(());
+	};
+
+	if field != album::smart_filter::NAME {
+		return Ok(());
+	}
+
+	let Some(album) = library
+		.db
+		.album()
+		.find_unique(album::pub_id::equals(id.pub_id))
+		.select(album::select!({ id smart_filter }))
+		.exec()
+		.await?
+	else {
+		return Ok(());
+	};
+
+	if let Some(filter) = album
+		.smart_filter
+		.and_then(|filter| parse_smart_filter(album.id, &filter))
+	{
+		filter.refresh(&library.db, album.id, None).await?;
+
+		invalidate_query!(library, "search.objects");
+	}
+
+	Ok(())
+}
+
+fn parse_smart_filter(album_id: album::id::Type, filter: &str) -> Option<SmartAlbumFilter> {
+	serde_json::from_str(filter)
+		.map_err(|e| error!("Invalid filter on smart album <id='{album_id}'>: {e:#?}"))
+		.ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn media_data(
+		location: Option<MediaLocation>,
+		device_model: Option<&str>,
+	) -> media_data_for_smart_albums::Data {
+		media_data_for_smart_albums::Data {
+			id: 1,
+			object_id: 1,
+			media_location: location
+				.map(|location| serde_json::to_vec(&location).expect("serializable")),
+			camera_data: Some(
+				serde_json::to_vec(&CameraData {
+					device_model: device_model.map(str::to_string),
+					..Default::default()
+				})
+				.expect("serializable"),
+			),
+		}
+	}
+
+	#[test]
+	fn matches_location_within_radius() {
+		let filter = SmartAlbumFilter {
+			near: Some(SmartAlbumLocation {
+				latitude: 38.7223,
+				longitude: -9.1393,
+				radius: 10_000.0,
+			}),
+			..Default::default()
+		};
+
+		let lisbon = MediaLocation::new(38.7169, -9.1399, None, None);
+		let porto = MediaLocation::new(41.1579, -8.6291, None, None);
+
+		assert!(filter.matches(&media_data(Some(lisbon), None)));
+		assert!(!filter.matches(&media_data(Some(porto), None)));
+		assert!(!filter.matches(&media_data(None, None)));
+	}
+
+	#[test]
+	fn matches_device_model_ignoring_case() {
+		let filter = SmartAlbumFilter {
+			device_model: Some("iPhone 15 Pro".to_string()),
+			..Default::default()
+		};
+
+		assert!(filter.matches(&media_data(None, Some("IPHONE 15 PRO"))));
+		assert!(!filter.matches(&media_data(None, Some("Pixel 8"))));
+		assert!(!filter.matches(&media_data(None, None)));
+		assert!(SmartAlbumFilter::default().is_empty());
+	}
+}
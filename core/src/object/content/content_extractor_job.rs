@@ -0,0 +1,253 @@
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobReportUpdate, JobResult, JobRunErrors,
+		JobRunMetadata, JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::file_path_helper::{
+		ensure_file_path_exists, ensure_sub_path_is_directory, ensure_sub_path_is_in_location,
+		file_path_for_media_processor, IsolatedFilePathData,
+	},
+	prisma::{location, PrismaClient},
+	util::db::maybe_missing,
+};
+
+use sd_file_ext::kind::ObjectKind;
+
+use std::{
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use prisma_client_rust::{raw, PrismaValue};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info, trace};
+
+use super::{extract_content, save_content, ContentExtractorError};
+
+const BATCH_SIZE: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ContentExtractorJobInit {
+	pub location: location::Data,
+	pub sub_path: Option<PathBuf>,
+}
+
+impl Hash for ContentExtractorJobInit {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.location.id.hash(state);
+		if let Some(ref sub_path) = self.sub_path {
+			sub_path.hash(state);
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ContentExtractorJobData {
+	location_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ContentExtractorJobRunMetadata {
+	extracted: u32,
+	/// Binary files and documents we can't read the text of yet
+	skipped: u32,
+}
+
+impl JobRunMetadata for ContentExtractorJobRunMetadata {
+	fn update(&mut self, new_data: Self) {
+		self.extracted += new_data.extracted;
+		self.skipped += new_data.skipped;
+	}
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for ContentExtractorJobInit {
+	type Data = ContentExtractorJobData;
+	type Step = Vec<file_path_for_media_processor::Data>;
+	type RunMetadata = ContentExtractorJobRunMetadata;
+
+	const NAME: &'static str = "content_extractor";
+	const IS_BATCHED: bool = true;
+
+	fn target_location(&self) -> location::id::Type {
+		self.location.id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let location_id = init.location.id;
+		let location_path =
+			maybe_missing(&init.location.path, "location.path").map(PathBuf::from)?;
+
+		let iso_file_path = match &init.sub_path {
+			Some(sub_path) if sub_path != Path::new("") => {
+				let full_path = ensure_sub_path_is_in_location(&location_path, sub_path)
+					.await
+					.map_err(ContentExtractorError::from)?;
+				ensure_sub_path_is_directory(&location_path, sub_path)
+					.await
+					.map_err(ContentExtractorError::from)?;
+
+				let sub_iso_file_path =
+					IsolatedFilePathData::new(location_id, &location_path, &full_path, true)
+						.map_err(ContentExtractorError::from)?;
+
+				ensure_file_path_exists(
+					sub_path,
+					&sub_iso_file_path,
+					db,
+					ContentExtractorError::SubPathNotFound,
+				)
+				.await?;
+
+				sub_iso_file_path
+			}
+			_ => IsolatedFilePathData::new(location_id, &location_path, &location_path, true)
+				.map_err(ContentExtractorError::from)?,
+		};
+
+		let file_paths = get_files_for_content_extraction(db, &iso_file_path).await?;
+
+		ctx.progress(vec![
+			JobReportUpdate::TaskCount(file_paths.len()),
+			JobReportUpdate::Message(format!(
+				"Preparing to extract the content of {} files",
+				file_paths.len()
+			)),
+		]);
+
+		let steps = file_paths
+			.into_iter()
+			.chunks(BATCH_SIZE)
+			.into_iter()
+			.map(|chunk| chunk.collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+
+		*data = Some(ContentExtractorJobData { location_path });
+
+		Ok(steps.into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, step_number }: CurrentStep<'_, Self::Step>,
+		data: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let mut run_metadata = ContentExtractorJobRunMetadata::default();
+		let mut errors = vec![];
+
+		for file_path in step {
+			let object_id = maybe_missing(file_path.object_id, "file_path.object_id")?;
+			let extension = maybe_missing(&file_path.extension, "file_path.extension")?;
+			let full_path = data.location_path.join(IsolatedFilePathData::try_from((
+				init.location.id,
+				file_path,
+			))?);
+
+			trace!("Extracting content of {}", full_path.display());
+
+			match extract_content(&full_path, extension).await {
+				Ok(Some(content)) => {
+					save_content(db, object_id, &content).await?;
+					run_metadata.extracted += 1;
+				}
+				Ok(None) => {
+					save_content(db, object_id, "").await?;
+					run_metadata.skipped += 1;
+				}
+				Err(e) => errors.push(format!(
+					"Failed to extract content of {}: {e}",
+					full_path.display()
+				)),
+			}
+		}
+
+		ctx.progress(vec![JobReportUpdate::CompletedTaskCount(
+			step_number * BATCH_SIZE + step.len(),
+		)]);
+
+		Ok((run_metadata, JobRunErrors(errors)).into())
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+
+		info!(
+			"Finished content extraction for location {} at {}",
+			init.location.id,
+			data.as_ref()
+				.expect("critical error: missing data on job state")
+				.location_path
+				.display()
+		);
+
+		if run_metadata.extracted > 0 {
+			invalidate_query!(ctx.library, "search.paths");
+			invalidate_query!(ctx.library, "search.objects");
+		}
+
+		Ok(Some(json!({ "init": init, "run_metadata": run_metadata })))
+	}
+}
+
+/// One file for each object of the text kinds that wasn't indexed yet
+async fn get_files_for_content_extraction(
+	db: &PrismaClient,
+	parent_iso_file_path: &IsolatedFilePathData<'_>,
+) -> Result<Vec<file_path_for_media_processor::Data>, ContentExtractorError> {
+	// We have no data coming from the user in the format! macro, so this is sql injection safe
+	db._query_raw(raw!(
+		&format!(
+			"SELECT file_path.id, materialized_path, is_dir, name, extension, cas_id, object_id
+			FROM file_path
+			INNER JOIN object ON object.id = file_path.object_id
+			WHERE
+				location_id={{}}
+				AND is_dir = FALSE
+				AND object.kind IN ({})
+				AND object_id NOT IN (SELECT rowid FROM object_content)
+				AND materialized_path LIKE {{}}
+			GROUP BY object_id
+			ORDER BY materialized_path ASC",
+			[
+				ObjectKind::Text,
+				ObjectKind::Code,
+				ObjectKind::Config,
+				ObjectKind::Document
+			]
+			.into_iter()
+			.map(|kind| (kind as i32).to_string())
+			.join(",")
+		),
+		PrismaValue::Int(parent_iso_file_path.location_id() as i64),
+		PrismaValue::String(format!(
+			"{}%",
+			parent_iso_file_path
+				.materialized_path_for_children()
+				.expect("sub path iso_file_path must be a directory")
+		))
+	))
+	.exec()
+	.await
+	.map_err(Into::into)
+}
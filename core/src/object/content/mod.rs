@@ -0,0 +1,240 @@
+use crate::{
+	location::file_path_helper::FilePathError,
+	object::media::thumbnail::read_text,
+	prisma::{object, PrismaClient},
+	util::error::FileIOError,
+};
+
+use sd_file_ext::extensions::DocumentExtension;
+
+use std::{path::Path, str::FromStr};
+
+use prisma_client_rust::{raw, PrismaValue, QueryError};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use thiserror::Error;
+use tokio::task::spawn_blocking;
+
+pub mod content_extractor_job;
+
+/// We index at most this many bytes of text of each object
+const MAX_CONTENT_LENGTH: usize = 1024 * 1024; // 1MB
+/// How many of the best matches of a content search are kept
+pub const MAX_CONTENT_MATCHES: u32 = 1000;
+
+// Snippets highlight the matched terms between these characters, which can't be in any content
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+
+#[derive(Error, Debug)]
+pub enum ContentExtractorError {
+	#[error("sub path not found: <path='{}'>", .0.display())]
+	SubPathNotFound(Box<Path>),
+
+	// Internal errors
+	#[error("database error: {0}")]
+	Database(#[from] QueryError),
+	#[error(transparent)]
+	FilePath(#[from] FilePathError),
+	#[error(transparent)]
+	FileIO(#[from] FileIOError),
+	#[error("failed to extract the text of the PDF: {0}")]
+	Pdf(#[from] sd_images::Error),
+	#[error("failed to execute extraction task: {0}")]
+	Task(#[from] tokio::task::JoinError),
+}
+
+#[derive(Serialize, Type, Debug, PartialEq, Eq)]
+pub struct SnippetPart {
+	pub text: String,
+	pub highlighted: bool,
+}
+
+/// An object whose content matched a search, with the excerpt of its content that matched best
+#[derive(Serialize, Type, Debug)]
+pub struct ContentMatch {
+	pub object_id: object::id::Type,
+	pub snippet: Vec<SnippetPart>,
+}
+
+#[derive(Deserialize)]
+struct RawContentMatch {
+	object_id: object::id::Type,
+	snippet: String,
+}
+
+/// Reads the text of a file, returning `None` for binary files and documents we can't read yet
+pub async fn extract_content(
+	path: impl AsRef<Path>,
+	extension: &str,
+) -> Result<Option<String>, ContentExtractorError> {
+	let path = path.as_ref();
+
+	match DocumentExtension::from_str(extension) {
+		Ok(DocumentExtension::Pdf) => {
+			let path = path.to_path_buf();
+
+			Ok(Some(
+				spawn_blocking(move || sd_images::pdf_text(&path, MAX_CONTENT_LENGTH)).await??,
+			))
+		}
+		Ok(_) => Ok(None),
+		Err(_) => read_text(path, MAX_CONTENT_LENGTH as u64)
+			.await
+			.map_err(Into::into),
+	}
+}
+
+/// Indexes the content of an object, replacing the previous one.
+///
+/// Objects without text get an empty content, so they aren't extracted again.
+pub async fn save_content(
+	db: &PrismaClient,
+	object_id: object::id::Type,
+	content: &str,
+) -> Result<(), QueryError> {
+	let content = content.replace([HIGHLIGHT_START, HIGHLIGHT_END], "");
+
+	db._execute_raw(raw!(
+		"DELETE FROM object_content WHERE rowid = {}",
+		PrismaValue::Int(object_id as i64)
+	))
+	.exec()
+	.await?;
+
+	db._execute_raw(raw!(
+		"INSERT INTO object_content (rowid, content) VALUES ({}, {})",
+		PrismaValue::Int(object_id as i64),
+		PrismaValue::String(content)
+	))
+	.exec()
+	.await
+	.map(|_| ())
+}
+
+/// Removes the content of objects whose files changed, so it's extracted again by the next scan
+pub async fn remove_content(
+	db: &PrismaClient,
+	object_ids: &[object::id::Type],
+) -> Result<(), QueryError> {
+	if object_ids.is_empty() {
+		return Ok(());
+	}
+
+	db._execute_raw(raw!(
+		"DELETE FROM object_content WHERE rowid IN (SELECT value FROM json_each({}))",
+		PrismaValue::String(
+			serde_json::to_string(object_ids).expect("object ids are always serializable")
+		)
+	))
+	.exec()
+	.await
+	.map(|_| ())
+}
+
+/// Removes the content of objects that were deleted
+pub async fn remove_orphaned_content(db: &PrismaClient) -> Result<(), QueryError> {
+	db._execute_raw(raw!(
+		"DELETE FROM object_content WHERE rowid NOT IN (SELECT id FROM object)"
+	))
+	.exec()
+	.await
+	.map(|_| ())
+}
+
+/// Finds the objects whose content has every word of `query`, the most relevant ones first
+pub async fn search_content(
+	db: &PrismaClient,
+	query: &str,
+	limit: u32,
+) -> Result<Vec<ContentMatch>, QueryError> {
+	let Some(query) = fts_query(query) else {
+		return Ok(vec![]);
+	};
+
+	Ok(db
+		._query_raw::<RawContentMatch>(raw!(
+			"SELECT rowid AS object_id, snippet(object_content, 0, char(2), char(3), '…', 24) AS snippet
+			FROM object_content
+			WHERE object_content MATCH {}
+			ORDER BY rank
+			LIMIT {}",
+			PrismaValue::String(query),
+			PrismaValue::Int(limit as i64)
+		))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|raw_match| ContentMatch {
+			object_id: raw_match.object_id,
+			snippet: snippet_parts(&raw_match.snippet),
+		})
+		.collect())
+}
+
+/// Quotes every word, so users don't need to know the FTS5 query syntax, and matches the last one
+/// as a prefix, as it may still be being typed
+fn fts_query(query: &str) -> Option<String> {
+	let terms = query
+		.split_whitespace()
+		.map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+		.collect::<Vec<_>>();
+
+	(!terms.is_empty()).then(|| format!("{}*", terms.join(" ")))
+}
+
+fn snippet_parts(snippet: &str) -> Vec<SnippetPart> {
+	let mut parts = Vec::new();
+	let mut highlighted = false;
+
+	for text in snippet.split([HIGHLIGHT_START, HIGHLIGHT_END]) {
+		if !text.is_empty() {
+			parts.push(SnippetPart {
+				text: text.to_string(),
+				highlighted,
+			});
+		}
+		highlighted = !highlighted;
+	}
+
+	parts
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn queries_are_quoted() {
+		assert_eq!(fts_query("  "), None);
+		assert_eq!(
+			fts_query("tax \"2023\" OR"),
+			Some(r#""tax" """2023""" "OR"*"#.to_string())
+		);
+	}
+
+	#[test]
+	fn snippets_are_split_by_highlights() {
+		assert_eq!(
+			snippet_parts("…the \u{2}tax\u{3} return for \u{2}2023\u{3}"),
+			vec![
+				SnippetPart {
+					text: "…the ".to_string(),
+					highlighted: false
+				},
+				SnippetPart {
+					text: "tax".to_string(),
+					highlighted: true
+				},
+				SnippetPart {
+					text: " return for ".to_string(),
+					highlighted: false
+				},
+				SnippetPart {
+					text: "2023".to_string(),
+					highlighted: true
+				},
+			]
+		);
+	}
+}
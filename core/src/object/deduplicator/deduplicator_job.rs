@@ -0,0 +1,395 @@
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobResult, JobRunErrors, JobRunMetadata,
+		JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::{
+		file_path_helper::{file_path_for_deduplicator, get_inode_from_path, IsolatedFilePathData},
+		get_location_path_from_location_id,
+	},
+	object::{fs::error::FileSystemJobsError, validation::hash::file_checksum},
+	prisma::{file_path, location},
+	util::{
+		db::{inode_to_db, maybe_missing},
+		error::FileIOError,
+	},
+};
+
+use sd_prisma::prisma_sync;
+use sd_sync::OperationFactory;
+
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::{fs, io, task::spawn_blocking};
+use tracing::trace;
+
+use super::size_in_bytes;
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeduplicationAction {
+	Delete,
+	/// Only possible when the original is on the same file system
+	Hardlink,
+	/// Copy-on-write clones, only supported by some file systems like APFS, Btrfs, XFS and ReFS
+	Reflink,
+}
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug)]
+pub struct DeduplicatorJobInit {
+	/// Only the duplicates in this location are removed or linked
+	pub location_id: location::id::Type,
+	/// The files to keep, one for each group of duplicates, from any location of this instance
+	pub originals_file_path_ids: Vec<file_path::id::Type>,
+	pub action: DeduplicationAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeduplicatorFile {
+	id: file_path::id::Type,
+	pub_id: Vec<u8>,
+	full_path: PathBuf,
+	integrity_checksum: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeduplicatorJobStep {
+	original: DeduplicatorFile,
+	duplicate: DeduplicatorFile,
+	size_in_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DeduplicatorJobRunMetadata {
+	deduplicated: u32,
+	reclaimed_bytes: u64,
+}
+
+impl JobRunMetadata for DeduplicatorJobRunMetadata {
+	fn update(&mut self, new_data: Self) {
+		self.deduplicated += new_data.deduplicated;
+		self.reclaimed_bytes += new_data.reclaimed_bytes;
+	}
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for DeduplicatorJobInit {
+	type Data = ();
+	type Step = DeduplicatorJobStep;
+	type RunMetadata = DeduplicatorJobRunMetadata;
+
+	const NAME: &'static str = "deduplicator";
+
+	fn target_location(&self) -> location::id::Type {
+		self.location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let location_path = get_location_path_from_location_id(db, init.location_id).await?;
+
+		let originals = db
+			.file_path()
+			.find_many(vec![
+				file_path::id::in_vec(init.originals_file_path_ids.clone()),
+				file_path::is_dir::equals(Some(false)),
+				file_path::location::is(vec![location::instance_id::equals(Some(
+					ctx.library.config().await.instance_id,
+				))]),
+			])
+			.select(file_path_for_deduplicator::select())
+			.exec()
+			.await?;
+
+		let mut originals_by_object = HashMap::with_capacity(originals.len());
+		for original in originals {
+			if let Some(object_id) = original.object_id {
+				let location = maybe_missing(&original.location, "file_path.location")?;
+				let full_path = maybe_missing(&location.path, "location.path")
+					.map(PathBuf::from)?
+					.join(IsolatedFilePathData::try_from((location.id, &original))?);
+
+				originals_by_object
+					.entry(object_id)
+					.or_insert_with(|| deduplicator_file(original, full_path));
+			}
+		}
+
+		let steps = db
+			.file_path()
+			.find_many(vec![
+				file_path::object_id::in_vec(originals_by_object.keys().copied().collect()),
+				file_path::location_id::equals(Some(init.location_id)),
+				file_path::id::not_in_vec(init.originals_file_path_ids.clone()),
+				file_path::is_dir::equals(Some(false)),
+			])
+			.select(file_path_for_deduplicator::select())
+			.exec()
+			.await?
+			.into_iter()
+			.filter_map(|duplicate| {
+				let original = originals_by_object.get(&duplicate.object_id?)?;
+
+				Some((original, duplicate))
+			})
+			.map(|(original, duplicate)| {
+				let full_path = location_path.join(IsolatedFilePathData::try_from((
+					init.location_id,
+					&duplicate,
+				))?);
+
+				Ok::<_, JobError>(DeduplicatorJobStep {
+					original: original.clone(),
+					size_in_bytes: size_in_bytes(duplicate.size_in_bytes_bytes.as_deref())
+						.unwrap_or_default(),
+					duplicate: deduplicator_file(duplicate, full_path),
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		// Must fill in the data, otherwise the job will not run
+		*data = Some(());
+
+		Ok(steps.into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, .. }: CurrentStep<'_, Self::Step>,
+		_: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+
+		ctx.progress_msg(step.duplicate.full_path.display().to_string());
+
+		match deduplicate(&ctx.library, step, init.action).await {
+			Ok(true) => Ok(DeduplicatorJobRunMetadata {
+				deduplicated: 1,
+				reclaimed_bytes: step.size_in_bytes,
+			}
+			.into()),
+			Ok(false) => Ok(JobRunErrors(vec![format!(
+				"{} isn't an exact copy of {}, so it was kept",
+				step.duplicate.full_path.display(),
+				step.original.full_path.display()
+			)])
+			.into()),
+			// Any of the files could have been removed since the job started
+			Err(JobError::FileIO(e)) if e.source.kind() == io::ErrorKind::NotFound => {
+				Ok(JobRunErrors(vec![e.to_string()]).into())
+			}
+			Err(JobError::FileIO(e)) if is_unsupported_link(&e.source) => {
+				Ok(JobRunErrors(vec![format!(
+					"{} was kept, as it can't be deduplicated with {:?} to {}: {}",
+					step.duplicate.full_path.display(),
+					init.action,
+					step.original.full_path.display(),
+					e.source
+				)])
+				.into())
+			}
+			Err(e) => Err(e),
+		}
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		_data: &Option<Self::Data>,
+		run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+
+		invalidate_query!(ctx.library, "search.paths");
+		invalidate_query!(ctx.library, "search.objects");
+		invalidate_query!(ctx.library, "search.duplicates");
+
+		Ok(Some(json!({ "init": init, "run_metadata": run_metadata })))
+	}
+}
+
+fn deduplicator_file(
+	file_path: file_path_for_deduplicator::Data,
+	full_path: PathBuf,
+) -> DeduplicatorFile {
+	DeduplicatorFile {
+		id: file_path.id,
+		pub_id: file_path.pub_id,
+		full_path,
+		integrity_checksum: file_path.integrity_checksum,
+	}
+}
+
+/// Returns `false` if the files turned out to be different, leaving the duplicate untouched
+async fn deduplicate(
+	library: &Library,
+	DeduplicatorJobStep {
+		original,
+		duplicate,
+		..
+	}: &DeduplicatorJobStep,
+	action: DeduplicationAction,
+) -> Result<bool, JobError> {
+	let Library { db, sync, .. } = library;
+
+	// The cas_id only samples big files and any of the files could have changed since they were
+	// validated, so we must be sure they're identical right before touching them
+	if checksum(library, original).await? != checksum(library, duplicate).await? {
+		return Ok(false);
+	}
+
+	trace!(
+		"Deduplicating {} with {action:?} to {}",
+		duplicate.full_path.display(),
+		original.full_path.display()
+	);
+
+	if action == DeduplicationAction::Delete {
+		fs::remove_file(&duplicate.full_path)
+			.await
+			.map_err(|e| FileIOError::from((&duplicate.full_path, e)))?;
+
+		db.file_path()
+			.delete(file_path::id::equals(duplicate.id))
+			.exec()
+			.await?;
+
+		return Ok(true);
+	}
+
+	// Linking to a temporary file first, so the duplicate is only replaced if it worked
+	let temporary_path = temporary_path(&duplicate.full_path);
+
+	let linked = if action == DeduplicationAction::Hardlink {
+		fs::hard_link(&original.full_path, &temporary_path).await
+	} else {
+		let (original_path, temporary_path) = (original.full_path.clone(), temporary_path.clone());
+		spawn_blocking(move || reflink_copy::reflink(original_path, temporary_path)).await?
+	};
+
+	let replaced = match linked {
+		Ok(()) => fs::rename(&temporary_path, &duplicate.full_path).await,
+		Err(e) => Err(e),
+	};
+
+	if let Err(e) = replaced {
+		fs::remove_file(&temporary_path).await.ok();
+
+		return Err(FileIOError::from((&duplicate.full_path, e)).into());
+	}
+
+	// The file path now points to a new inode
+	let inode = get_inode_from_path(&duplicate.full_path)
+		.await
+		.map_err(FileSystemJobsError::from)?;
+
+	sync.write_op(
+		db,
+		sync.shared_update(
+			prisma_sync::file_path::SyncId {
+				pub_id: duplicate.pub_id.clone(),
+			},
+			file_path::inode::NAME,
+			json!(inode.to_le_bytes()),
+		),
+		db.file_path().update(
+			file_path::id::equals(duplicate.id),
+			vec![file_path::inode::set(Some(inode_to_db(inode)))],
+		),
+	)
+	.await?;
+
+	Ok(true)
+}
+
+/// Always computes the checksum from the file on disk, as the stored one isn't cleared when the
+/// file changes while its location is offline. The stored one is updated when it's outdated.
+async fn checksum(
+	Library { db, sync, .. }: &Library,
+	file: &DeduplicatorFile,
+) -> Result<String, JobError> {
+	let checksum = file_checksum(&file.full_path)
+		.await
+		.map_err(|e| FileIOError::from((&file.full_path, e)))?;
+
+	if file.integrity_checksum.as_ref() == Some(&checksum) {
+		return Ok(checksum);
+	}
+
+	sync.write_op(
+		db,
+		sync.shared_update(
+			prisma_sync::file_path::SyncId {
+				pub_id: file.pub_id.clone(),
+			},
+			file_path::integrity_checksum::NAME,
+			json!(&checksum),
+		),
+		db.file_path().update(
+			file_path::id::equals(file.id),
+			vec![file_path::integrity_checksum::set(Some(checksum.clone()))],
+		),
+	)
+	.await?;
+
+	Ok(checksum)
+}
+
+/// Hard links can't cross file systems and not every file system supports reflinks, which only
+/// means this pair of files can't be deduplicated with the chosen action
+fn is_unsupported_link(e: &io::Error) -> bool {
+	// EXDEV on unix and ERROR_NOT_SAME_DEVICE on windows
+	#[cfg(unix)]
+	let crosses_devices = e.raw_os_error() == Some(18);
+	#[cfg(windows)]
+	let crosses_devices = e.raw_os_error() == Some(17);
+
+	crosses_devices || e.kind() == io::ErrorKind::Unsupported
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+	path.with_file_name(format!(
+		".{}.sd-dedup",
+		path.file_name().unwrap_or_default().to_string_lossy()
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn temporary_path_is_a_hidden_sibling() {
+		assert_eq!(
+			temporary_path(Path::new("/photos/IMG_0001.jpg")),
+			Path::new("/photos/.IMG_0001.jpg.sd-dedup")
+		);
+	}
+
+	#[test]
+	fn unsupported_links_are_detected() {
+		#[cfg(unix)]
+		assert!(is_unsupported_link(&io::Error::from_raw_os_error(18)));
+		assert!(is_unsupported_link(&io::Error::from(
+			io::ErrorKind::Unsupported
+		)));
+		assert!(!is_unsupported_link(&io::Error::from(
+			io::ErrorKind::PermissionDenied
+		)));
+	}
+}
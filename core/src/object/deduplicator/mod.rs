@@ -0,0 +1,135 @@
+use crate::prisma::{file_path, instance, location, object, PrismaClient};
+
+use std::collections::HashMap;
+
+use prisma_client_rust::{raw, PrismaValue, QueryError};
+use serde::Deserialize;
+
+pub mod deduplicator_job;
+
+// Reading the file paths of every duplicated object at once could take too much memory
+const CHUNK_SIZE: usize = 1000;
+
+/// Files sharing the same object, and optionally the same full checksum, so all of them but one
+/// can be removed or linked to the one we keep
+#[derive(Debug)]
+pub struct DuplicateGroup {
+	pub object_id: object::id::Type,
+	pub integrity_checksum: Option<String>,
+	pub size_in_bytes: u64,
+	pub file_paths: Vec<file_path::Data>,
+}
+
+impl DuplicateGroup {
+	/// Bytes freed by keeping a single file of the group
+	pub fn wasted_bytes(&self) -> u64 {
+		self.size_in_bytes * (self.file_paths.len() as u64).saturating_sub(1)
+	}
+}
+
+#[derive(Deserialize)]
+struct DuplicatedObject {
+	object_id: object::id::Type,
+}
+
+/// Finds every group of duplicated files in the locations of this instance, sorted by the bytes
+/// they waste, as the files of other instances can't be removed or linked from here.
+///
+/// The `cas_id` of objects only samples big files, so `by_integrity_checksum` also splits groups by
+/// their full checksum, leaving out the files that weren't validated yet.
+pub async fn find_duplicates(
+	db: &PrismaClient,
+	instance_id: instance::id::Type,
+	by_integrity_checksum: bool,
+) -> Result<Vec<DuplicateGroup>, QueryError> {
+	let objects_ids = db
+		._query_raw::<DuplicatedObject>(raw!(
+			"SELECT file_path.object_id
+			FROM file_path
+			INNER JOIN location ON location.id = file_path.location_id
+			WHERE file_path.object_id IS NOT NULL
+				AND file_path.is_dir = FALSE
+				AND location.instance_id = {}
+			GROUP BY file_path.object_id
+			HAVING COUNT(*) > 1",
+			PrismaValue::Int(instance_id as i64)
+		))
+		.exec()
+		.await?
+		.into_iter()
+		.map(|duplicated| duplicated.object_id)
+		.collect::<Vec<_>>();
+
+	let mut file_paths = Vec::new();
+	for chunk in objects_ids.chunks(CHUNK_SIZE) {
+		file_paths.extend(
+			db.file_path()
+				.find_many(vec![
+					file_path::object_id::in_vec(chunk.to_vec()),
+					file_path::is_dir::equals(Some(false)),
+					file_path::location::is(vec![location::instance_id::equals(Some(instance_id))]),
+				])
+				.exec()
+				.await?,
+		);
+	}
+
+	Ok(group_duplicates(file_paths, by_integrity_checksum))
+}
+
+fn group_duplicates(
+	file_paths: Vec<file_path::Data>,
+	by_integrity_checksum: bool,
+) -> Vec<DuplicateGroup> {
+	let mut groups = HashMap::<_, Vec<_>>::new();
+
+	for file_path in file_paths {
+		let Some(object_id) = file_path.object_id else {
+			continue;
+		};
+
+		let integrity_checksum = if by_integrity_checksum {
+			let Some(integrity_checksum) = file_path.integrity_checksum.clone() else {
+				continue;
+			};
+			Some(integrity_checksum)
+		} else {
+			None
+		};
+
+		groups
+			.entry((object_id, integrity_checksum))
+			.or_default()
+			.push(file_path);
+	}
+
+	let mut groups = groups
+		.into_iter()
+		.filter(|(_, file_paths)| file_paths.len() > 1)
+		.map(
+			|((object_id, integrity_checksum), file_paths)| DuplicateGroup {
+				object_id,
+				integrity_checksum,
+				size_in_bytes: file_paths
+					.iter()
+					.find_map(|file_path| size_in_bytes(file_path.size_in_bytes_bytes.as_deref()))
+					.unwrap_or_default(),
+				file_paths,
+			},
+		)
+		.collect::<Vec<_>>();
+
+	groups.sort_by(|a, b| {
+		b.wasted_bytes()
+			.cmp(&a.wasted_bytes())
+			.then(a.object_id.cmp(&b.object_id))
+	});
+
+	groups
+}
+
+fn size_in_bytes(size_in_bytes_bytes: Option<&[u8]>) -> Option<u64> {
+	size_in_bytes_bytes
+		.and_then(|bytes| bytes.try_into().ok())
+		.map(u64::from_be_bytes)
+}
@@ -3,7 +3,7 @@ use crate::{
 		CurrentStep, JobError, JobInitOutput, JobReportUpdate, JobResult, JobRunMetadata,
 		JobStepOutput, StatefulJob, WorkerContext,
 	},
-	library::Library,
+	library::{spawn_update_library_statistics, Library},
 	location::file_path_helper::{
 		ensure_file_path_exists, ensure_sub_path_is_directory, ensure_sub_path_is_in_location,
 		file_path_for_file_identifier, IsolatedFilePathData,
@@ -15,6 +15,7 @@ use crate::{
 use std::{
 	hash::{Hash, Hasher},
 	path::{Path, PathBuf},
+	sync::Arc,
 };
 
 use prisma_client_rust::or;
@@ -236,13 +237,17 @@ impl StatefulJob for FileIdentifierJobInit {
 
 	async fn finalize(
 		&self,
-		_: &WorkerContext,
+		ctx: &WorkerContext,
 		_data: &Option<Self::Data>,
 		run_metadata: &Self::RunMetadata,
 	) -> JobResult {
 		let init = self;
 		info!("Finalizing identifier job: {:?}", &run_metadata);
 
+		if run_metadata.total_objects_created > 0 || run_metadata.total_objects_linked > 0 {
+			spawn_update_library_statistics(Arc::clone(&ctx.node), Arc::clone(&ctx.library));
+		}
+
 		Ok(Some(json!({"init: ": init, "run_metadata": run_metadata})))
 	}
 }
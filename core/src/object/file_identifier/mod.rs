@@ -29,8 +29,10 @@ use uuid::Uuid;
 
 pub mod file_identifier_job;
 mod shallow;
+mod strict;
 
 pub use shallow::*;
+pub(crate) use strict::IntegrityChecker;
 
 // we break these jobs into chunks of 100 to improve performance
 const CHUNK_SIZE: usize = 100;
@@ -98,7 +100,7 @@ impl FileMetadata {
 }
 
 async fn identifier_job_step(
-	Library { db, sync, .. }: &Library,
+	library @ Library { db, sync, .. }: &Library,
 	location: &location::Data,
 	file_paths: &[file_path_for_file_identifier::Data],
 ) -> Result<(usize, usize), JobError> {
@@ -153,7 +155,7 @@ async fn identifier_job_step(
 		.filter_map(|(metadata, _)| metadata.cas_id.clone())
 		.collect::<HashSet<_>>()
 		.into_iter()
-		.collect();
+		.collect::<Vec<_>>();
 
 	// Assign cas_id to each file path
 	sync.write_ops(
@@ -179,56 +181,87 @@ async fn identifier_job_step(
 	)
 	.await?;
 
-	// Retrieves objects that are already connected to file paths with the same id
-	let existing_objects = db
-		.object()
-		.find_many(vec![object::file_paths::some(vec![
-			file_path::cas_id::in_vec(unique_cas_ids),
-		])])
-		.select(object_for_file_identifier::select())
-		.exec()
-		.await?;
+	// Finds an object for each file path among the ones already connected to
+	// file paths with the same cas_id
+	let object_pub_ids = if location.strict_identity.unwrap_or(false) {
+		let mut checker = IntegrityChecker::new(library, unique_cas_ids).await?;
+		let mut object_pub_ids = HashMap::new();
 
-	let existing_object_cas_ids = existing_objects
-		.iter()
-		.flat_map(|object| {
-			object
-				.file_paths
-				.iter()
-				.filter_map(|file_path| file_path.cas_id.as_ref())
-		})
-		.collect::<HashSet<_>>();
+		for (pub_id, (metadata, file_path)) in &file_paths_metadatas {
+			// Filtering out files without cas_id due to being empty
+			let Some(cas_id) = &metadata.cas_id else {
+				continue;
+			};
+
+			if !checker.has_candidates(cas_id) {
+				continue;
+			}
+
+			let path =
+				location_path.join(IsolatedFilePathData::try_from((location.id, *file_path))?);
+
+			match checker
+				.find_object(&uuid_to_bytes(*pub_id), &path, cas_id)
+				.await
+			{
+				Ok(Some(object_pub_id)) => {
+					object_pub_ids.insert(*pub_id, object_pub_id);
+				}
+				Ok(None) => {}
+				// Without the checksum we can't be sure, so it gets an object of its own
+				Err(e) => error!("Failed to check file integrity: {e:#?}"),
+			}
+		}
+
+		checker.save().await?;
+
+		object_pub_ids
+	} else {
+		// Retrieves objects that are already connected to file paths with the same id
+		let existing_objects = db
+			.object()
+			.find_many(vec![object::file_paths::some(vec![
+				file_path::cas_id::in_vec(unique_cas_ids),
+			])])
+			.select(object_for_file_identifier::select())
+			.exec()
+			.await?;
+
+		file_paths_metadatas
+			.iter()
+			.filter_map(|(pub_id, (metadata, _))| {
+				// Filtering out files without cas_id due to being empty
+				let cas_id = metadata.cas_id.as_ref()?;
+
+				existing_objects
+					.iter()
+					.find(|object| {
+						object
+							.file_paths
+							.iter()
+							.any(|file_path| file_path.cas_id.as_ref() == Some(cas_id))
+					})
+					.map(|object| (*pub_id, object.pub_id.clone()))
+			})
+			.collect::<HashMap<_, _>>()
+	};
+
+	trace!(
+		"Found {} existing Objects in Library, linking file paths...",
+		object_pub_ids.len()
+	);
 
-	// Attempt to associate each file path with an object that has been
-	// connected to file paths with the same cas_id
+	// Attempt to associate each file path with the object found for it
 	let updated_file_paths = sync
 		.write_ops(
 			db,
-			file_paths_metadatas
+			object_pub_ids
 				.iter()
-				.filter_map(|(pub_id, (metadata, file_path))| {
-					// Filtering out files without cas_id due to being empty
-					metadata
-						.cas_id
-						.is_some()
-						.then_some((pub_id, (metadata, file_path)))
-				})
-				.flat_map(|(pub_id, (metadata, _))| {
-					existing_objects
-						.iter()
-						.find(|object| {
-							object
-								.file_paths
-								.iter()
-								.any(|file_path| file_path.cas_id == metadata.cas_id)
-						})
-						.map(|object| (*pub_id, object))
-				})
-				.map(|(pub_id, object)| {
+				.map(|(pub_id, object_pub_id)| {
 					let (crdt_op, db_op) = connect_file_path_to_object(
-						pub_id,
+						*pub_id,
 						// SAFETY: This pub_id is generated by the uuid lib, but we have to store bytes in sqlite
-						Uuid::from_slice(&object.pub_id).expect("uuid bytes are invalid"),
+						Uuid::from_slice(object_pub_id).expect("uuid bytes are invalid"),
 						sync,
 						db,
 					);
@@ -239,20 +272,10 @@ async fn identifier_job_step(
 		)
 		.await?;
 
-	trace!(
-		"Found {} existing Objects in Library, linking file paths...",
-		existing_objects.len()
-	);
-
 	// extract objects that don't already exist in the database
 	let file_paths_requiring_new_object = file_paths_metadatas
 		.into_iter()
-		.filter(|(_, (FileMetadata { cas_id, .. }, _))| {
-			cas_id
-				.as_ref()
-				.map(|cas_id| !existing_object_cas_ids.contains(cas_id))
-				.unwrap_or(true)
-		})
+		.filter(|(pub_id, _)| !object_pub_ids.contains_key(pub_id))
 		.collect::<Vec<_>>();
 
 	let total_created = if !file_paths_requiring_new_object.is_empty() {
@@ -0,0 +1,168 @@
+use crate::{
+	library::Library,
+	location::file_path_helper::{file_path_for_strict_identity, IsolatedFilePathData},
+	object::validation::hash::file_checksum,
+	prisma::{file_path, instance},
+	util::error::FileIOError,
+};
+
+use sd_prisma::prisma_sync;
+use sd_sync::OperationFactory;
+
+use std::path::Path;
+
+use prisma_client_rust::QueryError;
+use serde_json::json;
+use tracing::{trace, warn};
+
+/// Used on locations with `strict_identity` enabled, to make sure files sharing a `cas_id` really
+/// have the same content before linking them to the same object.
+///
+/// The `cas_id` of big files is generated from a few samples of their content, so different files
+/// can end up with the same one. Here we compare their full `integrity_checksum`s instead, computing
+/// the missing ones for files that live in locations of this instance. Files from other instances
+/// that were never checksummed can't be compared, so they are never considered identical.
+pub(crate) struct IntegrityChecker<'lib> {
+	library: &'lib Library,
+	instance_id: instance::id::Type,
+	candidates: Vec<file_path_for_strict_identity::Data>,
+	checksums: Vec<(Vec<u8>, String)>,
+}
+
+impl<'lib> IntegrityChecker<'lib> {
+	/// Loads every file path already linked to an object with one of these `cas_ids`
+	pub async fn new(library: &'lib Library, cas_ids: Vec<String>) -> Result<Self, QueryError> {
+		let candidates = library
+			.db
+			.file_path()
+			.find_many(vec![
+				file_path::cas_id::in_vec(cas_ids),
+				file_path::object_id::not(None),
+			])
+			.select(file_path_for_strict_identity::select())
+			.exec()
+			.await?;
+
+		Ok(Self {
+			library,
+			instance_id: library.config().await.instance_id,
+			candidates,
+			checksums: vec![],
+		})
+	}
+
+	pub fn has_candidates(&self, cas_id: &str) -> bool {
+		self.candidates
+			.iter()
+			.any(|candidate| candidate.cas_id.as_deref() == Some(cas_id))
+	}
+
+	/// Returns the `pub_id` of an object holding exactly the same content as the file at `path`,
+	/// if there is one. The checksum of the file is kept to be saved along with the others.
+	pub async fn find_object(
+		&mut self,
+		file_path_pub_id: &[u8],
+		path: impl AsRef<Path>,
+		cas_id: &str,
+	) -> Result<Option<Vec<u8>>, FileIOError> {
+		let path = path.as_ref();
+		let checksum = file_checksum(path)
+			.await
+			.map_err(|e| FileIOError::from((path, e)))?;
+
+		let mut object_pub_id = None;
+
+		for candidate in self
+			.candidates
+			.iter_mut()
+			.filter(|candidate| candidate.cas_id.as_deref() == Some(cas_id))
+		{
+			let Some(object) = &candidate.object else {
+				continue;
+			};
+
+			if candidate.integrity_checksum.is_none() {
+				if let Some(candidate_checksum) =
+					candidate_checksum(self.instance_id, candidate).await
+				{
+					self.checksums
+						.push((candidate.pub_id.clone(), candidate_checksum.clone()));
+					candidate.integrity_checksum = Some(candidate_checksum);
+				}
+			}
+
+			if candidate.integrity_checksum.as_ref() == Some(&checksum) {
+				object_pub_id = Some(object.pub_id.clone());
+				break;
+			}
+		}
+
+		if object_pub_id.is_none() {
+			trace!(
+				"No object with the same content as <path='{}'>, it will get its own",
+				path.display()
+			);
+		}
+
+		self.checksums.push((file_path_pub_id.to_vec(), checksum));
+
+		Ok(object_pub_id)
+	}
+
+	/// Saves every checksum computed along the way, so they don't have to be computed again
+	pub async fn save(self) -> Result<(), QueryError> {
+		let Library { db, sync, .. } = self.library;
+
+		if self.checksums.is_empty() {
+			return Ok(());
+		}
+
+		sync.write_ops(
+			db,
+			self.checksums
+				.into_iter()
+				.map(|(pub_id, checksum)| {
+					(
+						sync.shared_update(
+							prisma_sync::file_path::SyncId {
+								pub_id: pub_id.clone(),
+							},
+							file_path::integrity_checksum::NAME,
+							json!(&checksum),
+						),
+						db.file_path().update(
+							file_path::pub_id::equals(pub_id),
+							vec![file_path::integrity_checksum::set(Some(checksum))],
+						),
+					)
+				})
+				.unzip::<_, _, _, Vec<_>>(),
+		)
+		.await?;
+
+		Ok(())
+	}
+}
+
+async fn candidate_checksum(
+	instance_id: instance::id::Type,
+	candidate: &file_path_for_strict_identity::Data,
+) -> Option<String> {
+	let location = candidate.location.as_ref()?;
+
+	// We can only read files from our own locations
+	if location.instance_id != Some(instance_id) {
+		return None;
+	}
+
+	let path = Path::new(location.path.as_ref()?).join(
+		IsolatedFilePathData::try_from(candidate)
+			.map_err(|e| warn!("Failed to extract isolated file path data: {e:#?}"))
+			.ok()?,
+	);
+
+	file_checksum(&path)
+		.await
+		.map_err(|e| warn!("Failed to checksum <path='{}'>: {e:#?}", path.display()))
+		.ok()
+}
@@ -0,0 +1,456 @@
+use crate::util::error::FileIOError;
+
+use sd_file_ext::extensions::ArchiveExtension;
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs::{self, File},
+	io::{self, BufReader, Read, Write},
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+	#[error("unsupported archive format: <path='{}'>", .0.display())]
+	UnsupportedFormat(Box<Path>),
+	#[error("entry not found in archive: <archive='{}', entry='{1}'>", .0.display())]
+	EntryNotFound(Box<Path>, String),
+	#[error("error reading zip archive: {0}")]
+	Zip(#[from] zip::result::ZipError),
+	#[error("error reading 7z archive: {0}")]
+	SevenZip(#[from] sevenz_rust::Error),
+	#[error(transparent)]
+	FileIO(#[from] FileIOError),
+	#[error("failed to execute archive task: {0}")]
+	Task(#[from] tokio::task::JoinError),
+}
+
+/// Archive formats that we're able to look into without extracting them to disk
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+	Zip,
+	Tar,
+	TarGz,
+	TarZst,
+	SevenZip,
+}
+
+impl ArchiveFormat {
+	pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+		let path = path.as_ref();
+		let extension = path.extension()?.to_str()?;
+
+		match ArchiveExtension::from_str(extension).ok()? {
+			ArchiveExtension::Zip => Some(Self::Zip),
+			ArchiveExtension::Tar => Some(Self::Tar),
+			// A plain `.gz` or `.zst` file is just a compressed file, only `.tar.*` is an archive
+			ArchiveExtension::Gz => has_tar_stem(path).then_some(Self::TarGz),
+			ArchiveExtension::Zst => has_tar_stem(path).then_some(Self::TarZst),
+			ArchiveExtension::_7z => Some(Self::SevenZip),
+			_ => None,
+		}
+	}
+
+	/// The file name of an archive without its archive extensions, like `photos` for `photos.tar.gz`
+	pub fn archive_stem(self, path: impl AsRef<Path>) -> Option<String> {
+		let path = path.as_ref();
+		let stem = path.file_stem()?;
+
+		Some(
+			match self {
+				Self::TarGz | Self::TarZst => Path::new(stem).file_stem()?,
+				_ => stem,
+			}
+			.to_string_lossy()
+			.to_string(),
+		)
+	}
+}
+
+fn has_tar_stem(path: &Path) -> bool {
+	path.file_stem()
+		.map(Path::new)
+		.and_then(Path::extension)
+		.is_some_and(|ext| ext.eq_ignore_ascii_case("tar"))
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+	/// Path of this entry relative to the archive root, always using `/` as separator
+	pub path: String,
+	pub is_dir: bool,
+	pub size: u64,
+	pub date_modified: Option<DateTime<Utc>>,
+}
+
+impl ArchiveEntry {
+	pub fn name(&self) -> &str {
+		self.path.rsplit('/').next().unwrap_or(&self.path)
+	}
+}
+
+/// Entries with absolute paths or `..` components could be used to write files outside
+/// of the extraction directory, so we refuse to extract them
+pub fn is_safe_entry_path(path: &str) -> bool {
+	!path.starts_with('/')
+		&& !path.starts_with('\\')
+		&& path.split(['/', '\\']).all(|component| component != "..")
+		&& !Path::new(path).has_root()
+}
+
+/// Normalizes paths stored inside archives, as they may start with `./` or `/` and
+/// directories may or may not end with a `/`
+pub fn normalize_entry_path(path: &str) -> String {
+	path.replace('\\', "/")
+		.split('/')
+		.filter(|component| !component.is_empty() && *component != ".")
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+/// Lists every entry in the archive. This is a blocking function, as all archive crates are sync.
+pub fn list_entries(
+	archive_path: impl AsRef<Path>,
+	format: ArchiveFormat,
+) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+	let archive_path = archive_path.as_ref();
+
+	match format {
+		ArchiveFormat::Zip => {
+			let mut archive = zip::ZipArchive::new(open(archive_path)?)?;
+
+			(0..archive.len())
+				.map(|idx| {
+					let file = archive.by_index_raw(idx)?;
+					let modified = file.last_modified();
+
+					Ok(ArchiveEntry {
+						path: normalize_entry_path(file.name()),
+						is_dir: file.is_dir(),
+						size: file.size(),
+						date_modified: NaiveDate::from_ymd_opt(
+							modified.year().into(),
+							modified.month().into(),
+							modified.day().into(),
+						)
+						.and_then(|date| {
+							date.and_hms_opt(
+								modified.hour().into(),
+								modified.minute().into(),
+								modified.second().into(),
+							)
+						})
+						.map(|date_time| Utc.from_utc_datetime(&date_time)),
+					})
+				})
+				.collect()
+		}
+		ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+			let mut archive = tar::Archive::new(tar_reader(archive_path, format)?);
+
+			archive
+				.entries()
+				.map_err(|e| FileIOError::from((archive_path, e)))?
+				.map(|entry| {
+					let entry = entry.map_err(|e| FileIOError::from((archive_path, e)))?;
+					let header = entry.header();
+
+					Ok(ArchiveEntry {
+						path: normalize_entry_path(&String::from_utf8_lossy(&entry.path_bytes())),
+						is_dir: header.entry_type().is_dir(),
+						size: header.size().unwrap_or_default(),
+						date_modified: header
+							.mtime()
+							.ok()
+							.and_then(|secs| Utc.timestamp_opt(secs as i64, 0).single()),
+					})
+				})
+				.collect()
+		}
+		ArchiveFormat::SevenZip => {
+			let reader =
+				sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())?;
+
+			Ok(reader
+				.archive()
+				.files
+				.iter()
+				.map(|file| ArchiveEntry {
+					path: normalize_entry_path(&file.name),
+					is_dir: file.is_directory,
+					size: file.size,
+					date_modified: None,
+				})
+				.collect())
+		}
+	}
+}
+
+/// Lists the direct children of `inner_dir` inside the archive, including directories
+/// that are only implied by the path of their children, as zip files commonly omit them.
+pub fn read_dir(
+	archive_path: impl AsRef<Path>,
+	format: ArchiveFormat,
+	inner_dir: &str,
+) -> Result<Vec<ArchiveEntry>, ArchiveError> {
+	let inner_dir = normalize_entry_path(inner_dir);
+	let prefix = if inner_dir.is_empty() {
+		String::new()
+	} else {
+		format!("{inner_dir}/")
+	};
+
+	let mut children = BTreeMap::new();
+
+	for entry in list_entries(archive_path, format)? {
+		let Some(relative) = entry.path.strip_prefix(&prefix) else {
+			continue;
+		};
+
+		if relative.is_empty() {
+			continue;
+		}
+
+		match relative.split_once('/') {
+			None => {
+				children.insert(relative.to_string(), entry);
+			}
+			Some((child_dir, _)) => {
+				children
+					.entry(child_dir.to_string())
+					.or_insert_with(|| ArchiveEntry {
+						path: format!("{prefix}{child_dir}"),
+						is_dir: true,
+						size: 0,
+						date_modified: None,
+					});
+			}
+		}
+	}
+
+	Ok(children.into_values().collect())
+}
+
+/// Decompresses a single entry of the archive into `writer`, returning the amount of bytes written.
+/// This is a blocking function, as all archive crates are sync.
+pub fn extract_entry(
+	archive_path: impl AsRef<Path>,
+	format: ArchiveFormat,
+	entry_path: &str,
+	writer: &mut impl Write,
+) -> Result<u64, ArchiveError> {
+	let archive_path = archive_path.as_ref();
+	let entry_path = normalize_entry_path(entry_path);
+
+	let not_found = || ArchiveError::EntryNotFound(archive_path.into(), entry_path.clone());
+
+	match format {
+		ArchiveFormat::Zip => {
+			let mut archive = zip::ZipArchive::new(open(archive_path)?)?;
+
+			let idx = (0..archive.len())
+				.find(|idx| {
+					archive.name_for_index(*idx).map(normalize_entry_path)
+						== Some(entry_path.clone())
+				})
+				.ok_or_else(not_found)?;
+
+			let mut file = archive.by_index(idx)?;
+
+			copy(&mut file, writer, archive_path)
+		}
+		ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+			let mut archive = tar::Archive::new(tar_reader(archive_path, format)?);
+
+			for entry in archive
+				.entries()
+				.map_err(|e| FileIOError::from((archive_path, e)))?
+			{
+				let mut entry = entry.map_err(|e| FileIOError::from((archive_path, e)))?;
+				let path = entry
+					.path()
+					.map_err(|e| FileIOError::from((archive_path, e)))?
+					.to_string_lossy()
+					.to_string();
+
+				if normalize_entry_path(&path) == entry_path {
+					return copy(&mut entry, writer, archive_path);
+				}
+			}
+
+			Err(not_found())
+		}
+		ArchiveFormat::SevenZip => {
+			let mut reader =
+				sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())?;
+
+			let mut written = None;
+
+			reader.for_each_entries(|entry, entry_reader| {
+				if normalize_entry_path(entry.name()) != entry_path {
+					return Ok(true);
+				}
+
+				written = Some(io::copy(entry_reader, writer)?);
+
+				// Found our entry, we can stop iterating
+				Ok(false)
+			})?;
+
+			written.ok_or_else(not_found)
+		}
+	}
+}
+
+/// Extracts every entry from `targets`, a map of normalized entry paths to their destination
+/// on disk, walking through the archive only once. Returns the entry paths that were extracted.
+/// This is a blocking function, as all archive crates are sync.
+pub fn extract_entries(
+	archive_path: impl AsRef<Path>,
+	format: ArchiveFormat,
+	targets: &HashMap<String, PathBuf>,
+) -> Result<Vec<String>, ArchiveError> {
+	let archive_path = archive_path.as_ref();
+	let mut extracted = Vec::with_capacity(targets.len());
+
+	match format {
+		ArchiveFormat::Zip => {
+			let mut archive = zip::ZipArchive::new(open(archive_path)?)?;
+
+			for idx in 0..archive.len() {
+				let mut file = archive.by_index(idx)?;
+				let entry_path = normalize_entry_path(file.name());
+
+				if let Some(target) = targets.get(&entry_path) {
+					write_entry(&mut file, file.is_dir(), target)?;
+					extracted.push(entry_path);
+				}
+			}
+		}
+		ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarZst => {
+			let mut archive = tar::Archive::new(tar_reader(archive_path, format)?);
+
+			for entry in archive
+				.entries()
+				.map_err(|e| FileIOError::from((archive_path, e)))?
+			{
+				let mut entry = entry.map_err(|e| FileIOError::from((archive_path, e)))?;
+				let entry_path =
+					normalize_entry_path(&String::from_utf8_lossy(&entry.path_bytes()));
+
+				if let Some(target) = targets.get(&entry_path) {
+					let is_dir = entry.header().entry_type().is_dir();
+					write_entry(&mut entry, is_dir, target)?;
+					extracted.push(entry_path);
+				}
+			}
+		}
+		ArchiveFormat::SevenZip => {
+			let mut reader =
+				sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())?;
+
+			reader.for_each_entries(|entry, entry_reader| {
+				let entry_path = normalize_entry_path(entry.name());
+
+				if let Some(target) = targets.get(&entry_path) {
+					write_entry(entry_reader, entry.is_directory(), target)
+						.map_err(|e| sevenz_rust::Error::other(e.to_string()))?;
+					extracted.push(entry_path);
+				}
+
+				Ok(true)
+			})?;
+		}
+	}
+
+	Ok(extracted)
+}
+
+fn write_entry(reader: &mut impl Read, is_dir: bool, target: &Path) -> Result<(), ArchiveError> {
+	if is_dir {
+		return fs::create_dir_all(target).map_err(|e| FileIOError::from((target, e)).into());
+	}
+
+	if let Some(parent) = target.parent() {
+		fs::create_dir_all(parent).map_err(|e| FileIOError::from((parent, e)))?;
+	}
+
+	let mut file = File::create(target).map_err(|e| FileIOError::from((target, e)))?;
+
+	io::copy(reader, &mut file)
+		.map(|_| ())
+		.map_err(|e| FileIOError::from((target, e)).into())
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, ArchiveError> {
+	File::open(path)
+		.map(BufReader::new)
+		.map_err(|e| FileIOError::from((path, e)).into())
+}
+
+fn tar_reader(path: &Path, format: ArchiveFormat) -> Result<Box<dyn Read>, ArchiveError> {
+	let file = open(path)?;
+
+	Ok(match format {
+		ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+		ArchiveFormat::TarZst => Box::new(
+			zstd::stream::read::Decoder::with_buffer(file)
+				.map_err(|e| FileIOError::from((path, e)))?,
+		),
+		_ => Box::new(file),
+	})
+}
+
+fn copy(
+	reader: &mut impl Read,
+	writer: &mut impl Write,
+	archive_path: &Path,
+) -> Result<u64, ArchiveError> {
+	io::copy(reader, writer).map_err(|e| FileIOError::from((archive_path, e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn normalize_archive_entry_paths() {
+		assert_eq!(normalize_entry_path("./photos/a.jpg"), "photos/a.jpg");
+		assert_eq!(normalize_entry_path("/photos/"), "photos");
+		assert_eq!(normalize_entry_path("photos\\a.jpg"), "photos/a.jpg");
+		assert_eq!(normalize_entry_path(""), "");
+	}
+
+	#[test]
+	fn archive_format_from_path() {
+		assert_eq!(
+			ArchiveFormat::from_path("/data/backup.zip"),
+			Some(ArchiveFormat::Zip)
+		);
+		assert_eq!(
+			ArchiveFormat::from_path("/data/backup.tar.gz"),
+			Some(ArchiveFormat::TarGz)
+		);
+		assert_eq!(
+			ArchiveFormat::from_path("/data/backup.tar.zst"),
+			Some(ArchiveFormat::TarZst)
+		);
+		assert_eq!(ArchiveFormat::from_path("/data/notes.txt.gz"), None);
+		assert_eq!(ArchiveFormat::from_path("/data/photos"), None);
+	}
+
+	#[test]
+	fn unsafe_archive_entry_paths() {
+		assert!(is_safe_entry_path("photos/a.jpg"));
+		assert!(is_safe_entry_path("photos/..a.jpg"));
+		assert!(!is_safe_entry_path("../a.jpg"));
+		assert!(!is_safe_entry_path("photos/../../a.jpg"));
+		assert!(!is_safe_entry_path("/etc/passwd"));
+	}
+}
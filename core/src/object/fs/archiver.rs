@@ -0,0 +1,615 @@
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobReportUpdate, JobResult, JobRunErrors,
+		JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::file_path_helper::join_location_relative_path,
+	prisma::{file_path, location},
+	util::{db::maybe_missing, error::FileIOError},
+};
+
+use std::{
+	fs::{self as std_fs, File},
+	hash::Hash,
+	io::{self, BufReader, BufWriter, Write},
+	mem,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::{fs, sync::mpsc, task::spawn_blocking};
+use tracing::{error, trace, warn};
+
+use super::{
+	archive::ArchiveError, construct_target_filename, error::FileSystemJobsError,
+	fetch_source_and_target_location_paths, find_available_filename_for_duplicate,
+	get_many_files_datas, rescan_target_directory,
+};
+
+/// Amount of entries archived on each step, so an interrupted job only archives its last batch again
+const BATCH_SIZE: usize = 100;
+
+/// A tar archive ends with two empty blocks, which are written once after every batch was joined
+const TAR_END_OF_ARCHIVE: [u8; 1024] = [0; 1024];
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiverFormat {
+	Zip,
+	TarZst,
+}
+
+impl ArchiverFormat {
+	fn extension(self) -> &'static str {
+		match self {
+			Self::Zip => "zip",
+			Self::TarZst => "tar.zst",
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileArchiverJobData {
+	target_directory: PathBuf,
+	target_archive_path: PathBuf,
+	/// Every step writes its batch of entries to a file in this directory, and they're only joined
+	/// into `target_archive_path` when the job finishes
+	partial_archive_dir: PathBuf,
+	batches: usize,
+}
+
+impl FileArchiverJobData {
+	fn batch_path(&self, step_number: usize) -> PathBuf {
+		self.partial_archive_dir.join(step_number.to_string())
+	}
+}
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug)]
+pub struct FileArchiverJobInit {
+	pub source_location_id: location::id::Type,
+	pub target_location_id: location::id::Type,
+	pub sources_file_path_ids: Vec<file_path::id::Type>,
+	pub target_location_relative_directory_path: PathBuf,
+	/// Name of the archive without its extension
+	pub archive_name: String,
+	pub format: ArchiverFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileArchiverEntry {
+	pub source_full_path: PathBuf,
+	/// Path of this entry inside the archive, always using `/` as separator
+	pub entry_path: String,
+	pub is_dir: bool,
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for FileArchiverJobInit {
+	type Data = FileArchiverJobData;
+	type Step = Vec<FileArchiverEntry>;
+	type RunMetadata = ();
+
+	const NAME: &'static str = "file_archiver";
+	const IS_BATCHED: bool = true;
+
+	fn target_location(&self) -> location::id::Type {
+		self.target_location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let (sources_location_path, targets_location_path) =
+			fetch_source_and_target_location_paths(
+				db,
+				init.source_location_id,
+				init.target_location_id,
+			)
+			.await?;
+
+		let mut pending =
+			get_many_files_datas(db, &sources_location_path, &init.sources_file_path_ids)
+				.await?
+				.into_iter()
+				.map(|file_data| {
+					Ok::<_, FileSystemJobsError>(FileArchiverEntry {
+						entry_path: construct_target_filename(&file_data)?,
+						is_dir: maybe_missing(file_data.file_path.is_dir, "file_path.is_dir")?,
+						source_full_path: file_data.full_path,
+					})
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+		let mut entries = Vec::with_capacity(pending.len());
+		let mut errors = vec![];
+
+		while let Some(entry) = pending.pop() {
+			if entry.is_dir {
+				pending.extend(read_dir_entries(&entry, &mut errors).await?);
+			}
+
+			entries.push(entry);
+		}
+
+		let target_directory = join_location_relative_path(
+			&targets_location_path,
+			&init.target_location_relative_directory_path,
+		);
+
+		let mut target_archive_path =
+			target_directory.join(format!("{}.{}", init.archive_name, init.format.extension()));
+
+		if fs::metadata(&target_archive_path).await.is_ok() {
+			target_archive_path =
+				find_available_filename_for_duplicate(target_archive_path).await?;
+		}
+
+		let partial_archive_dir = target_directory.join(format!(
+			".{}.part",
+			target_archive_path
+				.file_name()
+				.expect("we just built this path with a file name")
+				.to_string_lossy()
+		));
+
+		fs::create_dir_all(&partial_archive_dir)
+			.await
+			.map_err(|e| FileIOError::from((&partial_archive_dir, e)))?;
+
+		ctx.progress(vec![JobReportUpdate::TaskCount(entries.len())]);
+
+		let steps = entries
+			.chunks(BATCH_SIZE)
+			.map(<[_]>::to_vec)
+			.collect::<Vec<_>>();
+
+		*data = Some(FileArchiverJobData {
+			target_directory,
+			target_archive_path,
+			partial_archive_dir,
+			batches: steps.len(),
+		});
+
+		Ok(((), steps, JobRunErrors(errors)).into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, step_number }: CurrentStep<'_, Self::Step>,
+		data: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+
+		trace!(
+			"Archiving batch {step_number} of {} entries into {}",
+			step.len(),
+			data.target_archive_path.display()
+		);
+
+		let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+		let stop = StopOnDrop::default();
+
+		let archiving = spawn_blocking({
+			let batch_path = data.batch_path(step_number);
+			let format = init.format;
+			let entries = step.clone();
+			let stop = Arc::clone(&stop.0);
+
+			move || write_batch(&batch_path, format, &entries, &progress_tx, &stop)
+		});
+
+		// Every batch but the last one is full
+		let archived = step_number * BATCH_SIZE;
+		while let Some(completed) = progress_rx.recv().await {
+			ctx.progress(vec![JobReportUpdate::CompletedTaskCount(
+				archived + completed,
+			)]);
+		}
+
+		Ok(JobRunErrors(archiving.await??).into())
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		_run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+		let data = data
+			.as_ref()
+			.expect("critical error: missing data on job state");
+
+		let joined_archive_path = data.partial_archive_dir.join("archive");
+
+		let joined = spawn_blocking({
+			let format = init.format;
+			let batch_paths = (0..data.batches)
+				.map(|step_number| data.batch_path(step_number))
+				.collect::<Vec<_>>();
+			let joined_archive_path = joined_archive_path.clone();
+
+			move || join_batches(&joined_archive_path, format, &batch_paths)
+		})
+		.await
+		.map_err(JobError::from)
+		.and_then(|res| res.map_err(Into::into));
+
+		let res: Result<(), JobError> = match joined {
+			Ok(()) => fs::rename(&joined_archive_path, &data.target_archive_path)
+				.await
+				.map_err(|e| FileIOError::from((&data.target_archive_path, e)).into()),
+			Err(e) => Err(e),
+		};
+
+		self.cleanup(ctx, data).await;
+		res?;
+
+		rescan_target_directory(ctx, init.target_location_id, data.target_directory.clone())
+			.await?;
+
+		invalidate_query!(ctx.library, "search.paths");
+
+		Ok(Some(json!({ "init": init })))
+	}
+
+	async fn cleanup(&self, _: &WorkerContext, data: &Self::Data) {
+		if let Err(e) = fs::remove_dir_all(&data.partial_archive_dir).await {
+			error!(
+				"Failed to remove partially written archive {}: {e:#?}",
+				data.partial_archive_dir.display()
+			);
+		}
+	}
+}
+
+/// Blocking tasks can't be aborted, so this tells the archiving task to stop when the step
+/// is dropped, as it happens when the job is canceled or shutdown
+#[derive(Default)]
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+	fn drop(&mut self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+}
+
+async fn read_dir_entries(
+	dir: &FileArchiverEntry,
+	errors: &mut Vec<String>,
+) -> Result<Vec<FileArchiverEntry>, FileSystemJobsError> {
+	let mut children = Vec::new();
+
+	let mut read_dir = fs::read_dir(&dir.source_full_path)
+		.await
+		.map_err(|e| FileIOError::from((&dir.source_full_path, e)))?;
+
+	while let Some(children_entry) = read_dir
+		.next_entry()
+		.await
+		.map_err(|e| FileIOError::from((&dir.source_full_path, e)))?
+	{
+		let children_path = children_entry.path();
+
+		let Some(children_name) = children_path.file_name().and_then(|name| name.to_str()) else {
+			warn!(
+				"Skipping archiving {} as it has a non UTF-8 name",
+				children_path.display()
+			);
+			continue;
+		};
+
+		let file_type = children_entry
+			.file_type()
+			.await
+			.map_err(|e| FileIOError::from((&children_path, e)))?;
+
+		// Symlinks are archived as what they point to, except for directories as they could form a loop
+		let is_dir = if file_type.is_symlink() {
+			match fs::metadata(&children_path).await {
+				Ok(metadata) if metadata.is_dir() => {
+					errors.push(format!(
+						"Skipped archiving {} as it's a symlink to a directory",
+						children_path.display()
+					));
+					continue;
+				}
+				Ok(_) => false,
+				Err(e) => {
+					errors.push(FileIOError::from((&children_path, e)).to_string());
+					continue;
+				}
+			}
+		} else {
+			file_type.is_dir()
+		};
+
+		children.push(FileArchiverEntry {
+			entry_path: format!("{}/{children_name}", dir.entry_path),
+			is_dir,
+			source_full_path: children_path,
+		});
+	}
+
+	Ok(children)
+}
+
+enum ArchiveWriter {
+	Zip(zip::ZipWriter<BufWriter<File>>),
+	TarZst(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+/// Writes a batch of entries to a new file, returning the errors of the files that were removed
+/// since the job started. The batch is removed if the step is stopped, as it will be written again.
+fn write_batch(
+	batch_path: &Path,
+	format: ArchiverFormat,
+	entries: &[FileArchiverEntry],
+	progress_tx: &mpsc::UnboundedSender<usize>,
+	stop: &AtomicBool,
+) -> Result<Vec<String>, FileSystemJobsError> {
+	let io_err = |e| FileIOError::from((batch_path, e));
+
+	let file = File::create(batch_path)
+		.map(BufWriter::new)
+		.map_err(io_err)?;
+
+	let mut writer = match format {
+		ArchiverFormat::Zip => ArchiveWriter::Zip(zip::ZipWriter::new(file)),
+		ArchiverFormat::TarZst => ArchiveWriter::TarZst(
+			zstd::stream::write::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)
+				.map_err(io_err)?,
+		),
+	};
+
+	let mut errors = vec![];
+
+	for (idx, entry) in entries.iter().enumerate() {
+		if stop.load(Ordering::Relaxed) {
+			drop(writer);
+			remove_batch(batch_path);
+			return Ok(errors);
+		}
+
+		match append_entry(&mut writer, entry) {
+			Ok(()) => {}
+			// The file could have been removed since the job started
+			Err(FileSystemJobsError::FileIO(e)) if e.source.kind() == io::ErrorKind::NotFound => {
+				errors.push(e.to_string());
+			}
+			Err(e) => {
+				drop(writer);
+				remove_batch(batch_path);
+				return Err(e);
+			}
+		}
+
+		progress_tx.send(idx + 1).ok();
+	}
+
+	match writer {
+		ArchiveWriter::Zip(mut writer) => writer
+			.finish()
+			.map_err(ArchiveError::from)?
+			.flush()
+			.map_err(io_err)?,
+		ArchiveWriter::TarZst(encoder) => encoder
+			.finish()
+			.and_then(|mut writer| writer.flush())
+			.map_err(io_err)?,
+	}
+
+	Ok(errors)
+}
+
+fn remove_batch(batch_path: &Path) {
+	if let Err(e) = std_fs::remove_file(batch_path) {
+		error!(
+			"Failed to remove partially written batch {}: {e:#?}",
+			batch_path.display()
+		);
+	}
+}
+
+/// Joins the batches written by every step into a single archive, without compressing them again
+fn join_batches(
+	archive_path: &Path,
+	format: ArchiverFormat,
+	batch_paths: &[PathBuf],
+) -> Result<(), FileSystemJobsError> {
+	let io_err = |e| FileIOError::from((archive_path, e));
+
+	let mut file = File::create(archive_path)
+		.map(BufWriter::new)
+		.map_err(io_err)?;
+
+	match format {
+		ArchiverFormat::Zip => {
+			let mut writer = zip::ZipWriter::new(file);
+
+			for batch_path in batch_paths {
+				let mut batch = zip::ZipArchive::new(
+					File::open(batch_path)
+						.map(BufReader::new)
+						.map_err(|e| FileIOError::from((batch_path, e)))?,
+				)
+				.map_err(ArchiveError::from)?;
+
+				for idx in 0..batch.len() {
+					writer
+						.raw_copy_file(batch.by_index_raw(idx).map_err(ArchiveError::from)?)
+						.map_err(ArchiveError::from)?;
+				}
+			}
+
+			writer
+				.finish()
+				.map_err(ArchiveError::from)?
+				.flush()
+				.map_err(io_err)?;
+		}
+		ArchiverFormat::TarZst => {
+			// Zstd frames can be concatenated, so the batches are just copied one after another
+			for batch_path in batch_paths {
+				let mut batch =
+					File::open(batch_path).map_err(|e| FileIOError::from((batch_path, e)))?;
+
+				io::copy(&mut batch, &mut file).map_err(io_err)?;
+			}
+
+			zstd::stream::write::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)
+				.and_then(|mut encoder| {
+					encoder.write_all(&TAR_END_OF_ARCHIVE)?;
+					encoder.finish()
+				})
+				.and_then(|mut writer| writer.flush())
+				.map_err(io_err)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Sources are opened before anything is written, so missing files don't leave broken entries
+fn append_entry(
+	writer: &mut ArchiveWriter,
+	FileArchiverEntry {
+		source_full_path,
+		entry_path,
+		is_dir,
+	}: &FileArchiverEntry,
+) -> Result<(), FileSystemJobsError> {
+	let source_err = |e| FileIOError::from((source_full_path, e));
+
+	match writer {
+		ArchiveWriter::Zip(writer) => {
+			let options = zip::write::FileOptions::default()
+				.compression_method(zip::CompressionMethod::Deflated)
+				.large_file(true);
+
+			if *is_dir {
+				writer
+					.add_directory(entry_path, options)
+					.map_err(ArchiveError::from)?;
+			} else {
+				let mut source = File::open(source_full_path)
+					.map(BufReader::new)
+					.map_err(source_err)?;
+
+				writer
+					.start_file(entry_path, options)
+					.map_err(ArchiveError::from)?;
+
+				io::copy(&mut source, writer).map_err(source_err)?;
+			}
+		}
+		ArchiveWriter::TarZst(encoder) => {
+			let source = if *is_dir {
+				None
+			} else {
+				Some(File::open(source_full_path).map_err(source_err)?)
+			};
+
+			let mut builder = tar::Builder::new(encoder);
+
+			let appended = match source {
+				None => builder.append_dir(entry_path, source_full_path),
+				Some(mut source) => builder.append_file(entry_path, &mut source),
+			};
+
+			// Dropping the builder would end the archive after this entry, while it only borrows
+			// the encoder, so forgetting it leaks nothing. The end is written once all batches are joined.
+			mem::forget(builder);
+
+			appended.map_err(source_err)?;
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::object::fs::archive::{self, ArchiveFormat};
+
+	use super::*;
+
+	#[test]
+	fn batches_are_joined_into_a_single_archive() {
+		let dir = tempfile::tempdir().unwrap();
+		std_fs::create_dir(dir.path().join("photos")).unwrap();
+		std_fs::write(dir.path().join("photos/a.txt"), b"first batch").unwrap();
+		std_fs::write(dir.path().join("b.txt"), b"second batch").unwrap();
+
+		let entry = |entry_path: &str, is_dir| FileArchiverEntry {
+			source_full_path: dir.path().join(entry_path),
+			entry_path: entry_path.to_string(),
+			is_dir,
+		};
+		let batches = [
+			vec![entry("photos", true), entry("photos/a.txt", false)],
+			vec![entry("b.txt", false)],
+		];
+
+		for (format, read_format) in [
+			(ArchiverFormat::Zip, ArchiveFormat::Zip),
+			(ArchiverFormat::TarZst, ArchiveFormat::TarZst),
+		] {
+			let (progress_tx, _progress_rx) = mpsc::unbounded_channel();
+			let batch_paths = batches
+				.iter()
+				.enumerate()
+				.map(|(idx, entries)| {
+					let batch_path = dir.path().join(format!("{idx}.{}", format.extension()));
+					let errors = write_batch(
+						&batch_path,
+						format,
+						entries,
+						&progress_tx,
+						&AtomicBool::new(false),
+					)
+					.unwrap();
+					assert!(errors.is_empty());
+
+					batch_path
+				})
+				.collect::<Vec<_>>();
+
+			let archive_path = dir.path().join(format!("archive.{}", format.extension()));
+			join_batches(&archive_path, format, &batch_paths).unwrap();
+
+			let mut entries = archive::list_entries(&archive_path, read_format)
+				.unwrap()
+				.into_iter()
+				.map(|entry| (entry.path, entry.is_dir))
+				.collect::<Vec<_>>();
+			entries.sort();
+			assert_eq!(
+				entries,
+				[
+					("b.txt".to_string(), false),
+					("photos".to_string(), true),
+					("photos/a.txt".to_string(), false),
+				]
+			);
+
+			let mut contents = vec![];
+			archive::extract_entry(&archive_path, read_format, "b.txt", &mut contents).unwrap();
+			assert_eq!(contents, b"second batch");
+		}
+	}
+}
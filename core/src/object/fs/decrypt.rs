@@ -1,159 +1,183 @@
-// use crate::{
-// 	invalidate_query,
-// 	job::{
-// 		JobError, JobInitData, JobReportUpdate, JobResult, JobState, StatefulJob, WorkerContext,
-// 	},
-// 	library::Library,
-// 	location::{file_path_helper:: location::id::Type},
-// 	util::error::FileIOError,
-// };
-
-// use sd_crypto::{crypto::Decryptor, header::file::FileHeader, Protected};
-
-// use serde::{Deserialize, Serialize};
-// use specta::Type;
-// use tokio::fs::File;
-
-// use super::{get_location_path_from_location_id, get_many_files_datas, FileData, BYTES_EXT};
-// pub struct FileDecryptorJob;
-
-// // decrypt could have an option to restore metadata (and another specific option for file name? - would turn "output file" into "output path" in the UI)
-// #[derive(Serialize, Deserialize, Debug, Type, Hash)]
-// pub struct FileDecryptorJobInit {
-// 	pub location_id: location::id::Type,
-// 	pub file_path_ids: Vec<file_path::id::Type>,
-// 	pub mount_associated_key: bool,
-// 	pub password: Option<String>, // if this is set, we can assume the user chose password decryption
-// 	pub save_to_library: Option<bool>,
-// }
-
-// impl JobInitData for FileDecryptorJobInit {
-// 	type Job = FileDecryptorJob;
-// }
-
-// #[async_trait::async_trait]
-// impl StatefulJob for FileDecryptorJob {
-// 	type Init = FileDecryptorJobInit;
-// 	type Data = ();
-// 	type Step = FileData;
-
-// 	const NAME: &'static str = "file_decryptor";
-
-// 	fn new() -> Self {
-// 		Self {}
-// 	}
-
-// 	async fn init(&self, ctx: WorkerContext, state: &mut JobState<Self>) -> Result<(), JobError> {
-// 		let Library { db, .. } = &*ctx.library;
-
-// 		state.steps = get_many_files_datas(
-// 			db,
-// 			get_location_path_from_location_id(db, state.init.location_id).await?,
-// 			&state.init.file_path_ids,
-// 		)
-// 		.await?
-// 		.into();
-
-// 		ctx.progress(vec![JobReportUpdate::TaskCount(state.steps.len())]);
-
-// 		Ok(())
-// 	}
-
-// 	async fn execute_step(
-// 		&self,
-// 		ctx: WorkerContext,
-// 		state: &mut JobState<Self>,
-// 	) -> Result<(), JobError> {
-// 		let step = &state.steps[0];
-// 		let key_manager = &ctx.library.key_manager;
-
-// 		// handle overwriting checks, and making sure there's enough available space
-// 		let output_path = {
-// 			let mut path = step.full_path.clone();
-// 			let extension = path.extension().map_or("decrypted", |ext| {
-// 				if ext == BYTES_EXT {
-// 					""
-// 				} else {
-// 					"decrypted"
-// 				}
-// 			});
-// 			path.set_extension(extension);
-// 			path
-// 		};
-
-// 		let mut reader = File::open(&step.full_path)
-// 			.await
-// 			.map_err(|e| FileIOError::from((&step.full_path, e)))?;
-// 		let mut writer = File::create(&output_path)
-// 			.await
-// 			.map_err(|e| FileIOError::from((output_path, e)))?;
-
-// 		let (header, aad) = FileHeader::from_reader(&mut reader).await?;
-
-// 		let master_key = if let Some(password) = state.init.password.clone() {
-// 			if let Some(save_to_library) = state.init.save_to_library {
-// 				// we can do this first, as `find_key_index` requires a successful decryption (just like `decrypt_master_key`)
-// 				let password_bytes = Protected::new(password.as_bytes().to_vec());
-
-// 				if save_to_library {
-// 					let index = header.find_key_index(password_bytes.clone()).await?;
-
-// 					// inherit the encryption algorithm from the keyslot
-// 					key_manager
-// 						.add_to_keystore(
-// 							Protected::new(password),
-// 							header.algorithm,
-// 							header.keyslots[index].hashing_algorithm,
-// 							false,
-// 							false,
-// 							Some(header.keyslots[index].salt),
-// 						)
-// 						.await?;
-// 				}
-
-// 				header.decrypt_master_key(password_bytes).await?
-// 			} else {
-// 				return Err(JobError::JobDataNotFound(String::from(
-// 					"Password decryption selected, but save to library boolean was not included",
-// 				)));
-// 			}
-// 		} else {
-// 			if state.init.mount_associated_key {
-// 				for key in key_manager.dump_keystore().iter().filter(|x| {
-// 					header
-// 						.keyslots
-// 						.iter()
-// 						.any(|k| k.content_salt == x.content_salt)
-// 				}) {
-// 					key_manager.mount(key.uuid).await.ok();
-// 				}
-// 			}
-
-// 			let keys = key_manager.enumerate_hashed_keys();
-
-// 			header.decrypt_master_key_from_prehashed(keys).await?
-// 		};
-
-// 		let decryptor = Decryptor::new(master_key, header.nonce, header.algorithm)?;
-
-// 		decryptor
-// 			.decrypt_streams(&mut reader, &mut writer, &aad)
-// 			.await?;
-
-// 		// need to decrypt preview media/metadata, and maybe add an option in the UI so the user can chosoe to restore these values
-// 		// for now this can't easily be implemented, as we don't know what the new object id for the file will be (we know the old one, but it may differ)
-
-// 		ctx.progress(vec![JobReportUpdate::CompletedTaskCount(
-// 			state.step_number + 1,
-// 		)]);
-
-// 		Ok(())
-// 	}
-
-// 	async fn finalize(&self, ctx: WorkerContext, state: &mut JobState<Self>) -> JobResult {
-// 		invalidate_query!(ctx.library, "search.paths");
-
-// 		// mark job as successful
-// 		Ok(Some(serde_json::to_value(&state.init)?))
-// 	}
-// }
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobResult, JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::get_location_path_from_location_id,
+	prisma::{file_path, location},
+	util::{db::maybe_missing, error::FileIOError},
+};
+
+use sd_crypto::{crypto::Decryptor, header::file::FileHeader, Protected};
+
+use std::{
+	collections::HashSet,
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::fs::{self, File};
+use tracing::{trace, warn};
+
+use super::{
+	error::FileSystemJobsError, find_available_filename_for_duplicate, get_many_files_datas,
+	remove_partial_output, rescan_target_directory, FileData, BYTES_EXT,
+};
+
+#[derive(Serialize, Deserialize, Type, Debug)]
+pub struct FileDecryptorJobInit {
+	pub location_id: location::id::Type,
+	pub file_path_ids: Vec<file_path::id::Type>,
+	/// The password is never persisted with the job report, so an interrupted
+	/// decryption job is canceled when the app restarts and has to be started again
+	#[serde(skip_serializing)]
+	pub password: Protected<String>,
+}
+
+impl Hash for FileDecryptorJobInit {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.location_id.hash(state);
+		self.file_path_ids.hash(state);
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileDecryptorJobData {
+	/// Directories where the decrypted files are written, to be rescanned at the end
+	target_directories: HashSet<PathBuf>,
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for FileDecryptorJobInit {
+	type Data = FileDecryptorJobData;
+	type Step = FileData;
+	type RunMetadata = ();
+
+	const NAME: &'static str = "file_decryptor";
+
+	fn target_location(&self) -> location::id::Type {
+		self.location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let location_path = get_location_path_from_location_id(db, init.location_id)
+			.await
+			.map_err(FileSystemJobsError::from)?;
+
+		let steps = get_many_files_datas(db, &location_path, &init.file_path_ids).await?;
+
+		*data = Some(FileDecryptorJobData {
+			target_directories: steps
+				.iter()
+				.filter_map(|step| step.full_path.parent().map(Path::to_path_buf))
+				.collect(),
+		});
+
+		Ok(steps.into())
+	}
+
+	async fn execute_step(
+		&self,
+		_: &WorkerContext,
+		CurrentStep { step, .. }: CurrentStep<'_, Self::Step>,
+		_data: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+
+		if maybe_missing(step.file_path.is_dir, "file_path.is_dir")? {
+			warn!(
+				"Decryption is skipping {} as it isn't a file",
+				step.full_path.display()
+			);
+
+			return Ok(().into());
+		}
+
+		// `photo.jpg.bytes` goes back to `photo.jpg`, anything else gets a `.decrypted` suffix
+		let mut output_path = step
+			.full_path
+			.to_str()
+			.and_then(|path| path.strip_suffix(BYTES_EXT))
+			.map_or_else(
+				|| {
+					let mut path = step.full_path.clone().into_os_string();
+					path.push(".decrypted");
+					PathBuf::from(path)
+				},
+				PathBuf::from,
+			);
+
+		if fs::metadata(&output_path).await.is_ok() {
+			output_path = find_available_filename_for_duplicate(&output_path).await?;
+		}
+
+		trace!(
+			"Decrypting {} into {}",
+			step.full_path.display(),
+			output_path.display()
+		);
+
+		let mut reader = File::open(&step.full_path)
+			.await
+			.map_err(|e| FileIOError::from((&step.full_path, e)))?;
+
+		let (header, aad) = FileHeader::from_reader(&mut reader).await?;
+
+		let master_key = header
+			.decrypt_master_key(Protected::<Vec<u8>>::from(init.password.clone()))
+			.await?;
+
+		// Only creating the output file after the password was validated against the header
+		let mut writer = File::create(&output_path)
+			.await
+			.map_err(|e| FileIOError::from((&output_path, e)))?;
+
+		let decrypted = async {
+			Decryptor::new(master_key, header.nonce, header.algorithm)?
+				.decrypt_streams(&mut reader, &mut writer, &aad)
+				.await
+		}
+		.await;
+
+		if let Err(e) = decrypted {
+			drop(writer);
+			remove_partial_output(&output_path).await;
+
+			return Err(e.into());
+		}
+
+		Ok(().into())
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		_run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+		let data = data
+			.as_ref()
+			.expect("critical error: missing data on job state");
+
+		for target_directory in &data.target_directories {
+			rescan_target_directory(ctx, init.location_id, target_directory.clone()).await?;
+		}
+
+		invalidate_query!(ctx.library, "search.paths");
+
+		Ok(Some(json!({ "init": init })))
+	}
+}
@@ -1,261 +1,233 @@
-// use crate::{
-// 	invalidate_query,
-// 	job::*,
-// 	library::Library,
-// 	location::{file_path_helper:: location::id::Type},
-// 	util::error::{FileIOError, NonUtf8PathError},
-// };
-
-// use sd_crypto::{
-// 	crypto::Encryptor,
-// 	header::{file::FileHeader, keyslot::Keyslot},
-// 	primitives::{LATEST_FILE_HEADER, LATEST_KEYSLOT, LATEST_METADATA, LATEST_PREVIEW_MEDIA},
-// 	types::{Algorithm, Key},
-// };
-
-// use chrono::FixedOffset;
-// use serde::{Deserialize, Serialize};
-// use specta::Type;
-// use tokio::{
-// 	fs::{self, File},
-// 	io,
-// };
-// use tracing::{error, warn};
-// use uuid::Uuid;
-
-// use super::{
-// 	error::FileSystemJobsError, get_location_path_from_location_id, get_many_files_datas, FileData,
-// 	BYTES_EXT,
-// };
-
-// pub struct FileEncryptorJob;
-
-// #[derive(Serialize, Deserialize, Type, Hash)]
-// pub struct FileEncryptorJobInit {
-// 	pub location_id: location::id::Type,
-// 	pub file_path_ids: Vec<file_path::id::Type>,
-// 	pub key_uuid: Uuid,
-// 	pub algorithm: Algorithm,
-// 	pub metadata: bool,
-// 	pub preview_media: bool,
-// }
-
-// #[derive(Serialize, Deserialize)]
-// pub struct Metadata {
-// 	pub file_path_id: file_path::id::Type,
-// 	pub name: String,
-// 	pub hidden: bool,
-// 	pub favorite: bool,
-// 	pub important: bool,
-// 	pub note: Option<String>,
-// 	pub date_created: chrono::DateTime<FixedOffset>,
-// }
-
-// impl JobInitData for FileEncryptorJobInit {
-// 	type Job = FileEncryptorJob;
-// }
-
-// #[async_trait::async_trait]
-// impl StatefulJob for FileEncryptorJob {
-// 	type Init = FileEncryptorJobInit;
-// 	type Data = ();
-// 	type Step = FileData;
-
-// 	const NAME: &'static str = "file_encryptor";
-
-// 	fn new() -> Self {
-// 		Self {}
-// 	}
-
-// 	async fn init(&self, ctx: WorkerContext, state: &mut JobState<Self>) -> Result<(), JobError> {
-// 		let Library { db, .. } = &*ctx.library;
-
-// 		state.steps = get_many_files_datas(
-// 			db,
-// 			get_location_path_from_location_id(db, state.init.location_id).await?,
-// 			&state.init.file_path_ids,
-// 		)
-// 		.await?
-// 		.into();
-
-// 		ctx.progress(vec![JobReportUpdate::TaskCount(state.steps.len())]);
-
-// 		Ok(())
-// 	}
-
-// 	async fn execute_step(
-// 		&self,
-// 		ctx: WorkerContext,
-// 		state: &mut JobState<Self>,
-// 	) -> Result<(), JobError> {
-// 		let step = &state.steps[0];
-
-// 		let Library { key_manager, .. } = &*ctx.library;
-
-// 		if !step.file_path.is_dir {
-// 			// handle overwriting checks, and making sure there's enough available space
-
-// 			let user_key = key_manager
-// 				.access_keymount(state.init.key_uuid)
-// 				.await?
-// 				.hashed_key;
-
-// 			let user_key_details = key_manager.access_keystore(state.init.key_uuid).await?;
-
-// 			let output_path = {
-// 				let mut path = step.full_path.clone();
-// 				let extension = path.extension().map_or_else(
-// 					|| Ok("bytes".to_string()),
-// 					|extension| {
-// 						Ok::<String, JobError>(format!(
-// 							"{}{BYTES_EXT}",
-// 							extension.to_str().ok_or(FileSystemJobsError::FilePath(
-// 								NonUtf8PathError(step.full_path.clone().into_boxed_path()).into()
-// 							))?
-// 						))
-// 					},
-// 				)?;
-
-// 				path.set_extension(extension);
-// 				path
-// 			};
-
-// 			let _guard = ctx
-// 				.library
-// 				.location_manager()
-// 				.temporary_ignore_events_for_path(
-// 					state.init.location_id,
-// 					ctx.library.clone(),
-// 					&output_path,
-// 				)
-// 				.await
-// 				.map_or_else(
-// 					|e| {
-// 						error!(
-// 							"Failed to make location manager ignore the path {}; Error: {e:#?}",
-// 							output_path.display()
-// 						);
-// 						None
-// 					},
-// 					Some,
-// 				);
-
-// 			let mut reader = File::open(&step.full_path)
-// 				.await
-// 				.map_err(|e| FileIOError::from((&step.full_path, e)))?;
-// 			let mut writer = File::create(&output_path)
-// 				.await
-// 				.map_err(|e| FileIOError::from((output_path, e)))?;
-
-// 			let master_key = Key::generate();
-
-// 			let mut header = FileHeader::new(
-// 				LATEST_FILE_HEADER,
-// 				state.init.algorithm,
-// 				vec![
-// 					Keyslot::new(
-// 						LATEST_KEYSLOT,
-// 						state.init.algorithm,
-// 						user_key_details.hashing_algorithm,
-// 						user_key_details.content_salt,
-// 						user_key,
-// 						master_key.clone(),
-// 					)
-// 					.await?,
-// 				],
-// 			)?;
-
-// 			if state.init.metadata || state.init.preview_media {
-// 				// if any are requested, we can make the query as it'll be used at least once
-// 				if let Some(ref object) = step.file_path.object {
-// 					if state.init.metadata {
-// 						let metadata = Metadata {
-// 							file_path_id: step.file_path.id,
-// 							name: step.file_path.materialized_path.clone(),
-// 							hidden: object.hidden,
-// 							favorite: object.favorite,
-// 							important: object.important,
-// 							note: object.note.clone(),
-// 							date_created: object.date_created,
-// 						};
-
-// 						header
-// 							.add_metadata(
-// 								LATEST_METADATA,
-// 								state.init.algorithm,
-// 								master_key.clone(),
-// 								&metadata,
-// 							)
-// 							.await?;
-// 					}
-
-// 					// if state.init.preview_media
-// 					// 	&& (object.has_thumbnail
-// 					// 		|| object.has_video_preview || object.has_thumbstrip)
-
-// 					// may not be the best - preview media (thumbnail) isn't guaranteed to be webp
-// 					let thumbnail_path = ctx
-// 						.library
-// 						.config()
-// 						.data_directory()
-// 						.join("thumbnails")
-// 						.join(
-// 							step.file_path
-// 								.cas_id
-// 								.as_ref()
-// 								.ok_or(JobError::MissingCasId)?,
-// 						)
-// 						.with_extension("wepb");
-
-// 					match fs::read(&thumbnail_path).await {
-// 						Ok(thumbnail_bytes) => {
-// 							header
-// 								.add_preview_media(
-// 									LATEST_PREVIEW_MEDIA,
-// 									state.init.algorithm,
-// 									master_key.clone(),
-// 									&thumbnail_bytes,
-// 								)
-// 								.await?;
-// 						}
-// 						Err(e) if e.kind() == io::ErrorKind::NotFound => {
-// 							// If the file just doesn't exist, then we don't care
-// 						}
-// 						Err(e) => {
-// 							return Err(FileIOError::from((thumbnail_path, e)).into());
-// 						}
-// 					}
-// 				} else {
-// 					// should use container encryption if it's a directory
-// 					warn!("skipping metadata/preview media inclusion, no associated object found")
-// 				}
-// 			}
-
-// 			header.write(&mut writer).await?;
-
-// 			let encryptor = Encryptor::new(master_key, header.nonce, header.algorithm)?;
-
-// 			encryptor
-// 				.encrypt_streams(&mut reader, &mut writer, &header.generate_aad())
-// 				.await?;
-// 		} else {
-// 			warn!(
-// 				"encryption is skipping {}/{} as it isn't a file",
-// 				step.file_path.materialized_path, step.file_path.name
-// 			)
-// 		}
-
-// 		ctx.progress(vec![JobReportUpdate::CompletedTaskCount(
-// 			state.step_number + 1,
-// 		)]);
-
-// 		Ok(())
-// 	}
-
-// 	async fn finalize(&self, ctx: WorkerContext, state: &mut JobState<Self>) -> JobResult {
-// 		invalidate_query!(ctx.library, "search.paths");
-
-// 		// mark job as successful
-// 		Ok(Some(serde_json::to_value(&state.init)?))
-// 	}
-// }
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobResult, JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::get_location_path_from_location_id,
+	object::media::thumbnail::{find_indexed_thumbnail, ThumbnailSize},
+	prisma::{file_path, location},
+	util::{db::maybe_missing, error::FileIOError},
+};
+
+use sd_crypto::{
+	crypto::Encryptor,
+	header::{file::FileHeader, keyslot::Keyslot},
+	primitives::{LATEST_FILE_HEADER, LATEST_KEYSLOT, LATEST_PREVIEW_MEDIA},
+	types::{Algorithm, HashingAlgorithm, Key, Salt},
+	Protected,
+};
+
+use std::{
+	collections::HashSet,
+	hash::{Hash, Hasher},
+	path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::{
+	fs::{self, File},
+	task::spawn_blocking,
+};
+use tracing::{trace, warn};
+
+use super::{
+	error::FileSystemJobsError, find_available_filename_for_duplicate, get_many_files_datas,
+	remove_partial_output, rescan_target_directory, FileData, BYTES_EXT,
+};
+
+#[derive(Serialize, Deserialize, Type, Debug)]
+pub struct FileEncryptorJobInit {
+	pub location_id: location::id::Type,
+	pub file_path_ids: Vec<file_path::id::Type>,
+	pub algorithm: Algorithm,
+	pub hashing_algorithm: HashingAlgorithm,
+	/// The password is never persisted with the job report, so an interrupted
+	/// encryption job is canceled when the app restarts and has to be started again
+	#[serde(skip_serializing)]
+	pub password: Protected<String>,
+	/// Embeds the file's thumbnail in the header, so it can be previewed without decrypting it
+	pub preview_media: bool,
+}
+
+impl Hash for FileEncryptorJobInit {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.location_id.hash(state);
+		self.file_path_ids.hash(state);
+		self.algorithm.hash(state);
+		self.preview_media.hash(state);
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileEncryptorJobData {
+	/// Directories where the encrypted files are written, to be rescanned at the end
+	target_directories: HashSet<PathBuf>,
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for FileEncryptorJobInit {
+	type Data = FileEncryptorJobData;
+	type Step = FileData;
+	type RunMetadata = ();
+
+	const NAME: &'static str = "file_encryptor";
+
+	fn target_location(&self) -> location::id::Type {
+		self.location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let location_path = get_location_path_from_location_id(db, init.location_id)
+			.await
+			.map_err(FileSystemJobsError::from)?;
+
+		let steps = get_many_files_datas(db, &location_path, &init.file_path_ids).await?;
+
+		*data = Some(FileEncryptorJobData {
+			target_directories: steps
+				.iter()
+				.filter_map(|step| step.full_path.parent().map(Path::to_path_buf))
+				.collect(),
+		});
+
+		Ok(steps.into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, .. }: CurrentStep<'_, Self::Step>,
+		_data: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+
+		if maybe_missing(step.file_path.is_dir, "file_path.is_dir")? {
+			warn!(
+				"Encryption is skipping {} as it isn't a file",
+				step.full_path.display()
+			);
+
+			return Ok(().into());
+		}
+
+		let mut output_path = step.full_path.clone().into_os_string();
+		output_path.push(BYTES_EXT);
+		let mut output_path = PathBuf::from(output_path);
+		if fs::metadata(&output_path).await.is_ok() {
+			output_path = find_available_filename_for_duplicate(&output_path).await?;
+		}
+
+		trace!(
+			"Encrypting {} into {}",
+			step.full_path.display(),
+			output_path.display()
+		);
+
+		// Password hashing is purposely slow, so we keep it away from the async runtime
+		let content_salt = Salt::generate();
+		let hashed_password = spawn_blocking({
+			let hashing_algorithm = init.hashing_algorithm;
+			let password = Protected::<Vec<u8>>::from(init.password.clone());
+			move || hashing_algorithm.hash(password, content_salt, None)
+		})
+		.await??;
+
+		let master_key = Key::generate();
+
+		let mut header = FileHeader::new(
+			LATEST_FILE_HEADER,
+			init.algorithm,
+			vec![
+				Keyslot::new(
+					LATEST_KEYSLOT,
+					init.algorithm,
+					init.hashing_algorithm,
+					content_salt,
+					hashed_password,
+					master_key.clone(),
+				)
+				.await?,
+			],
+		)?;
+
+		if init.preview_media {
+			if let Some(cas_id) = &step.file_path.cas_id {
+				// Not every file has a thumbnail, so we just don't embed anything
+				if let Some(thumbnail_path) =
+					find_indexed_thumbnail(&ctx.node, cas_id, ctx.library.id, ThumbnailSize::Medium)
+						.await?
+				{
+					let thumbnail_bytes = fs::read(&thumbnail_path)
+						.await
+						.map_err(|e| FileIOError::from((thumbnail_path, e)))?;
+
+					header
+						.add_preview_media(
+							LATEST_PREVIEW_MEDIA,
+							init.algorithm,
+							master_key.clone(),
+							&thumbnail_bytes,
+						)
+						.await?;
+				}
+			}
+		}
+
+		let mut reader = File::open(&step.full_path)
+			.await
+			.map_err(|e| FileIOError::from((&step.full_path, e)))?;
+		let mut writer = File::create(&output_path)
+			.await
+			.map_err(|e| FileIOError::from((&output_path, e)))?;
+
+		let encrypted = async {
+			header.write(&mut writer).await?;
+
+			Encryptor::new(master_key, header.nonce, header.algorithm)?
+				.encrypt_streams(&mut reader, &mut writer, &header.generate_aad())
+				.await
+		}
+		.await;
+
+		if let Err(e) = encrypted {
+			drop(writer);
+			remove_partial_output(&output_path).await;
+
+			return Err(e.into());
+		}
+
+		Ok(().into())
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		_run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+		let data = data
+			.as_ref()
+			.expect("critical error: missing data on job state");
+
+		// The encrypted files are identified as `ObjectKind::Encrypted` by their extension and magic bytes
+		for target_directory in &data.target_directories {
+			rescan_target_directory(ctx, init.location_id, target_directory.clone()).await?;
+		}
+
+		invalidate_query!(ctx.library, "search.paths");
+
+		Ok(Some(json!({ "init": init })))
+	}
+}
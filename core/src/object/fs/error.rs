@@ -1,5 +1,6 @@
 use crate::{
 	location::{file_path_helper::FilePathError, LocationError},
+	object::fs::archive::ArchiveError,
 	prisma::file_path,
 	util::{
 		db::MissingFieldError,
@@ -41,6 +42,8 @@ pub enum FileSystemJobsError {
 	NonUTF8Path(#[from] NonUtf8PathError),
 	#[error("failed to find an available name to avoid duplication: <path='{}'>", .0.display())]
 	FailedToFindAvailableName(Box<Path>),
+	#[error(transparent)]
+	Archive(#[from] ArchiveError),
 }
 
 impl From<FileSystemJobsError> for rspc::Error {
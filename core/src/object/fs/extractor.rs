@@ -0,0 +1,224 @@
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobResult, JobRunErrors, JobStepOutput, StatefulJob,
+		WorkerContext,
+	},
+	library::Library,
+	location::file_path_helper::join_location_relative_path,
+	prisma::{file_path, location},
+	util::error::FileIOError,
+};
+
+use std::{collections::HashMap, hash::Hash, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::{fs, task::spawn_blocking};
+use tracing::trace;
+
+use super::{
+	archive::{self, is_safe_entry_path, ArchiveError, ArchiveFormat},
+	error::FileSystemJobsError,
+	fetch_source_and_target_location_paths, find_available_filename_for_duplicate,
+	get_many_files_datas, rescan_target_directory,
+};
+
+/// Amount of archive entries extracted on each step, as extracting from compressed tar archives
+/// requires decompressing everything before the entry, we don't want a step per entry
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileExtractorJobData {
+	target_directory: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug)]
+pub struct FileExtractorJobInit {
+	pub source_location_id: location::id::Type,
+	pub target_location_id: location::id::Type,
+	pub sources_file_path_ids: Vec<file_path::id::Type>,
+	pub target_location_relative_directory_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileExtractorJobStep {
+	pub archive_path: PathBuf,
+	pub format: ArchiveFormat,
+	/// Normalized entry paths inside the archive and where they must be extracted to
+	pub entries: HashMap<String, PathBuf>,
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for FileExtractorJobInit {
+	type Data = FileExtractorJobData;
+	type Step = FileExtractorJobStep;
+	type RunMetadata = ();
+
+	const NAME: &'static str = "file_extractor";
+
+	fn target_location(&self) -> location::id::Type {
+		self.target_location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let (sources_location_path, targets_location_path) =
+			fetch_source_and_target_location_paths(
+				db,
+				init.source_location_id,
+				init.target_location_id,
+			)
+			.await?;
+
+		let target_directory = join_location_relative_path(
+			&targets_location_path,
+			&init.target_location_relative_directory_path,
+		);
+
+		let mut steps = vec![];
+		let mut errors = vec![];
+
+		for file_data in
+			get_many_files_datas(db, &sources_location_path, &init.sources_file_path_ids).await?
+		{
+			let archive_path = file_data.full_path;
+
+			let Some((format, archive_stem)) =
+				ArchiveFormat::from_path(&archive_path).and_then(|format| {
+					format
+						.archive_stem(&archive_path)
+						.map(|stem| (format, stem))
+				})
+			else {
+				errors.push(ArchiveError::UnsupportedFormat(archive_path.into()).to_string());
+				continue;
+			};
+
+			// Each archive is extracted into its own directory, named after the archive
+			let mut extraction_directory = target_directory.join(archive_stem);
+			if fs::metadata(&extraction_directory).await.is_ok() {
+				extraction_directory =
+					find_available_filename_for_duplicate(extraction_directory).await?;
+			}
+
+			fs::create_dir_all(&extraction_directory)
+				.await
+				.map_err(|e| FileIOError::from((&extraction_directory, e)))
+				.map_err(FileSystemJobsError::CreateFileOrFolder)?;
+
+			let entries = spawn_blocking({
+				let archive_path = archive_path.clone();
+				move || archive::list_entries(archive_path, format)
+			})
+			.await?
+			.map_err(FileSystemJobsError::from)?;
+
+			let (safe_entries, unsafe_entries) = entries
+				.into_iter()
+				.filter(|entry| !entry.path.is_empty())
+				.partition::<Vec<_>, _>(|entry| is_safe_entry_path(&entry.path));
+
+			errors.extend(unsafe_entries.into_iter().map(|entry| {
+				format!(
+					"Skipped extracting <entry='{}'> from <archive='{}'> as it would be written \
+					outside of the extraction directory",
+					entry.path,
+					archive_path.display()
+				)
+			}));
+
+			steps.extend(safe_entries.chunks(BATCH_SIZE).map(|chunk| {
+				FileExtractorJobStep {
+					archive_path: archive_path.clone(),
+					format,
+					entries: chunk
+						.iter()
+						.map(|entry| (entry.path.clone(), extraction_directory.join(&entry.path)))
+						.collect(),
+				}
+			}));
+		}
+
+		*data = Some(FileExtractorJobData { target_directory });
+
+		Ok(((), steps, JobRunErrors(errors)).into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, .. }: CurrentStep<'_, Self::Step>,
+		_data: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let FileExtractorJobStep {
+			archive_path,
+			format,
+			entries,
+		} = step;
+
+		ctx.progress_msg(format!(
+			"Extracting {} entries from {}",
+			entries.len(),
+			archive_path.display()
+		));
+
+		trace!(
+			"Extracting {} entries from {}",
+			entries.len(),
+			archive_path.display()
+		);
+
+		let extracted = spawn_blocking({
+			let archive_path = archive_path.clone();
+			let format = *format;
+			let entries = entries.clone();
+			move || archive::extract_entries(archive_path, format, &entries)
+		})
+		.await?
+		.map_err(FileSystemJobsError::from)?;
+
+		if extracted.len() == entries.len() {
+			Ok(().into())
+		} else {
+			Ok(JobRunErrors(
+				entries
+					.keys()
+					.filter(|entry_path| !extracted.contains(entry_path))
+					.map(|entry_path| {
+						ArchiveError::EntryNotFound(archive_path.clone().into(), entry_path.clone())
+							.to_string()
+					})
+					.collect(),
+			)
+			.into())
+		}
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		_run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+		let FileExtractorJobData { target_directory } = data
+			.as_ref()
+			.expect("critical error: missing data on job state")
+			.clone();
+
+		rescan_target_directory(ctx, init.target_location_id, target_directory).await?;
+
+		invalidate_query!(ctx.library, "search.paths");
+
+		Ok(Some(json!({ "init": init })))
+	}
+}
@@ -1,7 +1,8 @@
 use crate::{
+	job::WorkerContext,
 	location::{
 		file_path_helper::{file_path_with_object, IsolatedFilePathData},
-		LocationError,
+		find_location, light_scan_location, location_with_indexer_rules, LocationError,
 	},
 	prisma::{file_path, location, PrismaClient},
 	util::{
@@ -18,15 +19,20 @@ use std::{
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::error;
 
+pub mod archive;
 pub mod delete;
 pub mod erase;
 
 pub mod copy;
 pub mod cut;
 
-// pub mod decrypt;
-// pub mod encrypt;
+pub mod archiver;
+pub mod extractor;
+
+pub mod decrypt;
+pub mod encrypt;
 
 pub mod error;
 
@@ -36,7 +42,8 @@ use tokio::{fs, io};
 static DUPLICATE_PATTERN: Lazy<Regex> =
 	Lazy::new(|| Regex::new(r" \(\d+\)").expect("Failed to compile hardcoded regex"));
 
-// pub const BYTES_EXT: &str = ".bytes";
+/// Extension appended to the files encrypted by the [`encrypt`] job
+pub const BYTES_EXT: &str = ".bytes";
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub enum ObjectType {
@@ -218,3 +225,41 @@ pub async fn find_available_filename_for_duplicate(
 		target_path.to_path_buf().into_boxed_path(),
 	))
 }
+
+/// Shallow rescans the directory where a job wrote its output, so the new files show up in the
+/// explorer without waiting for the location watcher
+pub async fn rescan_target_directory(
+	ctx: &WorkerContext,
+	location_id: location::id::Type,
+	target_directory: PathBuf,
+) -> Result<(), FileSystemJobsError> {
+	let location = find_location(&ctx.library, location_id)
+		.include(location_with_indexer_rules::include())
+		.exec()
+		.await?
+		.ok_or(LocationError::IdNotFound(location_id))?;
+
+	let node = ctx.node.clone();
+	let library = ctx.library.clone();
+
+	tokio::spawn(async move {
+		if let Err(e) = light_scan_location(node, library, location, target_directory).await {
+			error!("light scan error: {e:#?}");
+		}
+	});
+
+	Ok(())
+}
+
+/// Removes the output file of a job step that failed halfway, as it would only have part of
+/// the data. Failing to remove it is only logged, so the original error is reported.
+pub async fn remove_partial_output(output_path: impl AsRef<Path>) {
+	let output_path = output_path.as_ref();
+
+	if let Err(e) = fs::remove_file(output_path).await {
+		error!(
+			"Failed to remove partially written file {}: {e:#?}",
+			output_path.display()
+		);
+	}
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use sd_prisma::prisma_sync;
+use sd_sync::*;
+use serde::Deserialize;
+use serde_json::json;
+use specta::Type;
+
+use uuid::Uuid;
+
+use crate::{library::Library, prisma::label};
+
+// Labels describe the contents of objects and are meant to be assigned by the node itself, while tags
+// are curated by users, so both are kept apart.
+
+#[derive(Type, Deserialize, Clone)]
+pub struct LabelCreateArgs {
+	pub name: String,
+}
+
+impl LabelCreateArgs {
+	/// Labels are identified by their name, so creating one that already exists returns it instead
+	pub async fn exec(
+		self,
+		Library { db, sync, .. }: &Library,
+	) -> prisma_client_rust::Result<label::Data> {
+		if let Some(label) = db
+			.label()
+			.find_first(vec![label::name::equals(Some(self.name.clone()))])
+			.exec()
+			.await?
+		{
+			return Ok(label);
+		}
+
+		let pub_id = Uuid::new_v4().as_bytes().to_vec();
+		let date_created: DateTime<FixedOffset> = Utc::now().into();
+
+		sync.write_ops(
+			db,
+			(
+				sync.shared_create(
+					prisma_sync::label::SyncId {
+						pub_id: pub_id.clone(),
+					},
+					[
+						(label::name::NAME, json!(&self.name)),
+						(label::date_created::NAME, json!(&date_created.to_rfc3339())),
+					],
+				),
+				db.label().create(
+					pub_id,
+					vec![
+						label::name::set(Some(self.name)),
+						label::date_created::set(date_created),
+					],
+				),
+			),
+		)
+		.await
+	}
+}
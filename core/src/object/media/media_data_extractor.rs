@@ -1,14 +1,22 @@
 use crate::{
 	job::JobRunErrors,
 	location::file_path_helper::{file_path_for_media_processor, IsolatedFilePathData},
+	object::album::refresh_smart_albums,
 	prisma::{location, media_data, PrismaClient},
 	util::error::FileIOError,
 };
 
-use sd_file_ext::extensions::{Extension, ImageExtension, ALL_IMAGE_EXTENSIONS};
-use sd_media_metadata::ImageMetadata;
+use sd_file_ext::extensions::{
+	AudioExtension, Extension, ImageExtension, ALL_AUDIO_EXTENSIONS, ALL_IMAGE_EXTENSIONS,
+};
+use sd_media_metadata::{AudioMetadata, ImageMetadata, MediaMetadata};
+
+#[cfg(feature = "ffmpeg")]
+use sd_file_ext::extensions::{VideoExtension, ALL_VIDEO_EXTENSIONS};
+#[cfg(feature = "ffmpeg")]
+use sd_media_metadata::VideoMetadata;
 
-use std::{collections::HashSet, path::Path};
+use std::{collections::HashSet, path::Path, str::FromStr};
 
 use futures_concurrency::future::Join;
 use once_cell::sync::Lazy;
@@ -17,7 +25,7 @@ use thiserror::Error;
 use tokio::task::spawn_blocking;
 use tracing::error;
 
-use super::media_data_image_to_query;
+use super::media_data_to_query;
 
 #[derive(Error, Debug)]
 pub enum MediaDataError {
@@ -47,6 +55,42 @@ pub(super) static FILTERED_IMAGE_EXTENSIONS: Lazy<Vec<Extension>> = Lazy::new(||
 		.collect()
 });
 
+pub(super) static FILTERED_AUDIO_EXTENSIONS: Lazy<Vec<Extension>> = Lazy::new(|| {
+	ALL_AUDIO_EXTENSIONS
+		.iter()
+		.cloned()
+		.filter(can_extract_media_data_for_audio)
+		.map(Extension::Audio)
+		.collect()
+});
+
+#[cfg(feature = "ffmpeg")]
+pub(super) static FILTERED_VIDEO_EXTENSIONS: Lazy<Vec<Extension>> = Lazy::new(|| {
+	ALL_VIDEO_EXTENSIONS
+		.iter()
+		.cloned()
+		.filter(can_extract_media_data_for_video)
+		.map(Extension::Video)
+		.collect()
+});
+
+pub(super) static ALL_MEDIA_DATA_EXTENSIONS: Lazy<Vec<Extension>> = Lazy::new(|| {
+	#[cfg(feature = "ffmpeg")]
+	return FILTERED_IMAGE_EXTENSIONS
+		.iter()
+		.chain(FILTERED_AUDIO_EXTENSIONS.iter())
+		.chain(FILTERED_VIDEO_EXTENSIONS.iter())
+		.cloned()
+		.collect();
+
+	#[cfg(not(feature = "ffmpeg"))]
+	FILTERED_IMAGE_EXTENSIONS
+		.iter()
+		.chain(FILTERED_AUDIO_EXTENSIONS.iter())
+		.cloned()
+		.collect()
+});
+
 pub const fn can_extract_media_data_for_image(image_extension: &ImageExtension) -> bool {
 	use ImageExtension::*;
 	matches!(
@@ -55,6 +99,21 @@ pub const fn can_extract_media_data_for_image(image_extension: &ImageExtension)
 	)
 }
 
+pub const fn can_extract_media_data_for_audio(audio_extension: &AudioExtension) -> bool {
+	use AudioExtension::*;
+	matches!(
+		audio_extension,
+		Mp3 | M4a | Wav | Aiff | Aif | Flac | Ogg | Oga | Aac | Adts
+	)
+}
+
+#[cfg(feature = "ffmpeg")]
+pub const fn can_extract_media_data_for_video(video_extension: &VideoExtension) -> bool {
+	use VideoExtension::*;
+	// Formats without a container that FFmpeg can reliably probe
+	!matches!(video_extension, Swf | Mjpeg | Hevc)
+}
+
 pub async fn extract_media_data(path: impl AsRef<Path>) -> Result<ImageMetadata, MediaDataError> {
 	let path = path.as_ref().to_path_buf();
 
@@ -64,6 +123,75 @@ pub async fn extract_media_data(path: impl AsRef<Path>) -> Result<ImageMetadata,
 		.map_err(Into::into)
 }
 
+pub async fn extract_audio_media_data(
+	path: impl AsRef<Path>,
+) -> Result<AudioMetadata, MediaDataError> {
+	let path = path.as_ref().to_path_buf();
+
+	// Probing audio containers does blocking file reads, so it also gets its own thread
+	spawn_blocking(|| AudioMetadata::from_path(path))
+		.await?
+		.map_err(Into::into)
+}
+
+#[cfg(feature = "ffmpeg")]
+pub async fn extract_video_media_data(
+	path: impl AsRef<Path>,
+) -> Result<VideoMetadata, MediaDataError> {
+	let path = path.as_ref().to_path_buf();
+
+	// FFmpeg reads the container headers synchronously
+	spawn_blocking(|| VideoMetadata::from_path(path))
+		.await?
+		.map_err(Into::into)
+}
+
+/// Extracts media data for any kind of file we know how to handle, chosen by its extension.
+/// Returns `Ok(None)` for extensions without media data support.
+pub async fn extract_any_media_data(
+	extension: &str,
+	path: impl AsRef<Path>,
+) -> Result<Option<MediaMetadata>, MediaDataError> {
+	if let Ok(image_extension) = ImageExtension::from_str(extension) {
+		if can_extract_media_data_for_image(&image_extension) {
+			return extract_media_data(path)
+				.await
+				.map(|mdi| Some(MediaMetadata::Image(Box::new(mdi))));
+		}
+	}
+
+	if let Ok(audio_extension) = AudioExtension::from_str(extension) {
+		if can_extract_media_data_for_audio(&audio_extension) {
+			return extract_audio_media_data(path)
+				.await
+				.map(|mda| Some(MediaMetadata::Audio(Box::new(mda))));
+		}
+	}
+
+	#[cfg(feature = "ffmpeg")]
+	if let Ok(video_extension) = VideoExtension::from_str(extension) {
+		if can_extract_media_data_for_video(&video_extension) {
+			return extract_video_media_data(path)
+				.await
+				.map(|mdv| Some(MediaMetadata::Video(Box::new(mdv))));
+		}
+	}
+
+	Ok(None)
+}
+
+/// Errors that only mean that the file has no media data for us to extract
+pub fn is_missing_media_data(e: &MediaDataError) -> bool {
+	matches!(
+		e,
+		MediaDataError::MediaData(
+			sd_media_metadata::Error::NoExifDataOnPath(_)
+				| sd_media_metadata::Error::NoAudioTrack(_)
+				| sd_media_metadata::Error::NoVideoStream(_)
+		)
+	)
+}
+
 pub async fn process(
 	files_paths: &[file_path_for_media_processor::Data],
 	location_id: location::id::Type,
@@ -117,10 +245,17 @@ pub async fn process(
 				IsolatedFilePathData::try_from((location_id, file_path))
 					.map_err(|e| error!("{e:#?}"))
 					.ok()
-					.map(|iso_file_path| (idx, location_path.join(iso_file_path), object_id))
+					.map(|iso_file_path| {
+						(
+							idx,
+							iso_file_path.extension().to_string(),
+							location_path.join(iso_file_path),
+							object_id,
+						)
+					})
 			})
-			.map(|(idx, path, object_id)| async move {
-				let res = extract_media_data(&path).await;
+			.map(|(idx, extension, path, object_id)| async move {
+				let res = extract_any_media_data(&extension, &path).await;
 				ctx_update_fn(idx + 1);
 				(res, path, object_id)
 			})
@@ -135,11 +270,13 @@ pub async fn process(
 			(Vec::with_capacity(total_media_data), Vec::new()),
 			|(mut media_datas, mut errors), (maybe_media_data, path, object_id)| {
 				match maybe_media_data {
-					Ok(media_data) => media_datas.push((media_data, object_id)),
-					Err(MediaDataError::MediaData(sd_media_metadata::Error::NoExifDataOnPath(
-						_,
-					))) => {
-						// No exif data on path, skipping
+					Ok(Some(media_data)) => media_datas.push((media_data, object_id)),
+					Ok(None) => {
+						// Not a kind of media that we extract data from, skipping
+						run_metadata.skipped += 1;
+					}
+					Err(e) if is_missing_media_data(&e) => {
+						// No exif data, audio track or video stream on path, skipping
 						run_metadata.skipped += 1;
 					}
 					Err(e) => errors.push((e, path)),
@@ -149,22 +286,25 @@ pub async fn process(
 		)
 	};
 
+	let (object_ids, creates): (Vec<_>, Vec<_>) = media_datas
+		.into_iter()
+		.filter_map(|(media_data, object_id)| {
+			media_data_to_query(media_data, object_id)
+				.map_err(|e| error!("{e:#?}"))
+				.ok()
+				.map(|create| (object_id, create))
+		})
+		.unzip();
+
 	let created = db
 		.media_data()
-		.create_many(
-			media_datas
-				.into_iter()
-				.filter_map(|(media_data, object_id)| {
-					media_data_image_to_query(media_data, object_id)
-						.map_err(|e| error!("{e:#?}"))
-						.ok()
-				})
-				.collect(),
-		)
+		.create_many(creates)
 		.skip_duplicates()
 		.exec()
 		.await?;
 
+	refresh_smart_albums(db, &object_ids).await?;
+
 	run_metadata.extracted = created as u32;
 	run_metadata.skipped += errors.len() as u32;
 
@@ -123,7 +123,7 @@ async fn get_files_for_media_data_extraction(
 	get_files_by_extensions(
 		db,
 		parent_iso_file_path,
-		&media_data_extractor::FILTERED_IMAGE_EXTENSIONS,
+		&media_data_extractor::ALL_MEDIA_DATA_EXTENSIONS,
 	)
 	.await
 	.map_err(Into::into)
@@ -1,9 +1,10 @@
 pub mod media_data_extractor;
 pub mod media_processor;
+pub mod similar_images;
 pub mod thumbnail;
 
 pub use media_processor::MediaProcessorJobInit;
-use sd_media_metadata::ImageMetadata;
+use sd_media_metadata::{AudioMetadata, ImageMetadata, MediaMetadata, VideoMetadata};
 use sd_prisma::prisma::media_data::*;
 
 use self::media_data_extractor::MediaDataError;
@@ -28,6 +29,48 @@ pub fn media_data_image_to_query(
 	})
 }
 
+pub fn media_data_audio_to_query(
+	mda: AudioMetadata,
+	object_id: object_id::Type,
+) -> Result<CreateUnchecked, MediaDataError> {
+	Ok(CreateUnchecked {
+		object_id,
+		_params: media_data_audio_to_query_params(mda)?,
+	})
+}
+
+pub fn media_data_video_to_query(
+	mdv: VideoMetadata,
+	object_id: object_id::Type,
+) -> Result<CreateUnchecked, MediaDataError> {
+	Ok(CreateUnchecked {
+		object_id,
+		_params: media_data_video_to_query_params(mdv)?,
+	})
+}
+
+pub fn media_data_to_query(
+	media_data: MediaMetadata,
+	object_id: object_id::Type,
+) -> Result<CreateUnchecked, MediaDataError> {
+	match media_data {
+		MediaMetadata::Image(mdi) => media_data_image_to_query(*mdi, object_id),
+		MediaMetadata::Audio(mda) => media_data_audio_to_query(*mda, object_id),
+		MediaMetadata::Video(mdv) => media_data_video_to_query(*mdv, object_id),
+	}
+}
+
+#[cfg(feature = "location-watcher")]
+pub fn media_data_to_query_params(
+	media_data: MediaMetadata,
+) -> Result<Vec<SetParam>, MediaDataError> {
+	match media_data {
+		MediaMetadata::Image(mdi) => media_data_image_to_query_params(*mdi),
+		MediaMetadata::Audio(mda) => media_data_audio_to_query_params(*mda),
+		MediaMetadata::Video(mdv) => media_data_video_to_query_params(*mdv),
+	}
+}
+
 #[cfg(feature = "location-watcher")]
 pub fn media_data_image_to_query_params(
 	mdi: ImageMetadata,
@@ -45,6 +88,43 @@ pub fn media_data_image_to_query_params(
 	])
 }
 
+pub fn media_data_audio_to_query_params(
+	mda: AudioMetadata,
+) -> Result<Vec<SetParam>, MediaDataError> {
+	Ok(vec![
+		duration::set(mda.duration),
+		bitrate::set(mda.bitrate),
+		audio_codec::set(mda.audio_codec),
+		sample_rate::set(mda.sample_rate),
+		channels::set(mda.channels),
+		title::set(mda.title),
+		artist::set(mda.artist),
+		album::set(mda.album),
+		album_artist::set(mda.album_artist),
+		genre::set(mda.genre),
+		track_number::set(mda.track_number),
+		year::set(mda.year),
+	])
+}
+
+pub fn media_data_video_to_query_params(
+	mdv: VideoMetadata,
+) -> Result<Vec<SetParam>, MediaDataError> {
+	Ok(vec![
+		duration::set(mdv.duration),
+		bitrate::set(mdv.bitrate),
+		audio_codec::set(mdv.audio_codec),
+		container::set(mdv.container),
+		video_codec::set(mdv.video_codec),
+		frame_rate::set(mdv.frame_rate),
+		rotation::set(mdv.rotation),
+		resolution::set(serde_json::to_vec(&mdv.resolution).ok()),
+		media_date::set(serde_json::to_vec(&mdv.date_created).ok()),
+		epoch_time::set(mdv.date_created.map(|x| x.unix_timestamp())),
+		streams::set(serde_json::to_vec(&mdv.streams).ok()),
+	])
+}
+
 pub fn media_data_image_from_prisma_data(
 	data: sd_prisma::prisma::media_data::Data,
 ) -> Result<ImageMetadata, MediaDataError> {
@@ -60,6 +140,42 @@ pub fn media_data_image_from_prisma_data(
 	})
 }
 
+pub fn media_data_audio_from_prisma_data(
+	data: sd_prisma::prisma::media_data::Data,
+) -> Result<AudioMetadata, MediaDataError> {
+	Ok(AudioMetadata {
+		duration: data.duration,
+		audio_codec: data.audio_codec,
+		bitrate: data.bitrate,
+		sample_rate: data.sample_rate,
+		channels: data.channels,
+		title: data.title,
+		artist: data.artist,
+		album: data.album,
+		album_artist: data.album_artist,
+		genre: data.genre,
+		track_number: data.track_number,
+		year: data.year,
+	})
+}
+
+pub fn media_data_video_from_prisma_data(
+	data: sd_prisma::prisma::media_data::Data,
+) -> Result<VideoMetadata, MediaDataError> {
+	Ok(VideoMetadata {
+		duration: data.duration,
+		container: data.container,
+		video_codec: data.video_codec,
+		audio_codec: data.audio_codec,
+		resolution: from_slice_option_to_option(data.resolution),
+		frame_rate: data.frame_rate,
+		bitrate: data.bitrate,
+		rotation: data.rotation,
+		date_created: from_slice_option_to_option(data.media_date),
+		streams: from_slice_option_to_option(data.streams).unwrap_or_default(),
+	})
+}
+
 #[must_use]
 fn from_slice_option_to_option<T: serde::Serialize + serde::de::DeserializeOwned>(
 	value: Option<Vec<u8>>,
@@ -0,0 +1,120 @@
+use crate::prisma::{file_path, object, PrismaClient};
+
+use sd_file_ext::kind::ObjectKind;
+use sd_images::hamming_distance;
+
+use prisma_client_rust::QueryError;
+
+/// Stores the perceptual hash of the image objects with this `cas_id`
+pub async fn save_perceptual_hash(
+	db: &PrismaClient,
+	cas_id: &str,
+	perceptual_hash: u64,
+) -> Result<(), QueryError> {
+	db.object()
+		.update_many(
+			vec![
+				object::kind::equals(Some(ObjectKind::Image as i32)),
+				object::file_paths::some(vec![file_path::cas_id::equals(Some(cas_id.to_string()))]),
+			],
+			vec![object::perceptual_hash::set(Some(
+				perceptual_hash.to_be_bytes().to_vec(),
+			))],
+		)
+		.exec()
+		.await
+		.map(|_| ())
+}
+
+/// Whether any image object with this `cas_id` still lacks a perceptual hash, like the ones whose
+/// thumbnails were generated before hashes were stored
+pub async fn is_missing_perceptual_hash(
+	db: &PrismaClient,
+	cas_id: &str,
+) -> Result<bool, QueryError> {
+	db.object()
+		.count(vec![
+			object::kind::equals(Some(ObjectKind::Image as i32)),
+			object::perceptual_hash::equals(None),
+			object::file_paths::some(vec![file_path::cas_id::equals(Some(cas_id.to_string()))]),
+		])
+		.exec()
+		.await
+		.map(|count| count > 0)
+}
+
+/// Finds the images that look like the given one, sorted from the most to the least similar.
+///
+/// Returns `None` if the image doesn't have a perceptual hash yet, as its thumbnail wasn't generated.
+pub async fn find_similar_images(
+	db: &PrismaClient,
+	object_id: object::id::Type,
+	max_distance: u32,
+) -> Result<Option<Vec<(object::id::Type, u32)>>, QueryError> {
+	let Some(target) = db
+		.object()
+		.find_unique(object::id::equals(object_id))
+		.select(object::select!({ perceptual_hash }))
+		.exec()
+		.await?
+		.and_then(|object| decode_hash(object.perceptual_hash.as_deref()))
+	else {
+		return Ok(None);
+	};
+
+	// SQLite can't count bits, so we compare every hash here, they're only 8 bytes each anyway
+	let candidates = db
+		.object()
+		.find_many(vec![
+			object::id::not(object_id),
+			object::perceptual_hash::not(None),
+		])
+		.select(object::select!({ id perceptual_hash }))
+		.exec()
+		.await?
+		.into_iter()
+		.filter_map(|object| {
+			decode_hash(object.perceptual_hash.as_deref()).map(|hash| (object.id, hash))
+		});
+
+	Ok(Some(similar_hashes(target, candidates, max_distance)))
+}
+
+fn similar_hashes(
+	target: u64,
+	candidates: impl IntoIterator<Item = (object::id::Type, u64)>,
+	max_distance: u32,
+) -> Vec<(object::id::Type, u32)> {
+	let mut similar = candidates
+		.into_iter()
+		.map(|(id, hash)| (id, hamming_distance(target, hash)))
+		.filter(|(_, distance)| *distance <= max_distance)
+		.collect::<Vec<_>>();
+
+	similar.sort_by_key(|&(id, distance)| (distance, id));
+
+	similar
+}
+
+fn decode_hash(perceptual_hash: Option<&[u8]>) -> Option<u64> {
+	perceptual_hash
+		.and_then(|bytes| bytes.try_into().ok())
+		.map(u64::from_be_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn similar_hashes_are_sorted_by_distance() {
+		assert_eq!(
+			similar_hashes(
+				0b1111,
+				[(1, 0b0000), (2, 0b1110), (3, 0b1111), (4, 0b1100)],
+				2
+			),
+			vec![(3, 0), (2, 1), (4, 2)]
+		);
+	}
+}
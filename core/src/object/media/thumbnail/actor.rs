@@ -2,6 +2,7 @@ use crate::{
 	api::CoreEvent,
 	library::{Libraries, LibraryId, LibraryManagerEvent},
 	node::config::NodePreferences,
+	object::media::similar_images::save_perceptual_hash,
 	util::error::{FileIOError, NonUtf8PathError},
 };
 
@@ -25,7 +26,7 @@ use uuid::Uuid;
 
 use super::{
 	directory::init_thumbnail_dir,
-	process::{generate_thumbnail, ThumbData},
+	process::{backfill_perceptual_hash, generate_thumbnail, ThumbData},
 	state::RegisterReporter,
 	worker::{worker, WorkerChannels},
 	BatchToProcess, ThumbnailKind, ThumbnailerError, ONE_SEC, THUMBNAIL_CACHE_DIR_NAME,
@@ -67,6 +68,7 @@ pub struct Thumbnailer {
 	progress_reporter_tx: chan::Sender<RegisterReporter>,
 	last_single_thumb_generated: Mutex<Instant>,
 	reporter: broadcast::Sender<CoreEvent>,
+	node_preferences_rx: watch::Receiver<NodePreferences>,
 	cancel_tx: chan::Sender<oneshot::Sender<()>>,
 }
 
@@ -203,6 +205,7 @@ impl Thumbnailer {
 			progress_reporter_tx: progress_management_tx,
 			last_single_thumb_generated: Mutex::new(Instant::now()),
 			reporter,
+			node_preferences_rx,
 			cancel_tx,
 		}
 	}
@@ -293,9 +296,18 @@ impl Thumbnailer {
 		cas_id: String,
 		path: impl AsRef<Path>,
 		library_id: LibraryId,
+		db: &PrismaClient,
 	) -> Result<(), ThumbnailerError> {
-		self.generate_single_thumbnail(extension, cas_id, path, ThumbnailKind::Indexed(library_id))
-			.await
+		let (cas_id, maybe_perceptual_hash) = self
+			.generate_single_thumbnail(extension, cas_id, &path, ThumbnailKind::Indexed(library_id))
+			.await?;
+
+		match maybe_perceptual_hash {
+			Some(perceptual_hash) => save_perceptual_hash(db, &cas_id, perceptual_hash)
+				.await
+				.map_err(Into::into),
+			None => backfill_perceptual_hash(db, extension, &cas_id, path).await,
+		}
 	}
 
 	async fn generate_single_thumbnail(
@@ -304,7 +316,7 @@ impl Thumbnailer {
 		cas_id: String,
 		path: impl AsRef<Path>,
 		kind: ThumbnailKind,
-	) -> Result<(), ThumbnailerError> {
+	) -> Result<(String, Option<u64>), ThumbnailerError> {
 		let mut last_single_thumb_generated_guard = self.last_single_thumb_generated.lock().await;
 
 		let elapsed = Instant::now() - *last_single_thumb_generated_guard;
@@ -314,6 +326,8 @@ impl Thumbnailer {
 			sleep(ONE_SEC - elapsed).await;
 		}
 
+		let thumbnailer_preferences = self.node_preferences_rx.borrow().thumbnailer.clone();
+
 		let res = generate_thumbnail(
 			self.thumbnails_directory.as_ref().clone(),
 			ThumbData {
@@ -323,11 +337,12 @@ impl Thumbnailer {
 				in_background: false,
 				should_regenerate: false,
 				kind,
+				sizes: thumbnailer_preferences.sizes(),
+				quality: thumbnailer_preferences.quality() as f32,
 			},
 			self.reporter.clone(),
 		)
-		.await
-		.map(|_| ());
+		.await;
 
 		*last_single_thumb_generated_guard = Instant::now();
 
@@ -8,7 +8,7 @@ use futures_concurrency::future::Join;
 use tokio::{fs, spawn};
 use tracing::{debug, error};
 
-use super::{ThumbnailerError, EPHEMERAL_DIR, WEBP_EXTENSION};
+use super::{ThumbnailSize, ThumbnailerError, EPHEMERAL_DIR, WEBP_EXTENSION};
 
 pub(super) async fn process_ephemeral_clean_up(
 	thumbnails_directory: Arc<PathBuf>,
@@ -98,11 +98,9 @@ pub(super) async fn process_indexed_clean_up(
 					.exec()
 					.await?
 					.into_iter()
-					.map(|file_path| {
-						OsString::from(format!(
-							"{}.webp",
-							file_path.cas_id.expect("we filtered right")
-						))
+					.flat_map(|file_path| {
+						let cas_id = file_path.cas_id.expect("we filtered right");
+						ThumbnailSize::ALL.map(|size| OsString::from(size.file_name(&cas_id)))
 					})
 					.collect::<HashSet<_>>();
 
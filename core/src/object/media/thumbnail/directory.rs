@@ -25,8 +25,8 @@ use tokio::{
 use tracing::{debug, error, info, trace, warn};
 
 use super::{
-	get_shard_hex, ThumbnailerError, EPHEMERAL_DIR, THIRTY_SECS, THUMBNAIL_CACHE_DIR_NAME,
-	VERSION_FILE, WEBP_EXTENSION,
+	get_shard_hex, ThumbnailSize, ThumbnailerError, EPHEMERAL_DIR, THIRTY_SECS,
+	THUMBNAIL_CACHE_DIR_NAME, VERSION_FILE, WEBP_EXTENSION,
 };
 
 #[derive(
@@ -37,10 +37,11 @@ pub enum ThumbnailVersion {
 	V1 = 1,
 	V2 = 2,
 	V3 = 3,
+	V4 = 4,
 }
 
 impl ManagedVersion<Self> for ThumbnailVersion {
-	const LATEST_VERSION: Self = Self::V3;
+	const LATEST_VERSION: Self = Self::V4;
 
 	const KIND: Kind = Kind::PlainText;
 
@@ -133,6 +134,9 @@ async fn process_migration(
 					(ThumbnailVersion::V2, ThumbnailVersion::V3) => {
 						segregate_thumbnails_by_library(thumbnails_directory, databases).await
 					}
+					(ThumbnailVersion::V3, ThumbnailVersion::V4) => {
+						rename_to_medium_size(thumbnails_directory, databases).await
+					}
 
 					_ => {
 						error!("Thumbnail version is not handled: {:?}", current);
@@ -383,3 +387,93 @@ async fn segregate_thumbnails_by_library(
 
 	Ok(())
 }
+
+/// Thumbnails used to have a single size, which is the same as the current medium one, so we just
+/// rename them to have the size on their file names. It is used to migrate from V3 to V4.
+async fn rename_to_medium_size(
+	thumbnails_directory: impl AsRef<Path>,
+	databases: &HashMap<LibraryId, Arc<PrismaClient>>,
+) -> Result<(), ThumbnailerError> {
+	let thumbnails_directory = thumbnails_directory.as_ref();
+
+	let renamed_count = databases
+		.keys()
+		.map(|library_id| thumbnails_directory.join(library_id.to_string()))
+		.chain([thumbnails_directory.join(EPHEMERAL_DIR)])
+		.map(|thumbs_dir| spawn(rename_dir_thumbnails_to_medium_size(thumbs_dir)))
+		.collect::<Vec<_>>()
+		.try_join()
+		.await?
+		.into_iter()
+		.collect::<Result<Vec<_>, _>>()?
+		.into_iter()
+		.sum::<usize>();
+
+	info!("Renamed {renamed_count} thumbnails to the medium size");
+
+	Ok(())
+}
+
+async fn rename_dir_thumbnails_to_medium_size(
+	thumbs_dir: PathBuf,
+) -> Result<usize, ThumbnailerError> {
+	let mut to_rename = vec![];
+
+	let mut read_thumbs_dir = match fs::read_dir(&thumbs_dir).await {
+		Ok(read_thumbs_dir) => read_thumbs_dir,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+		Err(e) => return Err(FileIOError::from((&thumbs_dir, e)).into()),
+	};
+
+	while let Some(shard_entry) = read_thumbs_dir
+		.next_entry()
+		.await
+		.map_err(|e| FileIOError::from((&thumbs_dir, e)))?
+	{
+		let shard_path = shard_entry.path();
+		if !shard_entry
+			.file_type()
+			.await
+			.map_err(|e| FileIOError::from((&shard_path, e)))?
+			.is_dir()
+		{
+			continue;
+		}
+
+		let mut read_shard_dir = fs::read_dir(&shard_path)
+			.await
+			.map_err(|e| FileIOError::from((&shard_path, e)))?;
+
+		while let Some(thumb_entry) = read_shard_dir
+			.next_entry()
+			.await
+			.map_err(|e| FileIOError::from((&shard_path, e)))?
+		{
+			let thumb_path = thumb_entry.path();
+			if thumb_path.extension() != Some(WEBP_EXTENSION.as_ref()) {
+				continue;
+			}
+
+			// we know they're cas_id's, so they're valid utf8
+			let Some(cas_id) = thumb_path.file_stem().and_then(|stem| stem.to_str()) else {
+				continue;
+			};
+
+			let new_thumb_path = shard_path.join(ThumbnailSize::Medium.file_name(cas_id));
+
+			to_rename.push(async move {
+				trace!(
+					"Renaming thumbnail to medium size: {} -> {}",
+					thumb_path.display(),
+					new_thumb_path.display()
+				);
+
+				fs::rename(&thumb_path, &new_thumb_path)
+					.await
+					.map_err(|e| FileIOError::from((thumb_path, e)))
+			});
+		}
+	}
+
+	Ok(to_rename.try_join().await?.len())
+}
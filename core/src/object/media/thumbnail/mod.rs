@@ -5,21 +5,25 @@ use crate::{
 };
 
 use sd_file_ext::extensions::{
-	DocumentExtension, Extension, ImageExtension, ALL_DOCUMENT_EXTENSIONS, ALL_IMAGE_EXTENSIONS,
+	AudioExtension, DocumentExtension, Extension, ImageExtension, ALL_AUDIO_EXTENSIONS,
+	ALL_CODE_EXTENSIONS, ALL_CONFIG_EXTENSIONS, ALL_DOCUMENT_EXTENSIONS, ALL_IMAGE_EXTENSIONS,
+	ALL_TEXT_EXTENSIONS,
 };
 
 #[cfg(feature = "ffmpeg")]
 use sd_file_ext::extensions::{VideoExtension, ALL_VIDEO_EXTENSIONS};
 
 use std::{
+	io,
 	path::{Path, PathBuf},
 	time::Duration,
 };
 
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use specta::Type;
 use thiserror::Error;
-use tokio::task;
+use tokio::{fs, task};
 use tracing::error;
 
 pub mod actor;
@@ -29,10 +33,12 @@ pub mod preferences;
 mod process;
 mod shard;
 mod state;
+mod text;
 mod worker;
 
 pub use process::{BatchToProcess, GenerateThumbnailArgs};
 pub use shard::get_shard_hex;
+pub(crate) use text::read_text;
 
 use directory::ThumbnailVersion;
 
@@ -43,13 +49,10 @@ const VERSION_FILE: &str = "version.txt";
 pub const WEBP_EXTENSION: &str = "webp";
 const EPHEMERAL_DIR: &str = "ephemeral";
 
-/// This is the target pixel count for all thumbnails to be resized to, and it is eventually downscaled
-/// to [`TARGET_QUALITY`].
-const TARGET_PX: f32 = 262144_f32;
-
-/// This is the target quality that we render thumbnails at, it is a float between 0-100
+/// This is the default quality that we render thumbnails at, it is a float between 0-100
 /// and is treated as a percentage (so 30% in this case, or it's the same as multiplying by `0.3`).
-const TARGET_QUALITY: f32 = 30_f32;
+/// It can be changed through [`ThumbnailerPreferences`](preferences::ThumbnailerPreferences).
+const DEFAULT_QUALITY: u8 = 30;
 
 // Some time constants
 const ONE_SEC: Duration = Duration::from_secs(1);
@@ -62,26 +65,127 @@ pub enum ThumbnailKind {
 	Indexed(LibraryId),
 }
 
-pub fn get_indexed_thumbnail_path(node: &Node, cas_id: &str, library_id: LibraryId) -> PathBuf {
-	get_thumbnail_path(node, cas_id, ThumbnailKind::Indexed(library_id))
+/// Every cas_id gets a thumbnail for each of the sizes enabled in the thumbnailer preferences,
+/// stored side by side in the same shard directory as `<cas_id>-<size>.webp`
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	Serialize,
+	Deserialize,
+	Type,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	strum::Display,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ThumbnailSize {
+	Small,
+	Medium,
+	Large,
+}
+
+impl ThumbnailSize {
+	pub const ALL: [Self; 3] = [Self::Small, Self::Medium, Self::Large];
+
+	/// The pixel count that images are resized to
+	const fn target_px(self) -> f32 {
+		match self {
+			Self::Small => 16384_f32,   // 128x128
+			Self::Medium => 262144_f32, // 512x512
+			Self::Large => 1048576_f32, // 1024x1024
+		}
+	}
+
+	/// The size of the biggest side of video thumbnails
+	#[cfg(feature = "ffmpeg")]
+	const fn video_size(self) -> u32 {
+		match self {
+			Self::Small => 128,
+			Self::Medium => 256,
+			Self::Large => 1024,
+		}
+	}
+
+	/// All sizes starting from this one and moving away from it, preferring bigger thumbnails
+	/// as downscaling looks better than upscaling
+	const fn by_proximity(self) -> [Self; 3] {
+		match self {
+			Self::Small => [Self::Small, Self::Medium, Self::Large],
+			Self::Medium => [Self::Medium, Self::Large, Self::Small],
+			Self::Large => [Self::Large, Self::Medium, Self::Small],
+		}
+	}
+
+	pub fn file_name(self, cas_id: &str) -> String {
+		format!("{cas_id}-{self}.{WEBP_EXTENSION}")
+	}
 }
 
 /// This does not check if a thumbnail exists, it just returns the path that it would exist at
-fn get_thumbnail_path(node: &Node, cas_id: &str, kind: ThumbnailKind) -> PathBuf {
-	let mut thumb_path = node.config.data_directory();
+pub fn get_indexed_thumbnail_path(
+	node: &Node,
+	cas_id: &str,
+	library_id: LibraryId,
+	size: ThumbnailSize,
+) -> PathBuf {
+	get_shard_dir(node, cas_id, ThumbnailKind::Indexed(library_id)).join(size.file_name(cas_id))
+}
+
+fn get_shard_dir(node: &Node, cas_id: &str, kind: ThumbnailKind) -> PathBuf {
+	let mut shard_dir = node.config.data_directory();
 
-	thumb_path.push(THUMBNAIL_CACHE_DIR_NAME);
+	shard_dir.push(THUMBNAIL_CACHE_DIR_NAME);
 	match kind {
-		ThumbnailKind::Ephemeral => thumb_path.push(EPHEMERAL_DIR),
+		ThumbnailKind::Ephemeral => shard_dir.push(EPHEMERAL_DIR),
 		ThumbnailKind::Indexed(library_id) => {
-			thumb_path.push(library_id.to_string());
+			shard_dir.push(library_id.to_string());
+		}
+	}
+	shard_dir.push(get_shard_hex(cas_id));
+
+	shard_dir
+}
+
+/// Returns the path of the indexed thumbnail closest to `size`, as not every size is generated
+/// depending on the thumbnailer preferences at the time
+pub async fn find_indexed_thumbnail(
+	node: &Node,
+	cas_id: &str,
+	library_id: LibraryId,
+	size: ThumbnailSize,
+) -> Result<Option<PathBuf>, FileIOError> {
+	find_nearest_thumbnail(
+		get_shard_dir(node, cas_id, ThumbnailKind::Indexed(library_id)),
+		cas_id,
+		size,
+	)
+	.await
+}
+
+/// Returns the path of the thumbnail closest to `size` among the ones in `shard_dir` for `cas_id`
+pub async fn find_nearest_thumbnail(
+	shard_dir: impl AsRef<Path>,
+	cas_id: &str,
+	size: ThumbnailSize,
+) -> Result<Option<PathBuf>, FileIOError> {
+	let shard_dir = shard_dir.as_ref();
+
+	for size in size.by_proximity() {
+		let thumb_path = shard_dir.join(size.file_name(cas_id));
+
+		match fs::metadata(&thumb_path).await {
+			Ok(_) => return Ok(Some(thumb_path)),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+			Err(e) => return Err(FileIOError::from((thumb_path, e))),
 		}
 	}
-	thumb_path.push(get_shard_hex(cas_id));
-	thumb_path.push(cas_id);
-	thumb_path.set_extension(WEBP_EXTENSION);
 
-	thumb_path
+	Ok(None)
 }
 
 pub fn get_indexed_thumb_key(cas_id: &str, library_id: LibraryId) -> Vec<String> {
@@ -128,6 +232,16 @@ pub(super) static THUMBNAILABLE_EXTENSIONS: Lazy<Vec<Extension>> = Lazy::new(||
 				.filter(can_generate_thumbnail_for_document)
 				.map(Extension::Document),
 		)
+		.chain(
+			ALL_AUDIO_EXTENSIONS
+				.iter()
+				.cloned()
+				.filter(can_generate_thumbnail_for_audio)
+				.map(Extension::Audio),
+		)
+		.chain(ALL_TEXT_EXTENSIONS.iter().cloned().map(Extension::Text))
+		.chain(ALL_CODE_EXTENSIONS.iter().cloned().map(Extension::Code))
+		.chain(ALL_CONFIG_EXTENSIONS.iter().cloned().map(Extension::Config))
 		.collect()
 });
 
@@ -159,6 +273,13 @@ pub enum ThumbnailerError {
 		path: Box<Path>,
 		error: sd_images::Error,
 	},
+	#[error("failed to render text preview")]
+	TextRendering { path: Box<Path>, reason: String },
+	#[error("error while reading the audio file")]
+	SdMediaMetadata {
+		path: Box<Path>,
+		error: sd_media_metadata::Error,
+	},
 	#[error("failed to execute converting task: {0}")]
 	Task(#[from] task::JoinError),
 	#[cfg(feature = "ffmpeg")]
@@ -202,3 +323,13 @@ pub const fn can_generate_thumbnail_for_document(document_extension: &DocumentEx
 
 	matches!(document_extension, Pdf)
 }
+
+/// Audio files get their embedded cover art as thumbnail, or a rendering of their waveform
+pub const fn can_generate_thumbnail_for_audio(audio_extension: &AudioExtension) -> bool {
+	use AudioExtension::*;
+
+	matches!(
+		audio_extension,
+		Mp3 | M4a | Wav | Aiff | Aif | Flac | Ogg | Oga | Aac | Adts
+	)
+}
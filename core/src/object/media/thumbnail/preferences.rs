@@ -1,15 +1,31 @@
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
+use super::{ThumbnailSize, DEFAULT_QUALITY};
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Type)]
 pub struct ThumbnailerPreferences {
 	background_processing_percentage: u8, // 0-100
+	#[serde(default = "default_sizes")]
+	sizes: Vec<ThumbnailSize>,
+	#[serde(default = "default_quality")]
+	quality: u8, // 0-100
+}
+
+fn default_sizes() -> Vec<ThumbnailSize> {
+	ThumbnailSize::ALL.to_vec()
+}
+
+const fn default_quality() -> u8 {
+	DEFAULT_QUALITY
 }
 
 impl Default for ThumbnailerPreferences {
 	fn default() -> Self {
 		Self {
 			background_processing_percentage: 50, // 50% of CPU cores available
+			sizes: default_sizes(),
+			quality: default_quality(),
 		}
 	}
 }
@@ -31,4 +47,36 @@ impl ThumbnailerPreferences {
 
 		self
 	}
+
+	pub fn sizes(&self) -> &[ThumbnailSize] {
+		&self.sizes
+	}
+
+	pub fn set_sizes(&mut self, mut sizes: Vec<ThumbnailSize>) -> &mut Self {
+		sizes.sort_unstable();
+		sizes.dedup();
+
+		// Without any size we wouldn't have thumbnails at all, so we keep the medium one
+		if sizes.is_empty() {
+			sizes.push(ThumbnailSize::Medium);
+		}
+
+		self.sizes = sizes;
+
+		self
+	}
+
+	pub fn quality(&self) -> u8 {
+		self.quality
+	}
+
+	pub fn set_quality(&mut self, mut quality: u8) -> &mut Self {
+		if quality > 100 {
+			quality = 100;
+		}
+
+		self.quality = quality;
+
+		self
+	}
 }
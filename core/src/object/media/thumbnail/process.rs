@@ -1,9 +1,19 @@
-use crate::{api::CoreEvent, util::error::FileIOError};
+use crate::{
+	api::CoreEvent,
+	object::media::similar_images::{is_missing_perceptual_hash, save_perceptual_hash},
+	util::error::FileIOError,
+};
 
-use sd_file_ext::extensions::{DocumentExtension, ImageExtension};
-use sd_images::{format_image, scale_dimensions, ConvertableExtension};
-use sd_media_metadata::image::Orientation;
-use sd_prisma::prisma::location;
+use sd_file_ext::extensions::{
+	AudioExtension, CodeExtension, ConfigExtension, DocumentExtension, ImageExtension,
+	TextExtension,
+};
+use sd_images::{format_image, perceptual_hash, scale_dimensions, ConvertableExtension};
+use sd_media_metadata::{
+	audio::{cover_art_from_path, waveform_from_path},
+	image::Orientation,
+};
+use sd_prisma::prisma::{location, PrismaClient};
 
 use std::{
 	collections::VecDeque,
@@ -16,7 +26,7 @@ use std::{
 
 use async_channel as chan;
 use futures_concurrency::future::{Join, Race};
-use image::{self, imageops, DynamicImage, GenericImageView};
+use image::{self, imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
 use serde::{Deserialize, Serialize};
 use tokio::{
 	fs, io,
@@ -29,11 +39,21 @@ use tracing::{debug, error, trace, warn};
 use webp::Encoder;
 
 use super::{
-	can_generate_thumbnail_for_document, can_generate_thumbnail_for_image, get_thumb_key,
-	preferences::ThumbnailerPreferences, shard::get_shard_hex, ThumbnailKind, ThumbnailerError,
-	EPHEMERAL_DIR, TARGET_PX, TARGET_QUALITY, THIRTY_SECS, WEBP_EXTENSION,
+	can_generate_thumbnail_for_audio, can_generate_thumbnail_for_document,
+	can_generate_thumbnail_for_image, get_thumb_key,
+	preferences::ThumbnailerPreferences,
+	shard::get_shard_hex,
+	text::{read_text_preview, render_text_preview, Syntax},
+	ThumbnailKind, ThumbnailSize, ThumbnailerError, EPHEMERAL_DIR, THIRTY_SECS,
 };
 
+/// How many bars are drawn on waveform thumbnails of audio files without cover art
+const WAVEFORM_BARS: usize = 96;
+/// Waveforms are rendered big enough to not be upscaled even for the large thumbnail size
+const WAVEFORM_WIDTH: u32 = 1536;
+const WAVEFORM_HEIGHT: u32 = 768;
+const WAVEFORM_COLOR: Rgba<u8> = Rgba([37, 153, 255, 255]);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateThumbnailArgs {
 	pub extension: String,
@@ -91,6 +111,8 @@ pub(super) async fn batch_processor(
 		},
 		kind,
 	): (BatchToProcess, ThumbnailKind),
+	// Only indexed thumbnails have a library database to store the perceptual hashes of images
+	maybe_db: Option<Arc<PrismaClient>>,
 	generated_ephemeral_thumbs_file_names_tx: chan::Sender<Vec<OsString>>,
 	ProcessorControlChannels {
 		stop_rx,
@@ -126,6 +148,9 @@ pub(super) async fn batch_processor(
 
 	let semaphore = Arc::new(Semaphore::new(in_parallel_count));
 
+	let sizes = Arc::<[ThumbnailSize]>::from(thumbnailer_preferences.sizes());
+	let quality = thumbnailer_preferences.quality() as f32;
+
 	let batch_size = batch.len();
 
 	// Tranforming to `VecDeque` so we don't need to move anything as we consume from the beginning
@@ -138,7 +163,7 @@ pub(super) async fn batch_processor(
 	}
 
 	let (maybe_cas_ids_tx, maybe_cas_ids_rx) = if kind == ThumbnailKind::Ephemeral {
-		let (tx, rx) = chan::bounded(batch_size);
+		let (tx, rx) = chan::bounded(batch_size * sizes.len());
 		(Some(tx), Some(rx))
 	} else {
 		(None, None)
@@ -166,10 +191,12 @@ pub(super) async fn batch_processor(
 					let thumbnails_directory = thumbnails_directory.as_ref().clone();
 					let report_progress_tx = batch_report_progress_tx.clone();
 					let maybe_cas_ids_tx = maybe_cas_ids_tx.clone();
+					let maybe_db = maybe_db.clone();
+					let sizes = Arc::clone(&sizes);
 
 					async move {
 						let res = timeout(THIRTY_SECS, async {
-							generate_thumbnail(
+							let (cas_id, maybe_perceptual_hash) = generate_thumbnail(
 								thumbnails_directory,
 								ThumbData {
 									extension: &extension,
@@ -178,23 +205,40 @@ pub(super) async fn batch_processor(
 									in_background,
 									should_regenerate,
 									kind,
+									sizes: &sizes,
+									quality,
 								},
 								reporter,
 							)
-							.await
-							.map(|cas_id| {
-								// this send_blocking never blocks as we have a bounded channel with
-								// the same capacity as the batch size, so there is always a space
-								// in the queue
-								if let Some(cas_ids_tx) = maybe_cas_ids_tx {
+							.await?;
+
+							// this send_blocking never blocks as we have a bounded channel with
+							// the same capacity as the batch size times the sizes count, so
+							// there is always a space in the queue
+							if let Some(cas_ids_tx) = maybe_cas_ids_tx {
+								for size in sizes.iter() {
 									if cas_ids_tx
-										.send_blocking(OsString::from(format!("{}.webp", cas_id)))
+										.send_blocking(OsString::from(size.file_name(&cas_id)))
 										.is_err()
 									{
 										warn!("No one to listen to generated ephemeral thumbnail cas id");
 									}
 								}
-							})
+							}
+
+							if let Some(db) = maybe_db {
+								match maybe_perceptual_hash {
+									Some(perceptual_hash) => {
+										save_perceptual_hash(&db, &cas_id, perceptual_hash).await?
+									}
+									None => {
+										backfill_perceptual_hash(&db, &extension, &cas_id, &path)
+											.await?
+									}
+								}
+							}
+
+							Ok::<_, ThumbnailerError>(())
 						})
 						.await
 						.unwrap_or_else(|_| {
@@ -303,13 +347,15 @@ pub(super) async fn batch_processor(
 	done_tx.send(()).ok();
 }
 
-pub(super) struct ThumbData<'ext, P: AsRef<Path>> {
-	pub extension: &'ext str,
+pub(super) struct ThumbData<'a, P: AsRef<Path>> {
+	pub extension: &'a str,
 	pub cas_id: String,
 	pub path: P,
 	pub in_background: bool,
 	pub should_regenerate: bool,
 	pub kind: ThumbnailKind,
+	pub sizes: &'a [ThumbnailSize],
+	pub quality: f32,
 }
 
 pub(super) async fn generate_thumbnail(
@@ -321,44 +367,71 @@ pub(super) async fn generate_thumbnail(
 		in_background,
 		should_regenerate,
 		kind,
+		sizes,
+		quality,
 	}: ThumbData<'_, impl AsRef<Path>>,
 	reporter: broadcast::Sender<CoreEvent>,
-) -> Result<String, ThumbnailerError> {
+) -> Result<(String, Option<u64>), ThumbnailerError> {
 	let path = path.as_ref();
 	trace!("Generating thumbnail for {}", path.display());
 
-	let mut output_path = thumbnails_directory;
+	let mut shard_dir = thumbnails_directory;
 	match kind {
-		ThumbnailKind::Ephemeral => output_path.push(EPHEMERAL_DIR),
-		ThumbnailKind::Indexed(library_id) => output_path.push(library_id.to_string()),
+		ThumbnailKind::Ephemeral => shard_dir.push(EPHEMERAL_DIR),
+		ThumbnailKind::Indexed(library_id) => shard_dir.push(library_id.to_string()),
 	};
-	output_path.push(get_shard_hex(&cas_id));
-	output_path.push(&cas_id);
-	output_path.set_extension(WEBP_EXTENSION);
-
-	if let Err(e) = fs::metadata(&output_path).await {
-		if e.kind() != io::ErrorKind::NotFound {
-			error!(
-				"Failed to check if thumbnail exists, but we will try to generate it anyway: {e:#?}"
-			);
+	shard_dir.push(get_shard_hex(&cas_id));
+
+	let mut outputs = Vec::with_capacity(sizes.len());
+	for &size in sizes {
+		let output_path = shard_dir.join(size.file_name(&cas_id));
+
+		if let Err(e) = fs::metadata(&output_path).await {
+			if e.kind() != io::ErrorKind::NotFound {
+				error!(
+					"Failed to check if thumbnail exists, but we will try to generate it anyway: {e:#?}"
+				);
+			}
+		// Otherwise we good, thumbnail doesn't exist so we can generate it
+		} else if !should_regenerate {
+			continue;
 		}
-	// Otherwise we good, thumbnail doesn't exist so we can generate it
-	} else if !should_regenerate {
+
+		outputs.push((size, output_path));
+	}
+
+	if outputs.is_empty() {
 		trace!(
 			"Skipping thumbnail generation for {} because it already exists",
 			path.display()
 		);
-		return Ok(cas_id);
+		return Ok((cas_id, None));
 	}
 
+	fs::create_dir_all(&shard_dir)
+		.await
+		.map_err(|e| FileIOError::from((&shard_dir, e)))?;
+
+	let mut maybe_perceptual_hash = None;
+
 	if let Ok(extension) = ImageExtension::from_str(extension) {
 		if can_generate_thumbnail_for_image(&extension) {
-			generate_image_thumbnail(&path, &output_path).await?;
+			maybe_perceptual_hash = generate_image_thumbnail(&path, outputs, quality, true).await?;
 		}
 	} else if let Ok(extension) = DocumentExtension::from_str(extension) {
 		if can_generate_thumbnail_for_document(&extension) {
-			generate_image_thumbnail(&path, &output_path).await?;
+			generate_image_thumbnail(&path, outputs, quality, false).await?;
+		}
+	} else if let Ok(extension) = AudioExtension::from_str(extension) {
+		if can_generate_thumbnail_for_audio(&extension) {
+			generate_audio_thumbnail(&path, outputs, quality).await?;
 		}
+	} else if let Ok(extension) = CodeExtension::from_str(extension) {
+		generate_text_thumbnail(&path, Some(extension.into()), outputs, quality).await?;
+	} else if TextExtension::from_str(extension).is_ok()
+		|| ConfigExtension::from_str(extension).is_ok()
+	{
+		generate_text_thumbnail(&path, None, outputs, quality).await?;
 	}
 
 	#[cfg(feature = "ffmpeg")]
@@ -368,7 +441,7 @@ pub(super) async fn generate_thumbnail(
 
 		if let Ok(extension) = VideoExtension::from_str(extension) {
 			if can_generate_thumbnail_for_video(&extension) {
-				generate_video_thumbnail(&path, &output_path).await?;
+				generate_video_thumbnail(&path, outputs, quality).await?;
 			}
 		}
 	}
@@ -387,86 +460,242 @@ pub(super) async fn generate_thumbnail(
 
 	trace!("Generated thumbnail for {}", path.display());
 
-	Ok(cas_id)
+	Ok((cas_id, maybe_perceptual_hash))
 }
 
-async fn generate_image_thumbnail(
+/// Thumbnails that already exist are skipped, so the images whose thumbnails were generated before
+/// perceptual hashes were stored are hashed here instead
+pub(super) async fn backfill_perceptual_hash(
+	db: &PrismaClient,
+	extension: &str,
+	cas_id: &str,
 	file_path: impl AsRef<Path>,
-	output_path: impl AsRef<Path>,
 ) -> Result<(), ThumbnailerError> {
+	if !ImageExtension::from_str(extension).map_or(false, |extension| {
+		can_generate_thumbnail_for_image(&extension)
+	}) || !is_missing_perceptual_hash(db, cas_id).await?
+	{
+		return Ok(());
+	}
+
 	let file_path = file_path.as_ref().to_path_buf();
+	let hash =
+		spawn_blocking(move || decode_image(&file_path).map(|img| perceptual_hash(&img))).await??;
 
-	let webp = spawn_blocking(move || -> Result<_, ThumbnailerError> {
-		let mut img = format_image(&file_path).map_err(|e| ThumbnailerError::SdImages {
-			path: file_path.clone().into_boxed_path(),
-			error: e,
-		})?;
+	save_perceptual_hash(db, cas_id, hash)
+		.await
+		.map_err(Into::into)
+}
 
-		let (w, h) = img.dimensions();
-		let (w_scaled, h_scaled) = scale_dimensions(w as f32, h as f32, TARGET_PX);
-
-		// Optionally, resize the existing photo and convert back into DynamicImage
-		if w != w_scaled && h != h_scaled {
-			img = DynamicImage::ImageRgba8(imageops::resize(
-				&img,
-				w_scaled,
-				h_scaled,
-				imageops::FilterType::Triangle,
-			));
+fn decode_image(file_path: &Path) -> Result<DynamicImage, ThumbnailerError> {
+	let mut img = format_image(file_path).map_err(|e| ThumbnailerError::SdImages {
+		path: file_path.to_path_buf().into_boxed_path(),
+		error: e,
+	})?;
+
+	// this corrects the rotation/flip of the image based on the *available* exif data
+	// not all images have exif data, so we don't error. we also don't rotate HEIF as that's against the spec
+	if let Some(orientation) = Orientation::from_path(file_path) {
+		if ConvertableExtension::try_from(file_path)
+			.expect("we already checked if the image was convertable")
+			.should_rotate()
+		{
+			img = orientation.correct_thumbnail(img);
 		}
+	}
+
+	Ok(img)
+}
+
+/// Decodes the image only once, then resizes and encodes it for each requested size, also
+/// returning its perceptual hash if asked, as the decoding is the expensive part
+async fn generate_image_thumbnail(
+	file_path: impl AsRef<Path>,
+	outputs: Vec<(ThumbnailSize, PathBuf)>,
+	quality: f32,
+	with_perceptual_hash: bool,
+) -> Result<Option<u64>, ThumbnailerError> {
+	let file_path = file_path.as_ref().to_path_buf();
+
+	let (webps, maybe_perceptual_hash) = spawn_blocking(move || -> Result<_, ThumbnailerError> {
+		let img = decode_image(&file_path)?;
+
+		Ok((
+			encode_thumbnails(&file_path, &img, outputs, quality)?,
+			with_perceptual_hash.then(|| perceptual_hash(&img)),
+		))
+	})
+	.await??;
+
+	write_thumbnails(webps).await?;
+
+	Ok(maybe_perceptual_hash)
+}
+
+/// Uses the embedded cover art of the audio file as thumbnail, rendering its waveform instead
+/// when it doesn't have any
+async fn generate_audio_thumbnail(
+	file_path: impl AsRef<Path>,
+	outputs: Vec<(ThumbnailSize, PathBuf)>,
+	quality: f32,
+) -> Result<(), ThumbnailerError> {
+	let file_path = file_path.as_ref().to_path_buf();
 
-		// this corrects the rotation/flip of the image based on the *available* exif data
-		// not all images have exif data, so we don't error. we also don't rotate HEIF as that's against the spec
-		if let Some(orientation) = Orientation::from_path(&file_path) {
-			if ConvertableExtension::try_from(file_path.as_ref())
-				.expect("we already checked if the image was convertable")
-				.should_rotate()
-			{
-				img = orientation.correct_thumbnail(img);
+	let webps = spawn_blocking(move || -> Result<_, ThumbnailerError> {
+		let cover_art = match cover_art_from_path(&file_path) {
+			Ok(Some(cover_art)) => image::load_from_memory(&cover_art)
+				.map_err(|e| {
+					warn!(
+						"Failed to decode cover art of <path='{}'>, rendering its waveform instead: {e:#?}",
+						file_path.display()
+					)
+				})
+				.ok(),
+			Ok(None) => None,
+			Err(e) => {
+				warn!(
+					"Failed to read cover art of <path='{}'>, rendering its waveform instead: {e:#?}",
+					file_path.display()
+				);
+				None
 			}
-		}
+		};
 
-		// Create the WebP encoder for the above image
-		let encoder =
-			Encoder::from_image(&img).map_err(|reason| ThumbnailerError::WebPEncoding {
-				path: file_path.into_boxed_path(),
-				reason: reason.to_string(),
-			})?;
-
-		// Type WebPMemory is !Send, which makes the Future in this function !Send,
-		// this make us `deref` to have a `&[u8]` and then `to_owned` to make a Vec<u8>
-		// which implies on a unwanted clone...
-		Ok(encoder.encode(TARGET_QUALITY).deref().to_owned())
+		let img = if let Some(cover_art) = cover_art {
+			cover_art
+		} else {
+			render_waveform(&waveform_from_path(&file_path, WAVEFORM_BARS).map_err(|e| {
+				ThumbnailerError::SdMediaMetadata {
+					path: file_path.clone().into_boxed_path(),
+					error: e,
+				}
+			})?)
+		};
+
+		encode_thumbnails(&file_path, &img, outputs, quality)
 	})
 	.await??;
 
-	let output_path = output_path.as_ref();
+	write_thumbnails(webps).await
+}
 
-	if let Some(shard_dir) = output_path.parent() {
-		fs::create_dir_all(shard_dir)
-			.await
-			.map_err(|e| FileIOError::from((shard_dir, e)))?;
-	} else {
-		error!(
-			"Failed to get parent directory of '{}' for sharding parent directory",
-			output_path.display()
+/// Renders the first lines of text files, skipping the ones that turn out to be binary
+async fn generate_text_thumbnail(
+	file_path: impl AsRef<Path>,
+	syntax: Option<Syntax>,
+	outputs: Vec<(ThumbnailSize, PathBuf)>,
+	quality: f32,
+) -> Result<(), ThumbnailerError> {
+	let file_path = file_path.as_ref().to_path_buf();
+
+	let Some(text) = read_text_preview(&file_path).await? else {
+		trace!(
+			"Skipping text preview for {} because it doesn't look like text",
+			file_path.display()
 		);
+		return Ok(());
+	};
+
+	let webps = spawn_blocking(move || -> Result<_, ThumbnailerError> {
+		let img = render_text_preview(&text, syntax).map_err(|reason| {
+			ThumbnailerError::TextRendering {
+				path: file_path.clone().into_boxed_path(),
+				reason,
+			}
+		})?;
+
+		encode_thumbnails(&file_path, &img, outputs, quality)
+	})
+	.await??;
+
+	write_thumbnails(webps).await
+}
+
+/// Draws a bar for each peak, mirrored around the horizontal center, on a transparent background
+fn render_waveform(peaks: &[f32]) -> DynamicImage {
+	let mut img = RgbaImage::new(WAVEFORM_WIDTH, WAVEFORM_HEIGHT);
+
+	let bar_width = WAVEFORM_WIDTH / (peaks.len().max(1) as u32);
+	let gap = bar_width / 4;
+	let center = WAVEFORM_HEIGHT / 2;
+
+	for (i, peak) in peaks.iter().enumerate() {
+		// Silence still gets a thin line, so the waveform doesn't look broken
+		let half_height = ((peak * center as f32) as u32).clamp(1, center);
+		let start_x = i as u32 * bar_width + gap / 2;
+
+		for x in start_x..(start_x + bar_width - gap).min(WAVEFORM_WIDTH) {
+			for y in (center - half_height)..(center + half_height) {
+				img.put_pixel(x, y, WAVEFORM_COLOR);
+			}
+		}
 	}
 
-	fs::write(output_path, &webp)
-		.await
-		.map_err(|e| FileIOError::from((output_path, e)))
-		.map_err(Into::into)
+	DynamicImage::ImageRgba8(img)
+}
+
+/// Resizes and encodes `img` for each requested size
+fn encode_thumbnails(
+	file_path: &Path,
+	img: &DynamicImage,
+	outputs: Vec<(ThumbnailSize, PathBuf)>,
+	quality: f32,
+) -> Result<Vec<(PathBuf, Vec<u8>)>, ThumbnailerError> {
+	let (w, h) = img.dimensions();
+
+	outputs
+		.into_iter()
+		.map(|(size, output_path)| {
+			let (w_scaled, h_scaled) = scale_dimensions(w as f32, h as f32, size.target_px());
+
+			// Optionally, resize the existing photo and convert back into DynamicImage
+			let resized = if w != w_scaled && h != h_scaled {
+				DynamicImage::ImageRgba8(imageops::resize(
+					img,
+					w_scaled,
+					h_scaled,
+					imageops::FilterType::Triangle,
+				))
+			} else {
+				img.clone()
+			};
+
+			// Create the WebP encoder for the above image
+			let encoder =
+				Encoder::from_image(&resized).map_err(|reason| ThumbnailerError::WebPEncoding {
+					path: file_path.into(),
+					reason: reason.to_string(),
+				})?;
+
+			// Type WebPMemory is !Send, which makes the Future in this function !Send,
+			// this make us `deref` to have a `&[u8]` and then `to_owned` to make a Vec<u8>
+			// which implies on a unwanted clone...
+			Ok((output_path, encoder.encode(quality).deref().to_owned()))
+		})
+		.collect()
+}
+
+async fn write_thumbnails(webps: Vec<(PathBuf, Vec<u8>)>) -> Result<(), ThumbnailerError> {
+	for (output_path, webp) in webps {
+		fs::write(&output_path, &webp)
+			.await
+			.map_err(|e| FileIOError::from((output_path, e)))?;
+	}
+
+	Ok(())
 }
 
 #[cfg(feature = "ffmpeg")]
 async fn generate_video_thumbnail(
 	file_path: impl AsRef<Path>,
-	output_path: impl AsRef<Path>,
+	outputs: Vec<(ThumbnailSize, PathBuf)>,
+	quality: f32,
 ) -> Result<(), ThumbnailerError> {
 	use sd_ffmpeg::to_thumbnail;
 
-	to_thumbnail(file_path, output_path, 256, TARGET_QUALITY)
-		.await
-		.map_err(Into::into)
+	for (size, output_path) in outputs {
+		to_thumbnail(&file_path, output_path, size.video_size(), quality).await?;
+	}
+
+	Ok(())
 }
@@ -14,7 +14,8 @@ use tokio::{fs, io};
 use tracing::{error, info, trace};
 
 use super::{
-	actor::ActorError, get_shard_hex, BatchToProcess, ThumbnailKind, EPHEMERAL_DIR, SAVE_STATE_FILE,
+	actor::ActorError, get_shard_hex, BatchToProcess, ThumbnailKind, ThumbnailSize, EPHEMERAL_DIR,
+	SAVE_STATE_FILE,
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -123,9 +124,11 @@ pub(super) async fn remove_by_cas_ids(
 
 	cas_ids
 		.into_iter()
-		.map(|cas_id| {
-			let thumbnail_path = base_dir.join(format!("{}/{cas_id}.webp", get_shard_hex(&cas_id)));
-
+		.flat_map(|cas_id| {
+			let shard_dir = base_dir.join(get_shard_hex(&cas_id));
+			ThumbnailSize::ALL.map(|size| shard_dir.join(size.file_name(&cas_id)))
+		})
+		.map(|thumbnail_path| {
 			trace!("Removing thumbnail: {}", thumbnail_path.display());
 
 			async move {
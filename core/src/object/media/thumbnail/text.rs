@@ -0,0 +1,493 @@
+use crate::util::error::FileIOError;
+
+use sd_file_ext::{extensions::CodeExtension, text::is_text};
+
+use std::{fmt::Write, path::Path};
+
+use image::{DynamicImage, RgbaImage};
+use once_cell::sync::Lazy;
+use resvg::{
+	tiny_skia::{Pixmap, Transform},
+	usvg::{self, fontdb, TreeParsing, TreeTextToPath},
+};
+use tokio::{fs::File, io::AsyncReadExt};
+
+/// We only need the first lines of the file, so there is no point in reading more than this
+const READ_LENGTH: u64 = 8 * 1024; // 8KB
+
+// The preview looks like a page from a dark themed editor
+const PAGE_WIDTH: usize = 600;
+const PAGE_HEIGHT: usize = 800;
+const PADDING: usize = 24;
+const FONT_SIZE: usize = 14;
+const LINE_HEIGHT: usize = 20;
+const MAX_LINES: usize = (PAGE_HEIGHT - 2 * PADDING) / LINE_HEIGHT;
+const MAX_COLUMNS: usize = 64;
+const TAB_WIDTH: usize = 4;
+
+/// Rendered at twice the page size, so it is not upscaled even for the large thumbnail size
+const RENDER_SCALE: u32 = 2;
+
+const BACKGROUND_COLOR: &str = "#1e1e1e";
+
+/// Monospaced fonts to look for, as not every system maps the generic `monospace` family
+const MONOSPACE_FAMILIES: [&str; 7] = [
+	"SF Mono",
+	"Menlo",
+	"Consolas",
+	"DejaVu Sans Mono",
+	"Liberation Mono",
+	"Noto Sans Mono",
+	"Courier New",
+];
+
+/// Words highlighted as keywords, shared by every language as most of them overlap
+const KEYWORDS: &[&str] = &[
+	"abstract",
+	"and",
+	"as",
+	"async",
+	"await",
+	"break",
+	"case",
+	"catch",
+	"class",
+	"const",
+	"continue",
+	"def",
+	"default",
+	"defer",
+	"del",
+	"do",
+	"done",
+	"elif",
+	"else",
+	"end",
+	"enum",
+	"esac",
+	"export",
+	"extends",
+	"false",
+	"fi",
+	"final",
+	"finally",
+	"fn",
+	"for",
+	"from",
+	"func",
+	"function",
+	"if",
+	"impl",
+	"implements",
+	"import",
+	"in",
+	"interface",
+	"let",
+	"local",
+	"loop",
+	"match",
+	"mod",
+	"module",
+	"mut",
+	"new",
+	"nil",
+	"none",
+	"not",
+	"null",
+	"or",
+	"package",
+	"private",
+	"protected",
+	"pub",
+	"public",
+	"return",
+	"self",
+	"static",
+	"struct",
+	"super",
+	"switch",
+	"then",
+	"this",
+	"throw",
+	"trait",
+	"true",
+	"try",
+	"type",
+	"use",
+	"var",
+];
+
+static FONTS: Lazy<fontdb::Database> = Lazy::new(|| {
+	let mut fonts = fontdb::Database::new();
+	fonts.load_system_fonts();
+
+	if let Some(family) = MONOSPACE_FAMILIES.into_iter().find(|family| {
+		fonts
+			.faces()
+			.any(|face| face.families.iter().any(|(name, _)| name == *family))
+	}) {
+		fonts.set_monospace_family(family);
+	}
+
+	fonts
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+	Plain,
+	Keyword,
+	String,
+	Number,
+	Comment,
+}
+
+impl Token {
+	const fn color(self) -> &'static str {
+		match self {
+			Self::Plain => "#d4d4d4",
+			Self::Keyword => "#c586c0",
+			Self::String => "#ce9178",
+			Self::Number => "#b5cea8",
+			Self::Comment => "#6a9955",
+		}
+	}
+}
+
+/// Just enough knowledge about a language to colour its comments, strings, numbers and keywords
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Syntax {
+	line_comments: &'static [&'static str],
+	block_comment: Option<(&'static str, &'static str)>,
+}
+
+impl From<CodeExtension> for Syntax {
+	fn from(extension: CodeExtension) -> Self {
+		use CodeExtension::*;
+
+		let (line_comments, block_comment): (&[_], _) = match extension {
+			Sh | Zsh | Fish | Bash | Rb | Cr | Py | Pl | R | Nim | Nims | Make | Dockerfile
+			| Ps1 | Psd1 | Psm1 => (&["#"], None),
+			Lua | Sql => (&["--"], None),
+			Hs => (&["--"], Some(("{-", "-}"))),
+			Ml | Mli | Mll | Mly => (&[], Some(("(*", "*)"))),
+			Scpt | Scptd | Applescript => (&["--", "#"], Some(("(*", "*)"))),
+			Html => (&[], Some(("<!--", "-->"))),
+			Css => (&[], Some(("/*", "*/"))),
+			Php | Php1 | Php2 | Php3 | Php4 | Php5 | Php6 | Phps | Phpt | Phtml => {
+				(&["//", "#"], Some(("/*", "*/")))
+			}
+			_ => (&["//"], Some(("/*", "*/"))),
+		};
+
+		Self {
+			line_comments,
+			block_comment,
+		}
+	}
+}
+
+/// Reads the beginning of the file at `path`, returning `None` if it doesn't look like text
+pub(super) async fn read_text_preview(
+	path: impl AsRef<Path>,
+) -> Result<Option<String>, FileIOError> {
+	read_text(path, READ_LENGTH).await
+}
+
+/// Reads at most `max_len` bytes of the file at `path`, returning `None` if it doesn't look like text
+pub(crate) async fn read_text(
+	path: impl AsRef<Path>,
+	max_len: u64,
+) -> Result<Option<String>, FileIOError> {
+	let path = path.as_ref();
+
+	let file = File::open(path)
+		.await
+		.map_err(|e| FileIOError::from((path, e)))?;
+	let file_len = file
+		.metadata()
+		.await
+		.map_err(|e| FileIOError::from((path, e)))?
+		.len();
+
+	let mut buf = Vec::with_capacity(max_len.min(file_len) as usize);
+	file.take(max_len)
+		.read_to_end(&mut buf)
+		.await
+		.map_err(|e| FileIOError::from((path, e)))?;
+
+	Ok(is_text(&buf, file_len > max_len).and_then(|charset| decode(&buf, charset)))
+}
+
+fn decode(buf: &[u8], charset: &str) -> Option<String> {
+	match charset {
+		"utf-8" => Some(
+			String::from_utf8_lossy(buf.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(buf)).into_owned(),
+		),
+		"utf-16le" | "utf-16be" => {
+			let units = buf.chunks_exact(2).map(|pair| {
+				if charset == "utf-16le" {
+					u16::from_le_bytes([pair[0], pair[1]])
+				} else {
+					u16::from_be_bytes([pair[0], pair[1]])
+				}
+			});
+
+			Some(
+				char::decode_utf16(units)
+					.map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+					.filter(|c| *c != '\u{FEFF}')
+					.collect(),
+			)
+		}
+		"iso-8859-1" => Some(buf.iter().map(|&byte| char::from(byte)).collect()),
+		_ => None,
+	}
+}
+
+/// Renders the first lines of `text` as they would look on a code editor, colouring them when we
+/// know the `syntax` of the file
+pub(super) fn render_text_preview(
+	text: &str,
+	syntax: Option<Syntax>,
+) -> Result<DynamicImage, String> {
+	let lines = text
+		.lines()
+		.take(MAX_LINES)
+		.map(|line| {
+			let mut expanded = String::with_capacity(line.len());
+			for c in line.chars() {
+				if c == '\t' {
+					expanded
+						.push_str(&" ".repeat(TAB_WIDTH - expanded.chars().count() % TAB_WIDTH));
+				} else if !c.is_control() {
+					expanded.push(c);
+				}
+			}
+
+			expanded.chars().take(MAX_COLUMNS).collect::<String>()
+		})
+		.collect::<Vec<_>>();
+
+	let mut tree = usvg::Tree::from_str(
+		&to_svg(&highlight(&lines, syntax)),
+		&usvg::Options::default(),
+	)
+	.map_err(|e| e.to_string())?;
+	tree.convert_text(&FONTS);
+
+	let mut pixmap = Pixmap::new(
+		PAGE_WIDTH as u32 * RENDER_SCALE,
+		PAGE_HEIGHT as u32 * RENDER_SCALE,
+	)
+	.ok_or_else(|| "failed to allocate the preview pixmap".to_string())?;
+
+	resvg::Tree::from_usvg(&tree).render(
+		Transform::from_scale(RENDER_SCALE as f32, RENDER_SCALE as f32),
+		&mut pixmap.as_mut(),
+	);
+
+	RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixmap.take())
+		.map(DynamicImage::ImageRgba8)
+		.ok_or_else(|| "failed to convert the preview pixmap".to_string())
+}
+
+/// Splits each line in coloured spans, carrying block comments from one line to the next
+fn highlight(lines: &[String], syntax: Option<Syntax>) -> Vec<Vec<(Token, &str)>> {
+	let Some(syntax) = syntax else {
+		return lines
+			.iter()
+			.map(|line| vec![(Token::Plain, line.as_str())])
+			.collect();
+	};
+
+	let mut in_block_comment = false;
+
+	lines
+		.iter()
+		.map(|line| {
+			let mut spans = Vec::<(Token, usize, usize)>::new();
+			let mut start = 0;
+
+			while start < line.len() {
+				let (token, len) = next_token(&line[start..], syntax, &mut in_block_comment);
+
+				// Merging neighbouring spans of the same colour keeps the SVG small
+				match spans.last_mut() {
+					Some((last_token, _, end)) if *last_token == token => *end += len,
+					_ => spans.push((token, start, start + len)),
+				}
+
+				start += len;
+			}
+
+			spans
+				.into_iter()
+				.map(|(token, start, end)| (token, &line[start..end]))
+				.collect()
+		})
+		.collect()
+}
+
+/// Returns the kind and byte length of the token at the beginning of `rest`
+fn next_token(rest: &str, syntax: Syntax, in_block_comment: &mut bool) -> (Token, usize) {
+	if *in_block_comment {
+		let (_, end) = syntax
+			.block_comment
+			.expect("we only get in a block comment if the syntax has them");
+
+		return match rest.find(end) {
+			Some(idx) => {
+				*in_block_comment = false;
+				(Token::Comment, idx + end.len())
+			}
+			None => (Token::Comment, rest.len()),
+		};
+	}
+
+	if syntax
+		.line_comments
+		.iter()
+		.any(|start| rest.starts_with(start))
+	{
+		return (Token::Comment, rest.len());
+	}
+
+	if let Some((start, _)) = syntax.block_comment {
+		if rest.starts_with(start) {
+			*in_block_comment = true;
+			return (Token::Comment, start.len());
+		}
+	}
+
+	let first = rest.chars().next().expect("rest is not empty");
+
+	if matches!(first, '"' | '\'' | '`') {
+		let mut escaped = false;
+		for (idx, c) in rest.char_indices().skip(1) {
+			if c == first && !escaped {
+				return (Token::String, idx + c.len_utf8());
+			}
+			escaped = c == '\\' && !escaped;
+		}
+
+		// Unclosed double quotes usually mean a multiline string, while single ones are more
+		// likely to be something else, like Rust lifetimes
+		return if first == '\'' {
+			(Token::Plain, first.len_utf8())
+		} else {
+			(Token::String, rest.len())
+		};
+	}
+
+	if first.is_alphanumeric() || first == '_' {
+		let len = rest
+			.find(|c: char| {
+				!(c.is_alphanumeric() || c == '_' || (first.is_ascii_digit() && c == '.'))
+			})
+			.unwrap_or(rest.len());
+
+		return if first.is_ascii_digit() {
+			(Token::Number, len)
+		} else if KEYWORDS.contains(&&rest[..len]) {
+			(Token::Keyword, len)
+		} else {
+			(Token::Plain, len)
+		};
+	}
+
+	(Token::Plain, first.len_utf8())
+}
+
+fn to_svg(lines: &[Vec<(Token, &str)>]) -> String {
+	let mut svg = format!(
+		r#"<svg xmlns="http://www.w3.org/2000/svg" width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}">"#
+	);
+	write!(
+		svg,
+		r#"<rect width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}" fill="{BACKGROUND_COLOR}"/>"#
+	)
+	.expect("writing to a String never fails");
+	write!(
+		svg,
+		r#"<text font-family="monospace" font-size="{FONT_SIZE}" fill="{}" xml:space="preserve">"#,
+		Token::Plain.color()
+	)
+	.expect("writing to a String never fails");
+
+	for (idx, spans) in lines.iter().enumerate() {
+		write!(
+			svg,
+			r#"<tspan x="{PADDING}" y="{}">"#,
+			PADDING + FONT_SIZE + idx * LINE_HEIGHT
+		)
+		.expect("writing to a String never fails");
+
+		for (token, text) in spans {
+			if *token == Token::Plain {
+				push_escaped(&mut svg, text);
+			} else {
+				write!(svg, r#"<tspan fill="{}">"#, token.color())
+					.expect("writing to a String never fails");
+				push_escaped(&mut svg, text);
+				svg.push_str("</tspan>");
+			}
+		}
+
+		svg.push_str("</tspan>");
+	}
+
+	svg.push_str("</text></svg>");
+
+	svg
+}
+
+fn push_escaped(svg: &mut String, text: &str) {
+	for c in text.chars() {
+		match c {
+			'&' => svg.push_str("&amp;"),
+			'<' => svg.push_str("&lt;"),
+			'>' => svg.push_str("&gt;"),
+			c => svg.push(c),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_highlight() {
+		let lines = [
+			"let answer = 42; // the answer",
+			"/* spans",
+			"lines */ \"a \\\" string\" 'a",
+		]
+		.map(String::from);
+
+		assert_eq!(
+			highlight(&lines, Some(Syntax::from(CodeExtension::Rs))),
+			vec![
+				vec![
+					(Token::Keyword, "let"),
+					(Token::Plain, " answer = "),
+					(Token::Number, "42"),
+					(Token::Plain, "; "),
+					(Token::Comment, "// the answer"),
+				],
+				vec![(Token::Comment, "/* spans")],
+				vec![
+					(Token::Comment, "lines */"),
+					(Token::Plain, " "),
+					(Token::String, "\"a \\\" string\""),
+					(Token::Plain, " 'a"),
+				],
+			]
+		);
+
+		assert_eq!(
+			highlight(&lines[..1], None),
+			vec![vec![(Token::Plain, "let answer = 42; // the answer")]]
+		);
+	}
+}
@@ -144,9 +144,16 @@ pub(super) async fn worker(
 						continue;
 					};
 
+					let maybe_db = if let ThumbnailKind::Indexed(library_id) = batch_and_kind.1 {
+						databases.get(&library_id).map(Arc::clone)
+					} else {
+						None
+					};
+
 					spawn(batch_processor(
 						thumbnails_directory.clone(),
 						batch_and_kind,
+						maybe_db,
 						generated_ephemeral_thumbnails_tx.clone(),
 						ProcessorControlChannels {
 							stop_rx: stop_older_processing_rx.clone(),
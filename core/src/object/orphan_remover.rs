@@ -1,4 +1,9 @@
-use crate::prisma::{object, tag_on_object, PrismaClient};
+use crate::{
+	object::content::remove_orphaned_content,
+	prisma::{
+		label_on_object, object, object_in_album, object_in_space, tag_on_object, PrismaClient,
+	},
+};
 
 use std::{sync::Arc, time::Duration};
 
@@ -84,6 +89,18 @@ impl OrphanRemoverActor {
 				._batch((
 					db.tag_on_object()
 						.delete_many(vec![tag_on_object::object_id::in_vec(objects_ids.clone())]),
+					db.label_on_object()
+						.delete_many(vec![label_on_object::object_id::in_vec(
+							objects_ids.clone(),
+						)]),
+					db.object_in_album()
+						.delete_many(vec![object_in_album::object_id::in_vec(
+							objects_ids.clone(),
+						)]),
+					db.object_in_space()
+						.delete_many(vec![object_in_space::object_id::in_vec(
+							objects_ids.clone(),
+						)]),
 					db.object()
 						.delete_many(vec![object::id::in_vec(objects_ids)]),
 				))
@@ -92,5 +109,9 @@ impl OrphanRemoverActor {
 				error!("Failed to remove orphaned objects: {e:#?}");
 			}
 		}
+
+		if let Err(e) = remove_orphaned_content(db).await {
+			error!("Failed to remove the content of orphaned objects: {e:#?}");
+		}
 	}
 }
@@ -0,0 +1,306 @@
+use crate::{
+	invalidate_query,
+	job::{
+		CurrentStep, JobError, JobInitOutput, JobResult, JobRunErrors, JobRunMetadata,
+		JobStepOutput, StatefulJob, WorkerContext,
+	},
+	library::Library,
+	location::file_path_helper::{join_location_relative_path, IsolatedFilePathData},
+	object::fs::{
+		error::FileSystemJobsError, find_available_filename_for_duplicate, rescan_target_directory,
+	},
+	prisma::{file_path, location, object_in_space, space, SortOrder},
+	util::{db::maybe_missing, error::FileIOError},
+};
+
+use std::{collections::HashMap, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use tokio::fs;
+use tracing::{trace, warn};
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceExportMode {
+	/// Falls back to copies for files on another file system than the target location
+	Hardlink,
+	Copy,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SpaceExporterJobData {
+	target_directory: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Hash, Type, Debug)]
+pub struct SpaceExporterJobInit {
+	pub space_id: space::id::Type,
+	pub target_location_id: location::id::Type,
+	/// The space is exported to a new directory, named after it, inside this one
+	pub target_location_relative_directory_path: PathBuf,
+	pub mode: SpaceExportMode,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpaceExporterJobStep {
+	pub source_full_path: PathBuf,
+	pub target_full_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SpaceExporterJobRunMetadata {
+	exported: u32,
+	/// Objects without any file in the locations of this instance
+	unavailable: u32,
+}
+
+impl JobRunMetadata for SpaceExporterJobRunMetadata {
+	fn update(&mut self, new_data: Self) {
+		self.exported += new_data.exported;
+		self.unavailable += new_data.unavailable;
+	}
+}
+
+#[async_trait::async_trait]
+impl StatefulJob for SpaceExporterJobInit {
+	type Data = SpaceExporterJobData;
+	type Step = SpaceExporterJobStep;
+	type RunMetadata = SpaceExporterJobRunMetadata;
+
+	const NAME: &'static str = "space_exporter";
+
+	fn target_location(&self) -> location::id::Type {
+		self.target_location_id
+	}
+
+	async fn init(
+		&self,
+		ctx: &WorkerContext,
+		data: &mut Option<Self::Data>,
+	) -> Result<JobInitOutput<Self::RunMetadata, Self::Step>, JobError> {
+		let init = self;
+		let Library { db, .. } = &*ctx.library;
+
+		let (space, objects_ids) = db
+			._batch((
+				db.space()
+					.find_unique(space::id::equals(init.space_id))
+					.select(space::select!({ name })),
+				db.object_in_space()
+					.find_many(vec![object_in_space::space_id::equals(init.space_id)])
+					.order_by(object_in_space::position::order(SortOrder::Asc))
+					.select(object_in_space::select!({ object_id })),
+			))
+			.await?;
+
+		let space =
+			space.ok_or_else(|| JobError::MissingFromDb("space", init.space_id.to_string()))?;
+
+		let objects_ids = objects_ids
+			.into_iter()
+			.map(|entry| entry.object_id)
+			.collect::<Vec<_>>();
+
+		let target_location_path = maybe_missing(
+			db.location()
+				.find_unique(location::id::equals(init.target_location_id))
+				.select(location::select!({ path }))
+				.exec()
+				.await?
+				.ok_or_else(|| {
+					JobError::MissingFromDb("location", init.target_location_id.to_string())
+				})?
+				.path,
+			"location.path",
+		)?;
+
+		// Spaces can hold objects from other instances, but we can only export the ones that
+		// have a file in one of our own locations
+		let locations_paths = db
+			.location()
+			.find_many(vec![location::instance_id::equals(Some(
+				ctx.library.config().await.instance_id,
+			))])
+			.select(location::select!({ id path }))
+			.exec()
+			.await?
+			.into_iter()
+			.filter_map(|location| location.path.map(|path| (location.id, PathBuf::from(path))))
+			.collect::<HashMap<_, _>>();
+
+		let mut files_by_object = HashMap::with_capacity(objects_ids.len());
+		for file_path in db
+			.file_path()
+			.find_many(vec![
+				file_path::object_id::in_vec(objects_ids.clone()),
+				file_path::location_id::in_vec(locations_paths.keys().copied().collect()),
+				file_path::is_dir::equals(Some(false)),
+			])
+			.exec()
+			.await?
+		{
+			if let Some(object_id) = file_path.object_id {
+				files_by_object.entry(object_id).or_insert(file_path);
+			}
+		}
+
+		let target_directory = join_location_relative_path(
+			target_location_path,
+			&init.target_location_relative_directory_path,
+		)
+		.join(directory_name(space.name.as_deref(), init.space_id));
+
+		let target_directory = if fs::metadata(&target_directory).await.is_ok() {
+			find_available_filename_for_duplicate(target_directory).await?
+		} else {
+			target_directory
+		};
+
+		fs::create_dir_all(&target_directory)
+			.await
+			.map_err(|e| FileIOError::from((&target_directory, e)))?;
+
+		let mut run_metadata = SpaceExporterJobRunMetadata::default();
+		let mut steps = Vec::with_capacity(files_by_object.len());
+
+		for object_id in &objects_ids {
+			let Some(file_path) = files_by_object.get(object_id) else {
+				run_metadata.unavailable += 1;
+				continue;
+			};
+
+			let location_id = maybe_missing(file_path.location_id, "file_path.location_id")?;
+			let Some(location_path) = locations_paths.get(&location_id) else {
+				run_metadata.unavailable += 1;
+				continue;
+			};
+
+			let iso_file_path = IsolatedFilePathData::try_from(file_path)?;
+
+			steps.push(SpaceExporterJobStep {
+				target_full_path: target_directory.join(ordered_file_name(
+					steps.len(),
+					objects_ids.len(),
+					&iso_file_path.full_name(),
+				)),
+				source_full_path: location_path.join(iso_file_path),
+			});
+		}
+
+		*data = Some(SpaceExporterJobData { target_directory });
+
+		Ok((run_metadata, steps).into())
+	}
+
+	async fn execute_step(
+		&self,
+		ctx: &WorkerContext,
+		CurrentStep { step, .. }: CurrentStep<'_, Self::Step>,
+		_: &Self::Data,
+		_: &Self::RunMetadata,
+	) -> Result<JobStepOutput<Self::Step, Self::RunMetadata>, JobError> {
+		let init = self;
+
+		ctx.progress_msg(step.target_full_path.display().to_string());
+
+		trace!(
+			"Exporting {} to {}",
+			step.source_full_path.display(),
+			step.target_full_path.display()
+		);
+
+		match export_file(step, init.mode).await {
+			Ok(()) => Ok(SpaceExporterJobRunMetadata {
+				exported: 1,
+				..Default::default()
+			}
+			.into()),
+			// The file could have been removed since the job started
+			Err(FileSystemJobsError::FileIO(e)) if e.source.kind() == io::ErrorKind::NotFound => {
+				Ok(JobRunErrors(vec![e.to_string()]).into())
+			}
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	async fn finalize(
+		&self,
+		ctx: &WorkerContext,
+		data: &Option<Self::Data>,
+		run_metadata: &Self::RunMetadata,
+	) -> JobResult {
+		let init = self;
+		let data = data
+			.as_ref()
+			.expect("critical error: missing data on job state");
+
+		rescan_target_directory(ctx, init.target_location_id, data.target_directory.clone())
+			.await?;
+
+		invalidate_query!(ctx.library, "search.paths");
+
+		Ok(Some(json!({ "init": init, "run_metadata": run_metadata })))
+	}
+}
+
+async fn export_file(
+	SpaceExporterJobStep {
+		source_full_path,
+		target_full_path,
+	}: &SpaceExporterJobStep,
+	mode: SpaceExportMode,
+) -> Result<(), FileSystemJobsError> {
+	if mode == SpaceExportMode::Hardlink {
+		match fs::hard_link(source_full_path, target_full_path).await {
+			Ok(()) => return Ok(()),
+			// Already linked before the job was paused
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(()),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {
+				return Err(FileIOError::from((source_full_path, e)).into())
+			}
+			Err(e) => warn!(
+				"Failed to hard link {}, copying it instead: {e:#?}",
+				source_full_path.display()
+			),
+		}
+	}
+
+	fs::copy(source_full_path, target_full_path)
+		.await
+		.map(|_| ())
+		.map_err(|e| FileIOError::from((source_full_path, e)).into())
+}
+
+fn directory_name(space_name: Option<&str>, space_id: space::id::Type) -> String {
+	let name = space_name
+		.map(|name| name.replace(['/', '\\'], "_"))
+		.filter(|name| !name.trim().is_empty() && name != "." && name != "..");
+
+	name.unwrap_or_else(|| format!("Space {space_id}"))
+}
+
+/// Prefixes the file name with its position, so file managers keep the order of the space
+fn ordered_file_name(index: usize, total: usize, file_name: &str) -> String {
+	let width = total.to_string().len();
+
+	format!("{:0width$} - {file_name}", index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn file_names_keep_the_space_order() {
+		assert_eq!(ordered_file_name(0, 9, "a.jpg"), "1 - a.jpg");
+		assert_eq!(ordered_file_name(4, 120, "b.png"), "005 - b.png");
+	}
+
+	#[test]
+	fn directory_names_are_sanitized() {
+		assert_eq!(directory_name(Some("Trip/2023"), 1), "Trip_2023");
+		assert_eq!(directory_name(Some(".."), 2), "Space 2");
+		assert_eq!(directory_name(None, 3), "Space 3");
+	}
+}
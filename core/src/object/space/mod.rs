@@ -0,0 +1,63 @@
+use crate::{library::Library, prisma::space};
+
+use sd_prisma::prisma_sync;
+use sd_sync::*;
+use sd_utils::chain_optional_iter;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use specta::Type;
+use uuid::Uuid;
+
+pub mod exporter;
+
+// Spaces are curated collections of objects, so unlike albums they can hold objects from any
+// location of any instance of the library, and they keep the order chosen by the user.
+
+#[derive(Type, Deserialize, Clone)]
+pub struct SpaceCreateArgs {
+	pub name: String,
+	#[specta(optional)]
+	pub description: Option<String>,
+}
+
+impl SpaceCreateArgs {
+	pub async fn exec(
+		self,
+		Library { db, sync, .. }: &Library,
+	) -> prisma_client_rust::Result<space::Data> {
+		let pub_id = Uuid::new_v4().as_bytes().to_vec();
+		let date_created: DateTime<FixedOffset> = Utc::now().into();
+
+		sync.write_ops(
+			db,
+			(
+				sync.shared_create(
+					prisma_sync::space::SyncId {
+						pub_id: pub_id.clone(),
+					},
+					chain_optional_iter(
+						[
+							(space::name::NAME, json!(&self.name)),
+							(space::date_created::NAME, json!(&date_created.to_rfc3339())),
+						],
+						[self
+							.description
+							.as_ref()
+							.map(|description| (space::description::NAME, json!(description)))],
+					),
+				),
+				db.space().create(
+					pub_id,
+					vec![
+						space::name::set(Some(self.name)),
+						space::description::set(self.description),
+						space::date_created::set(Some(date_created)),
+					],
+				),
+			),
+		)
+		.await
+	}
+}
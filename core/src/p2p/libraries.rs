@@ -4,12 +4,18 @@ use std::{
 	sync::{Arc, PoisonError, RwLock},
 };
 
-use sd_p2p::Service;
+use sd_p2p::{
+	spacetunnel::{RemoteIdentity, Tunnel},
+	Service,
+};
 use tokio::sync::mpsc;
 use tracing::{error, warn};
 use uuid::Uuid;
 
-use crate::library::{Libraries, Library, LibraryManagerEvent};
+use crate::{
+	library::{Libraries, Library, LibraryManagerEvent},
+	prisma::instance,
+};
 
 use super::{IdentityOrRemoteIdentity, LibraryMetadata, P2PManager};
 
@@ -151,3 +157,105 @@ impl LibraryServices {
 		);
 	}
 }
+
+/// Only the instances of a library are allowed to access its files and sync operations over P2P.
+///
+/// The tunnel only authenticates the node on the other end, so both sides prove which instance of the library they are.
+pub(crate) async fn authenticate_library_instance(
+	tunnel: &mut Tunnel,
+	library: &Library,
+) -> Result<RemoteIdentity, ()> {
+	let peer = tunnel.remote_identity();
+	let identity = tunnel
+		.authenticate_as(&library.identity)
+		.await
+		.map_err(|err| {
+			warn!(
+				"failed to authenticate instance of library '{}' with peer '{peer}': {err:?}",
+				library.id
+			);
+		})?;
+
+	let instances = library
+		.db
+		.instance()
+		.find_many(vec![])
+		.select(instance::select!({ identity }))
+		.exec()
+		.await
+		.map_err(|err| {
+			warn!(
+				"error checking the instances of library '{}': {err:?}",
+				library.id
+			);
+		})?;
+
+	if !is_library_instance(instances.iter().map(|i| i.identity.as_slice()), identity) {
+		warn!(
+			"peer '{peer}' authenticated as '{identity}', which isn't an instance of library '{}'",
+			library.id
+		);
+		return Err(());
+	}
+
+	Ok(identity)
+}
+
+fn is_library_instance<'a>(
+	mut instances: impl Iterator<Item = &'a [u8]>,
+	identity: RemoteIdentity,
+) -> bool {
+	let identity = IdentityOrRemoteIdentity::RemoteIdentity(identity).to_bytes();
+	instances.any(|i| i == identity.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+	use sd_p2p::spacetunnel::Identity;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn paired_instance_is_a_library_instance() {
+		let (originator_node, responder_node) = (Identity::new(), Identity::new());
+		let (originator_instance, responder_instance) = (Identity::new(), Identity::new());
+
+		// What each side's library holds after pairing
+		let instances = [
+			IdentityOrRemoteIdentity::Identity(responder_instance.clone()).to_bytes(),
+			IdentityOrRemoteIdentity::RemoteIdentity(originator_instance.to_remote_identity())
+				.to_bytes(),
+		];
+
+		let (client, server) = tokio::io::duplex(1024);
+		let (client, server) = tokio::join!(
+			Tunnel::initiator_with(
+				client,
+				&originator_node,
+				responder_node.to_remote_identity()
+			),
+			Tunnel::responder_with(
+				server,
+				&responder_node,
+				originator_node.to_remote_identity()
+			),
+		);
+		let (mut client, mut server) = (client.unwrap(), server.unwrap());
+
+		let (_, identity) = tokio::join!(
+			client.authenticate_as(&originator_instance),
+			server.authenticate_as(&responder_instance)
+		);
+		let identity = identity.unwrap();
+
+		assert!(is_library_instance(
+			instances.iter().map(Vec::as_slice),
+			identity
+		));
+		// The node identity the tunnel authenticated never belongs to the library
+		assert!(!is_library_instance(
+			instances.iter().map(Vec::as_slice),
+			server.remote_identity()
+		));
+	}
+}
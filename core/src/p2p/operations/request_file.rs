@@ -1,4 +1,5 @@
 use std::{
+	io::SeekFrom,
 	path::Path,
 	sync::{
 		atomic::{AtomicBool, Ordering},
@@ -9,12 +10,12 @@ use std::{
 use sd_p2p::{
 	spaceblock::{BlockSize, Range, SpaceblockRequest, SpaceblockRequests, Transfer},
 	spacetime::UnicastStream,
-	PeerMessageEvent,
+	spacetunnel::{RemoteIdentity, Tunnel},
 };
 use sd_prisma::prisma::file_path;
 use tokio::{
 	fs::File,
-	io::{AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+	io::{AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader},
 };
 use tracing::{debug, warn};
 use uuid::Uuid;
@@ -22,40 +23,63 @@ use uuid::Uuid;
 use crate::{
 	library::Library,
 	location::file_path_helper::{file_path_to_handle_p2p_serve_file, IsolatedFilePathData},
-	p2p::{Header, HeaderFile},
+	p2p::{authenticate_library_instance, Header, HeaderFile},
 	Node,
 };
 
 /// Request a file from the remote machine over P2P. This is used for preview media and quick preview.
+/// The `stream` must have been dialed to `identity`.
 ///
 /// DO NOT USE THIS WITHOUT `node.files_over_p2p_flag == true`
 pub async fn request_file(
 	mut stream: UnicastStream,
+	identity: &RemoteIdentity,
 	library: &Library,
 	file_path_id: Uuid,
 	range: Range,
 	output: impl AsyncWrite + Unpin,
 ) -> Result<(), ()> {
 	let id = Uuid::new_v4();
-	// TODO: Tunnel for encryption + authentication
+
+	stream
+		.write_all(&Header::File.to_bytes())
+		.await
+		.map_err(|err| {
+			warn!("({id}): failed to write `Header::File`: {err:?}");
+
+			// TODO: UI error
+		})?;
+
+	let mut stream = Tunnel::initiator(stream, identity).await.map_err(|err| {
+		warn!("({id}): failed to establish tunnel: {err:?}");
+
+		// TODO: UI error
+	})?;
 
 	stream
 		.write_all(
-			&Header::File(HeaderFile {
+			&HeaderFile {
 				id,
 				library_id: library.id,
 				file_path_id,
 				range: range.clone(),
-			})
+			}
 			.to_bytes(),
 		)
 		.await
 		.map_err(|err| {
-			warn!("({id}): failed to read `Header::File`: {err:?}");
+			warn!("({id}): failed to write `HeaderFile`: {err:?}");
 
 			// TODO: UI error
 			// TODO: Error sent to remote peer
 		})?;
+	stream.flush().await.map_err(|err| {
+		warn!("({id}): failed to flush `HeaderFile`: {err:?}");
+
+		// TODO: UI error
+	})?;
+
+	authenticate_library_instance(&mut stream, library).await?;
 
 	let block_size = BlockSize::from_stream(&mut stream).await.map_err(|err| {
 		warn!("({id}): failed to read block size: {err:?}");
@@ -70,22 +94,35 @@ pub async fn request_file(
 		// TODO: Error sent to remote peer
 	})?;
 
-	Transfer::new(
+	let request = SpaceblockRequest {
+		// TODO: Removing need for this field in this case
+		name: "todo".to_string(),
+		// TODO: Maybe removing need for `size` from this side
+		size,
+		range: range.clone(),
+	};
+
+	// The range is clamped to the size the remote says the file has, so we'd get less than we asked for
+	let bounds = request.bounds();
+	if let Range::Partial(requested) = &range {
+		if bounds != *requested {
+			warn!("({id}): requested range {requested:?} but the remote can only send {bounds:?}");
+
+			// TODO: UI error
+			return Err(());
+		}
+	}
+
+	let completed = Transfer::new(
 		&SpaceblockRequests {
 			id,
 			block_size,
-			requests: vec![SpaceblockRequest {
-				// TODO: Removing need for this field in this case
-				name: "todo".to_string(),
-				// TODO: Maybe removing need for `size` from this side
-				size,
-				range,
-			}],
+			requests: vec![request],
 		},
-		|percent| {
+		|progress| {
 			debug!(
 				"P2P receiving file path '{}' - progress {}%",
-				file_path_id, percent
+				file_path_id, progress.percent
 			);
 		},
 		&Arc::new(AtomicBool::new(false)),
@@ -99,26 +136,34 @@ pub async fn request_file(
 		// TODO: Send error to remote peer???
 	})?;
 
+	if !completed {
+		warn!("({id}): transfer was cancelled by the remote before the whole range was received");
+
+		// TODO: Error in UI
+		return Err(());
+	}
+
 	Ok(())
 }
 
-pub(crate) async fn receiver(
-	node: &Arc<Node>,
-	HeaderFile {
-		id,
-		library_id,
-		file_path_id,
-		range,
-	}: HeaderFile,
-	event: PeerMessageEvent,
-) -> Result<(), ()> {
-	let mut stream = event.stream;
+pub(crate) async fn receiver(node: &Arc<Node>, mut stream: Tunnel) -> Result<(), ()> {
 	#[allow(clippy::panic)] // If you've made it this far that's on you.
 	if !node.files_over_p2p_flag.load(Ordering::Relaxed) {
 		panic!("Files over P2P is disabled!");
 	}
 
-	// TODO: Tunnel and authentication
+	let HeaderFile {
+		id,
+		library_id,
+		file_path_id,
+		range,
+	} = HeaderFile::from_stream(&mut stream).await.map_err(|err| {
+		warn!(
+			"failed to read `HeaderFile` from peer '{}': {err:?}",
+			stream.remote_identity()
+		);
+	})?;
+
 	// TODO: Use BufReader
 
 	let library = node
@@ -132,6 +177,17 @@ pub(crate) async fn receiver(
 			// TODO: Send error to remote peer??? -> Can we avoid constructing connection until this is done so it's only an error on one side?
 		})?;
 
+	authenticate_library_instance(&mut stream, &library)
+		.await
+		.map_err(|()| {
+			warn!(
+				"({id}): rejected request for library '{library_id:?}' from '{}'",
+				stream.remote_identity()
+			);
+
+			// TODO: Send error to remote peer???
+		})?;
+
 	let file_path = library
 		.db
 		.file_path()
@@ -177,7 +233,7 @@ pub(crate) async fn receiver(
 
 	debug!("Serving path '{:?}' over P2P", path);
 
-	let file = File::open(&path).await.map_err(|err| {
+	let mut file = File::open(&path).await.map_err(|err| {
 		warn!("({id}): failed to open file '{path:?}': {err:?}");
 
 		// TODO: Error in UI
@@ -207,6 +263,28 @@ pub(crate) async fn receiver(
 		.map_err(|err| {
 			warn!("({id}): failed to write length: {err:?}");
 
+			// TODO: Error in UI
+			// TODO: Send error to remote peer???
+		})?;
+	stream.flush().await.map_err(|err| {
+		warn!("({id}): failed to flush file header: {err:?}");
+
+		// TODO: Error in UI
+	})?;
+
+	let request = SpaceblockRequest {
+		// TODO: Removing need for this field in this case
+		name: "todo".to_string(),
+		size: metadata.len(),
+		range,
+	};
+
+	// Only the requested range is read, so seeking into a video doesn't read everything before it
+	file.seek(SeekFrom::Start(request.bounds().start))
+		.await
+		.map_err(|err| {
+			warn!("({id}): failed to seek file '{path:?}': {err:?}");
+
 			// TODO: Error in UI
 			// TODO: Send error to remote peer???
 		})?;
@@ -216,17 +294,12 @@ pub(crate) async fn receiver(
 		&SpaceblockRequests {
 			id,
 			block_size,
-			requests: vec![SpaceblockRequest {
-				// TODO: Removing need for this field in this case
-				name: "todo".to_string(),
-				size: metadata.len(),
-				range,
-			}],
+			requests: vec![request],
 		},
-		|percent| {
+		|progress| {
 			debug!(
 				"P2P loading file path '{}' - progress {}%",
-				file_path_id, percent
+				file_path_id, progress.percent
 			);
 		},
 		&Arc::new(AtomicBool::new(false)),
@@ -1,6 +1,7 @@
 use std::{
-	borrow::Cow,
-	path::PathBuf,
+	collections::HashSet,
+	io,
+	path::{Component, Path, PathBuf},
 	sync::{
 		atomic::{AtomicBool, Ordering},
 		Arc,
@@ -8,14 +9,15 @@ use std::{
 	time::Duration,
 };
 
-use futures::future::join_all;
 use sd_p2p::{
-	spaceblock::{BlockSize, Range, SpaceblockRequest, SpaceblockRequests, Transfer},
-	spacetunnel::RemoteIdentity,
-	PeerMessageEvent,
+	spaceblock::{
+		BlockSize, PartialFile, Range, SpaceblockRequest, SpaceblockRequests, SpaceblockResume,
+		Transfer,
+	},
+	spacetunnel::{RemoteIdentity, Tunnel},
 };
 use tokio::{
-	fs::{create_dir_all, File},
+	fs::{self, create_dir_all, File},
 	io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
 	sync::oneshot,
 	time::{sleep, Instant},
@@ -28,6 +30,14 @@ use crate::p2p::{Header, P2PEvent, P2PManager};
 /// The amount of time to wait for a Spacedrop request to be accepted or rejected before it's automatically rejected
 pub(crate) const SPACEDROP_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// How the user responded to an incoming Spacedrop
+#[derive(Debug)]
+pub(crate) struct SpacedropAcceptance {
+	path: PathBuf,
+	/// Indexes of the files to receive, or all of them if `None`
+	files: Option<HashSet<u32>>,
+}
+
 // TODO: Proper error handling
 pub async fn spacedrop(
 	p2p: Arc<P2PManager>,
@@ -39,30 +49,10 @@ pub async fn spacedrop(
 		return Err(());
 	}
 
-	let (files, requests): (Vec<_>, Vec<_>) = join_all(paths.into_iter().map(|path| async move {
-		let file = File::open(&path).await?;
-		let metadata = file.metadata().await?;
-		let name = path
-			.file_name()
-			.map(|v| v.to_string_lossy())
-			.unwrap_or(Cow::Borrowed(""))
-			.to_string();
-
-		Ok((
-			(path, file),
-			SpaceblockRequest {
-				name,
-				size: metadata.len(),
-				range: Range::Full,
-			},
-		))
-	}))
-	.await
-	.into_iter()
-	.collect::<Result<Vec<_>, std::io::Error>>()
-	.map_err(|_| ())? // TODO: Error handling
-	.into_iter()
-	.unzip();
+	let (files, requests) = collect_files(paths).await.map_err(|err| {
+		debug!("failed to collect files to Spacedrop: {err:?}");
+		// TODO: Error handling
+	})?;
 
 	let total_length: u64 = requests.iter().map(|req| req.size).sum();
 
@@ -75,18 +65,32 @@ pub async fn spacedrop(
 
 	tokio::spawn(async move {
 		debug!("({id}): connected, sending header");
-		let header = Header::Spacedrop(SpaceblockRequests {
+		if let Err(err) = stream.write_all(&Header::Spacedrop.to_bytes()).await {
+			debug!("({id}): failed to send header: {err}");
+			return;
+		}
+
+		let mut stream = match Tunnel::initiator(stream, &identity).await {
+			Ok(tunnel) => tunnel,
+			Err(err) => {
+				debug!("({id}): failed to establish tunnel: {err}");
+				return;
+			}
+		};
+
+		let mut requests = SpaceblockRequests {
 			id,
 			block_size: BlockSize::from_size(total_length),
 			requests,
-		});
-		if let Err(err) = stream.write_all(&header.to_bytes()).await {
-			debug!("({id}): failed to send header: {err}");
+		};
+		if let Err(err) = stream.write_all(&requests.to_bytes()).await {
+			debug!("({id}): failed to send requests: {err}");
+			return;
+		}
+		if let Err(err) = stream.flush().await {
+			debug!("({id}): failed to flush requests: {err}");
 			return;
 		}
-		let Header::Spacedrop(requests) = header else {
-			unreachable!();
-		};
 
 		debug!("({id}): waiting for response");
 		let result = tokio::select! {
@@ -110,6 +114,21 @@ pub async fn spacedrop(
 			Err(_) => todo!(), // TODO: Proper error
 		}
 
+		// The receiver tells us which parts of each file it still needs from a previous attempt,
+		// and skips the files it doesn't want
+		match SpaceblockResume::from_stream(&mut stream).await {
+			Ok(resume) => {
+				if let Err(err) = requests.resume(resume) {
+					debug!("({id}): received an invalid resume request: {err}");
+					return;
+				}
+			}
+			Err(err) => {
+				debug!("({id}): failed to read resume request: {err}");
+				return;
+			}
+		}
+
 		let cancelled = Arc::new(AtomicBool::new(false));
 		p2p.spacedrop_cancelations
 			.lock()
@@ -121,19 +140,40 @@ pub async fn spacedrop(
 
 		let mut transfer = Transfer::new(
 			&requests,
-			|percent| {
+			|progress| {
 				p2p.events
 					.0
-					.send(P2PEvent::SpacedropProgress { id, percent })
+					.send(P2PEvent::SpacedropProgress {
+						id,
+						percent: progress.percent,
+						file: progress.file as u32,
+						file_percent: progress.file_percent,
+					})
 					.ok();
 			},
 			&cancelled,
 		);
 
-		for (file_id, (path, file)) in files.into_iter().enumerate() {
-			debug!("({id}): transmitting '{file_id}' from '{path:?}'");
-			let file = BufReader::new(file);
-			if let Err(err) = transfer.send(&mut stream, file).await {
+		for (file_id, path) in files.into_iter().enumerate() {
+			let result = if requests.requests[file_id].bounds().is_empty() {
+				// Nothing is read from the file as there is nothing to send
+				transfer
+					.send_resumable(&mut stream, tokio::io::empty())
+					.await
+			} else {
+				debug!("({id}): transmitting '{file_id}' from '{path:?}'");
+				async {
+					// The receiver may already have the start of the file from a previous attempt,
+					// which is still read to hash the whole file
+					let file = File::open(&path).await?;
+					transfer
+						.send_resumable(&mut stream, BufReader::new(file))
+						.await
+				}
+				.await
+			};
+
+			if let Err(err) = result {
 				debug!("({id}): failed to send file '{file_id}': {err}");
 				// TODO: Error to frontend
 				// p2p.events
@@ -150,15 +190,86 @@ pub async fn spacedrop(
 	Ok(id)
 }
 
+/// Expand directories into the files within them, named by their path relative to the directory's
+/// parent so the receiver can recreate the same structure.
+async fn collect_files(
+	paths: Vec<PathBuf>,
+) -> Result<(Vec<PathBuf>, Vec<SpaceblockRequest>), io::Error> {
+	let mut files = Vec::new();
+	let mut requests = Vec::new();
+
+	let mut stack = paths
+		.into_iter()
+		.map(|path| {
+			let name = path
+				.file_name()
+				.map(|name| name.to_string_lossy().to_string())
+				.unwrap_or_default();
+			(path, name)
+		})
+		.collect::<Vec<_>>();
+	stack.reverse();
+
+	while let Some((path, name)) = stack.pop() {
+		let metadata = fs::metadata(&path).await?;
+		if !metadata.is_dir() {
+			files.push(path);
+			requests.push(SpaceblockRequest {
+				name,
+				size: metadata.len(),
+				range: Range::Full,
+			});
+			continue;
+		}
+
+		let mut entries = Vec::new();
+		let mut read_dir = fs::read_dir(&path).await?;
+		while let Some(entry) = read_dir.next_entry().await? {
+			// Following symlinks could send files from outside the directory or loop forever
+			if entry.file_type().await?.is_symlink() {
+				continue;
+			}
+
+			let entry_name = format!("{name}/{}", entry.file_name().to_string_lossy());
+			entries.push((entry.path(), entry_name));
+		}
+
+		// So the files are sent in a stable order
+		entries.sort_by(|(a, _), (b, _)| b.cmp(a));
+		stack.extend(entries);
+	}
+
+	Ok((files, requests))
+}
+
+/// Turn the name of a file in a Spacedrop into a relative path,
+/// refusing anything that could be written outside of the directory picked by the user.
+fn relative_path(name: &str) -> Option<PathBuf> {
+	let mut path = PathBuf::new();
+	for component in name.split('/') {
+		let mut components = Path::new(component).components();
+		match (components.next(), components.next()) {
+			(Some(Component::Normal(component)), None) => path.push(component),
+			_ => return None,
+		}
+	}
+
+	Some(path)
+}
+
 // TODO: Move these off the manager
 impl P2PManager {
-	pub async fn accept_spacedrop(&self, id: Uuid, path: String) {
+	/// Accept an incoming Spacedrop, `files` are the indexes of the files to receive or all of them if `None`
+	pub async fn accept_spacedrop(&self, id: Uuid, path: String, files: Option<Vec<u32>>) {
 		if let Some(chan) = self.spacedrop_pairing_reqs.lock().await.remove(&id) {
-			chan.send(Some(path))
-				.map_err(|err| {
-					warn!("error accepting Spacedrop '{id:?}': '{err:?}'");
-				})
-				.ok();
+			chan.send(Some(SpacedropAcceptance {
+				path: PathBuf::from(path),
+				files: files.map(|files| files.into_iter().collect()),
+			}))
+			.map_err(|err| {
+				warn!("error accepting Spacedrop '{id:?}': '{err:?}'");
+			})
+			.ok();
 		}
 	}
 
@@ -179,19 +290,20 @@ impl P2PManager {
 	}
 }
 
-pub(crate) async fn reciever(
-	this: &Arc<P2PManager>,
-	req: SpaceblockRequests,
-	event: PeerMessageEvent,
-) -> Result<(), ()> {
+pub(crate) async fn reciever(this: &Arc<P2PManager>, mut stream: Tunnel) -> Result<(), ()> {
+	let identity = stream.remote_identity();
+	let mut req = SpaceblockRequests::from_stream(&mut stream)
+		.await
+		.map_err(|err| {
+			error!("error reading Spacedrop requests from peer '{identity}': '{err:?}'");
+		})?;
 	let id = req.id;
-	let mut stream = event.stream;
 	let (tx, rx) = oneshot::channel();
 
 	info!(
 		"({id}): received '{}' files from peer '{}' with block size '{:?}'",
 		req.requests.len(),
-		event.identity,
+		identity,
 		req.block_size
 	);
 	this.spacedrop_pairing_reqs.lock().await.insert(id, tx);
@@ -201,7 +313,7 @@ pub(crate) async fn reciever(
 		.0
 		.send(P2PEvent::SpacedropRequest {
 			id,
-			identity: event.identity,
+			identity,
 			peer_name: "Unknown".into(),
 			// TODO: A better solution to this
 			// manager
@@ -236,11 +348,53 @@ pub(crate) async fn reciever(
 				error!("({id}): error flushing reject bit: '{err:?}'");
 			})?;
 		}
-		file_path = rx => {
-			match file_path {
-				Ok(Some(file_path)) => {
+		acceptance = rx => {
+			match acceptance {
+				Ok(Some(SpacedropAcceptance { path: file_path, files })) => {
 					info!("({id}): accepted saving to '{:?}'", file_path);
 
+					// A single file is saved to the path picked by the user, anything else goes into it as a directory
+					let single_file = req.requests.len() == 1 && !req.requests[0].name.contains('/');
+
+					// Anything left over from an interrupted attempt at this Spacedrop is resumed
+					let mut destinations = Vec::<Option<(PathBuf, PartialFile)>>::with_capacity(req.requests.len());
+					for (file_id, request) in req.requests.iter().enumerate() {
+						if !files.as_ref().map_or(true, |files| files.contains(&(file_id as u32))) {
+							destinations.push(None);
+							continue;
+						}
+
+						let path = if single_file {
+							file_path.clone()
+						} else if let Some(relative_path) = relative_path(&request.name) {
+							file_path.join(relative_path)
+						} else {
+							warn!("({id}): declining '{}' as it would be saved outside of '{file_path:?}'", request.name);
+							destinations.push(None);
+							continue;
+						};
+
+						let partial = this.spacedrop_partials.open(&identity, request).await.map_err(|err| {
+							error!("({id}): error opening partial file for '{}': '{err:?}'", request.name);
+
+							// TODO: Send error to the frontend
+						})?;
+						destinations.push(Some((path, partial)));
+					}
+
+					// Declined files get an empty range, so the sender skips them
+					let resume = SpaceblockResume {
+						ranges: req
+							.requests
+							.iter()
+							.zip(&destinations)
+							.map(|(request, destination)| match destination {
+								Some((_, partial)) => partial.range(request),
+								None => Range::Partial(0..0),
+							})
+							.collect(),
+					};
+
 					let cancelled = Arc::new(AtomicBool::new(false));
 					this.spacedrop_cancelations
 						.lock()
@@ -254,23 +408,40 @@ pub(crate) async fn reciever(
 
 						// TODO: make sure the other peer times out or we retry???
 					})?;
+					stream.write_all(&resume.to_bytes()).await.map_err(|err| {
+						error!("({id}): error sending resume request: '{err:?}'");
+					})?;
+					stream.flush().await.map_err(|err| {
+						error!("({id}): error flushing continuation bit: '{err:?}'");
+					})?;
+					req.resume(resume).map_err(|err| {
+						error!("({id}): error applying resume request: '{err:?}'");
+					})?;
 
-					let names = req.requests.iter().map(|req| req.name.clone()).collect::<Vec<_>>();
-					let mut transfer = Transfer::new(&req, |percent| {
-						this.events.0.send(P2PEvent::SpacedropProgress { id, percent }).ok();
+					let mut transfer = Transfer::new(&req, |progress| {
+						this.events.0.send(P2PEvent::SpacedropProgress {
+							id,
+							percent: progress.percent,
+							file: progress.file as u32,
+							file_percent: progress.file_percent,
+						}).ok();
 					}, &cancelled);
 
-					let file_path = PathBuf::from(file_path);
-					let names_len = names.len();
-					for file_name in names {
-						 // When transferring more than 1 file we wanna join the incoming file name to the directory provided by the user
-						 let mut path = file_path.clone();
-						 if names_len != 1 {
-							// We know the `file_path` will be a directory so we can just push the file name to it
-							path.push(&file_name);
-						}
+					for (file_id, destination) in destinations.into_iter().enumerate() {
+						let file_name = &req.requests[file_id].name;
+						let Some((path, mut partial)) = destination else {
+							// The range of a declined file is empty, so this doesn't touch the stream
+							transfer.receive(&mut stream, tokio::io::sink()).await.map_err(|err| {
+								error!("({id}): error skipping file '{file_name}': '{err:?}'");
+							})?;
+							continue;
+						};
 
-						debug!("({id}): accepting '{file_name}' and saving to '{:?}'", path);
+						debug!(
+							"({id}): accepting '{file_name}' and saving to '{:?}' after '{}' previously received bytes",
+							path,
+							partial.received()
+						);
 
 						if let Some(parent) = path.parent() {
 						  create_dir_all(&parent).await.map_err(|err| {
@@ -282,20 +453,44 @@ pub(crate) async fn reciever(
 							})?;
 						}
 
-						let f = File::create(&path).await.map_err(|err| {
-							error!("({id}): error creating file at '{path:?}': '{err:?}'");
-
-							// TODO: Send error to the frontend
-
-							// TODO: Send error to remote peer
+						let received = partial.received_data().await.map_err(|err| {
+							error!("({id}): error reading previously received data of '{file_name}': '{err:?}'");
 						})?;
-						let f = BufWriter::new(f);
-						if let Err(err) = transfer.receive(&mut stream, f).await {
-							error!("({id}): error receiving file '{file_name}': '{err:?}'");
-
-							// TODO: Send error to frontend
-
-							break;
+						let result = {
+							let mut f = BufWriter::new(partial.file());
+							let result = transfer.receive_resumable(&mut stream, received, &mut f).await;
+							// Everything written so far was verified, so we keep as much as possible for resuming
+							f.flush().await.ok();
+							result
+						};
+
+						match result {
+							Ok(true) => {
+								partial.complete(&path).await.map_err(|err| {
+									error!("({id}): error moving received file to '{path:?}': '{err:?}'");
+
+									// TODO: Send error to the frontend
+								})?;
+							}
+							Ok(false) => {
+								info!("({id}): cancelled while receiving '{file_name}'");
+								break;
+							}
+							Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+								error!("({id}): received corrupted data for '{file_name}': '{err:?}'");
+								partial.discard().await.ok();
+
+								// TODO: Send error to frontend
+
+								break;
+							}
+							Err(err) => {
+								error!("({id}): error receiving file '{file_name}', it can be resumed later: '{err:?}'");
+
+								// TODO: Send error to frontend
+
+								break;
+							}
 						}
 					}
 
@@ -320,3 +515,28 @@ pub(crate) async fn reciever(
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_relative_path() {
+		assert_eq!(
+			relative_path("Photos/2023/beach.jpg"),
+			Some(PathBuf::from("Photos").join("2023").join("beach.jpg"))
+		);
+		assert_eq!(relative_path("demo.txt"), Some(PathBuf::from("demo.txt")));
+
+		for name in [
+			"",
+			"../demo.txt",
+			"Photos/../../demo.txt",
+			"/etc/passwd",
+			"a//b",
+			"./a",
+		] {
+			assert_eq!(relative_path(name), None, "{name}");
+		}
+	}
+}
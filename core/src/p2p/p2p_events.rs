@@ -31,6 +31,9 @@ pub enum P2PEvent {
 	SpacedropProgress {
 		id: Uuid,
 		percent: u8,
+		/// Index of the file being transferred within the `files` of the request
+		file: u32,
+		file_percent: u8,
 	},
 	SpacedropTimedout {
 		id: Uuid,
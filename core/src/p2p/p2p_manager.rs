@@ -1,11 +1,13 @@
 use std::{
 	collections::{HashMap, HashSet},
 	net::SocketAddr,
+	path::PathBuf,
 	sync::{atomic::AtomicBool, Arc},
 };
 
 use sd_p2p::{
-	spacetunnel::RemoteIdentity, Manager, ManagerConfig, ManagerError, PeerStatus, Service,
+	spaceblock::PartialStore, spacetunnel::RemoteIdentity, Manager, ManagerConfig, ManagerError,
+	PeerStatus, Service,
 };
 use serde::Serialize;
 use specta::Type;
@@ -19,7 +21,8 @@ use crate::{
 };
 
 use super::{
-	LibraryMetadata, LibraryServices, P2PEvent, P2PManagerActor, PairingManager, PeerMetadata,
+	operations::spacedrop::SpacedropAcceptance, LibraryMetadata, LibraryServices, P2PEvent,
+	P2PManagerActor, PairingManager, PeerMetadata,
 };
 
 pub struct P2PManager {
@@ -28,8 +31,11 @@ pub struct P2PManager {
 
 	pub events: (broadcast::Sender<P2PEvent>, broadcast::Receiver<P2PEvent>),
 	pub manager: Arc<Manager>,
-	pub(super) spacedrop_pairing_reqs: Arc<Mutex<HashMap<Uuid, oneshot::Sender<Option<String>>>>>,
+	pub(super) spacedrop_pairing_reqs:
+		Arc<Mutex<HashMap<Uuid, oneshot::Sender<Option<SpacedropAcceptance>>>>>,
 	pub(super) spacedrop_cancelations: Arc<Mutex<HashMap<Uuid, Arc<AtomicBool>>>>,
+	/// Files of Spacedrops which were interrupted, so they can be resumed
+	pub(super) spacedrop_partials: PartialStore,
 	pub pairing: Arc<PairingManager>,
 	node_config_manager: Arc<config::Manager>,
 }
@@ -38,6 +44,7 @@ impl P2PManager {
 	pub async fn new(
 		node_config: Arc<config::Manager>,
 		libraries: Arc<crate::library::Libraries>,
+		spacedrop_partials_dir: PathBuf,
 	) -> Result<(Arc<P2PManager>, P2PManagerActor), ManagerError> {
 		let (keypair, manager_config) = {
 			let config = node_config.get().await;
@@ -68,6 +75,7 @@ impl P2PManager {
 			manager,
 			spacedrop_pairing_reqs: Default::default(),
 			spacedrop_cancelations: Default::default(),
+			spacedrop_partials: PartialStore::new(spacedrop_partials_dir),
 			node_config_manager: node_config,
 		});
 		this.update_metadata().await;
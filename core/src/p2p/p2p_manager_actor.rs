@@ -7,7 +7,10 @@ use tracing::error;
 
 use crate::Node;
 
-use super::{operations, sync::SyncMessage, Header, LibraryMetadata, P2PEvent, P2PManager};
+use super::{
+	authenticate_library_instance, operations, sync::SyncMessage, Header, LibraryMetadata,
+	P2PEvent, P2PManager,
+};
 
 pub struct P2PManagerActor {
 	pub(super) manager: Arc<P2PManager>,
@@ -79,8 +82,13 @@ impl P2PManagerActor {
 
 										match header {
 											Header::Ping => operations::ping::reciever(event).await,
-											Header::Spacedrop(req) => {
-												operations::spacedrop::reciever(&this, req, event).await?
+											Header::Spacedrop => {
+												let tunnel =
+													Tunnel::responder(event.stream).await.map_err(|err| {
+														error!("Failed `Tunnel::responder`: {}", err);
+													})?;
+
+												operations::spacedrop::reciever(&this, tunnel).await?
 											}
 											Header::Pair => {
 												this.pairing
@@ -99,11 +107,6 @@ impl P2PManagerActor {
 														error!("Failed `Tunnel::responder`: {}", err);
 													})?;
 
-												let msg =
-													SyncMessage::from_stream(&mut tunnel).await.map_err(|err| {
-														error!("Failed `SyncMessage::from_stream`: {}", err);
-													})?;
-
 												let library =
 													node.libraries.get_library(&library_id).await.ok_or_else(|| {
 														error!("Failed to get library '{library_id}'");
@@ -111,14 +114,31 @@ impl P2PManagerActor {
 														// TODO: Respond to remote client with warning!
 													})?;
 
+												authenticate_library_instance(&mut tunnel, &library).await.map_err(|()| {
+													error!(
+														"Rejected sync for library '{library_id}' from '{}'",
+														tunnel.remote_identity()
+													);
+												})?;
+
+												let msg =
+													SyncMessage::from_stream(&mut tunnel).await.map_err(|err| {
+														error!("Failed `SyncMessage::from_stream`: {}", err);
+													})?;
+
 												match msg {
 													SyncMessage::NewOperations => {
 														super::sync::responder(&mut tunnel, library).await?;
 													}
 												};
 											}
-											Header::File(req) => {
-												operations::request_file::receiver(&node, req, event).await?;
+											Header::File => {
+												let tunnel =
+													Tunnel::responder(event.stream).await.map_err(|err| {
+														error!("Failed `Tunnel::responder`: {}", err);
+													})?;
+
+												operations::request_file::receiver(&node, tunnel).await?;
 											}
 										}
 
@@ -4,9 +4,10 @@ use uuid::Uuid;
 
 use sd_p2p::{
 	proto::{decode, encode},
-	spaceblock::{Range, SpaceblockRequests, SpaceblockRequestsError},
+	spaceblock::Range,
 };
 
+/// Sent through the tunnel after a [`Header::File`]
 #[derive(Debug, PartialEq, Eq)]
 pub struct HeaderFile {
 	// Request ID
@@ -16,15 +17,18 @@ pub struct HeaderFile {
 	pub(crate) range: Range,
 }
 
-/// TODO
+/// The first thing sent on every stream, so the receiver knows how to handle it.
+///
+/// Anything following a `Spacedrop`, `Sync` or `File` header is sent through a [`sd_p2p::spacetunnel::Tunnel`],
+/// which is why they don't carry their request with them.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Header {
 	// TODO: Split out cause this is a broadcast
 	Ping,
-	Spacedrop(SpaceblockRequests),
+	Spacedrop,
 	Pair,
 	Sync(Uuid),
-	File(HeaderFile),
+	File,
 }
 
 #[derive(Debug, Error)]
@@ -33,8 +37,6 @@ pub enum HeaderError {
 	DiscriminatorIo(std::io::Error),
 	#[error("invalid discriminator '{0}'")]
 	DiscriminatorInvalid(u8),
-	#[error("error reading spacedrop request: {0}")]
-	SpacedropRequest(#[from] SpaceblockRequestsError),
 	#[error("error reading sync request: {0}")]
 	SyncRequest(decode::Error),
 	#[error("error reading header file: {0}")]
@@ -43,6 +45,57 @@ pub enum HeaderError {
 	HeaderFileDiscriminatorInvalid(u8),
 }
 
+impl HeaderFile {
+	pub async fn from_stream(stream: &mut (impl AsyncRead + Unpin)) -> Result<Self, HeaderError> {
+		Ok(Self {
+			id: decode::uuid(stream)
+				.await
+				.map_err(HeaderError::HeaderFile)?,
+			library_id: decode::uuid(stream)
+				.await
+				.map_err(HeaderError::HeaderFile)?,
+			file_path_id: decode::uuid(stream)
+				.await
+				.map_err(HeaderError::HeaderFile)?,
+			range: match stream
+				.read_u8()
+				.await
+				.map_err(|err| HeaderError::HeaderFile(err.into()))?
+			{
+				0 => Range::Full,
+				1 => {
+					let start = stream
+						.read_u64_le()
+						.await
+						.map_err(|err| HeaderError::HeaderFile(err.into()))?;
+					let end = stream
+						.read_u64_le()
+						.await
+						.map_err(|err| HeaderError::HeaderFile(err.into()))?;
+					Range::Partial(start..end)
+				}
+				i => return Err(HeaderError::HeaderFileDiscriminatorInvalid(i)),
+			},
+		})
+	}
+
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let Self {
+			id,
+			library_id,
+			file_path_id,
+			range,
+		} = self;
+
+		let mut buf = Vec::new();
+		encode::uuid(&mut buf, id);
+		encode::uuid(&mut buf, library_id);
+		encode::uuid(&mut buf, file_path_id);
+		buf.extend_from_slice(&range.to_bytes());
+		buf
+	}
+}
+
 impl Header {
 	pub async fn from_stream(stream: &mut (impl AsyncRead + Unpin)) -> Result<Self, HeaderError> {
 		let discriminator = stream
@@ -51,9 +104,7 @@ impl Header {
 			.map_err(HeaderError::DiscriminatorIo)?;
 
 		match discriminator {
-			0 => Ok(Self::Spacedrop(
-				SpaceblockRequests::from_stream(stream).await?,
-			)),
+			0 => Ok(Self::Spacedrop),
 			1 => Ok(Self::Ping),
 			2 => Ok(Self::Pair),
 			3 => Ok(Self::Sync(
@@ -61,47 +112,14 @@ impl Header {
 					.await
 					.map_err(HeaderError::SyncRequest)?,
 			)),
-			4 => Ok(Self::File(HeaderFile {
-				id: decode::uuid(stream)
-					.await
-					.map_err(HeaderError::HeaderFile)?,
-				library_id: decode::uuid(stream)
-					.await
-					.map_err(HeaderError::HeaderFile)?,
-				file_path_id: decode::uuid(stream)
-					.await
-					.map_err(HeaderError::HeaderFile)?,
-				range: match stream
-					.read_u8()
-					.await
-					.map_err(|err| HeaderError::HeaderFile(err.into()))?
-				{
-					0 => Range::Full,
-					1 => {
-						let start = stream
-							.read_u64_le()
-							.await
-							.map_err(|err| HeaderError::HeaderFile(err.into()))?;
-						let end = stream
-							.read_u64_le()
-							.await
-							.map_err(|err| HeaderError::HeaderFile(err.into()))?;
-						Range::Partial(start..end)
-					}
-					i => return Err(HeaderError::HeaderFileDiscriminatorInvalid(i)),
-				},
-			})),
+			4 => Ok(Self::File),
 			d => Err(HeaderError::DiscriminatorInvalid(d)),
 		}
 	}
 
 	pub fn to_bytes(&self) -> Vec<u8> {
 		match self {
-			Self::Spacedrop(transfer_request) => {
-				let mut bytes = vec![0];
-				bytes.extend_from_slice(&transfer_request.to_bytes());
-				bytes
-			}
+			Self::Spacedrop => vec![0],
 			Self::Ping => vec![1],
 			Self::Pair => vec![2],
 			Self::Sync(uuid) => {
@@ -109,45 +127,43 @@ impl Header {
 				encode::uuid(&mut bytes, uuid);
 				bytes
 			}
-			Self::File(HeaderFile {
-				id,
-				library_id,
-				file_path_id,
-				range,
-			}) => {
-				let mut buf = vec![4];
-				encode::uuid(&mut buf, id);
-				encode::uuid(&mut buf, library_id);
-				encode::uuid(&mut buf, file_path_id);
-				buf.extend_from_slice(&range.to_bytes());
-				buf
-			}
+			Self::File => vec![4],
 		}
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	// use super::*;
-
-	#[test]
-	fn test_header() {
-		// TODO: Finish this
+	use super::*;
 
-		// 	assert_eq!(
-		// 		Header::from_bytes(&Header::Ping.to_bytes()),
-		// 		Ok(Header::Ping)
-		// 	);
-
-		// 	assert_eq!(
-		// 		Header::from_bytes(&Header::Spacedrop.to_bytes()),
-		// 		Ok(Header::Spacedrop)
-		// 	);
+	#[tokio::test]
+	async fn test_header() {
+		for header in [
+			Header::Ping,
+			Header::Spacedrop,
+			Header::Pair,
+			Header::Sync(Uuid::new_v4()),
+			Header::File,
+		] {
+			let bytes = header.to_bytes();
+			assert_eq!(
+				Header::from_stream(&mut bytes.as_slice()).await.unwrap(),
+				header
+			);
+		}
 
-		// 	let uuid = Uuid::new_v4();
-		// 	assert_eq!(
-		// 		Header::from_bytes(&Header::Sync(uuid).to_bytes()),
-		// 		Ok(Header::Sync(uuid))
-		// 	);
+		let header = HeaderFile {
+			id: Uuid::new_v4(),
+			library_id: Uuid::new_v4(),
+			file_path_id: Uuid::new_v4(),
+			range: Range::Partial(5..42),
+		};
+		let bytes = header.to_bytes();
+		assert_eq!(
+			HeaderFile::from_stream(&mut bytes.as_slice())
+				.await
+				.unwrap(),
+			header
+		);
 	}
 }
@@ -13,9 +13,9 @@ use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tracing::*;
 use uuid::Uuid;
 
-use crate::{library::Library, sync};
+use crate::{library::Library, object::album::refresh_ingested_smart_album, sync};
 
-use super::{Header, P2PManager};
+use super::{authenticate_library_instance, Header, P2PManager};
 
 mod proto;
 pub use proto::*;
@@ -83,7 +83,8 @@ mod originator {
 	}
 
 	/// REMEMBER: This only syncs one direction!
-	pub async fn run(library_id: Uuid, sync: &Arc<sync::Manager>, p2p: &Arc<super::P2PManager>) {
+	pub async fn run(library: &Arc<Library>, p2p: &Arc<super::P2PManager>) {
+		let library_id = library.id;
 		let service = p2p.get_library_service(&library_id).unwrap();
 
 		// TODO: Deduplicate any duplicate peer ids -> This is an edge case but still
@@ -92,7 +93,7 @@ mod originator {
 				continue;
 			};
 
-			let sync = sync.clone();
+			let library = library.clone();
 			let p2p = p2p.clone();
 			let service = service.clone();
 
@@ -107,12 +108,26 @@ mod originator {
 					.map_err(|_| ())
 					.unwrap(); // TODO: handle providing incorrect peer id
 
-				stream
-					.write_all(&Header::Sync(library_id).to_bytes())
-					.await
-					.unwrap();
+				if let Err(err) = stream.write_all(&Header::Sync(library_id).to_bytes()).await {
+					error!("Failed to send sync header to '{remote_identity:?}': {err}");
+					return;
+				}
+
+				let mut tunnel = match Tunnel::initiator(stream, &remote_identity).await {
+					Ok(tunnel) => tunnel,
+					Err(err) => {
+						error!("Failed to establish tunnel with '{remote_identity:?}': {err}");
+						return;
+					}
+				};
 
-				let mut tunnel = Tunnel::initiator(stream).await.unwrap();
+				if authenticate_library_instance(&mut tunnel, &library)
+					.await
+					.is_err()
+				{
+					error!("Refusing to sync library '{library_id:?}' with '{remote_identity:?}'");
+					return;
+				}
 
 				tunnel
 					.write_all(&SyncMessage::NewOperations.to_bytes())
@@ -123,7 +138,7 @@ mod originator {
 				while let Ok(rx::MainRequest::GetOperations(args)) =
 					rx::MainRequest::from_stream(&mut tunnel).await
 				{
-					let ops = sync.get_ops(args).await.unwrap();
+					let ops = library.sync.get_ops(args).await.unwrap();
 
 					tunnel
 						.write_all(&tx::Operations(ops).to_bytes())
@@ -228,7 +243,12 @@ mod responder {
 			let timestamps = match req {
 				Request::FinishedIngesting => break,
 				Request::Messages { timestamps } => timestamps,
-				_ => continue,
+				Request::Ingested(op) => {
+					if let Err(e) = refresh_ingested_smart_album(&library, op).await {
+						error!("Failed to refresh an ingested smart album: {e:#?}");
+					}
+					continue;
+				}
 			};
 
 			debug!("Getting ops for timestamps {timestamps:?}");
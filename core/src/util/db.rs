@@ -1,6 +1,12 @@
-use crate::prisma::{self, PrismaClient};
-use prisma_client_rust::{migrations::*, NewClientError};
+use crate::prisma::{self, key, PrismaClient};
+use prisma_client_rust::{migrations::*, NewClientError, QueryError};
+use sd_crypto::{
+	keys::keymanager::{KeyManager, StoredKey},
+	types::{EncryptedKey, Nonce, Salt},
+};
+use std::str::FromStr;
 use thiserror::Error;
+use uuid::Uuid;
 
 /// MigrationError represents an error that occurring while opening a initialising and running migrations on the database.
 #[derive(Error, Debug)]
@@ -10,6 +16,9 @@ pub enum MigrationError {
 	#[cfg(debug_assertions)]
 	#[error("An error occurred during migration: {0}")]
 	MigrateFailed(#[from] DbPushError),
+	#[cfg(debug_assertions)]
+	#[error("An error occurred while recreating the full-text search table: {0}")]
+	FullTextSearch(#[from] QueryError),
 	#[cfg(not(debug_assertions))]
 	#[error("An error occurred during migration: {0}")]
 	MigrateFailed(#[from] MigrateDeployError),
@@ -23,6 +32,17 @@ pub async fn load_and_migrate(db_url: &str) -> Result<PrismaClient, MigrationErr
 
 	#[cfg(debug_assertions)]
 	{
+		// `db push` only knows about the prisma schema, so it would drop the full-text search table
+		// of the migrations. It only holds content extracted from the files, so we drop it
+		// ourselves and recreate it from its migration, its content is extracted again by the next
+		// scan of each location.
+		client
+			._execute_raw(prisma_client_rust::raw!(
+				"DROP TABLE IF EXISTS \"object_content\""
+			))
+			.exec()
+			.await?;
+
 		let mut builder = client._db_push();
 
 		if std::env::var("SD_ACCEPT_DATA_LOSS")
@@ -49,6 +69,13 @@ pub async fn load_and_migrate(db_url: &str) -> Result<PrismaClient, MigrationErr
 			}
 			Err(e) => Err(e)?,
 		}
+
+		client
+			._execute_raw(prisma_client_rust::raw!(include_str!(
+				"../../prisma/migrations/20231221093512_object_content/migration.sql"
+			)))
+			.exec()
+			.await?;
 	}
 
 	#[cfg(not(debug_assertions))]
@@ -113,3 +140,90 @@ pub fn maybe_missing<T: OptionalField>(
 ) -> Result<T::Out, MissingFieldError> {
 	data.transform().ok_or(MissingFieldError(field))
 }
+
+/// StoredKeyError represents an error that occurred while moving keys between the key manager and the database.
+#[derive(Error, Debug)]
+pub enum StoredKeyError {
+	#[error("database error: {0}")]
+	Database(#[from] QueryError),
+	#[error("failed to (de)serialize a stored key: {0}")]
+	Serialization(#[from] serde_json::Error),
+	#[error("failed to parse the key's uuid: {0}")]
+	Uuid(#[from] uuid::Error),
+	#[error(transparent)]
+	Crypto(#[from] sd_crypto::Error),
+}
+
+impl From<StoredKeyError> for rspc::Error {
+	fn from(value: StoredKeyError) -> Self {
+		rspc::Error::with_cause(
+			rspc::ErrorCode::InternalServerError,
+			value.to_string(),
+			value,
+		)
+	}
+}
+
+/// write_storedkey_to_db writes a key from the key manager into the library's database, so it's kept across restarts.
+pub async fn write_storedkey_to_db(
+	db: &PrismaClient,
+	key: &StoredKey,
+) -> Result<(), StoredKeyError> {
+	db.key()
+		.create(
+			key.uuid.to_string(),
+			serde_json::to_string(&key.version)?,
+			serde_json::to_string(&key.key_type)?,
+			serde_json::to_string(&key.algorithm)?,
+			serde_json::to_string(&key.hashing_algorithm)?,
+			key.content_salt.to_vec(),
+			key.master_key.to_vec(),
+			key.master_key_nonce.to_vec(),
+			key.key_nonce.to_vec(),
+			key.key.clone(),
+			key.salt.to_vec(),
+			vec![key::automount::set(key.automount)],
+		)
+		.exec()
+		.await?;
+
+	Ok(())
+}
+
+fn storedkey_from_db(key: key::Data) -> Result<StoredKey, StoredKeyError> {
+	Ok(StoredKey {
+		uuid: Uuid::from_str(&key.uuid)?,
+		version: serde_json::from_str(&key.version)?,
+		key_type: serde_json::from_str(&key.key_type)?,
+		algorithm: serde_json::from_str(&key.algorithm)?,
+		hashing_algorithm: serde_json::from_str(&key.hashing_algorithm)?,
+		content_salt: Salt::try_from(key.content_salt)?,
+		master_key: EncryptedKey::try_from(key.master_key)?,
+		master_key_nonce: Nonce::try_from(key.master_key_nonce)?,
+		key_nonce: Nonce::try_from(key.key_nonce)?,
+		key: key.key,
+		salt: Salt::try_from(key.salt)?,
+		// memory only keys are never written to the database
+		memory_only: false,
+		automount: key.automount,
+	})
+}
+
+/// seed_keymanager populates the key manager's keystore with every key stored in the library's database.
+pub async fn seed_keymanager(
+	db: &PrismaClient,
+	key_manager: &KeyManager,
+) -> Result<(), StoredKeyError> {
+	let stored_keys = db
+		.key()
+		.find_many(vec![])
+		.exec()
+		.await?
+		.into_iter()
+		.map(storedkey_from_db)
+		.collect::<Result<Vec<_>, _>>()?;
+
+	key_manager.populate_keystore(stored_keys).await?;
+
+	Ok(())
+}
@@ -35,7 +35,7 @@
 //! let keys = key_manager.enumerate_hashed_keys();
 //! ```
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use tokio::sync::Mutex;
 
@@ -121,8 +121,17 @@ pub struct KeyManager {
 }
 impl KeyManager {
 	/// Initialize the Key Manager with `StoredKeys` retrieved from the database.
-	pub async fn new(stored_keys: Vec<StoredKey>) -> Result<Self> {
-		let keyring = KeyringInterface::new()
+	///
+	/// If a keyring fallback path is provided, it will be used to store secrets on disk whenever the OS keyring isn't available.
+	pub async fn new(
+		stored_keys: Vec<StoredKey>,
+		keyring_fallback_path: Option<PathBuf>,
+	) -> Result<Self> {
+		let keyring = keyring_fallback_path
+			.map_or_else(
+				KeyringInterface::new,
+				KeyringInterface::new_with_file_fallback,
+			)
 			.map(|k| Arc::new(Mutex::new(k)))
 			.ok();
 
@@ -253,8 +262,6 @@ impl KeyManager {
 		let content_salt = Salt::generate();
 		let secret_key = SecretKey::generate();
 
-		dbg!(SecretKeyString::from(secret_key.clone()).expose());
-
 		let algorithm = config.algorithm;
 		let hashing_algorithm = config.hashing_algorithm;
 
@@ -295,17 +302,15 @@ impl KeyManager {
 		)
 		.await?;
 
-		// attempt to insert into the OS keyring
-		// can ignore false here as we want to silently error
-		if let Ok(keyring) = KeyringInterface::new() {
-			let identifier = Identifier {
-				application: APP_IDENTIFIER,
-				library_uuid: &library_uuid.to_string(),
-				usage: SECRET_KEY_IDENTIFIER,
-			};
-
-			keyring.insert(identifier, secret_key.into()).ok();
-		}
+		// attempt to insert into the keyring
+		// can ignore the error here as we want to silently error
+		self.keyring_insert(
+			library_uuid,
+			SECRET_KEY_IDENTIFIER.to_string(),
+			secret_key.into(),
+		)
+		.await
+		.ok();
 
 		let verification_key = StoredKey {
 			uuid: Uuid::new_v4(),
@@ -388,8 +393,6 @@ impl KeyManager {
 		let secret_key = SecretKey::generate();
 		let content_salt = Salt::generate();
 
-		dbg!(SecretKeyString::from(secret_key.clone()).expose());
-
 		let hashed_password = hashing_algorithm.hash(
 			master_password.into(),
 			content_salt,
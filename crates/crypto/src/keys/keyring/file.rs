@@ -0,0 +1,74 @@
+//! This is Spacedrive's file-based keyring, which is used as a fallback when the OS keyring isn't available.
+//!
+//! This is mostly for headless Linux machines, where there's no `DBus` session or Secret Service implementor running.
+//!
+//! Each item is stored in its own file within the keyring directory, and on Unix systems they're only accessible by the current user.
+
+use std::{
+	fs::{self, OpenOptions},
+	io::{ErrorKind, Write},
+	path::PathBuf,
+};
+
+use crate::{
+	keys::keyring::{Identifier, Keyring},
+	types::SecretKeyString,
+	Error, Protected, Result,
+};
+
+pub struct FileKeyring {
+	pub path: PathBuf,
+}
+
+impl FileKeyring {
+	pub fn new(path: PathBuf) -> Result<Self> {
+		fs::create_dir_all(&path)?;
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			fs::set_permissions(&path, fs::Permissions::from_mode(0o700))?;
+		}
+
+		Ok(Self { path })
+	}
+
+	fn item_path(&self, identifier: Identifier) -> PathBuf {
+		self.path.join(identifier.to_file_name())
+	}
+}
+
+impl Keyring for FileKeyring {
+	fn insert(&self, identifier: Identifier, value: SecretKeyString) -> Result<()> {
+		let mut options = OpenOptions::new();
+		options.write(true).create(true).truncate(true);
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::OpenOptionsExt;
+			options.mode(0o600);
+		}
+
+		options
+			.open(self.item_path(identifier))?
+			.write_all(value.expose().as_bytes())?;
+
+		Ok(())
+	}
+
+	fn retrieve(&self, identifier: Identifier) -> Result<Protected<Vec<u8>>> {
+		match fs::read(self.item_path(identifier)) {
+			Ok(value) => Ok(Protected::new(value)),
+			Err(e) if e.kind() == ErrorKind::NotFound => Err(Error::KeyringError),
+			Err(e) => Err(e.into()),
+		}
+	}
+
+	fn delete(&self, identifier: Identifier) -> Result<()> {
+		match fs::remove_file(self.item_path(identifier)) {
+			Ok(()) => Ok(()),
+			Err(e) if e.kind() == ErrorKind::NotFound => Err(Error::KeyringError),
+			Err(e) => Err(e.into()),
+		}
+	}
+}
@@ -16,9 +16,13 @@ pub struct LinuxKeyring<'a> {
 
 impl<'a> LinuxKeyring<'a> {
 	pub fn new() -> Result<Self> {
-		Ok(Self {
-			service: SecretService::new(EncryptionType::Dh)?,
-		})
+		let service = SecretService::new(EncryptionType::Dh)?;
+
+		// A `DBus` session may be running without anything implementing the Secret Service API,
+		// so we make sure there's a collection available before committing to this keyring
+		service.get_default_collection()?;
+
+		Ok(Self { service })
 	}
 
 	fn get_collection(&self) -> Result<Collection> {
@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use crate::{types::SecretKeyString, Protected, Result};
 
+pub mod file;
+
 #[cfg(target_os = "linux")]
 pub mod linux;
 
@@ -15,6 +19,11 @@ pub struct Identifier<'a> {
 }
 
 impl<'a> Identifier<'a> {
+	#[must_use]
+	pub fn to_file_name(self) -> String {
+		format!("{}-{}", self.library_uuid, self.usage.replace(' ', "-")).to_lowercase()
+	}
+
 	#[cfg(target_os = "linux")]
 	#[must_use]
 	pub fn to_hashmap(self) -> std::collections::HashMap<&'a str, &'a str> {
@@ -66,6 +75,15 @@ impl KeyringInterface {
 		Ok(Self { keyring })
 	}
 
+	/// This uses the OS keyring if it's available, and otherwise falls back to a [`file::FileKeyring`] stored at `path`.
+	pub fn new_with_file_fallback(path: PathBuf) -> Result<Self> {
+		Self::new().or_else(|_| {
+			Ok(Self {
+				keyring: Box::new(self::file::FileKeyring::new(path)?),
+			})
+		})
+	}
+
 	pub fn insert(&self, identifier: Identifier, value: SecretKeyString) -> Result<()> {
 		self.keyring.insert(identifier, value)
 	}
@@ -0,0 +1,218 @@
+use crate::{
+	error::{Error, FfmpegError},
+	utils::from_path,
+};
+
+use ffmpeg_sys_next::{
+	av_dict_get, av_display_rotation_get, av_stream_get_side_data, avcodec_get_name,
+	avformat_close_input, avformat_find_stream_info, avformat_open_input, AVDictionary,
+	AVFormatContext, AVMediaType, AVPacketSideDataType, AVRational, AVStream, AV_TIME_BASE,
+};
+use std::{
+	ffi::{c_char, CStr, CString},
+	path::Path,
+	time::Duration,
+};
+
+/// The kind of data carried by a stream inside a media container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+	Video,
+	Audio,
+	Subtitle,
+	Other,
+}
+
+/// Information about a single stream inside a media container
+#[derive(Debug, Clone)]
+pub struct StreamProbe {
+	pub index: u32,
+	pub kind: StreamKind,
+	pub codec: Option<String>,
+	pub width: Option<u32>,
+	pub height: Option<u32>,
+	pub frame_rate: Option<f64>,
+	pub bitrate: Option<i64>,
+	/// Counterclockwise rotation in degrees, taken from the display matrix side data
+	pub rotation: Option<i32>,
+	pub sample_rate: Option<u32>,
+	pub channels: Option<u32>,
+}
+
+/// Information about a media container and all of its streams, obtained without decoding any frame
+#[derive(Debug, Clone)]
+pub struct MediaProbe {
+	pub duration: Option<Duration>,
+	pub container: Option<String>,
+	pub bitrate: Option<i64>,
+	/// Raw value of the `creation_time` tag, usually an ISO 8601 timestamp
+	pub creation_time: Option<String>,
+	pub streams: Vec<StreamProbe>,
+}
+
+impl MediaProbe {
+	/// The first video stream of the container, which is the one we generate thumbnails from
+	#[must_use]
+	pub fn video_stream(&self) -> Option<&StreamProbe> {
+		self.streams
+			.iter()
+			.find(|stream| stream.kind == StreamKind::Video)
+	}
+
+	/// The first audio stream of the container
+	#[must_use]
+	pub fn audio_stream(&self) -> Option<&StreamProbe> {
+		self.streams
+			.iter()
+			.find(|stream| stream.kind == StreamKind::Audio)
+	}
+}
+
+/// Owns an opened `AVFormatContext`, closing it on drop
+struct FormatContext(*mut AVFormatContext);
+
+impl Drop for FormatContext {
+	fn drop(&mut self) {
+		if !self.0.is_null() {
+			unsafe { avformat_close_input(&mut self.0) };
+			self.0 = std::ptr::null_mut();
+		}
+	}
+}
+
+/// Opens the media container at `path` and reads its stream information.
+///
+/// This is a blocking call, as `FFmpeg` reads the beginning of the file to find the streams.
+pub fn probe(path: impl AsRef<Path>) -> Result<MediaProbe, Error> {
+	let path = path.as_ref();
+	let path_cstring = from_path(path)?;
+
+	let mut format_context = FormatContext(std::ptr::null_mut());
+
+	unsafe {
+		match avformat_open_input(
+			&mut format_context.0,
+			path_cstring.as_ptr(),
+			std::ptr::null_mut(),
+			std::ptr::null_mut(),
+		) {
+			0 => {}
+			e => {
+				return Err(Error::FfmpegWithReason(
+					FfmpegError::from(e),
+					"Failed to open input".to_string(),
+				))
+			}
+		}
+
+		let ret = avformat_find_stream_info(format_context.0, std::ptr::null_mut());
+		if ret < 0 {
+			return Err(Error::FfmpegWithReason(
+				FfmpegError::from(ret),
+				"Failed to get stream info".to_string(),
+			));
+		}
+	}
+
+	let ctx = unsafe { &*format_context.0 };
+
+	// Non positive durations and bitrates are FFmpeg's way of saying "unknown"
+	#[allow(clippy::cast_precision_loss)]
+	let duration = (ctx.duration > 0)
+		.then(|| Duration::from_secs_f64(ctx.duration as f64 / f64::from(AV_TIME_BASE)));
+
+	let container = (!ctx.iformat.is_null())
+		.then(|| unsafe { c_str_to_string((*ctx.iformat).name) })
+		.flatten();
+
+	let streams = (0..ctx.nb_streams)
+		.map(|idx| unsafe { probe_stream(idx, *ctx.streams.offset(idx as isize)) })
+		.collect();
+
+	Ok(MediaProbe {
+		duration,
+		container,
+		bitrate: (ctx.bit_rate > 0).then_some(ctx.bit_rate),
+		creation_time: unsafe { get_dict_value(ctx.metadata, "creation_time") },
+		streams,
+	})
+}
+
+unsafe fn probe_stream(index: u32, stream: *mut AVStream) -> StreamProbe {
+	let codec_params = &*(*stream).codecpar;
+
+	let kind = match codec_params.codec_type {
+		AVMediaType::AVMEDIA_TYPE_VIDEO => StreamKind::Video,
+		AVMediaType::AVMEDIA_TYPE_AUDIO => StreamKind::Audio,
+		AVMediaType::AVMEDIA_TYPE_SUBTITLE => StreamKind::Subtitle,
+		_ => StreamKind::Other,
+	};
+
+	let is_video = kind == StreamKind::Video;
+	let is_audio = kind == StreamKind::Audio;
+
+	#[allow(clippy::cast_sign_loss)]
+	StreamProbe {
+		index,
+		kind,
+		codec: c_str_to_string(avcodec_get_name(codec_params.codec_id)),
+		width: (is_video && codec_params.width > 0).then_some(codec_params.width as u32),
+		height: (is_video && codec_params.height > 0).then_some(codec_params.height as u32),
+		frame_rate: is_video
+			.then(|| {
+				rational_to_f64((*stream).avg_frame_rate)
+					.or_else(|| rational_to_f64((*stream).r_frame_rate))
+			})
+			.flatten(),
+		bitrate: (codec_params.bit_rate > 0).then_some(codec_params.bit_rate),
+		rotation: is_video.then(|| get_rotation(stream)).flatten(),
+		sample_rate: (is_audio && codec_params.sample_rate > 0)
+			.then_some(codec_params.sample_rate as u32),
+		channels: (is_audio && codec_params.ch_layout.nb_channels > 0)
+			.then_some(codec_params.ch_layout.nb_channels as u32),
+	}
+}
+
+#[allow(clippy::cast_ptr_alignment, clippy::cast_possible_truncation)]
+unsafe fn get_rotation(stream: *mut AVStream) -> Option<i32> {
+	let matrix = av_stream_get_side_data(
+		stream,
+		AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+		std::ptr::null_mut(),
+	) as *const i32;
+
+	if matrix.is_null() {
+		return None;
+	}
+
+	let angle = av_display_rotation_get(matrix);
+
+	(!angle.is_nan()).then(|| angle.round() as i32)
+}
+
+unsafe fn get_dict_value(dict: *mut AVDictionary, key: &str) -> Option<String> {
+	if dict.is_null() {
+		return None;
+	}
+
+	let key = CString::new(key).ok()?;
+	let entry = av_dict_get(dict, key.as_ptr(), std::ptr::null(), 0);
+
+	if entry.is_null() {
+		None
+	} else {
+		c_str_to_string((*entry).value)
+	}
+}
+
+// WARNING: NEVER use CString with foreign raw pointer (causes double-free)
+unsafe fn c_str_to_string(ptr: *const c_char) -> Option<String> {
+	(!ptr.is_null())
+		.then(|| CStr::from_ptr(ptr).to_str().ok().map(ToString::to_string))
+		.flatten()
+}
+
+fn rational_to_f64(rational: AVRational) -> Option<f64> {
+	(rational.num > 0 && rational.den > 0)
+		.then(|| f64::from(rational.num) / f64::from(rational.den))
+}
@@ -99,7 +99,7 @@ extension_category_enum! {
 
 // audio extensions
 extension_category_enum! {
-	AudioExtension _ALL_AUDIO_EXTENSIONS {
+	AudioExtension ALL_AUDIO_EXTENSIONS {
 		Mp3 = [0x49, 0x44, 0x33],
 		Mp2 = [0xFF, 0xFB] | [0xFF, 0xFD],
 		M4a = [0x66, 0x74, 0x79, 0x70, 0x4D, 0x34, 0x41, 0x20] + 4,
@@ -136,6 +136,7 @@ extension_category_enum! {
 		#[strum(serialize = "7z")]
 		_7z = [0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C],
 		Xz = [0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00],
+		Zst = [0x28, 0xB5, 0x2F, 0xFD],
 	}
 }
 
@@ -178,7 +179,7 @@ extension_category_enum! {
 
 // text file extensions
 extension_category_enum! {
-	TextExtension _ALL_TEXT_EXTENSIONS {
+	TextExtension ALL_TEXT_EXTENSIONS {
 		Txt,
 		Rtf,
 		Md,
@@ -187,7 +188,7 @@ extension_category_enum! {
 }
 // config file extensions
 extension_category_enum! {
-	ConfigExtension _ALL_CONFIG_EXTENSIONS {
+	ConfigExtension ALL_CONFIG_EXTENSIONS {
 		Ini,
 		Json,
 		Yaml,
@@ -247,7 +248,7 @@ extension_category_enum! {
 
 // code extensions
 extension_category_enum! {
-	CodeExtension _ALL_CODE_EXTENSIONS {
+	CodeExtension ALL_CODE_EXTENSIONS {
 		// AppleScript
 		Scpt,
 		Scptd,
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 // Note: The order of this enum should never change, and always be kept in sync with `packages/client/src/utils/objectKind.ts`
 #[repr(i32)]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash, strum::EnumString)]
 pub enum ObjectKind {
 	/// A file that can not be identified by the indexer
 	Unknown = 0,
@@ -29,6 +29,7 @@ mod handler;
 #[cfg(feature = "heif")]
 mod heif;
 mod pdf;
+mod perceptual_hash;
 mod svg;
 
 use consts::MAXIMUM_FILE_SIZE;
@@ -38,6 +39,8 @@ pub use consts::{all_compatible_extensions, ConvertableExtension};
 pub use error::{Error, Result};
 pub use handler::{convert_image, format_image};
 pub use image::DynamicImage;
+pub use pdf::pdf_text;
+pub use perceptual_hash::{hamming_distance, perceptual_hash};
 
 pub trait ImageHandler {
 	#[inline]
@@ -77,14 +77,20 @@ static LANDSCAPE_CONFIG: Lazy<PdfRenderConfig> = Lazy::new(|| {
 	thumbnail_config(PdfRenderConfig::new().set_target_width(PDF_LANDSCAPE_RENDER_WIDTH))
 });
 
+fn pdfium() -> Result<Pdfium> {
+	Ok(Pdfium::new(
+		Pdfium::bind_to_library(PDFIUM_LIB.as_str()).or_else(|err| {
+			error!("{err:#?}");
+			Pdfium::bind_to_system_library()
+		})?,
+	))
+}
+
 pub struct PdfHandler {}
 
 impl ImageHandler for PdfHandler {
 	fn handle_image(&self, path: &Path) -> Result<DynamicImage> {
-		let pdfium = Pdfium::new(Pdfium::bind_to_library(PDFIUM_LIB.as_str()).or_else(|err| {
-			error!("{err:#?}");
-			Pdfium::bind_to_system_library()
-		})?);
+		let pdfium = pdfium()?;
 
 		let pdf = pdfium.load_pdf_from_file(path, None)?;
 		let first_page = pdf.pages().first()?;
@@ -100,3 +106,21 @@ impl ImageHandler for PdfHandler {
 		Ok(image)
 	}
 }
+
+/// Extracts the text of the PDF page by page, stopping once it has at least `max_len` bytes
+pub fn pdf_text(path: &Path, max_len: usize) -> Result<String> {
+	let pdfium = pdfium()?;
+	let pdf = pdfium.load_pdf_from_file(path, None)?;
+
+	let mut text = String::new();
+	for page in pdf.pages().iter() {
+		if text.len() >= max_len {
+			break;
+		}
+
+		text.push_str(&page.text()?.all());
+		text.push('\n');
+	}
+
+	Ok(text)
+}
@@ -0,0 +1,34 @@
+use image::DynamicImage;
+
+/// The image is shrunk to one more column than the bits we get from each row,
+/// as every bit compares two neighbouring pixels
+const HASH_WIDTH: u32 = 9;
+const HASH_HEIGHT: u32 = 8;
+
+/// Computes the difference hash (dHash) of an image, which stays the same or changes only a few bits
+/// when the image is resized, re-encoded or slightly edited.
+///
+/// Similar images are found by comparing their hashes with [`hamming_distance`].
+#[must_use]
+pub fn perceptual_hash(img: &DynamicImage) -> u64 {
+	let pixels = img.thumbnail_exact(HASH_WIDTH, HASH_HEIGHT).into_luma8();
+
+	let mut hash = 0;
+	for y in 0..HASH_HEIGHT {
+		for x in 0..HASH_WIDTH - 1 {
+			hash <<= 1;
+			if pixels.get_pixel(x, y)[0] < pixels.get_pixel(x + 1, y)[0] {
+				hash |= 1;
+			}
+		}
+	}
+
+	hash
+}
+
+/// The number of different bits between two perceptual hashes, where `0` means the images look
+/// the same and anything above `10` is usually a different image
+#[must_use]
+pub const fn hamming_distance(a: u64, b: u64) -> u32 {
+	(a ^ b).count_ones()
+}
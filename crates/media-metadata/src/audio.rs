@@ -1,19 +1,300 @@
-use std::path::Path;
+use std::{fs::File, io, path::Path};
 
-use crate::Result;
+use symphonia::core::{
+	audio::SampleBuffer,
+	codecs::DecoderOptions,
+	errors::Error as SymphoniaError,
+	formats::FormatOptions,
+	io::{MediaSourceStream, MediaSourceStreamOptions},
+	meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Value, Visual},
+	probe::{Hint, ProbeResult},
+};
+
+use crate::{Error, Result};
 
 #[derive(
 	Default, Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize, specta::Type,
 )]
 pub struct AudioMetadata {
-	duration: Option<i32>, // can't use `Duration` due to bigint
-	audio_codec: Option<String>,
+	pub duration: Option<i32>, // in seconds, can't use `Duration` due to bigint
+	pub audio_codec: Option<String>,
+	pub bitrate: Option<i32>, // average bits per second
+	pub sample_rate: Option<i32>,
+	pub channels: Option<i32>,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub album: Option<String>,
+	pub album_artist: Option<String>,
+	pub genre: Option<String>,
+	pub track_number: Option<i32>,
+	pub year: Option<i32>,
 }
 
 impl AudioMetadata {
-	#[allow(clippy::missing_errors_doc)]
-	#[allow(clippy::missing_panics_doc)]
-	pub fn from_path(_path: impl AsRef<Path>) -> Result<Self> {
-		todo!()
+	/// Probes the audio container at `path`, reading the stream parameters of its default track
+	/// and any ID3, Vorbis comment or MP4 tags found on it.
+	pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+
+		let (mut probed, file_size) = probe(path)?;
+
+		let codec_params = probed
+			.format
+			.default_track()
+			.map(|track| track.codec_params.clone())
+			.ok_or_else(|| Error::NoAudioTrack(path.to_path_buf()))?;
+
+		let duration = codec_params
+			.time_base
+			.zip(codec_params.n_frames)
+			.map(|(time_base, n_frames)| time_base.calc_time(n_frames).seconds);
+
+		let mut data = Self {
+			duration: duration.and_then(|secs| i32::try_from(secs).ok()),
+			audio_codec: symphonia::default::get_codecs()
+				.get_codec(codec_params.codec)
+				.map(|descriptor| descriptor.short_name.to_string()),
+			// Containers rarely store the bitrate, so we take the average over the whole file
+			bitrate: duration
+				.filter(|secs| *secs > 0)
+				.and_then(|secs| i32::try_from(file_size * 8 / secs).ok()),
+			sample_rate: codec_params
+				.sample_rate
+				.and_then(|rate| i32::try_from(rate).ok()),
+			channels: codec_params
+				.channels
+				.and_then(|channels| i32::try_from(channels.count()).ok()),
+			..Default::default()
+		};
+
+		// Tags stored inside the container take precedence over the ones found while probing,
+		// like an ID3v2 block in front of an MP3 stream
+		if let Some(revision) = probed.format.metadata().current() {
+			data.fill_tags(revision);
+		}
+
+		if let Some(metadata) = probed.metadata.get() {
+			if let Some(revision) = metadata.current() {
+				data.fill_tags(revision);
+			}
+		}
+
+		Ok(data)
+	}
+
+	/// Fills every tag that is still missing with the ones from this metadata revision
+	fn fill_tags(&mut self, revision: &MetadataRevision) {
+		for tag in revision.tags() {
+			let Some(key) = tag.std_key else {
+				continue;
+			};
+
+			let field = match key {
+				StandardTagKey::TrackTitle => &mut self.title,
+				StandardTagKey::Artist => &mut self.artist,
+				StandardTagKey::Album => &mut self.album,
+				StandardTagKey::AlbumArtist => &mut self.album_artist,
+				StandardTagKey::Genre => &mut self.genre,
+				StandardTagKey::TrackNumber => {
+					if self.track_number.is_none() {
+						self.track_number = parse_leading_number(&tag.value);
+					}
+					continue;
+				}
+				StandardTagKey::Date | StandardTagKey::OriginalDate => {
+					if self.year.is_none() {
+						self.year = parse_leading_number(&tag.value);
+					}
+					continue;
+				}
+				_ => continue,
+			};
+
+			if field.is_none() {
+				let value = tag.value.to_string().trim().to_string();
+				if !value.is_empty() {
+					*field = Some(value);
+				}
+			}
+		}
+	}
+}
+
+/// Returns the embedded cover art of the audio file at `path`, like ID3 `APIC` frames, FLAC
+/// `PICTURE` blocks or MP4 `covr` atoms, preferring the front cover when there are many pictures.
+pub fn cover_art_from_path(path: impl AsRef<Path>) -> Result<Option<Vec<u8>>> {
+	let path = path.as_ref();
+
+	let (mut probed, _) = probe(path)?;
+
+	let mut visuals = probed
+		.format
+		.metadata()
+		.current()
+		.map(|revision| revision.visuals().to_vec())
+		.unwrap_or_default();
+
+	if let Some(metadata) = probed.metadata.get() {
+		if let Some(revision) = metadata.current() {
+			visuals.extend_from_slice(revision.visuals());
+		}
+	}
+
+	Ok(visuals
+		.iter()
+		.find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+		.or_else(|| visuals.first())
+		.map(|Visual { data, .. }| data.to_vec()))
+}
+
+/// Decodes the default track of the audio file at `path`, returning the peak amplitude of each one
+/// of `bars` evenly sized slices of it, normalized so the loudest one is `1.0`
+pub fn waveform_from_path(path: impl AsRef<Path>, bars: usize) -> Result<Vec<f32>> {
+	let path = path.as_ref();
+
+	let (mut probed, _) = probe(path)?;
+
+	let track = probed
+		.format
+		.default_track()
+		.ok_or_else(|| Error::NoAudioTrack(path.to_path_buf()))?;
+	let track_id = track.id;
+
+	let mut decoder = symphonia::default::get_codecs()
+		.make(&track.codec_params, &DecoderOptions::default())
+		.map_err(|e| Error::Audio(e, path.into()))?;
+
+	let mut sample_buffer = None;
+	let mut peaks = vec![];
+
+	loop {
+		let packet = match probed.format.next_packet() {
+			Ok(packet) => packet,
+			// The end of the stream is reported as an unexpected EOF
+			Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+			Err(e) => return Err(Error::Audio(e, path.into())),
+		};
+
+		if packet.track_id() != track_id {
+			continue;
+		}
+
+		let decoded = match decoder.decode(&packet) {
+			Ok(decoded) => decoded,
+			// Corrupted packets are just skipped, like players do
+			Err(SymphoniaError::DecodeError(_)) => continue,
+			Err(e) => return Err(Error::Audio(e, path.into())),
+		};
+
+		let sample_buffer = sample_buffer.get_or_insert_with(|| {
+			SampleBuffer::<f32>::new(
+				u64::try_from(decoded.capacity()).unwrap_or(u64::MAX),
+				*decoded.spec(),
+			)
+		});
+		sample_buffer.copy_interleaved_ref(decoded);
+
+		peaks.push(
+			sample_buffer
+				.samples()
+				.iter()
+				.fold(0_f32, |peak, sample| peak.max(sample.abs())),
+		);
+	}
+
+	Ok(downsample_peaks(&peaks, bars))
+}
+
+fn probe(path: &Path) -> Result<(ProbeResult, u64)> {
+	let file = File::open(path).map_err(|e| Error::Io(e, path.into()))?;
+	let file_size = file
+		.metadata()
+		.map_err(|e| Error::Io(e, path.into()))?
+		.len();
+
+	let mut hint = Hint::new();
+	if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+		hint.with_extension(extension);
+	}
+
+	let probed = symphonia::default::get_probe()
+		.format(
+			&hint,
+			MediaSourceStream::new(Box::new(file), MediaSourceStreamOptions::default()),
+			&FormatOptions::default(),
+			&MetadataOptions::default(),
+		)
+		.map_err(|e| Error::Audio(e, path.into()))?;
+
+	Ok((probed, file_size))
+}
+
+/// Merges the peaks of every decoded packet into at most `bars` peaks
+fn downsample_peaks(peaks: &[f32], bars: usize) -> Vec<f32> {
+	if peaks.is_empty() || bars == 0 {
+		return vec![];
+	}
+
+	let bars = peaks
+		.chunks((peaks.len() + bars - 1) / bars)
+		.map(|chunk| chunk.iter().copied().fold(0_f32, f32::max))
+		.collect::<Vec<_>>();
+
+	let loudest = bars.iter().copied().fold(0_f32, f32::max);
+	if loudest > 0_f32 {
+		bars.into_iter().map(|bar| bar / loudest).collect()
+	} else {
+		bars
+	}
+}
+
+/// Track numbers are usually stored as `3/12` and dates as `2023-09-26`, so we only
+/// care about the leading digits
+fn parse_leading_number(value: &Value) -> Option<i32> {
+	match value {
+		Value::UnsignedInt(n) => i32::try_from(*n).ok(),
+		Value::SignedInt(n) => i32::try_from(*n).ok(),
+		value => {
+			let value = value.to_string();
+			let digits = value
+				.trim()
+				.split(|c: char| !c.is_ascii_digit())
+				.next()
+				.unwrap_or_default();
+
+			digits.parse().ok()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn leading_number() {
+		assert_eq!(
+			parse_leading_number(&Value::String("3/12".to_string())),
+			Some(3)
+		);
+		assert_eq!(
+			parse_leading_number(&Value::String("2023-09-26".to_string())),
+			Some(2023)
+		);
+		assert_eq!(parse_leading_number(&Value::UnsignedInt(7)), Some(7));
+		assert_eq!(
+			parse_leading_number(&Value::String("unknown".to_string())),
+			None
+		);
+	}
+
+	#[test]
+	fn downsampled_peaks() {
+		assert_eq!(
+			downsample_peaks(&[0.1, 0.2, 0.4, 0.1, 0.2], 2),
+			vec![1.0, 0.5]
+		);
+		assert_eq!(downsample_peaks(&[0.0, 0.0], 4), vec![0.0, 0.0]);
+		assert!(downsample_peaks(&[], 4).is_empty());
 	}
 }
@@ -23,6 +23,14 @@ pub enum Error {
 	NoExifDataOnPath(PathBuf),
 	#[error("the slice provided contains no exif data")]
 	NoExifDataOnSlice,
+	#[error("error while probing audio file at {}: {0}", .1.display())]
+	Audio(symphonia::core::errors::Error, Box<Path>),
+	#[error("the file provided at ({0}) contains no audio track")]
+	NoAudioTrack(PathBuf),
+	#[error("error while probing video file at {}: {0}", .1.display())]
+	Video(String, Box<Path>),
+	#[error("the file provided at ({0}) contains no video stream")]
+	NoVideoStream(PathBuf),
 
 	#[error("serde error {0}")]
 	Serde(#[from] serde_json::Error),
@@ -38,6 +38,9 @@ pub const LAT_MAX_POS: f64 = 90_f64;
 /// The negative value of this is known as the anti-meridian, and when combined they make a 360 degree circle around the Earth.
 pub const LONG_MAX_POS: f64 = 180_f64;
 
+/// The Earth's mean radius in metres, used to measure distances between coordinates.
+pub const EARTH_RADIUS: f64 = 6_371_000_f64;
+
 /// 125km. This is the Kármán line + a 25km additional padding just to be safe.
 pub const ALT_MAX_HEIGHT: i32 = 125_000_i32;
 
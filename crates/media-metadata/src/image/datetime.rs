@@ -50,6 +50,12 @@ impl MediaDate {
 			.map(Clone::clone)
 	}
 
+	/// Parses timestamps in the format stored by media containers, like `2023-09-26T22:04:37.000000Z`
+	#[must_use]
+	pub fn from_rfc3339(value: &str) -> Option<Self> {
+		DateTime::parse_from_rfc3339(value).ok().map(Self::Utc)
+	}
+
 	/// Returns the amount of non-leap secods since the Unix Epoch (1970-01-01T00:00:00+00:00)
 	///
 	/// This is for search ordering/sorting
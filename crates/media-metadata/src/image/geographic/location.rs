@@ -1,8 +1,8 @@
 use crate::{
 	image::{
 		consts::{
-			ALT_MAX_HEIGHT, ALT_MIN_HEIGHT, DECIMAL_SF, DIRECTION_MAX, DMS_DIVISION, LAT_MAX_POS,
-			LONG_MAX_POS,
+			ALT_MAX_HEIGHT, ALT_MIN_HEIGHT, DECIMAL_SF, DIRECTION_MAX, DMS_DIVISION, EARTH_RADIUS,
+			LAT_MAX_POS, LONG_MAX_POS,
 		},
 		ExifReader, PlusCode,
 	},
@@ -160,6 +160,43 @@ impl MediaLocation {
 		self.pluscode.clone()
 	}
 
+	#[inline]
+	#[must_use]
+	pub const fn latitude(&self) -> f64 {
+		self.latitude
+	}
+
+	#[inline]
+	#[must_use]
+	pub const fn longitude(&self) -> f64 {
+		self.longitude
+	}
+
+	/// The great-circle distance to another location in metres, using the haversine formula.
+	///
+	/// The altitude of both locations is ignored.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use sd_media_metadata::image::MediaLocation;
+	///
+	/// let lisbon = MediaLocation::new(38.7223, -9.1393, None, None);
+	/// let porto = MediaLocation::new(41.1579, -8.6291, None, None);
+	/// assert!((lisbon.distance_to(&porto) - 274_295_f64).abs() < 1_f64);
+	/// ```
+	#[must_use]
+	pub fn distance_to(&self, other: &Self) -> f64 {
+		let (lat, other_lat) = (self.latitude.to_radians(), other.latitude.to_radians());
+		let half_delta_lat = (other_lat - lat) / 2_f64;
+		let half_delta_long = (other.longitude - self.longitude).to_radians() / 2_f64;
+
+		let haversine = (lat.cos() * other_lat.cos())
+			.mul_add(half_delta_long.sin().powi(2), half_delta_lat.sin().powi(2));
+
+		2_f64 * EARTH_RADIUS * haversine.sqrt().asin()
+	}
+
 	/// This also re-generates the Plus Code for your coordinates
 	///
 	/// # Examples
@@ -1,20 +1,93 @@
-use std::path::Path;
+use crate::image::{MediaDate, Resolution};
 
-use crate::Result;
-
-#[derive(
-	Default, Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize, specta::Type,
-)]
+#[derive(Default, Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize, specta::Type)]
 pub struct VideoMetadata {
-	duration: Option<i32>, // bigint
-	video_codec: Option<String>,
-	audio_codec: Option<String>,
+	pub duration: Option<i32>, // in seconds, can't use `Duration` due to bigint
+	pub container: Option<String>,
+	pub video_codec: Option<String>,
+	pub audio_codec: Option<String>,
+	pub resolution: Option<Resolution>,
+	pub frame_rate: Option<f64>,
+	pub bitrate: Option<i32>, // bits per second
+	pub rotation: Option<i32>, // counterclockwise, in degrees
+	pub date_created: Option<MediaDate>,
+	pub streams: Vec<VideoStream>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize, specta::Type)]
+pub struct VideoStream {
+	pub kind: VideoStreamKind,
+	pub codec: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize, specta::Type)]
+pub enum VideoStreamKind {
+	Video,
+	Audio,
+	Subtitle,
+	Other,
 }
 
 impl VideoMetadata {
-	#[allow(clippy::missing_errors_doc)]
-	#[allow(clippy::missing_panics_doc)]
-	pub fn from_path(_path: impl AsRef<Path>) -> Result<Self> {
-		todo!()
+	#[cfg(feature = "ffmpeg")]
+	pub fn from_path(path: impl AsRef<std::path::Path>) -> crate::Result<Self> {
+		let path = path.as_ref();
+
+		let probe =
+			sd_ffmpeg::probe(path).map_err(|e| crate::Error::Video(e.to_string(), path.into()))?;
+
+		Self::from_probe(probe).ok_or_else(|| crate::Error::NoVideoStream(path.to_path_buf()))
+	}
+
+	/// Builds the metadata from a container probe, returning `None` if it has no video stream
+	#[cfg(feature = "ffmpeg")]
+	#[must_use]
+	pub fn from_probe(probe: sd_ffmpeg::MediaProbe) -> Option<Self> {
+		use sd_ffmpeg::StreamKind;
+
+		let video_stream = probe.video_stream()?;
+
+		Some(Self {
+			duration: probe
+				.duration
+				.and_then(|duration| i32::try_from(duration.as_secs()).ok()),
+			container: probe.container.clone(),
+			video_codec: video_stream.codec.clone(),
+			audio_codec: probe
+				.audio_stream()
+				.and_then(|audio_stream| audio_stream.codec.clone()),
+			resolution: video_stream
+				.width
+				.zip(video_stream.height)
+				.and_then(|(width, height)| {
+					Some(Resolution::new(
+						i32::try_from(width).ok()?,
+						i32::try_from(height).ok()?,
+					))
+				}),
+			frame_rate: video_stream.frame_rate,
+			bitrate: probe
+				.bitrate
+				.or(video_stream.bitrate)
+				.and_then(|bitrate| i32::try_from(bitrate).ok()),
+			rotation: video_stream.rotation,
+			date_created: probe
+				.creation_time
+				.as_deref()
+				.and_then(MediaDate::from_rfc3339),
+			streams: probe
+				.streams
+				.iter()
+				.map(|stream| VideoStream {
+					kind: match stream.kind {
+						StreamKind::Video => VideoStreamKind::Video,
+						StreamKind::Audio => VideoStreamKind::Audio,
+						StreamKind::Subtitle => VideoStreamKind::Subtitle,
+						StreamKind::Other => VideoStreamKind::Other,
+					},
+					codec: stream.codec.clone(),
+				})
+				.collect(),
+		})
 	}
 }
@@ -5,20 +5,32 @@ use tokio::io::AsyncReadExt;
 /// TODO
 #[derive(Debug, PartialEq, Eq)]
 pub struct Block<'a> {
-	// TODO: File content, checksum, source location so it can be resent!
+	// TODO: Source location so it can be resent!
 	pub offset: u64,
 	pub size: u64,
+	/// BLAKE3 hash of `data`, checked by the receiver before the block is written anywhere
+	pub hash: blake3::Hash,
 	pub data: &'a [u8],
-	// TODO: Checksum?
 }
 
 impl<'a> Block<'a> {
+	#[must_use]
+	pub fn new(offset: u64, data: &'a [u8]) -> Self {
+		Self {
+			offset,
+			size: data.len() as u64,
+			hash: blake3::hash(data),
+			data,
+		}
+	}
+
 	#[must_use]
 	pub fn to_bytes(&self) -> Vec<u8> {
 		let mut buf = Vec::new();
 		buf.extend_from_slice(&self.offset.to_le_bytes());
 		debug_assert_eq!(self.data.len(), self.size as usize); // TODO: Should `self.size` be inferred instead?
 		buf.extend_from_slice(&self.size.to_le_bytes());
+		buf.extend_from_slice(self.hash.as_bytes());
 		buf.extend_from_slice(self.data);
 		buf
 	}
@@ -35,6 +47,10 @@ impl<'a> Block<'a> {
 		stream.read_exact(&mut size).await?;
 		let size = u64::from_le_bytes(size);
 
+		let mut hash = [0; blake3::OUT_LEN];
+		stream.read_exact(&mut hash).await?;
+		let hash = blake3::Hash::from(hash);
+
 		// TODO: Ensure `size` is `block_size` or smaller else buffer overflow
 
 		if size as usize > data_buf.len() {
@@ -46,9 +62,17 @@ impl<'a> Block<'a> {
 
 		stream.read_exact(&mut data_buf[..size as usize]).await?;
 
+		if blake3::hash(&data_buf[..size as usize]) != hash {
+			return Err(io::Error::new(
+				ErrorKind::InvalidData,
+				format!("checksum mismatch for block at offset {offset}"),
+			));
+		}
+
 		Ok(Self {
 			offset,
 			size,
+			hash,
 			data: &[], // TODO: This is super cringe. Data should be decoded here but lifetimes and extra allocations become a major concern.
 		})
 	}
@@ -64,11 +88,7 @@ mod tests {
 
 	#[tokio::test]
 	async fn test_block() {
-		let mut req = Block {
-			offset: 420,
-			size: 10, // Matches length of string on next line
-			data: b"Spacedrive".as_ref(),
-		};
+		let mut req = Block::new(420, b"Spacedrive".as_ref());
 		let bytes = req.to_bytes();
 		let mut data2 = vec![0; req.data.len()];
 		let req2 = Block::from_stream(&mut Cursor::new(bytes), &mut data2)
@@ -82,11 +102,7 @@ mod tests {
 	#[tokio::test]
 	#[should_panic] // TODO: This currently panics but long term it should have proper error handling
 	async fn test_block_data_buf_overflow() {
-		let mut req = Block {
-			offset: 420,
-			size: 10, // Matches length of string on next line
-			data: b"Spacedrive".as_ref(),
-		};
+		let mut req = Block::new(420, b"Spacedrive".as_ref());
 		let bytes = req.to_bytes();
 		let mut data2 = vec![0; 5]; // Length smaller than `req.data.len()`
 		let req2 = Block::from_stream(&mut Cursor::new(bytes), &mut data2)
@@ -96,4 +112,17 @@ mod tests {
 		assert_eq!(req, req2);
 		assert_eq!(data, data2);
 	}
+
+	#[tokio::test]
+	async fn test_block_checksum_mismatch() {
+		let mut bytes = Block::new(420, b"Spacedrive".as_ref()).to_bytes();
+		// Flip a bit in the data, which comes after the offset, size and hash
+		bytes[8 + 8 + blake3::OUT_LEN] ^= 1;
+
+		let mut data2 = vec![0; 10];
+		let err = Block::from_stream(&mut Cursor::new(bytes), &mut data2)
+			.await
+			.unwrap_err();
+		assert_eq!(err.kind(), ErrorKind::InvalidData);
+	}
 }
@@ -28,10 +28,12 @@ use crate::{
 
 mod block;
 mod block_size;
+mod partial;
 mod sb_request;
 
 pub use block::*;
 pub use block_size::*;
+pub use partial::*;
 pub use sb_request::*;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -70,6 +72,17 @@ impl<'a> Msg<'a> {
 	}
 }
 
+/// Progress of a [`Transfer`], reported after every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferProgress {
+	/// Index of the file being transferred within `SpaceblockRequests::requests`
+	pub file: usize,
+	/// Percentage of the file that has been transferred
+	pub file_percent: u8,
+	/// Percentage of the whole transfer
+	pub percent: u8,
+}
+
 /// TODO
 pub struct Transfer<'a, F> {
 	reqs: &'a SpaceblockRequests,
@@ -83,30 +96,84 @@ pub struct Transfer<'a, F> {
 
 impl<'a, F> Transfer<'a, F>
 where
-	F: Fn(u8) + 'a,
+	F: Fn(TransferProgress) + 'a,
 {
-	// TODO: Handle `req.range` correctly in this code
-
 	pub fn new(req: &'a SpaceblockRequests, on_progress: F, cancelled: &'a AtomicBool) -> Self {
 		Self {
 			reqs: req,
 			on_progress,
-			total_offset: 0,
-			total_bytes: req.requests.iter().map(|req| req.size).sum(),
+			// Anything before the requested range was received by a previous transfer
+			// and files with an empty range are skipped entirely.
+			total_offset: req.requests.iter().map(|req| req.bounds().start).sum(),
+			total_bytes: req.requests.iter().map(|req| req.bounds().end).sum(),
 			i: 0,
 			cancelled,
 		}
 	}
 
+	fn progress(&mut self, size: u64, file_offset: u64, file_size: u64) {
+		self.total_offset += size;
+
+		// SAFETY: Percent must be between 0 and 100
+		(self.on_progress)(TransferProgress {
+			file: self.i - 1,
+			file_percent: ((file_offset as f64 / file_size as f64) * 100.0) as u8,
+			percent: ((self.total_offset as f64 / self.total_bytes as f64) * 100.0) as u8,
+		});
+	}
+
 	// TODO: Should `new` take in the streams too cause this means we `Stream` `SpaceblockRequest` could get outta sync.
+	/// Send the next file.
+	///
+	/// `file` must already be positioned at the start of the requested range, as only the bytes of the
+	/// range are read, sent and hashed for the receiver to verify them.
 	pub async fn send(
+		&mut self,
+		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+		file: (impl AsyncBufRead + Unpin),
+	) -> Result<(), io::Error> {
+		self.send_inner(stream, file, false).await
+	}
+
+	/// Send the next file, which the receiver may have partially received before. See [`Self::receive_resumable`].
+	///
+	/// `file` must be positioned at its start. Everything before the requested range is only read to be hashed,
+	/// so the final hash covers the whole file and not just the part which is sent.
+	pub async fn send_resumable(
+		&mut self,
+		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+		file: (impl AsyncBufRead + Unpin),
+	) -> Result<(), io::Error> {
+		self.send_inner(stream, file, true).await
+	}
+
+	async fn send_inner(
 		&mut self,
 		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
 		mut file: (impl AsyncBufRead + Unpin),
+		hash_received: bool,
 	) -> Result<(), io::Error> {
+		let range = self
+			.reqs
+			.requests
+			.get(self.i)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no more files to send"))?
+			.bounds();
+		self.i += 1;
+
+		// Nothing to send, or the receiver didn't want this file
+		if range.is_empty() {
+			return Ok(());
+		}
+
 		// We manually implement what is basically a `BufReader` so we have more control
 		let mut buf = vec![0u8; self.reqs.block_size.size() as usize];
-		let mut offset: u64 = 0;
+		let mut hasher = blake3::Hasher::new();
+		let mut offset = range.start;
+
+		if hash_received {
+			hash_exact(&mut file, &mut hasher, &mut buf, range.start).await?;
+		}
 
 		loop {
 			if self.cancelled.load(Ordering::Relaxed) {
@@ -115,34 +182,25 @@ where
 				return Ok(());
 			}
 
-			let read = file.read(&mut buf[..]).await?;
-			self.total_offset += read as u64;
-			(self.on_progress)(
-				((self.total_offset as f64 / self.total_bytes as f64) * 100.0) as u8,
-			); // SAFETY: Percent must be between 0 and 100
-
+			let len = buf.len().min((range.end - offset) as usize);
+			let read = file.read(&mut buf[..len]).await?;
 			if read == 0 {
-				#[allow(clippy::panic)] // TODO: Remove panic
-						// The file may have been modified during sender on the sender and we don't account for that.
-						// TODO: Error handling + send error to remote
-				assert!(
-					(offset + read as u64) == self.reqs.requests[self.i].size,
-					"File sending has stopped but it doesn't match the expected length!"
-				);
-
-				return Ok(());
+				// The file may have been modified on the sender and we don't account for that.
+				// TODO: Send error to remote
+				return Err(io::Error::new(
+					io::ErrorKind::UnexpectedEof,
+					"file sending has stopped but it doesn't match the expected length",
+				));
 			}
+			hasher.update(&buf[..read]);
 
-			let block = Block {
-				offset,
-				size: read as u64,
-				data: &buf[..read],
-			};
+			let block = Block::new(offset, &buf[..read]);
 			debug!(
 				"Sending block at offset {} of size {}",
 				block.offset, block.size
 			);
 			offset += read as u64;
+			self.progress(read as u64, offset, range.end);
 
 			stream.write_all(&Msg::Block(block).to_bytes()).await?;
 			stream.flush().await?;
@@ -155,27 +213,75 @@ where
 					debug!("Receiver cancelled Spacedrop transfer!");
 					return Ok(());
 				}
-				// Transfer complete
-				2 => return Ok(()),
-				_ => todo!(),
+				// Transfer complete, so the receiver can verify what it got
+				2 => {
+					stream.write_all(hasher.finalize().as_bytes()).await?;
+					stream.flush().await?;
+					return Ok(());
+				}
+				_ => {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidData,
+						"invalid response from receiver",
+					))
+				}
 			}
 		}
 	}
 
 	// TODO: Timeout on receiving/sending
+	/// Receive the requested range of the next file. Resolves to `false` if the transfer was cancelled.
+	///
+	/// Errors of kind [`io::ErrorKind::InvalidData`] mean the received data can't be trusted.
 	pub async fn receive(
 		&mut self,
 		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
-		mut file: (impl AsyncWrite + Unpin),
+		file: (impl AsyncWrite + Unpin),
 		// TODO: Proper error type
-	) -> Result<(), io::Error> {
+	) -> Result<bool, io::Error> {
+		self.receive_inner(stream, None::<tokio::io::Empty>, file)
+			.await
+	}
+
+	/// Receive the rest of the next file, after the bytes `received` by a previous transfer.
+	///
+	/// The sender must use [`Self::send_resumable`]. `received` is hashed along with the new data, so the
+	/// file is only accepted if it matches the sender's file as a whole. Eg. if it was modified between the transfers.
+	pub async fn receive_resumable(
+		&mut self,
+		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+		received: (impl AsyncRead + Unpin),
+		file: (impl AsyncWrite + Unpin),
+	) -> Result<bool, io::Error> {
+		self.receive_inner(stream, Some(received), file).await
+	}
+
+	async fn receive_inner(
+		&mut self,
+		stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+		received: Option<impl AsyncRead + Unpin>,
+		mut file: (impl AsyncWrite + Unpin),
+	) -> Result<bool, io::Error> {
+		let range = self
+			.reqs
+			.requests
+			.get(self.i)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no more files to receive"))?
+			.bounds();
+		self.i += 1;
+
+		// Nothing to receive, or we didn't want this file
+		if range.is_empty() {
+			return Ok(true);
+		}
+
 		// We manually implement what is basically a `BufReader` so we have more control
 		let mut data_buf = vec![0u8; self.reqs.block_size.size() as usize];
-		let mut offset: u64 = 0;
+		let mut hasher = blake3::Hasher::new();
+		let mut offset = range.start;
 
-		if self.reqs.requests[self.i].size == 0 {
-			self.i += 1;
-			return Ok(());
+		if let Some(mut received) = received {
+			hash_exact(&mut received, &mut hasher, &mut data_buf, range.start).await?;
 		}
 
 		// TODO: Prevent loop being a DOS vector
@@ -183,29 +289,34 @@ where
 			if self.cancelled.load(Ordering::Relaxed) {
 				stream.write_u8(1).await?;
 				stream.flush().await?;
-				return Ok(());
+				return Ok(false);
 			}
 
 			// TODO: Timeout if nothing is being received
 			let msg = Msg::from_stream(stream, &mut data_buf).await?;
 			match msg {
 				Msg::Block(block) => {
-					self.total_offset += block.size;
-					(self.on_progress)(
-						((self.total_offset as f64 / self.total_bytes as f64) * 100.0) as u8,
-					); // SAFETY: Percent must be between 0 and 100
-
+					if block.offset != offset || offset + block.size > range.end {
+						return Err(io::Error::new(
+							io::ErrorKind::InvalidData,
+							format!(
+								"received block at offset {} of size {} but expected offset {offset}",
+								block.offset, block.size
+							),
+						));
+					}
 					debug!(
 						"Received block at offset {} of size {}",
 						block.offset, block.size
 					);
 					offset += block.size;
+					self.progress(block.size, offset, range.end);
 
-					file.write_all(&data_buf[..block.size as usize]).await?;
+					let data = &data_buf[..block.size as usize];
+					hasher.update(data);
+					file.write_all(data).await?;
 
-					// TODO: Should this be `read == 0`
-					// TODO: Out of range protection on indexed access
-					if offset == self.reqs.requests[self.i].size {
+					if offset == range.end {
 						break;
 					}
 
@@ -216,7 +327,7 @@ where
 				}
 				Msg::Cancelled => {
 					debug!("Sender cancelled Spacedrop transfer!");
-					return Ok(());
+					return Ok(false);
 				}
 			}
 		}
@@ -224,12 +335,45 @@ where
 		stream.write_u8(2).await?;
 		stream.flush().await?;
 		file.flush().await?;
-		self.i += 1;
 
-		Ok(())
+		let mut hash = [0; blake3::OUT_LEN];
+		stream.read_exact(&mut hash).await?;
+		if hasher.finalize() != blake3::Hash::from(hash) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"checksum mismatch for the received file",
+			));
+		}
+
+		Ok(true)
 	}
 }
 
+/// Hash the first `len` bytes of `reader`, which is the part of a file the receiver already has
+async fn hash_exact(
+	reader: &mut (impl AsyncRead + Unpin),
+	hasher: &mut blake3::Hasher,
+	buf: &mut [u8],
+	len: u64,
+) -> Result<(), io::Error> {
+	let mut remaining = len;
+	while remaining > 0 {
+		let n = remaining.min(buf.len() as u64) as usize;
+		let read = reader.read(&mut buf[..n]).await?;
+		if read == 0 {
+			return Err(io::Error::new(
+				io::ErrorKind::UnexpectedEof,
+				"file is shorter than the part which was received before",
+			));
+		}
+
+		hasher.update(&buf[..read]);
+		remaining -= read as u64;
+	}
+
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use std::{io::Cursor, mem};
@@ -447,12 +591,111 @@ mod tests {
 	}
 
 	#[tokio::test]
-	async fn test_msg() {
-		let block = Block {
-			offset: 0,
-			size: 10,
-			data: b"Spacedrive".as_ref(),
+	async fn test_spaceblock_partial_range() {
+		let (mut client, mut server) = tokio::io::duplex(64);
+
+		// This is sent out of band of Spaceblock
+		let block_size = 25u32;
+		let data = (0..100u8).collect::<Vec<_>>();
+		let block_size = BlockSize::dangerously_new(block_size);
+
+		// Like a video player seeking into the middle of the file
+		let req = SpaceblockRequests {
+			id: Uuid::new_v4(),
+			block_size,
+			requests: vec![SpaceblockRequest {
+				name: "Demo".to_string(),
+				size: data.len() as u64,
+				range: Range::Partial(40..70),
+			}],
 		};
+
+		tokio::spawn({
+			let req = req.clone();
+			let data = data.clone();
+			async move {
+				let mut file = Cursor::new(data);
+				file.set_position(40);
+				Transfer::new(&req, |_| {}, &Default::default())
+					.send(&mut client, BufReader::new(file))
+					.await
+					.unwrap();
+			}
+		});
+
+		let mut result = Vec::new();
+		let received = Transfer::new(&req, |_| {}, &Default::default())
+			.receive(&mut server, &mut result)
+			.await
+			.unwrap();
+		assert!(received);
+		assert_eq!(result, &data[40..70]);
+	}
+
+	async fn transfer_resumed(
+		sent: Vec<u8>,
+		received: &[u8],
+	) -> (Result<bool, io::Error>, Vec<u8>) {
+		let (mut client, mut server) = tokio::io::duplex(64);
+
+		// This is sent out of band of Spaceblock
+		let block_size = BlockSize::dangerously_new(25);
+
+		let mut req = SpaceblockRequests {
+			id: Uuid::new_v4(),
+			block_size,
+			requests: vec![SpaceblockRequest {
+				name: "Demo".to_string(),
+				size: sent.len() as u64,
+				range: Range::Full,
+			}],
+		};
+		// The start of the file was received by a previous transfer
+		req.resume(SpaceblockResume {
+			ranges: vec![Range::Partial(received.len() as u64..sent.len() as u64)],
+		})
+		.unwrap();
+
+		tokio::spawn({
+			let req = req.clone();
+			async move {
+				Transfer::new(&req, |_| {}, &Default::default())
+					.send_resumable(&mut client, BufReader::new(Cursor::new(sent)))
+					.await
+					.unwrap();
+			}
+		});
+
+		let mut result = Vec::new();
+		let received = Transfer::new(&req, |_| {}, &Default::default())
+			.receive_resumable(&mut server, received, &mut result)
+			.await;
+		(received, result)
+	}
+
+	#[tokio::test]
+	async fn test_spaceblock_resume() {
+		let data = (0..100u8).collect::<Vec<_>>();
+
+		let (received, result) = transfer_resumed(data.clone(), &data[..40]).await;
+		assert!(received.unwrap());
+		assert_eq!(result, &data[40..]);
+	}
+
+	#[tokio::test]
+	async fn test_spaceblock_resume_modified_source() {
+		let data = (0..100u8).collect::<Vec<_>>();
+		// The start of the file changed after the previous transfer was interrupted
+		let mut modified = data.clone();
+		modified[..10].fill(0);
+
+		let (received, _) = transfer_resumed(modified, &data[..40]).await;
+		assert_eq!(received.unwrap_err().kind(), io::ErrorKind::InvalidData);
+	}
+
+	#[tokio::test]
+	async fn test_msg() {
+		let block = Block::new(0, b"Spacedrive".as_ref());
 		let data_len = block.data.len();
 		let mut msg = Msg::Block(block);
 		let bytes = msg.to_bytes();
@@ -0,0 +1,127 @@
+use std::{
+	io,
+	path::{Path, PathBuf},
+};
+
+use tokio::{
+	fs::{self, File, OpenOptions},
+	io::{AsyncReadExt, AsyncWriteExt, Take},
+};
+
+use crate::spacetunnel::RemoteIdentity;
+
+use super::{Range, SpaceblockRequest};
+
+/// Keeps the files of interrupted transfers on the receiving side, so they can continue from where
+/// they stopped once the peer reconnects.
+///
+/// Files are keyed by the peer they come from along with their name and size, as the request id
+/// changes every time a transfer is started.
+#[derive(Debug, Clone)]
+pub struct PartialStore {
+	dir: PathBuf,
+}
+
+impl PartialStore {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self { dir: dir.into() }
+	}
+
+	fn path(&self, from: &RemoteIdentity, req: &SpaceblockRequest) -> PathBuf {
+		let mut hasher = blake3::Hasher::new();
+		hasher.update(&from.get_bytes());
+		hasher.update(&req.size.to_le_bytes());
+		hasher.update(req.name.as_bytes());
+
+		self.dir.join(hasher.finalize().to_hex().as_str())
+	}
+
+	/// Open the partial file for `req`, which will be empty if nothing was received before.
+	pub async fn open(
+		&self,
+		from: &RemoteIdentity,
+		req: &SpaceblockRequest,
+	) -> io::Result<PartialFile> {
+		fs::create_dir_all(&self.dir).await?;
+
+		let path = self.path(from, req);
+		let mut file = OpenOptions::new()
+			.read(true)
+			.append(true)
+			.create(true)
+			.open(&path)
+			.await?;
+
+		// Everything on disk was already checked block by block as it was received,
+		// so we only continue after it
+		let mut received = file.metadata().await?.len();
+
+		// Can't belong to this request, so we start over
+		if received > req.size {
+			file.set_len(0).await?;
+			received = 0;
+		}
+
+		Ok(PartialFile {
+			path,
+			file,
+			received,
+		})
+	}
+}
+
+/// A file that is being received, see [`PartialStore`].
+#[derive(Debug)]
+pub struct PartialFile {
+	path: PathBuf,
+	file: File,
+	received: u64,
+}
+
+impl PartialFile {
+	/// Amount of bytes received by previous transfers
+	#[must_use]
+	pub fn received(&self) -> u64 {
+		self.received
+	}
+
+	/// The range which still has to be requested from the sender
+	#[must_use]
+	pub fn range(&self, req: &SpaceblockRequest) -> Range {
+		match self.received {
+			0 => Range::Full,
+			received => Range::Partial(received..req.size),
+		}
+	}
+
+	/// The bytes received by previous transfers, which must be hashed along with the rest of the file.
+	pub async fn received_data(&self) -> io::Result<Take<File>> {
+		Ok(File::open(&self.path).await?.take(self.received))
+	}
+
+	/// The file to write the rest of the transfer into. Writes are always appended.
+	pub fn file(&mut self) -> &mut File {
+		&mut self.file
+	}
+
+	/// Move the fully received file to where it belongs
+	pub async fn complete(mut self, to: impl AsRef<Path>) -> io::Result<()> {
+		let to = to.as_ref();
+		self.file.flush().await?;
+		drop(self.file);
+
+		// Renaming fails across filesystems so we fallback to copying the file
+		if fs::rename(&self.path, to).await.is_err() {
+			fs::copy(&self.path, to).await?;
+			fs::remove_file(&self.path).await?;
+		}
+
+		Ok(())
+	}
+
+	/// Throw away what was received, so the next transfer starts from the beginning
+	pub async fn discard(self) -> io::Result<()> {
+		drop(self.file);
+		fs::remove_file(&self.path).await
+	}
+}
@@ -82,8 +82,7 @@ impl SpaceblockRequests {
 			.map_err(SpaceblockRequestsError::BlockSize)?;
 
 		let size = stream
-			// Max of 255 files in one request
-			.read_u8()
+			.read_u32_le()
 			.await
 			.map_err(SpaceblockRequestsError::InvalidLen)?;
 
@@ -106,16 +105,10 @@ impl SpaceblockRequests {
 			block_size,
 			requests,
 		} = self;
-		#[allow(clippy::panic)] // TODO: Remove this panic
-		assert!(
-			requests.len() <= 255,
-			"Can't Spacedrop more than 255 files at once!"
-		);
-
 		let mut buf = vec![];
 		encode::uuid(&mut buf, id);
 		buf.append(&mut block_size.to_bytes().to_vec());
-		buf.push(requests.len() as u8);
+		buf.extend_from_slice(&(requests.len() as u32).to_le_bytes());
 		for request in requests {
 			buf.extend_from_slice(&request.to_bytes());
 		}
@@ -123,6 +116,74 @@ impl SpaceblockRequests {
 	}
 }
 
+impl SpaceblockRequests {
+	/// Apply the ranges the receiver still needs, so only those are sent
+	pub fn resume(&mut self, resume: SpaceblockResume) -> Result<(), SpaceblockResumeError> {
+		if resume.ranges.len() != self.requests.len() {
+			return Err(SpaceblockResumeError::LenMismatch {
+				expected: self.requests.len(),
+				got: resume.ranges.len(),
+			});
+		}
+
+		for (request, range) in self.requests.iter_mut().zip(resume.ranges) {
+			request.range = range;
+		}
+
+		Ok(())
+	}
+}
+
+/// Sent by the receiver once it has accepted a transfer, with the range it still needs of each file.
+/// This allows a transfer that was interrupted to continue from where the previous attempt stopped.
+///
+/// Files with an empty range are skipped, which is how the receiver declines individual files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceblockResume {
+	pub ranges: Vec<Range>,
+}
+
+#[derive(Debug, Error)]
+pub enum SpaceblockResumeError {
+	#[error("SpaceblockResumeError::InvalidLen({0})")]
+	InvalidLen(std::io::Error),
+	#[error("SpaceblockResumeError::Range({0})")]
+	Range(std::io::Error),
+	#[error("SpaceblockResumeError::LenMismatch(expected {expected}, got {got})")]
+	LenMismatch { expected: usize, got: usize },
+}
+
+impl SpaceblockResume {
+	pub async fn from_stream(
+		stream: &mut (impl AsyncRead + Unpin),
+	) -> Result<Self, SpaceblockResumeError> {
+		let size = stream
+			.read_u32_le()
+			.await
+			.map_err(SpaceblockResumeError::InvalidLen)?;
+
+		let mut ranges = Vec::new();
+		for _ in 0..size {
+			ranges.push(
+				Range::from_stream(stream)
+					.await
+					.map_err(SpaceblockResumeError::Range)?,
+			);
+		}
+
+		Ok(Self { ranges })
+	}
+
+	#[must_use]
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut buf = (self.ranges.len() as u32).to_le_bytes().to_vec();
+		for range in &self.ranges {
+			buf.extend_from_slice(&range.to_bytes());
+		}
+		buf
+	}
+}
+
 /// TODO
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpaceblockRequest {
@@ -165,6 +226,18 @@ impl SpaceblockRequest {
 		})
 	}
 
+	/// The part of the file which has to be sent, clamped to the size of the file
+	#[must_use]
+	pub fn bounds(&self) -> std::ops::Range<u64> {
+		match &self.range {
+			Range::Full => 0..self.size,
+			Range::Partial(range) => {
+				let end = range.end.min(self.size);
+				range.start.min(end)..end
+			}
+		}
+	}
+
 	#[must_use]
 	pub fn to_bytes(&self) -> Vec<u8> {
 		let Self { name, size, range } = self;
@@ -267,4 +340,43 @@ mod tests {
 			.unwrap();
 		assert_eq!(req, req2);
 	}
+
+	#[tokio::test]
+	async fn test_spaceblock_resume() {
+		let mut req = SpaceblockRequests {
+			id: Uuid::new_v4(),
+			block_size: BlockSize::from_size(42069),
+			requests: vec![
+				SpaceblockRequest {
+					name: "Demo".to_string(),
+					size: 42069,
+					range: Range::Full,
+				},
+				SpaceblockRequest {
+					name: "Demo2".to_string(),
+					size: 420,
+					range: Range::Full,
+				},
+			],
+		};
+
+		let resume = SpaceblockResume {
+			ranges: vec![Range::Partial(69..42069), Range::Full],
+		};
+		let bytes = resume.to_bytes();
+		let resume2 = SpaceblockResume::from_stream(&mut Cursor::new(bytes))
+			.await
+			.unwrap();
+		assert_eq!(resume, resume2);
+
+		req.resume(resume).unwrap();
+		assert_eq!(req.requests[0].bounds(), 69..42069);
+		assert_eq!(req.requests[1].bounds(), 0..420);
+
+		assert!(req
+			.resume(SpaceblockResume {
+				ranges: vec![Range::Full]
+			})
+			.is_err());
+	}
 }
@@ -22,7 +22,6 @@ pub const CHALLENGE_LENGTH: usize = 32;
 
 /// A unicast stream is a direct stream to a specific peer.
 #[derive(Debug)]
-#[allow(unused)] // TODO: Remove this lint override
 pub struct UnicastStream {
 	io: Compat<Stream>,
 	me: Identity,
@@ -98,6 +97,10 @@ impl UnicastStream {
 		self.remote
 	}
 
+	pub(crate) fn identity(&self) -> &Identity {
+		&self.me
+	}
+
 	pub async fn close(self) -> Result<(), io::Error> {
 		self.io.into_inner().close().await
 	}
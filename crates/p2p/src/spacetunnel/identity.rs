@@ -4,7 +4,7 @@ use std::{
 };
 
 use base64::{engine::general_purpose, Engine};
-use ed25519_dalek::{VerifyingKey, SECRET_KEY_LENGTH};
+use ed25519_dalek::{Signature, Signer, VerifyingKey, SECRET_KEY_LENGTH};
 use rand_core::OsRng;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -60,6 +60,11 @@ impl Identity {
 	pub fn to_remote_identity(&self) -> RemoteIdentity {
 		RemoteIdentity(self.0.verifying_key())
 	}
+
+	#[must_use]
+	pub(crate) fn sign(&self, message: &[u8]) -> Signature {
+		self.0.sign(message)
+	}
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Type)]
@@ -160,6 +165,11 @@ impl RemoteIdentity {
 	pub fn verifying_key(&self) -> VerifyingKey {
 		self.0
 	}
+
+	/// Verify `signature` was produced over `message` by the holder of this identity's private key
+	pub(crate) fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), IdentityErr> {
+		self.0.verify_strict(message, signature).map_err(Into::into)
+	}
 }
 
 impl From<ed25519_dalek::SigningKey> for Identity {
@@ -1,77 +1,567 @@
 use std::{
-	io,
+	fmt, io,
 	pin::Pin,
-	task::{Context, Poll},
+	task::{ready, Context, Poll},
 };
 
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use ed25519_dalek::{Signature, SIGNATURE_LENGTH};
+use rand_core::OsRng;
+use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use x25519_dalek::{EphemeralSecret, PublicKey};
 
 use crate::spacetime::UnicastStream;
 
-#[derive(Debug)]
-pub struct Tunnel {
-	stream: UnicastStream,
+use super::{Identity, IdentityErr, RemoteIdentity, REMOTE_IDENTITY_LEN};
+
+const DISCRIMINATOR: u8 = b'T';
+
+/// Domain separation for everything signed or derived during the handshake
+const HANDSHAKE_CONTEXT: &str = "spacedrive 2023-12-09 spacetunnel v1";
+const INITIATOR_KEY_CONTEXT: &str = "spacedrive 2023-12-09 spacetunnel v1 initiator to responder";
+const RESPONDER_KEY_CONTEXT: &str = "spacedrive 2023-12-09 spacetunnel v1 responder to initiator";
+const INSTANCE_CONTEXT: &str = "spacedrive 2023-12-09 spacetunnel v1 instance";
+
+/// Maximum amount of plaintext sealed into a single frame
+const MAX_FRAME_LEN: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const LEN_PREFIX: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum TunnelError {
+	#[error("io error: {0}")]
+	Io(#[from] io::Error),
+	#[error("invalid discriminator '{0}'. Is this stream actually a tunnel?")]
+	InvalidDiscriminator(u8),
+	#[error("remote peer failed to prove its identity: {0}")]
+	Authentication(#[from] IdentityErr),
+	#[error("key exchange produced a non-contributory shared secret")]
+	NonContributory,
+	#[error("dialed peer '{expected}' but the stream is connected to '{found}'")]
+	UnexpectedIdentity {
+		expected: RemoteIdentity,
+		found: RemoteIdentity,
+	},
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+	Initiator,
+	Responder,
+}
+
+impl Role {
+	fn signature_label(self) -> &'static [u8] {
+		match self {
+			Self::Initiator => b"initiator",
+			Self::Responder => b"responder",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReadState {
+	Header,
+	Body(usize),
+}
+
+/// An authenticated and encrypted stream between two peers.
+///
+/// Both sides prove they hold the private key of the `Identity` the other side expects by signing
+/// an ephemeral X25519 key exchange. Everything afterwards is split into length-prefixed
+/// ChaCha20-Poly1305 frames, using a separate key and nonce counter for each direction.
+pub struct Tunnel<S = UnicastStream> {
+	stream: S,
+	role: Role,
+	remote: RemoteIdentity,
+	transcript: blake3::Hash,
+	sealer: ChaCha20Poly1305,
+	send_nonce: u64,
+	opener: ChaCha20Poly1305,
+	recv_nonce: u64,
+	// Sealed frame which hasn't been fully written to `stream` yet
+	write_buf: Vec<u8>,
+	write_pos: usize,
+	// Frame being read from `stream`
+	read_state: ReadState,
+	read_buf: Vec<u8>,
+	read_filled: usize,
+	// Opened frame which hasn't been fully returned to the caller yet
+	plaintext: Vec<u8>,
+	plaintext_pos: usize,
+}
+
+impl<S> fmt::Debug for Tunnel<S> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Tunnel")
+			.field("remote", &self.remote)
+			.finish_non_exhaustive()
+	}
 }
 
 impl Tunnel {
-	// TODO: Proper errors
-	pub async fn initiator(mut stream: UnicastStream) -> Result<Self, &'static str> {
-		stream
-			.write_all(&[b'T'])
-			.await
-			.map_err(|_| "Error writing discriminator")?;
-
-		// TODO: Do pairing + authentication
-
-		Ok(Self { stream })
-	}
-
-	// TODO: Proper errors
-	pub async fn responder(mut stream: UnicastStream) -> Result<Self, &'static str> {
-		let discriminator = stream
-			.read_u8()
-			.await
-			.map_err(|_| "Error reading discriminator. Is this stream actually a tunnel?")?;
-		if discriminator != b'T' {
-			return Err("Invalid discriminator. Is this stream actually a tunnel?");
+	/// Establish a tunnel with the peer that was dialed as `remote`.
+	///
+	/// The identity a stream reports for the remote is whatever the remote claimed, so it must only be used to fail early.
+	pub async fn initiator(
+		stream: UnicastStream,
+		remote: &RemoteIdentity,
+	) -> Result<Self, TunnelError> {
+		let found = stream.remote_identity();
+		if found != *remote {
+			return Err(TunnelError::UnexpectedIdentity {
+				expected: *remote,
+				found,
+			});
 		}
 
-		// TODO: Do pairing + authentication
+		let identity = stream.identity().clone();
+		Self::initiator_with(stream, &identity, *remote).await
+	}
 
-		Ok(Self { stream })
+	pub async fn responder(stream: UnicastStream) -> Result<Self, TunnelError> {
+		let identity = stream.identity().clone();
+		let remote = stream.remote_identity();
+		Self::responder_with(stream, &identity, remote).await
 	}
 }
 
-impl AsyncRead for Tunnel {
+impl<S: AsyncRead + AsyncWrite + Unpin> Tunnel<S> {
+	/// Establish a tunnel over any transport, proving `identity` and expecting the responder to be `remote`
+	pub async fn initiator_with(
+		stream: S,
+		identity: &Identity,
+		remote: RemoteIdentity,
+	) -> Result<Self, TunnelError> {
+		Self::establish(stream, Role::Initiator, identity, remote).await
+	}
+
+	/// Establish a tunnel over any transport, proving `identity` and expecting the initiator to be `remote`
+	pub async fn responder_with(
+		stream: S,
+		identity: &Identity,
+		remote: RemoteIdentity,
+	) -> Result<Self, TunnelError> {
+		Self::establish(stream, Role::Responder, identity, remote).await
+	}
+
+	async fn establish(
+		mut stream: S,
+		role: Role,
+		identity: &Identity,
+		remote: RemoteIdentity,
+	) -> Result<Self, TunnelError> {
+		let secret = EphemeralSecret::random_from_rng(OsRng);
+		let ephemeral = PublicKey::from(&secret);
+
+		let (initiator_ephemeral, responder_ephemeral) = match role {
+			Role::Initiator => {
+				let mut buf = [0u8; 33];
+				buf[0] = DISCRIMINATOR;
+				buf[1..].copy_from_slice(ephemeral.as_bytes());
+				stream.write_all(&buf).await?;
+				stream.flush().await?;
+
+				let mut buf = [0u8; 32];
+				stream.read_exact(&mut buf).await?;
+				(ephemeral, PublicKey::from(buf))
+			}
+			Role::Responder => {
+				let discriminator = stream.read_u8().await?;
+				if discriminator != DISCRIMINATOR {
+					return Err(TunnelError::InvalidDiscriminator(discriminator));
+				}
+
+				let mut buf = [0u8; 32];
+				stream.read_exact(&mut buf).await?;
+				stream.write_all(ephemeral.as_bytes()).await?;
+				(PublicKey::from(buf), ephemeral)
+			}
+		};
+
+		let (initiator_identity, responder_identity) = match role {
+			Role::Initiator => (identity.to_remote_identity(), remote),
+			Role::Responder => (remote, identity.to_remote_identity()),
+		};
+
+		// Binding both identities into the transcript stops a signature being replayed to another peer
+		let transcript = blake3::Hasher::new_derive_key(HANDSHAKE_CONTEXT)
+			.update(initiator_ephemeral.as_bytes())
+			.update(responder_ephemeral.as_bytes())
+			.update(&initiator_identity.get_bytes())
+			.update(&responder_identity.get_bytes())
+			.finalize();
+
+		let remote_role = match role {
+			Role::Initiator => Role::Responder,
+			Role::Responder => Role::Initiator,
+		};
+		let signature = identity.sign(&[role.signature_label(), transcript.as_bytes()].concat());
+
+		// The responder proves itself first, so the initiator never signs for an unauthenticated peer
+		match role {
+			Role::Initiator => {
+				read_and_verify(&mut stream, &remote, remote_role, &transcript).await?;
+				stream.write_all(&signature.to_bytes()).await?;
+				stream.flush().await?;
+			}
+			Role::Responder => {
+				stream.write_all(&signature.to_bytes()).await?;
+				stream.flush().await?;
+				read_and_verify(&mut stream, &remote, remote_role, &transcript).await?;
+			}
+		}
+
+		let remote_ephemeral = match role {
+			Role::Initiator => responder_ephemeral,
+			Role::Responder => initiator_ephemeral,
+		};
+		let shared = secret.diffie_hellman(&remote_ephemeral);
+		if !shared.was_contributory() {
+			return Err(TunnelError::NonContributory);
+		}
+
+		let key_material = [shared.as_bytes(), transcript.as_bytes()].concat();
+		let initiator_key = blake3::derive_key(INITIATOR_KEY_CONTEXT, &key_material);
+		let responder_key = blake3::derive_key(RESPONDER_KEY_CONTEXT, &key_material);
+		let (send_key, recv_key) = match role {
+			Role::Initiator => (initiator_key, responder_key),
+			Role::Responder => (responder_key, initiator_key),
+		};
+
+		Ok(Self {
+			stream,
+			role,
+			remote,
+			transcript,
+			sealer: ChaCha20Poly1305::new(Key::from_slice(&send_key)),
+			send_nonce: 0,
+			opener: ChaCha20Poly1305::new(Key::from_slice(&recv_key)),
+			recv_nonce: 0,
+			write_buf: Vec::new(),
+			write_pos: 0,
+			read_state: ReadState::Header,
+			read_buf: Vec::new(),
+			read_filled: 0,
+			plaintext: Vec::new(),
+			plaintext_pos: 0,
+		})
+	}
+
+	/// The identity of the peer on the other end, which has been verified during the handshake
+	#[must_use]
+	pub fn remote_identity(&self) -> RemoteIdentity {
+		self.remote
+	}
+
+	/// Prove to the remote that we also hold `identity` and learn which identity the remote holds in turn.
+	///
+	/// The tunnel authenticates the peers with their node identities, while an application may scope access
+	/// to another identity (Eg. a library instance). The proofs are bound to this tunnel so they can't be replayed.
+	pub async fn authenticate_as(
+		&mut self,
+		identity: &Identity,
+	) -> Result<RemoteIdentity, TunnelError> {
+		let remote_role = match self.role {
+			Role::Initiator => Role::Responder,
+			Role::Responder => Role::Initiator,
+		};
+
+		let mut proof = identity.to_remote_identity().get_bytes().to_vec();
+		proof.extend_from_slice(
+			&identity
+				.sign(&instance_message(self.role, &self.transcript))
+				.to_bytes(),
+		);
+
+		// Same order as the handshake, so the initiator never proves itself to an unauthenticated peer
+		if self.role == Role::Responder {
+			self.write_all(&proof).await?;
+			self.flush().await?;
+		}
+
+		let mut buf = [0u8; REMOTE_IDENTITY_LEN + SIGNATURE_LENGTH];
+		self.read_exact(&mut buf).await?;
+		let remote = RemoteIdentity::from_bytes(&buf[..REMOTE_IDENTITY_LEN])?;
+		remote.verify(
+			&instance_message(remote_role, &self.transcript),
+			&Signature::from_bytes(
+				buf[REMOTE_IDENTITY_LEN..]
+					.try_into()
+					.expect("buffer is the size of a signature"),
+			),
+		)?;
+
+		if self.role == Role::Initiator {
+			self.write_all(&proof).await?;
+			self.flush().await?;
+		}
+
+		Ok(remote)
+	}
+
+	/// Write out the remainder of the last sealed frame
+	fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		while self.write_pos < self.write_buf.len() {
+			let n = ready!(
+				Pin::new(&mut self.stream).poll_write(cx, &self.write_buf[self.write_pos..])
+			)?;
+			if n == 0 {
+				return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+			}
+			self.write_pos += n;
+		}
+
+		Poll::Ready(Ok(()))
+	}
+
+	/// Read and open the next frame into `plaintext`. Resolves to `false` if the stream ended cleanly.
+	fn poll_next_frame(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
+		loop {
+			let target = match self.read_state {
+				ReadState::Header => LEN_PREFIX,
+				ReadState::Body(len) => len,
+			};
+			self.read_buf.resize(target, 0);
+
+			while self.read_filled < target {
+				let mut buf = ReadBuf::new(&mut self.read_buf[self.read_filled..target]);
+				ready!(Pin::new(&mut self.stream).poll_read(cx, &mut buf))?;
+
+				let n = buf.filled().len();
+				if n == 0 {
+					if self.read_state == ReadState::Header && self.read_filled == 0 {
+						return Poll::Ready(Ok(false));
+					}
+
+					return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+				}
+				self.read_filled += n;
+			}
+			self.read_filled = 0;
+
+			match self.read_state {
+				ReadState::Header => {
+					let len = u32::from_le_bytes(
+						self.read_buf[..LEN_PREFIX]
+							.try_into()
+							.expect("buffer is the size of the length prefix"),
+					) as usize;
+					if !(TAG_LEN..=MAX_FRAME_LEN + TAG_LEN).contains(&len) {
+						return Poll::Ready(Err(io::Error::new(
+							io::ErrorKind::InvalidData,
+							format!("tunnel frame of invalid length {len}"),
+						)));
+					}
+
+					self.read_state = ReadState::Body(len);
+				}
+				ReadState::Body(_) => {
+					let nonce = next_nonce(&mut self.recv_nonce)?;
+					self.plaintext = self
+						.opener
+						.decrypt(&nonce, self.read_buf.as_slice())
+						.map_err(|_| {
+							io::Error::new(
+								io::ErrorKind::InvalidData,
+								"failed to authenticate tunnel frame",
+							)
+						})?;
+					self.plaintext_pos = 0;
+					self.read_state = ReadState::Header;
+
+					return Poll::Ready(Ok(true));
+				}
+			}
+		}
+	}
+}
+
+async fn read_and_verify(
+	stream: &mut (impl AsyncRead + Unpin),
+	remote: &RemoteIdentity,
+	remote_role: Role,
+	transcript: &blake3::Hash,
+) -> Result<(), TunnelError> {
+	let mut buf = [0u8; SIGNATURE_LENGTH];
+	stream.read_exact(&mut buf).await?;
+
+	remote.verify(
+		&[remote_role.signature_label(), transcript.as_bytes()].concat(),
+		&Signature::from_bytes(&buf),
+	)?;
+
+	Ok(())
+}
+
+fn instance_message(role: Role, transcript: &blake3::Hash) -> Vec<u8> {
+	[
+		INSTANCE_CONTEXT.as_bytes(),
+		role.signature_label(),
+		transcript.as_bytes(),
+	]
+	.concat()
+}
+
+fn next_nonce(counter: &mut u64) -> io::Result<Nonce> {
+	let mut nonce = Nonce::default();
+	nonce[..8].copy_from_slice(&counter.to_le_bytes());
+	*counter = counter
+		.checked_add(1)
+		.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "tunnel nonce space exhausted"))?;
+
+	Ok(nonce)
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for Tunnel<S> {
 	fn poll_read(
 		self: Pin<&mut Self>,
 		cx: &mut Context<'_>,
 		buf: &mut ReadBuf<'_>,
 	) -> Poll<io::Result<()>> {
-		// TODO: Do decryption
+		let this = self.get_mut();
+
+		while this.plaintext_pos == this.plaintext.len() {
+			if !ready!(this.poll_next_frame(cx))? {
+				return Poll::Ready(Ok(()));
+			}
+		}
 
-		Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+		let n = buf
+			.remaining()
+			.min(this.plaintext.len() - this.plaintext_pos);
+		buf.put_slice(&this.plaintext[this.plaintext_pos..this.plaintext_pos + n]);
+		this.plaintext_pos += n;
+
+		Poll::Ready(Ok(()))
 	}
 }
 
-impl AsyncWrite for Tunnel {
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for Tunnel<S> {
 	fn poll_write(
 		self: Pin<&mut Self>,
 		cx: &mut Context<'_>,
 		buf: &[u8],
 	) -> Poll<io::Result<usize>> {
-		// TODO: Do encryption
+		let this = self.get_mut();
+		ready!(this.poll_write_pending(cx))?;
+
+		if buf.is_empty() {
+			return Poll::Ready(Ok(0));
+		}
+
+		let n = buf.len().min(MAX_FRAME_LEN);
+		let nonce = next_nonce(&mut this.send_nonce)?;
+		let ciphertext = this
+			.sealer
+			.encrypt(&nonce, &buf[..n])
+			.map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to seal tunnel frame"))?;
+
+		this.write_buf.clear();
+		// The length is bounded by `MAX_FRAME_LEN`, so it always fits
+		this.write_buf
+			.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+		this.write_buf.extend_from_slice(&ciphertext);
+		this.write_pos = 0;
+
+		// The frame now owns this data, so we only start sending it and leave the rest to the next write or flush
+		if let Poll::Ready(Err(err)) = this.poll_write_pending(cx) {
+			return Poll::Ready(Err(err));
+		}
 
-		Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+		Poll::Ready(Ok(n))
 	}
 
 	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-		Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+		let this = self.get_mut();
+		ready!(this.poll_write_pending(cx))?;
+		Pin::new(&mut this.stream).poll_flush(cx)
 	}
 
 	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-		Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+		let this = self.get_mut();
+		ready!(this.poll_write_pending(cx))?;
+		Pin::new(&mut this.stream).poll_shutdown(cx)
 	}
 }
 
-// TODO: Unit tests
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	async fn establish_pair(
+		initiator: &Identity,
+		responder: &Identity,
+		expected_responder: RemoteIdentity,
+	) -> (
+		Result<Tunnel<tokio::io::DuplexStream>, TunnelError>,
+		Result<Tunnel<tokio::io::DuplexStream>, TunnelError>,
+	) {
+		let (client, server) = tokio::io::duplex(64);
+
+		tokio::join!(
+			Tunnel::establish(client, Role::Initiator, initiator, expected_responder),
+			Tunnel::establish(
+				server,
+				Role::Responder,
+				responder,
+				initiator.to_remote_identity()
+			),
+		)
+	}
+
+	#[tokio::test]
+	async fn test_tunnel_roundtrip() {
+		let (alice, bob) = (Identity::new(), Identity::new());
+		let (client, server) = establish_pair(&alice, &bob, bob.to_remote_identity()).await;
+		let (mut client, mut server) = (client.unwrap(), server.unwrap());
+
+		assert_eq!(client.remote_identity(), bob.to_remote_identity());
+		assert_eq!(server.remote_identity(), alice.to_remote_identity());
+
+		// Spans multiple frames
+		let data = (0..MAX_FRAME_LEN * 3 + 7)
+			.map(|i| (i % 251) as u8)
+			.collect::<Vec<_>>();
+
+		let (_, result) = tokio::join!(
+			async {
+				client.write_all(&data).await.unwrap();
+				client.shutdown().await.unwrap();
+			},
+			async {
+				let mut result = Vec::new();
+				server.read_to_end(&mut result).await.unwrap();
+				result
+			}
+		);
+		assert_eq!(result, data);
+	}
+
+	#[tokio::test]
+	async fn test_tunnel_wrong_identity() {
+		let (alice, bob, mallory) = (Identity::new(), Identity::new(), Identity::new());
+		let (client, _) = establish_pair(&alice, &mallory, bob.to_remote_identity()).await;
+
+		assert!(matches!(client, Err(TunnelError::Authentication(_))));
+	}
+
+	#[tokio::test]
+	async fn test_tunnel_authenticate_as() {
+		let (alice, bob) = (Identity::new(), Identity::new());
+		let (alice_instance, bob_instance) = (Identity::new(), Identity::new());
+		let (client, server) = establish_pair(&alice, &bob, bob.to_remote_identity()).await;
+		let (mut client, mut server) = (client.unwrap(), server.unwrap());
+
+		let (client_remote, server_remote) = tokio::join!(
+			client.authenticate_as(&alice_instance),
+			server.authenticate_as(&bob_instance)
+		);
+
+		assert_eq!(client_remote.unwrap(), bob_instance.to_remote_identity());
+		assert_eq!(server_remote.unwrap(), alice_instance.to_remote_identity());
+		// The node identities are still what the tunnel itself authenticated
+		assert_eq!(server.remote_identity(), alice.to_remote_identity());
+	}
+}
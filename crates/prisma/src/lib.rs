@@ -32,3 +32,21 @@ impl sd_cache::Model for prisma::file_path::Data {
 		"FilePath"
 	}
 }
+
+impl sd_cache::Model for prisma::label::Data {
+	fn name() -> &'static str {
+		"Label"
+	}
+}
+
+impl sd_cache::Model for prisma::album::Data {
+	fn name() -> &'static str {
+		"Album"
+	}
+}
+
+impl sd_cache::Model for prisma::space::Data {
+	fn name() -> &'static str {
+		"Space"
+	}
+}
@@ -71,6 +71,7 @@ export const RenamableItemText = ({ item, allowHighlight = true, style, lines }:
 						generate_preview_media: null,
 						sync_preview_media: null,
 						hidden: null,
+						strict_identity: null,
 						indexer_rules_ids: []
 					});
 
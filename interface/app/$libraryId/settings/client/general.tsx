@@ -2,6 +2,7 @@ import clsx from 'clsx';
 import { Controller, FormProvider } from 'react-hook-form';
 import {
 	getDebugState,
+	ThumbnailSize,
 	useBridgeMutation,
 	useBridgeQuery,
 	useConnectedPeers,
@@ -22,6 +23,8 @@ const NodeSettingLabel = tw.div`mb-1 text-xs font-medium`;
 // https://doc.rust-lang.org/std/u16/index.html
 const u16 = z.number().min(0).max(65_535);
 
+const thumbnailSizes = ['small', 'medium', 'large'] as const;
+
 export const Component = () => {
 	const node = useBridgeQuery(['nodeState']);
 	const platform = usePlatform();
@@ -43,7 +46,9 @@ export const Component = () => {
 					})
 					.int()
 					.nonnegative()
-					.lte(100)
+					.lte(100),
+				thumbnail_sizes: z.array(z.enum(thumbnailSizes)).min(1),
+				thumbnail_quality: z.coerce.number().int().nonnegative().lte(100)
 			})
 			.strict(),
 		reValidateMode: 'onChange',
@@ -53,13 +58,17 @@ export const Component = () => {
 			p2p_port: node.data?.p2p_port || 0,
 			customOrDefault: node.data?.p2p_port ? 'Custom' : 'Default',
 			background_processing_percentage:
-				node.data?.preferences.thumbnailer.background_processing_percentage || 50
+				node.data?.preferences.thumbnailer.background_processing_percentage || 50,
+			thumbnail_sizes: node.data?.preferences.thumbnailer.sizes ?? [...thumbnailSizes],
+			thumbnail_quality: node.data?.preferences.thumbnailer.quality ?? 30
 		}
 	});
 
 	const watchCustomOrDefault = form.watch('customOrDefault');
 	const watchP2pEnabled = form.watch('p2p_enabled');
 	const watchBackgroundProcessingPercentage = form.watch('background_processing_percentage');
+	const watchThumbnailSizes = form.watch('thumbnail_sizes');
+	const watchThumbnailQuality = form.watch('thumbnail_quality');
 
 	useDebouncedFormWatch(form, async (value) => {
 		if (await form.trigger()) {
@@ -71,7 +80,9 @@ export const Component = () => {
 
 			if (value.background_processing_percentage != undefined) {
 				await updateThumbnailerPreferences.mutateAsync({
-					background_processing_percentage: value.background_processing_percentage
+					background_processing_percentage: value.background_processing_percentage,
+					sizes: value.thumbnail_sizes?.filter((size): size is ThumbnailSize => !!size),
+					quality: value.thumbnail_quality
 				});
 			}
 		}
@@ -218,6 +229,62 @@ export const Component = () => {
 					/>
 				</div>
 			</Setting>
+			<Setting
+				mini
+				registerName="thumbnail_sizes"
+				title="Thumbnail sizes"
+				description="Choose which thumbnail sizes are generated. Views fall back to the nearest available size."
+			>
+				<div className="flex flex-row gap-4">
+					{thumbnailSizes.map((size) => (
+						<div key={size} className="flex items-center gap-2">
+							<Switch
+								size="sm"
+								checked={watchThumbnailSizes.includes(size)}
+								onCheckedChange={(checked) =>
+									form.setValue(
+										'thumbnail_sizes',
+										checked
+											? [...watchThumbnailSizes, size]
+											: watchThumbnailSizes.filter((s) => s !== size)
+									)
+								}
+							/>
+							<span className="text-sm font-medium capitalize text-ink-dull">
+								{size}
+							</span>
+						</div>
+					))}
+				</div>
+			</Setting>
+			<Setting
+				mini
+				registerName="thumbnail_quality"
+				title="Thumbnail quality"
+				description="Higher quality thumbnails look sharper but take more space."
+			>
+				<div className="flex h-[30px] w-80 items-center gap-2">
+					<Slider
+						onValueChange={(value) => {
+							if (value.length > 0) {
+								form.setValue('thumbnail_quality', value[0] ?? 0);
+							}
+						}}
+						max={100}
+						step={5}
+						min={0}
+						value={[watchThumbnailQuality]}
+					/>
+					<Input
+						className="after:h-initial relative h-[30px] w-[8ch]
+						after:absolute after:right-[0.8em] after:top-1/2 after:inline-block after:-translate-y-2/4 after:content-['%']"
+						maxLength={3}
+						{...form.register('thumbnail_quality', {
+							valueAsNumber: true
+						})}
+					/>
+				</div>
+			</Setting>
 			<div className="flex flex-col gap-4">
 				<h1 className="mb-3 text-lg font-bold text-ink">Networking</h1>
 
@@ -1,6 +1,13 @@
 import { iconNames } from '@sd/assets/util';
 import { memo, useEffect, useMemo, useState } from 'react';
-import { byteSize, useDiscoveredPeers, useLibraryQuery, useNodes } from '@sd/client';
+import {
+	byteSize,
+	ObjectKind,
+	useCache,
+	useDiscoveredPeers,
+	useLibraryQuery,
+	useNodes
+} from '@sd/client';
 import { Card } from '@sd/ui';
 import { Icon } from '~/components';
 import { useCounter } from '~/hooks';
@@ -10,13 +17,18 @@ import { Heading } from '../Layout';
 export const Component = () => {
 	const stats = useLibraryQuery(['library.statistics'], {
 		refetchOnWindowFocus: false,
-		initialData: { total_bytes_capacity: '0', library_db_size: '0' }
+		initialData: {
+			total_bytes_capacity: '0',
+			library_db_size: '0',
+			locations: [],
+			kinds: []
+		}
 	});
 	const locations = useLibraryQuery(['locations.list'], {
 		refetchOnWindowFocus: false
 	});
 	useNodes(locations.data?.nodes);
-	// const locations = useCache(result.data?.items);
+	const locationsList = useCache(locations.data?.items);
 
 	const discoveredPeers = useDiscoveredPeers();
 	const info = useMemo(() => {
@@ -25,6 +37,8 @@ export const Component = () => {
 			const free_space = byteSize(stats.data?.total_bytes_free);
 			const library_db_size = byteSize(stats.data?.library_db_size);
 			const preview_media = byteSize(stats.data?.preview_media_bytes);
+			const bytes_used = byteSize(stats.data?.total_bytes_used);
+			const unique_bytes = byteSize(stats.data?.total_unique_bytes);
 			const data: {
 				icon: keyof typeof iconNames;
 				title?: string;
@@ -69,6 +83,18 @@ export const Component = () => {
 					numberTitle: preview_media.value,
 					sub: 'Preview media',
 					unit: preview_media.unit
+				},
+				{
+					icon: 'Drive',
+					numberTitle: bytes_used.value,
+					sub: 'Indexed files',
+					unit: bytes_used.unit
+				},
+				{
+					icon: 'Database',
+					numberTitle: unique_bytes.value,
+					sub: 'Unique files',
+					unit: unique_bytes.unit
 				}
 			];
 			return data;
@@ -94,10 +120,57 @@ export const Component = () => {
 					))}
 				</div>
 			</Card>
+			<div className="grid grid-cols-1 gap-2 lg:grid-cols-2">
+				<Breakdown
+					title="Space by kind"
+					items={(stats.data?.kinds ?? []).map((kind) => ({
+						name: ObjectKind[kind.kind] ?? 'Unknown',
+						fileCount: kind.file_count,
+						totalBytes: kind.total_bytes
+					}))}
+				/>
+				<Breakdown
+					title="Space by location"
+					items={(stats.data?.locations ?? []).map((location) => ({
+						name:
+							locationsList?.find((l) => l.id === location.location_id)?.name ??
+							'Removed location',
+						fileCount: location.file_count,
+						totalBytes: location.total_bytes
+					}))}
+				/>
+			</div>
 		</>
 	);
 };
 
+interface BreakdownProps {
+	title: string;
+	items: { name: string; fileCount: number; totalBytes: string }[];
+}
+
+const Breakdown = ({ title, items }: BreakdownProps) => (
+	<Card className="flex flex-col gap-2 !p-5">
+		<h2 className="text-sm font-medium">{title}</h2>
+		{items
+			.sort((a, b) => Number(BigInt(b.totalBytes) - BigInt(a.totalBytes)))
+			.map((item) => {
+				const size = byteSize(item.totalBytes);
+				return (
+					<div key={item.name} className="flex justify-between text-sm">
+						<span className="truncate text-ink-dull">
+							{item.name}
+							<span className="ml-1 text-ink-faint">({item.fileCount} files)</span>
+						</span>
+						<span>
+							{size.value} {size.unit}
+						</span>
+					</div>
+				);
+			})}
+	</Card>
+);
+
 interface Props {
 	icon: keyof typeof iconNames;
 	title: string;
@@ -37,7 +37,8 @@ const schema = z.object({
 	indexerRulesIds: z.array(z.number()),
 	locationType: z.string(),
 	syncPreviewMedia: z.boolean().nullable(),
-	generatePreviewMedia: z.boolean().nullable()
+	generatePreviewMedia: z.boolean().nullable(),
+	strictIdentity: z.boolean().nullable()
 });
 
 export const Component = () => {
@@ -69,7 +70,8 @@ const EditLocationForm = () => {
 			path: locationData?.path ?? '',
 			hidden: locationData?.hidden ?? false,
 			syncPreviewMedia: locationData?.sync_preview_media ?? false,
-			generatePreviewMedia: locationData?.generate_preview_media ?? false
+			generatePreviewMedia: locationData?.generate_preview_media ?? false,
+			strictIdentity: locationData?.strict_identity ?? false
 		}
 	});
 
@@ -91,7 +93,8 @@ const EditLocationForm = () => {
 			hidden: data.hidden,
 			indexer_rules_ids: data.indexerRulesIds,
 			sync_preview_media: data.syncPreviewMedia,
-			generate_preview_media: data.generatePreviewMedia
+			generate_preview_media: data.generatePreviewMedia,
+			strict_identity: data.strictIdentity
 		})
 	);
 
@@ -186,6 +189,15 @@ const EditLocationForm = () => {
 						</Label>
 						<SwitchField {...form.register('hidden')} size="sm" />
 					</ToggleSection>
+					<ToggleSection>
+						<Label className="grow">
+							Compare the full contents of files before grouping them{' '}
+							<Tooltip label="Files are only grouped as the same object after checking every byte of them, instead of a few samples. Slower, but two different large files can never be mistaken for one another. Run a Full Reindex to apply it to files already indexed.">
+								<Info className="inline" />
+							</Tooltip>
+						</Label>
+						<SwitchField {...form.register('strictIdentity')} size="sm" />
+					</ToggleSection>
 				</div>
 				<Divider />
 				<Controller
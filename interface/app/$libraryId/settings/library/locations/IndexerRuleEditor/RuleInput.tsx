@@ -1,12 +1,29 @@
 import clsx from 'clsx';
 import { ChangeEvent, ChangeEventHandler, forwardRef, memo } from 'react';
+import { ObjectKind, ObjectKindKey } from '@sd/client';
 import { Input, toast } from '@sd/ui';
 import { useOperatingSystem } from '~/hooks';
 import { usePlatform } from '~/util/Platform';
 
 import { openDirectoryPickerDialog } from '../openDirectoryPickerDialog';
 
-export type InputKinds = 'Name' | 'Extension' | 'Path' | 'Advanced';
+export type InputKinds =
+	| 'Name'
+	| 'Extension'
+	| 'Path'
+	| 'Advanced'
+	| 'Size'
+	| 'Age'
+	| 'Kind'
+	| 'Attribute'
+	| 'Ignore File';
+
+// Splits comma separated values, for rules which accept many of them
+export const splitRuleValues = (value: string) =>
+	value
+		.split(',')
+		.map((v) => v.trim())
+		.filter(Boolean);
 
 interface Props {
 	kind: InputKinds;
@@ -56,6 +73,34 @@ export const validateInput = (
 				message: value ? 'Invalid glob' : 'Value required'
 			};
 		}
+		case 'Size': {
+			return {
+				value: /^\d+$/.test(value),
+				message: value ? 'Invalid size' : 'Value required'
+			};
+		}
+		case 'Age': {
+			return {
+				value: /^\d+$/.test(value),
+				message: value ? 'Invalid number of days' : 'Value required'
+			};
+		}
+		case 'Kind': {
+			const kinds = splitRuleValues(value);
+			return {
+				value:
+					kinds.length > 0 &&
+					kinds.every((kind) => ObjectKind[kind as ObjectKindKey] !== undefined),
+				message: value ? 'Invalid kind' : 'Value required'
+			};
+		}
+		case 'Attribute':
+		case 'Ignore File': {
+			return {
+				value: splitRuleValues(value).length > 0,
+				message: 'Value required'
+			};
+		}
 		default:
 			return undefined;
 	}
@@ -152,6 +197,78 @@ export const RuleInput = memo(
 						{...props}
 					/>
 				);
+			case 'Size':
+				return (
+					<Input
+						ref={ref}
+						size="md"
+						onBlur={(event) => {
+							if (event.target.value) {
+								props.onBlur?.(event);
+							}
+						}}
+						inputMode="numeric"
+						placeholder="Maximum size in bytes"
+						{...props}
+					/>
+				);
+			case 'Age':
+				return (
+					<Input
+						ref={ref}
+						size="md"
+						onBlur={(event) => {
+							if (event.target.value) {
+								props.onBlur?.(event);
+							}
+						}}
+						inputMode="numeric"
+						placeholder="Modified in the last N days"
+						{...props}
+					/>
+				);
+			case 'Kind':
+				return (
+					<Input
+						ref={ref}
+						size="md"
+						onBlur={(event) => {
+							if (event.target.value) {
+								props.onBlur?.(event);
+							}
+						}}
+						placeholder="Kinds (e.g., Image, Video)"
+						{...props}
+					/>
+				);
+			case 'Attribute':
+				return (
+					<Input
+						ref={ref}
+						size="md"
+						onBlur={(event) => {
+							if (event.target.value) {
+								props.onBlur?.(event);
+							}
+						}}
+						placeholder="Extended attribute (e.g., user.sd.ignore)"
+						{...props}
+					/>
+				);
+			case 'Ignore File':
+				return (
+					<Input
+						ref={ref}
+						size="md"
+						onBlur={(event) => {
+							if (event.target.value) {
+								props.onBlur?.(event);
+							}
+						}}
+						placeholder="Ignore file names (e.g., .gitignore, .sdignore)"
+						{...props}
+					/>
+				);
 			default:
 				throw new Error('Valid kind is required');
 		}
@@ -14,16 +14,30 @@ import {
 import { Button, Card, Divider, Input, Select, SelectOption, Tooltip } from '@sd/ui';
 import { ErrorMessage, Form, z } from '@sd/ui/src/forms';
 
-import { InputKinds, RuleInput, validateInput } from './RuleInput';
+import { InputKinds, RuleInput, splitRuleValues, validateInput } from './RuleInput';
 
 const ruleKinds: UnionToTuple<RuleKind> = [
 	'AcceptFilesByGlob',
 	'RejectFilesByGlob',
 	'AcceptIfChildrenDirectoriesArePresent',
-	'RejectIfChildrenDirectoriesArePresent'
+	'RejectIfChildrenDirectoriesArePresent',
+	'RejectFilesLargerThan',
+	'AcceptFilesModifiedWithinDays',
+	'AcceptFilesByKind',
+	'RejectFilesByExtendedAttribute',
+	'RejectFilesByIgnoreFiles'
 ];
 const ruleKindEnum = z.enum(ruleKinds);
 
+// Rule types that only work in a single mode
+const fixedModeOptions: Partial<Record<InputKinds, { value: RuleKind; label: string }>> = {
+	Size: { value: 'RejectFilesLargerThan', label: 'Reject larger files' },
+	Age: { value: 'AcceptFilesModifiedWithinDays', label: 'Accept newer files' },
+	Kind: { value: 'AcceptFilesByKind', label: 'Accept files' },
+	Attribute: { value: 'RejectFilesByExtendedAttribute', label: 'Reject files' },
+	'Ignore File': { value: 'RejectFilesByIgnoreFiles', label: 'Reject ignored files' }
+};
+
 const schema = z.object({
 	name: z.string().trim().min(3).max(18),
 	rules: z.array(
@@ -42,7 +56,17 @@ interface Props {
 }
 
 const RulesForm = ({ onSubmitted }: Props) => {
-	const selectValues = ['Name', 'Extension', 'Path', 'Advanced'];
+	const selectValues = [
+		'Name',
+		'Extension',
+		'Path',
+		'Advanced',
+		'Size',
+		'Age',
+		'Kind',
+		'Attribute',
+		'Ignore File'
+	];
 	const REMOTE_ERROR_FORM_FIELD = 'root.serverError';
 	const createIndexerRules = useLibraryMutation(['locations.indexer_rules.create']);
 	const formId = useId();
@@ -106,6 +130,10 @@ const RulesForm = ({ onSubmitted }: Props) => {
 					case 'Extension':
 						// .tar should work for .tar.gz, .tar.bz2, etc.
 						return [kind, [`**/*${value}`, `**/*${value}.*`]];
+					case 'Kind':
+					case 'Attribute':
+					case 'Ignore File':
+						return [kind, splitRuleValues(value)];
 					default:
 						return [kind, [value]];
 				}
@@ -179,6 +207,11 @@ const RulesForm = ({ onSubmitted }: Props) => {
 											onChange={(value) => {
 												field.onChange(value);
 												form.resetField(`rules.${index}.value`);
+												form.setValue(
+													`rules.${index}.kind`,
+													fixedModeOptions[value as InputKinds]
+														?.value ?? 'RejectFilesByGlob'
+												);
 											}}
 										>
 											{selectValues.map((value) => (
@@ -230,21 +263,28 @@ const RulesForm = ({ onSubmitted }: Props) => {
 								/>
 								<Controller
 									name={`rules.${index}.kind` as const}
-									render={({ field }) => (
-										<Select
-											{...field}
-											className="!h-[30px] w-full"
-											onChange={(value) => {
-												field.onChange(value);
-											}}
-										>
-											{modeOptions.map(({ label, value }) => (
-												<SelectOption key={value} value={value}>
-													{label}
-												</SelectOption>
-											))}
-										</Select>
-									)}
+									render={({ field }) => {
+										const fixedMode = fixedModeOptions[memoWatchRules(index)];
+
+										return (
+											<Select
+												{...field}
+												className="!h-[30px] w-full"
+												disabled={!!fixedMode}
+												onChange={(value) => {
+													field.onChange(value);
+												}}
+											>
+												{(fixedMode ? [fixedMode] : modeOptions).map(
+													({ label, value }) => (
+														<SelectOption key={value} value={value}>
+															{label}
+														</SelectOption>
+													)
+												)}
+											</Select>
+										);
+									}}
 									control={form.control}
 								/>
 								{index !== 0 && (
@@ -75,14 +75,15 @@ function useIncomingSpacedropToast() {
 			{
 				duration: 30 * 1000,
 				onClose: ({ event }) => {
-					event !== 'on-action' && acceptSpacedrop.mutate([data.id, null]);
+					event !== 'on-action' && acceptSpacedrop.mutate([data.id, null, null]);
 				},
 				action: {
 					label: 'Accept',
 					async onClick() {
 						let destinationFilePath = filePathInput.current?.value ?? placeholder;
 
-						if (data.files.length != 1) {
+						// Directories are sent as their files, named by their relative path
+						if (data.files.length != 1 || data.files[0]?.includes('/')) {
 							if (platform.openDirectoryPickerDialog) {
 								const result = await platform.openDirectoryPickerDialog({
 									title: 'Save Spacedrop',
@@ -107,7 +108,7 @@ function useIncomingSpacedropToast() {
 						}
 
 						if (destinationFilePath === '') return;
-						await acceptSpacedrop.mutateAsync([data.id, destinationFilePath]);
+						await acceptSpacedrop.mutateAsync([data.id, destinationFilePath, null]);
 					}
 				},
 				cancel: 'Reject'
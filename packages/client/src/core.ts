@@ -3,6 +3,8 @@
 
 export type Procedures = {
     queries: 
+        { key: "albums.get", input: LibraryArgs<number>, result: { item: Reference<Album>; nodes: CacheNode[] } | null } | 
+        { key: "albums.list", input: LibraryArgs<null>, result: NormalisedResults<Album> } | 
         { key: "auth.me", input: never, result: { id: string; email: string } } | 
         { key: "backups.getAll", input: never, result: GetAll } | 
         { key: "buildInfo", input: never, result: BuildInfo } | 
@@ -16,8 +18,16 @@ export type Procedures = {
         { key: "invalidation.test-invalidate", input: never, result: number } | 
         { key: "jobs.isActive", input: LibraryArgs<null>, result: boolean } | 
         { key: "jobs.reports", input: LibraryArgs<null>, result: JobGroup[] } | 
+        { key: "keys.getDefault", input: LibraryArgs<null>, result: string | null } | 
+        { key: "keys.isSetup", input: LibraryArgs<null>, result: boolean } | 
+        { key: "keys.isUnlocked", input: LibraryArgs<null>, result: boolean } | 
+        { key: "keys.list", input: LibraryArgs<null>, result: NormalisedResults<KeyListItem> } | 
+        { key: "labels.getForObject", input: LibraryArgs<number>, result: NormalisedResults<Label> } | 
+        { key: "labels.getWithObjects", input: LibraryArgs<number[]>, result: { [key in number]: ({ date_created: string; object: { id: number } })[] } } | 
+        { key: "labels.list", input: LibraryArgs<null>, result: NormalisedResults<Label> } | 
         { key: "library.list", input: never, result: NormalisedResults<LibraryConfigWrapped> } | 
-        { key: "library.statistics", input: LibraryArgs<null>, result: Statistics } | 
+        { key: "library.statistics", input: LibraryArgs<null>, result: StatisticsWithBreakdowns } | 
+        { key: "library.statisticsHistory", input: LibraryArgs<StatisticsHistoryArgs>, result: Statistics[] } | 
         { key: "locations.get", input: LibraryArgs<number>, result: { item: Reference<Location>; nodes: CacheNode[] } | null } | 
         { key: "locations.getWithRules", input: LibraryArgs<number>, result: { item: Reference<LocationWithIndexerRule>; nodes: CacheNode[] } | null } | 
         { key: "locations.indexer_rules.get", input: LibraryArgs<number>, result: NormalisedResult<IndexerRule> } | 
@@ -31,6 +41,12 @@ export type Procedures = {
         { key: "notifications.dismissAll", input: never, result: null } | 
         { key: "notifications.get", input: never, result: Notification[] } | 
         { key: "preferences.get", input: LibraryArgs<null>, result: LibraryPreferences } | 
+        { key: "search.duplicates", input: LibraryArgs<{ 
+/**
+ * Also splits the duplicates by their full checksum, leaving out the files that
+ * weren't validated yet
+ */
+byIntegrityChecksum?: boolean; take?: number | null }>, result: DuplicatesResult } | 
         { key: "search.ephemeralPaths", input: LibraryArgs<EphemeralPathSearchArgs>, result: EphemeralPathsResult } | 
         { key: "search.objects", input: LibraryArgs<ObjectSearchArgs>, result: SearchData<ExplorerItem> } | 
         { key: "search.objectsCount", input: LibraryArgs<{ filters?: SearchFilterArgs[] }>, result: number } | 
@@ -38,6 +54,15 @@ export type Procedures = {
         { key: "search.pathsCount", input: LibraryArgs<{ filters?: SearchFilterArgs[] }>, result: number } | 
         { key: "search.saved.get", input: LibraryArgs<number>, result: { id: number; pub_id: number[]; search: string | null; filters: string | null; name: string | null; icon: string | null; description: string | null; date_created: string | null; date_modified: string | null } | null } | 
         { key: "search.saved.list", input: LibraryArgs<null>, result: SavedSearch[] } | 
+        { key: "search.similarImages", input: LibraryArgs<{ objectId: number; 
+/**
+ * How many of the 64 bits of the perceptual hashes can differ, `0` only finds
+ * resized or re-encoded copies while higher values also find edited ones
+ */
+maxDistance?: number | null; take?: number | null }>, result: SimilarImagesResult } | 
+        { key: "spaces.get", input: LibraryArgs<number>, result: { item: Reference<Space>; nodes: CacheNode[] } | null } | 
+        { key: "spaces.getObjects", input: LibraryArgs<number>, result: NormalisedResults<Object> } | 
+        { key: "spaces.list", input: LibraryArgs<null>, result: NormalisedResults<Space> } | 
         { key: "sync.messages", input: LibraryArgs<null>, result: CRDTOperation[] } | 
         { key: "tags.get", input: LibraryArgs<number>, result: { item: Reference<Tag>; nodes: CacheNode[] } | null } | 
         { key: "tags.getForObject", input: LibraryArgs<number>, result: NormalisedResults<Tag> } | 
@@ -45,6 +70,11 @@ export type Procedures = {
         { key: "tags.list", input: LibraryArgs<null>, result: NormalisedResults<Tag> } | 
         { key: "volumes.list", input: never, result: NormalisedResults<Volume> },
     mutations: 
+        { key: "albums.assign", input: LibraryArgs<{ album_id: number; object_ids: number[] }>, result: null } | 
+        { key: "albums.create", input: LibraryArgs<AlbumCreateArgs>, result: Album } | 
+        { key: "albums.delete", input: LibraryArgs<number>, result: null } | 
+        { key: "albums.unassign", input: LibraryArgs<{ album_id: number; object_ids: number[] }>, result: null } | 
+        { key: "albums.update", input: LibraryArgs<AlbumUpdateArgs>, result: null } | 
         { key: "api.sendFeedback", input: Feedback, result: null } | 
         { key: "auth.logout", input: never, result: null } | 
         { key: "backups.backup", input: LibraryArgs<null>, result: string } | 
@@ -57,12 +87,17 @@ export type Procedures = {
         { key: "ephemeralFiles.cutFiles", input: LibraryArgs<EphemeralFileSystemOps>, result: null } | 
         { key: "ephemeralFiles.deleteFiles", input: LibraryArgs<string[]>, result: null } | 
         { key: "ephemeralFiles.renameFile", input: LibraryArgs<EphemeralRenameFileArgs>, result: null } | 
+        { key: "files.archiveFiles", input: LibraryArgs<FileArchiverJobInit>, result: null } | 
         { key: "files.convertImage", input: LibraryArgs<ConvertImageArgs>, result: null } | 
         { key: "files.copyFiles", input: LibraryArgs<FileCopierJobInit>, result: null } | 
         { key: "files.createFolder", input: LibraryArgs<CreateFolderArgs>, result: string } | 
         { key: "files.cutFiles", input: LibraryArgs<FileCutterJobInit>, result: null } | 
+        { key: "files.decryptFiles", input: LibraryArgs<FileDecryptorJobInit>, result: null } | 
+        { key: "files.deduplicate", input: LibraryArgs<DeduplicatorJobInit>, result: null } | 
         { key: "files.deleteFiles", input: LibraryArgs<FileDeleterJobInit>, result: null } | 
+        { key: "files.encryptFiles", input: LibraryArgs<FileEncryptorJobInit>, result: null } | 
         { key: "files.eraseFiles", input: LibraryArgs<FileEraserJobInit>, result: null } | 
+        { key: "files.extractFiles", input: LibraryArgs<FileExtractorJobInit>, result: null } | 
         { key: "files.removeAccessTime", input: LibraryArgs<number[]>, result: null } | 
         { key: "files.renameFile", input: LibraryArgs<RenameFileArgs>, result: null } | 
         { key: "files.setFavorite", input: LibraryArgs<SetFavoriteArgs>, result: null } | 
@@ -77,6 +112,20 @@ export type Procedures = {
         { key: "jobs.objectValidator", input: LibraryArgs<ObjectValidatorArgs>, result: null } | 
         { key: "jobs.pause", input: LibraryArgs<string>, result: null } | 
         { key: "jobs.resume", input: LibraryArgs<string>, result: null } | 
+        { key: "keys.add", input: LibraryArgs<KeyAddArgs>, result: string } | 
+        { key: "keys.backupKeystore", input: LibraryArgs<string>, result: null } | 
+        { key: "keys.delete", input: LibraryArgs<string>, result: null } | 
+        { key: "keys.lock", input: LibraryArgs<null>, result: null } | 
+        { key: "keys.mount", input: LibraryArgs<string>, result: null } | 
+        { key: "keys.restoreKeystore", input: LibraryArgs<RestoreBackupArgs>, result: number } | 
+        { key: "keys.setDefault", input: LibraryArgs<string>, result: null } | 
+        { key: "keys.setup", input: LibraryArgs<OnboardingConfig>, result: null } | 
+        { key: "keys.unlock", input: LibraryArgs<UnlockKeyManagerArgs>, result: null } | 
+        { key: "keys.unmount", input: LibraryArgs<string>, result: null } | 
+        { key: "labels.assign", input: LibraryArgs<{ targets: LabelTarget[]; label_id: number }>, result: null } | 
+        { key: "labels.create", input: LibraryArgs<LabelCreateArgs>, result: Label } | 
+        { key: "labels.delete", input: LibraryArgs<number>, result: null } | 
+        { key: "labels.unassign", input: LibraryArgs<{ targets: LabelTarget[]; label_id: number }>, result: null } | 
         { key: "library.create", input: CreateLibraryArgs, result: NormalisedResult<LibraryConfigWrapped> } | 
         { key: "library.delete", input: string, result: null } | 
         { key: "library.edit", input: EditLibraryArgs, result: null } | 
@@ -93,15 +142,23 @@ export type Procedures = {
         { key: "nodes.updateThumbnailerPreferences", input: UpdateThumbnailerPreferences, result: null } | 
         { key: "notifications.test", input: never, result: null } | 
         { key: "notifications.testLibrary", input: LibraryArgs<null>, result: null } | 
-        { key: "p2p.acceptSpacedrop", input: [string, string | null], result: null } | 
+        { key: "p2p.acceptSpacedrop", input: [string, string | null, number[] | null], result: null } | 
         { key: "p2p.cancelSpacedrop", input: string, result: null } | 
         { key: "p2p.pair", input: RemoteIdentity, result: number } | 
         { key: "p2p.pairingResponse", input: [number, PairingDecision], result: null } | 
         { key: "p2p.spacedrop", input: SpacedropArgs, result: string } | 
+        { key: "p2p.spacedropFilePaths", input: LibraryArgs<SpacedropFilePathsArgs>, result: string } | 
         { key: "preferences.update", input: LibraryArgs<LibraryPreferences>, result: null } | 
         { key: "search.saved.create", input: LibraryArgs<{ name: string; search?: string | null; filters?: string | null; description?: string | null; icon?: string | null }>, result: null } | 
         { key: "search.saved.delete", input: LibraryArgs<number>, result: null } | 
         { key: "search.saved.update", input: LibraryArgs<[number, Args]>, result: null } | 
+        { key: "spaces.addObjects", input: LibraryArgs<{ space_id: number; object_ids: number[] }>, result: null } | 
+        { key: "spaces.create", input: LibraryArgs<SpaceCreateArgs>, result: Space } | 
+        { key: "spaces.delete", input: LibraryArgs<number>, result: null } | 
+        { key: "spaces.export", input: LibraryArgs<SpaceExporterJobInit>, result: null } | 
+        { key: "spaces.removeObjects", input: LibraryArgs<{ space_id: number; object_ids: number[] }>, result: null } | 
+        { key: "spaces.reorder", input: LibraryArgs<{ space_id: number; object_ids: number[] }>, result: null } | 
+        { key: "spaces.update", input: LibraryArgs<SpaceUpdateArgs>, result: null } | 
         { key: "tags.assign", input: LibraryArgs<{ targets: Target[]; tag_id: number; unassign: boolean }>, result: null } | 
         { key: "tags.create", input: LibraryArgs<TagCreateArgs>, result: Tag } | 
         { key: "tags.delete", input: LibraryArgs<number>, result: null } | 
@@ -119,9 +176,26 @@ export type Procedures = {
         { key: "sync.newMessage", input: LibraryArgs<null>, result: null }
 };
 
+/**
+ * These are all possible algorithms that can be used for encryption and decryption
+ */
+export type Album = { id: number; pub_id: number[]; name: string | null; is_hidden: boolean | null; smart_filter: string | null; date_created: string | null; date_modified: string | null }
+
+export type AlbumCreateArgs = { name: string; smart_filter?: SmartAlbumFilter | null }
+
+export type AlbumUpdateArgs = { id: number; name: string | null; is_hidden: boolean | null; 
+/**
+ * Only smart albums can have their filter replaced
+ */
+smart_filter: SmartAlbumFilter | null }
+
+export type Algorithm = "XChaCha20Poly1305" | "Aes256Gcm"
+
+export type ArchiverFormat = "Zip" | "TarZst"
+
 export type Args = { search?: string | null; filters?: string | null; name?: string | null; icon?: string | null; description?: string | null }
 
-export type AudioMetadata = { duration: number | null; audio_codec: string | null }
+export type AudioMetadata = { duration: number | null; audio_codec: string | null; bitrate: number | null; sample_rate: number | null; channels: number | null; title: string | null; artist: string | null; album: string | null; album_artist: string | null; genre: string | null; track_number: number | null; year: number | null }
 
 /**
  * All of the feature flags provided by the core itself. The frontend has it's own set of feature flags!
@@ -164,6 +238,11 @@ export type Composite =
  */
 "Live"
 
+/**
+ * An object whose content matched a search, with the excerpt of its content that matched best
+ */
+export type ContentMatch = { object_id: number; snippet: SnippetPart[] }
+
 export type ConvertImageArgs = { location_id: number; file_path_id: number; delete_src: boolean; desired_extension: ConvertableExtension; quality_percentage: number | null }
 
 export type ConvertableExtension = "bmp" | "dib" | "ff" | "gif" | "ico" | "jpg" | "jpeg" | "png" | "pnm" | "qoi" | "tga" | "icb" | "vda" | "vst" | "tiff" | "tif" | "hif" | "heif" | "heifs" | "heic" | "heics" | "avif" | "avci" | "avcs" | "svg" | "svgz" | "pdf" | "webp"
@@ -176,12 +255,40 @@ export type CreateLibraryArgs = { name: LibraryName; default_locations: DefaultL
 
 export type CursorOrderItem<T> = { order: SortOrder; data: T }
 
+export type DeduplicationAction = "Delete" | 
+/**
+ * Only possible when the original is on the same file system
+ */
+"Hardlink" | 
+/**
+ * Copy-on-write clones, only supported by some file systems like APFS, Btrfs, XFS and ReFS
+ */
+"Reflink"
+
+export type DeduplicatorJobInit = { 
+/**
+ * Only the duplicates in this location are removed or linked
+ */
+location_id: number; 
+/**
+ * The files to keep, one for each group of duplicates, from any location of this instance
+ */
+originals_file_path_ids: number[]; action: DeduplicationAction }
+
 export type DefaultLocations = { desktop: boolean; documents: boolean; downloads: boolean; pictures: boolean; music: boolean; videos: boolean }
 
 export type DiskType = "SSD" | "HDD" | "Removable"
 
 export type DoubleClickAction = "openFile" | "quickPreview"
 
+export type DuplicateGroup = { object_id: number; integrity_checksum: string | null; size_in_bytes: string; wasted_bytes: string; file_paths: Reference<FilePath>[] }
+
+export type DuplicatesResult = { groups: DuplicateGroup[]; nodes: CacheNode[]; 
+/**
+ * Bytes freed by keeping a single file of every group, not only the returned ones
+ */
+reclaimable_bytes: string }
+
 export type EditLibraryArgs = { id: string; name: LibraryName | null; description: MaybeUndefined<string> }
 
 export type EphemeralFileSystemOps = { sources: string[]; target_dir: string }
@@ -215,14 +322,40 @@ export type ExplorerSettings<TOrder> = { layoutMode: ExplorerLayout | null; grid
 
 export type Feedback = { message: string; emoji: number }
 
+export type FileArchiverJobInit = { source_location_id: number; target_location_id: number; sources_file_path_ids: number[]; target_location_relative_directory_path: string; 
+/**
+ * Name of the archive without its extension
+ */
+archive_name: string; format: ArchiverFormat }
+
 export type FileCopierJobInit = { source_location_id: number; target_location_id: number; sources_file_path_ids: number[]; target_location_relative_directory_path: string }
 
 export type FileCutterJobInit = { source_location_id: number; target_location_id: number; sources_file_path_ids: number[]; target_location_relative_directory_path: string }
 
+export type FileDecryptorJobInit = { location_id: number; file_path_ids: number[]; 
+/**
+ * The password is never persisted with the job report, so an interrupted
+ * decryption job has to be started again instead of being resumed
+ */
+password: string }
+
 export type FileDeleterJobInit = { location_id: number; file_path_ids: number[] }
 
+export type FileEncryptorJobInit = { location_id: number; file_path_ids: number[]; algorithm: Algorithm; hashing_algorithm: HashingAlgorithm; 
+/**
+ * The password is never persisted with the job report, so an interrupted
+ * encryption job has to be started again instead of being resumed
+ */
+password: string; 
+/**
+ * Embeds the file's thumbnail in the header, so it can be previewed without decrypting it
+ */
+preview_media: boolean }
+
 export type FileEraserJobInit = { location_id: number; file_path_ids: number[]; passes: string }
 
+export type FileExtractorJobInit = { source_location_id: number; target_location_id: number; sources_file_path_ids: number[]; target_location_relative_directory_path: string }
+
 export type FilePath = { id: number; pub_id: number[]; is_dir: boolean | null; cas_id: string | null; integrity_checksum: string | null; location_id: number | null; materialized_path: string | null; name: string | null; extension: string | null; hidden: boolean | null; size_in_bytes: string | null; size_in_bytes_bytes: number[] | null; inode: number[] | null; object_id: number | null; key_id: number | null; date_created: string | null; date_modified: string | null; date_indexed: string | null }
 
 export type FilePathCursor = { isDir: boolean; variant: FilePathCursorVariant }
@@ -237,7 +370,7 @@ export type FilePathOrder = { field: "name"; value: SortOrder } | { field: "size
 
 export type FilePathSearchArgs = { take?: number | null; orderAndPagination?: OrderAndPagination<number, FilePathOrder, FilePathCursor> | null; filters?: SearchFilterArgs[]; groupDirectories?: boolean }
 
-export type FilePathWithObject = { id: number; pub_id: number[]; is_dir: boolean | null; cas_id: string | null; integrity_checksum: string | null; location_id: number | null; materialized_path: string | null; name: string | null; extension: string | null; hidden: boolean | null; size_in_bytes: string | null; size_in_bytes_bytes: number[] | null; inode: number[] | null; object_id: number | null; key_id: number | null; date_created: string | null; date_modified: string | null; date_indexed: string | null; object: { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null } | null }
+export type FilePathWithObject = { id: number; pub_id: number[]; is_dir: boolean | null; cas_id: string | null; integrity_checksum: string | null; location_id: number | null; materialized_path: string | null; name: string | null; extension: string | null; hidden: boolean | null; size_in_bytes: string | null; size_in_bytes_bytes: number[] | null; inode: number[] | null; object_id: number | null; key_id: number | null; date_created: string | null; date_modified: string | null; date_indexed: string | null; object: { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; perceptual_hash: number[] | null } | null }
 
 export type Flash = { 
 /**
@@ -292,6 +425,11 @@ export type GenerateThumbsForLocationArgs = { id: number; path: string; regenera
 
 export type GetAll = { backups: Backup[]; directory: string }
 
+/**
+ * This defines all available password hashing algorithms.
+ */
+export type HashingAlgorithm = { name: "Argon2id"; params: Params } | { name: "BalloonBlake3"; params: Params }
+
 export type IdentifyUniqueFilesArgs = { id: number; path: string }
 
 export type ImageMetadata = { resolution: Resolution; date_taken: MediaDate | null; location: MediaLocation | null; camera_data: CameraData; artist: string | null; description: string | null; copyright: string | null; exif_version: string | null }
@@ -309,6 +447,18 @@ export type IndexerRule = { id: number; pub_id: number[]; name: string | null; d
  * 
  * In case of `RuleKind::AcceptIfChildrenDirectoriesArePresent` or `RuleKind::RejectIfChildrenDirectoriesArePresent` the
  * `parameters` field must be a vector of strings containing the names of the directories.
+ * 
+ * In case of `RuleKind::RejectFilesLargerThan` or `RuleKind::AcceptFilesModifiedWithinDays` the `parameters` field
+ * must be a vector with a single string containing the maximum size in bytes or the number of days.
+ * 
+ * In case of `RuleKind::AcceptFilesByKind` the `parameters` field must be a vector of strings containing
+ * `ObjectKind` names, like `Image` or `Video`.
+ * 
+ * In case of `RuleKind::RejectFilesByExtendedAttribute` the `parameters` field must be a vector of strings
+ * containing the names of the extended attributes, like `user.sd.ignore`.
+ * 
+ * In case of `RuleKind::RejectFilesByIgnoreFiles` the `parameters` field must be a vector of strings
+ * containing the names of the ignore files, like `.gitignore`, where the later ones take precedence.
  */
 export type IndexerRuleCreateArgs = { name: string; dry_run: boolean; rules: ([RuleKind, string[]])[] }
 
@@ -324,9 +474,22 @@ export type JobStatus = "Queued" | "Running" | "Completed" | "Canceled" | "Faile
 
 export type JsonValue = null | boolean | number | string | JsonValue[] | { [key in string]: JsonValue }
 
+export type KeyAddArgs = { algorithm: Algorithm; hashing_algorithm: HashingAlgorithm; key: string; library_sync: boolean; automount: boolean }
+
+export type KeyListItem = { uuid: string; algorithm: Algorithm; hashing_algorithm: HashingAlgorithm; memory_only: boolean; automount: boolean; mounted: boolean; default: boolean }
+
 /**
  * Can wrap a query argument to require it to contain a `library_id` and provide helpers for working with libraries.
  */
+export type Label = { id: number; pub_id: number[]; name: string | null; date_created: string; date_modified: string }
+
+export type LabelCreateArgs = { name: string }
+
+/**
+ * Same as the target of `tags.assign`, but a type can't share its name with another
+ */
+export type LabelTarget = { Object: number } | { FilePath: number }
+
 export type LibraryArgs<T> = { library_id: string; arg: T }
 
 /**
@@ -358,7 +521,7 @@ export type LightScanArgs = { location_id: number; sub_path: string }
 
 export type ListenerStatus = { status: "Disabled" } | { status: "Enabling" } | { status: "Listening"; port: number } | { status: "Error"; error: string }
 
-export type Location = { id: number; pub_id: number[]; name: string | null; path: string | null; total_capacity: number | null; available_capacity: number | null; size_in_bytes: number[] | null; is_archived: boolean | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; date_created: string | null; instance_id: number | null }
+export type Location = { id: number; pub_id: number[]; name: string | null; path: string | null; total_capacity: number | null; available_capacity: number | null; size_in_bytes: number[] | null; is_archived: boolean | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; strict_identity: boolean | null; date_created: string | null; instance_id: number | null }
 
 /**
  * `LocationCreateArgs` is the argument received from the client using `rspc` to create a new location.
@@ -377,13 +540,15 @@ export type LocationSettings = { explorer: ExplorerSettings<FilePathOrder> }
  * It is important to note that only the indexer rule ids in this vector will be used from now on.
  * Old rules that aren't in this vector will be purged.
  */
-export type LocationUpdateArgs = { id: number; name: string | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; indexer_rules_ids: number[]; path: string | null }
+export type LocationUpdateArgs = { id: number; name: string | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; strict_identity: boolean | null; indexer_rules_ids: number[]; path: string | null }
 
-export type LocationWithIndexerRule = { id: number; pub_id: number[]; name: string | null; path: string | null; total_capacity: number | null; available_capacity: number | null; size_in_bytes: number[] | null; is_archived: boolean | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; date_created: string | null; instance_id: number | null; indexer_rules: Reference<IndexerRule>[] }
+export type LocationWithIndexerRule = { id: number; pub_id: number[]; name: string | null; path: string | null; total_capacity: number | null; available_capacity: number | null; size_in_bytes: number[] | null; is_archived: boolean | null; generate_preview_media: boolean | null; sync_preview_media: boolean | null; hidden: boolean | null; strict_identity: boolean | null; date_created: string | null; instance_id: number | null; indexer_rules: Reference<IndexerRule>[] }
 
 export type MaybeUndefined<T> = null | T
 
-export type MediaDataOrder = { field: "epochTime"; value: SortOrder }
+export type MediaDataFilterArgs = { title: TextMatch } | { artist: TextMatch } | { album: TextMatch } | { genre: TextMatch } | { year: Range<number> }
+
+export type MediaDataOrder = { field: "epochTime"; value: SortOrder } | { field: "duration"; value: SortOrder } | { field: "title"; value: SortOrder } | { field: "artist"; value: SortOrder } | { field: "album"; value: SortOrder } | { field: "genre"; value: SortOrder } | { field: "trackNumber"; value: SortOrder } | { field: "year"; value: SortOrder }
 
 /**
  * This can be either naive with no TZ (`YYYY-MM-DD HH-MM-SS`) or UTC (`YYYY-MM-DD HH-MM-SS ±HHMM`),
@@ -436,11 +601,15 @@ export type NotificationData = { PairingRequest: { id: string; pairing_id: numbe
 
 export type NotificationId = { type: "library"; id: [string, number] } | { type: "node"; id: number }
 
-export type Object = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null }
+export type Object = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; perceptual_hash: number[] | null }
 
 export type ObjectCursor = "none" | { dateAccessed: CursorOrderItem<string> } | { kind: CursorOrderItem<number> }
 
-export type ObjectFilterArgs = { favorite: boolean } | { hidden: ObjectHiddenFilter } | { kind: InOrNotIn<number> } | { tags: InOrNotIn<number> } | { dateAccessed: Range<string> }
+export type ObjectFilterArgs = { favorite: boolean } | { hidden: ObjectHiddenFilter } | { kind: InOrNotIn<number> } | { tags: InOrNotIn<number> } | { labels: InOrNotIn<number> } | { albums: InOrNotIn<number> } | { dateAccessed: Range<string> } | { mediaData: MediaDataFilterArgs } | 
+/**
+ * Objects whose indexed text has every word of the query
+ */
+{ content: string }
 
 export type ObjectHiddenFilter = "exclude" | "include"
 
@@ -450,9 +619,11 @@ export type ObjectSearchArgs = { take: number; orderAndPagination?: OrderAndPagi
 
 export type ObjectValidatorArgs = { id: number; path: string }
 
-export type ObjectWithFilePaths = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; file_paths: FilePath[] }
+export type ObjectWithFilePaths = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; perceptual_hash: number[] | null; file_paths: FilePath[] }
 
-export type ObjectWithFilePaths2 = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; file_paths: Reference<FilePath>[] }
+export type ObjectWithFilePaths2 = { id: number; pub_id: number[]; kind: number | null; key_id: number | null; hidden: boolean | null; favorite: boolean | null; important: boolean | null; note: string | null; date_created: string | null; date_accessed: string | null; perceptual_hash: number[] | null; file_paths: Reference<FilePath>[] }
+
+export type OnboardingConfig = { password: string; algorithm: Algorithm; hashing_algorithm: HashingAlgorithm }
 
 /**
  * Represents the operating system which the remote peer is running.
@@ -467,7 +638,11 @@ export type Orientation = "Normal" | "CW90" | "CW180" | "CW270" | "MirroredVerti
 /**
  * TODO: P2P event for the frontend
  */
-export type P2PEvent = { type: "DiscoveredPeer"; identity: RemoteIdentity; metadata: PeerMetadata } | { type: "ExpiredPeer"; identity: RemoteIdentity } | { type: "ConnectedPeer"; identity: RemoteIdentity } | { type: "DisconnectedPeer"; identity: RemoteIdentity } | { type: "SpacedropRequest"; id: string; identity: RemoteIdentity; peer_name: string; files: string[] } | { type: "SpacedropProgress"; id: string; percent: number } | { type: "SpacedropTimedout"; id: string } | { type: "SpacedropRejected"; id: string } | { type: "PairingRequest"; id: number; name: string; os: OperatingSystem } | { type: "PairingProgress"; id: number; status: PairingStatus }
+export type P2PEvent = { type: "DiscoveredPeer"; identity: RemoteIdentity; metadata: PeerMetadata } | { type: "ExpiredPeer"; identity: RemoteIdentity } | { type: "ConnectedPeer"; identity: RemoteIdentity } | { type: "DisconnectedPeer"; identity: RemoteIdentity } | { type: "SpacedropRequest"; id: string; identity: RemoteIdentity; peer_name: string; files: string[] } | { type: "SpacedropProgress"; id: string; percent: number; 
+/**
+ * Index of the file being transferred within the `files` of the request
+ */
+file: number; file_percent: number } | { type: "SpacedropTimedout"; id: string } | { type: "SpacedropRejected"; id: string } | { type: "PairingRequest"; id: number; name: string; os: OperatingSystem } | { type: "PairingProgress"; id: number; status: PairingStatus }
 
 export type P2PStatus = { ipv4: ListenerStatus; ipv6: ListenerStatus }
 
@@ -475,6 +650,13 @@ export type PairingDecision = { decision: "accept"; libraryId: string } | { deci
 
 export type PairingStatus = { type: "EstablishingConnection" } | { type: "PairingRequested" } | { type: "LibraryAlreadyExists" } | { type: "PairingDecisionRequest" } | { type: "PairingInProgress"; data: { library_name: string; library_description: string | null } } | { type: "InitialSyncProgress"; data: number } | { type: "PairingComplete"; data: string } | { type: "PairingRejected" }
 
+/**
+ * These parameters define the password-hashing level.
+ * 
+ * The greater the parameter, the longer the password will take to hash.
+ */
+export type Params = "Standard" | "Hardened" | "Paranoid"
+
 export type PeerMetadata = { name: string; operating_system: OperatingSystem | null; version: string | null }
 
 export type PlusCode = string
@@ -511,11 +693,17 @@ export type Resolution = { width: number; height: number }
 
 export type Response = { Start: { user_code: string; verification_url: string; verification_url_complete: string } } | "Complete" | "Error"
 
-export type RuleKind = "AcceptFilesByGlob" | "RejectFilesByGlob" | "AcceptIfChildrenDirectoriesArePresent" | "RejectIfChildrenDirectoriesArePresent"
+export type RestoreBackupArgs = { password: string; secret_key: string; path: string }
+
+export type RuleKind = "AcceptFilesByGlob" | "RejectFilesByGlob" | "AcceptIfChildrenDirectoriesArePresent" | "RejectIfChildrenDirectoriesArePresent" | "RejectFilesLargerThan" | "AcceptFilesModifiedWithinDays" | "AcceptFilesByKind" | "RejectFilesByExtendedAttribute" | "RejectFilesByIgnoreFiles"
 
 export type SavedSearch = { id: number; pub_id: number[]; search: string | null; filters: string | null; name: string | null; icon: string | null; description: string | null; date_created: string | null; date_modified: string | null }
 
-export type SearchData<T> = { cursor: number[] | null; items: Reference<T>[]; nodes: CacheNode[] }
+export type SearchData<T> = { cursor: number[] | null; items: Reference<T>[]; nodes: CacheNode[]; 
+/**
+ * The parts of the content of the returned objects that matched the content filters
+ */
+content_matches: ContentMatch[] }
 
 export type SearchFilterArgs = { filePath: FilePathFilterArgs } | { object: ObjectFilterArgs }
 
@@ -527,18 +715,70 @@ export type SharedOperation = { record_id: JsonValue; model: string; data: Share
 
 export type SharedOperationData = "c" | { u: { field: string; value: JsonValue } } | "d"
 
+export type SimilarImage = { distance: number; item: Reference<ExplorerItem> }
+
+export type SimilarImagesResult = { items: SimilarImage[]; nodes: CacheNode[] }
+
 export type SingleInvalidateOperationEvent = { 
 /**
  * This fields are intentionally private.
  */
 key: string; arg: JsonValue; result: JsonValue | null }
 
+/**
+ * Smart albums hold every object whose media data matches all of the given criteria, instead of
+ * objects picked by hand.
+ * 
+ * Their objects are computed by each node from its own media data, so they aren't synced, only
+ * the filter is.
+ */
+export type SmartAlbumFilter = { near?: SmartAlbumLocation | null; taken_after?: string | null; taken_before?: string | null; 
+/**
+ * Matched against `CameraData::device_model`, ignoring the case
+ */
+device_model?: string | null }
+
+/**
+ * Media taken within `radius` metres of the given coordinates
+ */
+export type SmartAlbumLocation = { latitude: number; longitude: number; radius: number }
+
+export type SnippetPart = { text: string; highlighted: boolean }
+
 export type SortOrder = "Asc" | "Desc"
 
+export type Space = { id: number; pub_id: number[]; name: string | null; description: string | null; date_created: string | null; date_modified: string | null }
+
+export type SpaceCreateArgs = { name: string; description?: string | null }
+
+export type SpaceExportMode = 
+/**
+ * Falls back to copies for files on another file system than the target location
+ */
+"Hardlink" | "Copy"
+
+export type SpaceExporterJobInit = { space_id: number; target_location_id: number; 
+/**
+ * The space is exported to a new directory, named after it, inside this one
+ */
+target_location_relative_directory_path: string; mode: SpaceExportMode }
+
+export type SpaceUpdateArgs = { id: number; name: string | null; description: string | null }
+
 export type SpacedropArgs = { identity: RemoteIdentity; file_path: string[] }
 
+export type SpacedropFilePathsArgs = { identity: RemoteIdentity; file_path_ids: number[] }
+
 export type Statistics = { id: number; date_captured: string; total_object_count: number; library_db_size: string; total_bytes_used: string; total_bytes_capacity: string; total_unique_bytes: string; total_bytes_free: string; preview_media_bytes: string }
 
+export type StatisticsHistoryArgs = { since?: string | null }
+
+export type StatisticsPerKind = { id: number; kind: number; file_count: number; total_bytes: string; statistics_id: number }
+
+export type StatisticsPerLocation = { id: number; location_id: number; file_count: number; total_bytes: string; statistics_id: number }
+
+export type StatisticsWithBreakdowns = { id: number; date_captured: string; total_object_count: number; library_db_size: string; total_bytes_used: string; total_bytes_capacity: string; total_unique_bytes: string; total_bytes_free: string; preview_media_bytes: string; locations: StatisticsPerLocation[]; kinds: StatisticsPerKind[] }
+
 export type SystemLocations = { desktop: string | null; documents: string | null; downloads: string | null; pictures: string | null; music: string | null; videos: string | null }
 
 export type Tag = { id: number; pub_id: number[]; name: string | null; color: string | null; redundancy_goal: number | null; date_created: string | null; date_modified: string | null }
@@ -551,10 +791,26 @@ export type Target = { Object: number } | { FilePath: number }
 
 export type TextMatch = { contains: string } | { startsWith: string } | { endsWith: string } | { equals: string }
 
-export type ThumbnailerPreferences = { background_processing_percentage: number }
+/**
+ * Every cas_id gets a thumbnail for each of the sizes enabled in the thumbnailer preferences,
+ * stored side by side in the same shard directory as `<cas_id>-<size>.webp`
+ */
+export type ThumbnailSize = "small" | "medium" | "large"
+
+export type ThumbnailerPreferences = { background_processing_percentage: number; sizes: ThumbnailSize[]; quality: number }
+
+export type UnlockKeyManagerArgs = { password: string; 
+/**
+ * Only required if the secret key can't be retrieved from the keyring
+ */
+secret_key: string | null }
+
+export type UpdateThumbnailerPreferences = { background_processing_percentage: number; sizes?: ThumbnailSize[] | null; quality?: number | null }
+
+export type VideoMetadata = { duration: number | null; container: string | null; video_codec: string | null; audio_codec: string | null; resolution: Resolution | null; frame_rate: number | null; bitrate: number | null; rotation: number | null; date_created: MediaDate | null; streams: VideoStream[] }
 
-export type UpdateThumbnailerPreferences = { background_processing_percentage: number }
+export type VideoStream = { kind: VideoStreamKind; codec: string | null }
 
-export type VideoMetadata = { duration: number | null; video_codec: string | null; audio_codec: string | null }
+export type VideoStreamKind = "Video" | "Audio" | "Subtitle" | "Other"
 
 export type Volume = { name: string; mount_points: string[]; total_capacity: string; available_capacity: string; disk_type: DiskType; file_system: string | null; is_root_filesystem: boolean }